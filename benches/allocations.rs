@@ -0,0 +1,64 @@
+//! Compares allocations and time of `Packet::parse` and
+//! `Packet::parse_inline`
+//!
+//! Run with `cargo bench --features smallvec`.
+extern crate dns_parser;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use dns_parser::Packet;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ITERATIONS: usize = 100_000;
+
+// Response for example.com A with two answers and one additional record
+const RESPONSE: &[u8] = b"\x06%\x81\x80\x00\x01\x00\x02\x00\x00\x00\x01\
+    \x07example\x03com\x00\x00\x01\x00\x01\
+    \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\x00\x04\x5d\xb8\xd8\x22\
+    \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\x00\x04\x5d\xb8\xd8\x23\
+    \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\x00\x04\x5d\xb8\xd8\x24";
+
+fn measure<F: Fn() -> usize>(name: &str, f: F) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut total = 0;
+    for _ in 0..ITERATIONS {
+        total += f();
+    }
+    let elapsed = start.elapsed();
+    let allocs = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{:<14} {:>6.2} allocations/packet {:>8.0} ns/packet ({})",
+        name,
+        allocs as f64 / ITERATIONS as f64,
+        elapsed.as_secs_f64() * 1e9 / ITERATIONS as f64,
+        total);
+}
+
+fn main() {
+    measure("parse", || {
+        let packet = Packet::parse(RESPONSE).unwrap();
+        packet.answers.len() + packet.additional.len()
+    });
+    measure("parse_inline", || {
+        let packet = Packet::parse_inline(RESPONSE).unwrap();
+        packet.answers.len() + packet.additional.len()
+    });
+}
@@ -0,0 +1,66 @@
+/* C interface of the dns-parser crate, built with the `ffi` feature */
+#ifndef DNS_PARSER_H
+#define DNS_PARSER_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DNS_OK 0
+#define DNS_ERR_NULL -1
+#define DNS_ERR_MALFORMED -2
+#define DNS_ERR_RANGE -3
+
+#define DNS_SECTION_ANSWER 1
+#define DNS_SECTION_AUTHORITY 2
+#define DNS_SECTION_ADDITIONAL 3
+
+typedef struct DnsPacket DnsPacket;
+
+typedef struct {
+    uint16_t id;
+    uint16_t flags;
+    uint16_t questions;
+    uint16_t answers;
+    uint16_t nameservers;
+    uint16_t additional;
+} DnsHeader;
+
+/* Names are not NUL-terminated */
+typedef struct {
+    const uint8_t *name;
+    size_t name_len;
+    uint16_t qtype;
+    uint16_t qclass;
+    bool prefer_unicast;
+} DnsQuestion;
+
+typedef struct {
+    const uint8_t *name;
+    size_t name_len;
+    uint16_t rtype;
+    uint16_t rclass;
+    uint32_t ttl;
+    const uint8_t *rdata;
+    size_t rdata_len;
+} DnsRecord;
+
+int dns_parse(const uint8_t *data, size_t len, DnsPacket **out);
+void dns_packet_free(DnsPacket *packet);
+int dns_packet_header(const DnsPacket *packet, DnsHeader *out);
+size_t dns_packet_question_count(const DnsPacket *packet);
+int dns_packet_question(const DnsPacket *packet, size_t index,
+                        DnsQuestion *out);
+size_t dns_packet_record_count(const DnsPacket *packet, int section);
+int dns_packet_record(const DnsPacket *packet, int section, size_t index,
+                      DnsRecord *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
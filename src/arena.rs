@@ -0,0 +1,117 @@
+use bumpalo::Bump;
+use bumpalo::collections::Vec as BumpVec;
+
+use std::ops::Range;
+
+use {Header, Packet, Question, ResourceRecord, Error, ParseOptions, Section};
+use {SkippedSection};
+use parser::{parse_into, Sections};
+use rdata::opt::Record as Opt;
+
+/// A packet whose sections are allocated in a bump arena
+///
+/// Returned by `Packet::parse_in`. The section vectors live in the arena
+/// and are freed all at once when the arena is reset, so a server may
+/// reuse one arena for many packets. Note that decoded names and TXT
+/// data are still allocated on the heap.
+#[derive(Debug)]
+#[allow(missing_docs)]  // same fields as in Packet
+pub struct ArenaPacket<'a> {
+    pub header: Header,
+    pub questions: BumpVec<'a, Question<'a>>,
+    pub answers: BumpVec<'a, ResourceRecord<'a>>,
+    pub nameservers: BumpVec<'a, ResourceRecord<'a>>,
+    pub additional: BumpVec<'a, ResourceRecord<'a>>,
+    pub opt: Option<Opt<'a>>,
+    pub skipped: Vec<SkippedSection>,
+}
+
+struct ArenaSections<'a> {
+    questions: BumpVec<'a, Question<'a>>,
+    answers: BumpVec<'a, ResourceRecord<'a>>,
+    nameservers: BumpVec<'a, ResourceRecord<'a>>,
+    additional: BumpVec<'a, ResourceRecord<'a>>,
+    skipped: Vec<SkippedSection>,
+}
+
+impl<'a> Sections<'a> for ArenaSections<'a> {
+    fn reserve(&mut self, header: &Header) {
+        self.questions.reserve(header.questions as usize);
+        self.answers.reserve(header.answers as usize);
+        self.nameservers.reserve(header.nameservers as usize);
+        self.additional.reserve(header.additional as usize);
+    }
+    fn question(&mut self, question: Question<'a>) {
+        self.questions.push(question);
+    }
+    fn record(&mut self, section: Section, record: ResourceRecord<'a>) {
+        match section {
+            Section::Question => unreachable!(),
+            Section::Answer => self.answers.push(record),
+            Section::Authority => self.nameservers.push(record),
+            Section::Additional => self.additional.push(record),
+        }
+    }
+    fn skipped(&mut self, section: Section, range: Range<usize>) {
+        self.skipped.push(SkippedSection { section, range });
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Parse a packet allocating the section vectors in `bump`
+    pub fn parse_in(data: &'a [u8], bump: &'a Bump)
+        -> Result<ArenaPacket<'a>, Error>
+    {
+        Packet::parse_in_with_options(data, bump, &ParseOptions::default())
+    }
+
+    /// Same as `parse_in` but with non-default settings
+    pub fn parse_in_with_options(data: &'a [u8], bump: &'a Bump,
+        options: &ParseOptions)
+        -> Result<ArenaPacket<'a>, Error>
+    {
+        let mut sections = ArenaSections {
+            questions: BumpVec::new_in(bump),
+            answers: BumpVec::new_in(bump),
+            nameservers: BumpVec::new_in(bump),
+            additional: BumpVec::new_in(bump),
+            skipped: Vec::new(),
+        };
+        let (header, opt) = parse_into(data, options, &mut sections)?;
+        Ok(ArenaPacket {
+            header,
+            questions: sections.questions,
+            answers: sections.answers,
+            nameservers: sections.nameservers,
+            additional: sections.additional,
+            opt,
+            skipped: sections.skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bumpalo::Bump;
+
+    use {Packet, RData};
+
+    #[test]
+    fn parse_in_arena() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04\x5d\xb8\xd8\x22";
+        let mut bump = Bump::new();
+        for _ in 0..3 {
+            {
+                let packet = Packet::parse_in(response, &bump).unwrap();
+                assert_eq!(packet.header.id, 1573);
+                assert_eq!(&packet.questions[0].qname.to_string()[..],
+                    "example.com");
+                assert!(matches!(packet.answers[0].data, RData::A(..)));
+            }
+            bump.reset();
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Response sanitization against cache poisoning (RFC 5452)
+//!
+//! A resolver asking the servers of a zone must only believe what they
+//! say about names in that zone. Records for other names are how spoofed
+//! or malicious responses sneak data into caches.
+
+use {Packet, ResourceRecord, Section};
+
+/// What `sanitize` removed from a response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The ID or question doesn't match the query, so every record was
+    /// dropped
+    pub mismatch: bool,
+    /// The section and owner name of each dropped record
+    pub dropped: Vec<(Section, String)>,
+}
+
+impl Report {
+    /// Returns true if nothing was dropped
+    pub fn is_clean(&self) -> bool {
+        !self.mismatch && self.dropped.is_empty()
+    }
+}
+
+/// Drops the records of the response that are out of bailiwick
+///
+/// `zone_cut` is the zone the queried server is authoritative for, as
+/// learned from the referral that led to it. Records in every section
+/// whose owner name isn't in that zone are removed, and the header counts
+/// are updated. If the response doesn't answer `query` (different ID or
+/// question, RFC 5452 section 9.1) all the records are dropped.
+pub fn sanitize<'a>(mut response: Packet<'a>, query: &Packet, zone_cut: &str)
+    -> (Packet<'a>, Report)
+{
+    let mismatch = response.header.id != query.header.id
+        || response.questions.len() != query.questions.len()
+        || response.questions.iter().zip(&query.questions).any(|(r, q)| {
+            r.qtype != q.qtype || r.qclass != q.qclass
+                || !r.qname.eq_ignore_case(&q.qname.str_val)
+        });
+    let mut report = Report { mismatch, dropped: Vec::new() };
+    let mut keep = |section, record: &ResourceRecord| {
+        if !mismatch && record.name.is_subdomain_of(zone_cut) {
+            return true;
+        }
+        report.dropped.push((section, record.name.to_string()));
+        false
+    };
+    response.retain_answers(|r| keep(Section::Answer, r));
+    response.nameservers.retain(|r| keep(Section::Authority, r));
+    response.header.nameservers = response.nameservers.len() as u16;
+    response.additional.retain(|r| keep(Section::Additional, r));
+    response.header.additional = (response.additional.len()
+        + response.opt.is_some() as usize) as u16;
+    (response, report)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use {Builder, Packet, Name, Class, RData, QueryType, QueryClass, Section};
+    use rdata::Ns;
+    use super::sanitize;
+
+    #[test]
+    fn out_of_bailiwick() {
+        let mut bld = Builder::new(1, false);
+        bld.question("www.example.com", false, QueryType::A, QueryClass::IN);
+        let query_data = bld.build().unwrap();
+        let query = Packet::parse(&query_data).unwrap();
+
+        bld.answer("www.example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.nameserver("example.com", Class::IN,
+            RData::NS(Ns::new(Name::from_string("ns.example.net"))),
+            false, 60);
+        bld.nameserver("com", Class::IN,
+            RData::NS(Ns::new(Name::from_string("ns.attacker.test"))),
+            false, 60);
+        bld.additional("ns.example.net", Class::IN,
+            RData::A(Ipv4Addr::new(203, 0, 113, 1).into()), false, 60);
+        let data = bld.build().unwrap();
+        let response = Packet::parse(&data).unwrap();
+
+        let (clean, report) = sanitize(response, &query, "example.com");
+        assert!(!report.mismatch);
+        assert_eq!(report.dropped, [
+            (Section::Authority, "com".to_string()),
+            (Section::Additional, "ns.example.net".to_string()),
+        ]);
+        assert_eq!(clean.answers.len(), 1);
+        assert_eq!(clean.header.nameservers, 1);
+        assert_eq!(clean.header.additional, 0);
+
+        let response = Packet::parse(&data).unwrap();
+        let (clean, report) = sanitize(response, &query, "www.example.com");
+        assert_eq!(report.dropped.len(), 3);
+        assert_eq!(clean.answers.len(), 1);
+
+        // Records added by hand aren't in the counts
+        let mut response = Packet::parse(&data).unwrap();
+        let mut extra = Packet::parse(&data).unwrap();
+        response.nameservers.append(&mut extra.nameservers);
+        response.additional.append(&mut extra.additional);
+        let (clean, _) = sanitize(response, &query, "example.com");
+        assert_eq!(clean.header.nameservers, 2);
+        assert_eq!(clean.header.additional, 0);
+
+        let mut other = data.clone();
+        other[1] ^= 1;
+        let response = Packet::parse(&other).unwrap();
+        let (clean, report) = sanitize(response, &query, "example.com");
+        assert!(report.mismatch);
+        assert!(!report.is_clean());
+        assert!(clean.answers.is_empty() && clean.nameservers.is_empty());
+    }
+}
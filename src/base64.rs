@@ -0,0 +1,88 @@
+//! Minimal unpadded base64url (RFC 4648 section 5) used by DoH and stamps
+//!
+//! Presentation format of DNSSEC records uses the standard alphabet with
+//! padding instead, see `encode`.
+
+const ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn encode_url(data: &[u8]) -> String {
+    let mut result = String::with_capacity((data.len() * 4).div_ceil(3));
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).cloned().unwrap_or(0),
+            chunk.get(2).cloned().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..chunk.len()+1 {
+            let idx = (n >> (18 - 6*i)) & 0x3F;
+            result.push(ALPHABET[idx as usize] as char);
+        }
+    }
+    result
+}
+
+/// Encodes with the standard alphabet and padding (RFC 4648 section 4)
+pub fn encode(data: &[u8]) -> String {
+    let mut result = encode_url(data).replace('-', "+").replace('_', "/");
+    while !result.len().is_multiple_of(4) {
+        result.push('=');
+    }
+    result
+}
+
+/// Returns `None` on invalid characters or length. Padding is ignored.
+pub fn decode_url(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=').as_bytes();
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut result = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let v = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'-' => 62,
+                b'_' => 63,
+                _ => return None,
+            };
+            n |= (v as u32) << (18 - 6*i);
+        }
+        for i in 0..chunk.len()-1 {
+            result.push((n >> (16 - 8*i)) as u8);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode, encode_url, decode_url};
+
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg"),
+            (b"fo", "Zm8"),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg"),
+            (b"fooba", "Zm9vYmE"),
+            (b"foobar", "Zm9vYmFy"),
+            (b"\xfb\xff", "-_8"),
+        ];
+        for &(raw, encoded) in vectors {
+            assert_eq!(encode_url(raw), encoded);
+            assert_eq!(decode_url(encoded).unwrap(), raw);
+        }
+        assert_eq!(decode_url("Zm8=").unwrap(), b"fo");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"\xfb\xff"), "+/8=");
+        assert_eq!(decode_url("Zm9vY"), None);
+        assert_eq!(decode_url("Zm9+"), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Prints DNS packets the way `dig` does
+//!
+//! Usage: `dns-parse [--hex | --base64 | --raw] [FILE...]`
+//!
+//! Reads standard input when no file is given. Without a format flag the
+//! input is taken as hex if it only has hex digits and whitespace, as
+//! base64 if it only has base64 characters, and as raw bytes otherwise.
+extern crate dns_parser;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+
+use dns_parser::Packet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Auto,
+    Hex,
+    Base64,
+    Raw,
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits = text.split_whitespace()
+        .map(|word| word.trim_start_matches("0x").replace("\\x", ""))
+        .collect::<String>();
+    if digits.len() % 2 != 0 || !digits.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    (0..digits.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i+2], 16).ok())
+        .collect()
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    // The DoH decoder takes base64url, the standard alphabet differs
+    // only in two characters
+    let url = text.split_whitespace().collect::<String>()
+        .replace('+', "-").replace('/', "_");
+    dns_parser::doh::decode_get_param(&url).ok()
+}
+
+fn is_hex(data: &[u8]) -> bool {
+    data.iter().all(|&b| b.is_ascii_hexdigit() || b.is_ascii_whitespace()
+                         || b == b'x' || b == b'\\')
+}
+
+fn is_base64(data: &[u8]) -> bool {
+    data.iter().all(|&b| b.is_ascii_alphanumeric() || b.is_ascii_whitespace()
+                         || b"+/-_=".contains(&b))
+}
+
+fn decode(data: Vec<u8>, format: Format) -> Result<Vec<u8>, String> {
+    let format = match format {
+        Format::Auto if is_hex(&data) => Format::Hex,
+        Format::Auto if is_base64(&data) => Format::Base64,
+        Format::Auto => Format::Raw,
+        x => x,
+    };
+    if format == Format::Raw {
+        return Ok(data);
+    }
+    let text = String::from_utf8(data)
+        .map_err(|_| "input is not text".to_string())?;
+    let result = match format {
+        Format::Hex => decode_hex(&text),
+        _ => decode_base64(&text),
+    };
+    result.ok_or_else(|| format!("invalid {:?} input", format))
+}
+
+fn show(name: &str, mut input: Box<dyn Read>, format: Format)
+    -> Result<(), String>
+{
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    let data = decode(data, format).map_err(|e| format!("{}: {}", name, e))?;
+    let packet = Packet::parse(&data)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    print!("{}", packet.dig());
+    Ok(())
+}
+
+fn main() {
+    let mut format = Format::Auto;
+    let mut files = Vec::new();
+    for arg in env::args().skip(1) {
+        match &arg[..] {
+            "--hex" => format = Format::Hex,
+            "--base64" => format = Format::Base64,
+            "--raw" => format = Format::Raw,
+            "-h" | "--help" => {
+                println!("Usage: dns-parse [--hex | --base64 | --raw] \
+                          [FILE...]");
+                return;
+            }
+            _ => files.push(arg),
+        }
+    }
+    let mut code = 0;
+    let mut report = |result: Result<(), String>| {
+        if let Err(e) = result {
+            eprintln!("dns-parse: {}", e);
+            code = 1;
+        }
+    };
+    if files.is_empty() {
+        report(show("<stdin>", Box::new(io::stdin()), format));
+    }
+    for (i, name) in files.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match File::open(name) {
+            Ok(file) => report(show(name, Box::new(file), format)),
+            Err(e) => report(Err(format!("{}: {}", name, e))),
+        }
+    }
+    process::exit(code);
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode_hex;
+
+    #[test]
+    fn hex() {
+        assert_eq!(decode_hex("0x1f \\x00ab\n"), Some(vec![0x1f, 0, 0xab]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("1\u{e9}1"), None);
+        assert_eq!(decode_hex("+1"), None);
+    }
+}
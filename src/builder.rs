@@ -1,12 +1,21 @@
-use byteorder::{BigEndian, WriteBytesExt};
+use std::borrow::Cow;
+use std::mem;
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
 
 use {Opcode, ResponseCode, Header, QueryType, QueryClass, Name, Class, RData};
-use {ResourceRecord};
+use {Packet, Type};
+use {ResourceRecord, Error};
+#[cfg(feature = "rand")] use Id;
+use compress::Compressor;
+use dnssd;
+use edns::CustomOption;
+use rdata::{Opt, Soa};
 
 #[derive(Debug)]
 #[allow(missing_docs)]  // should be covered by spec
 struct Question<'a> {
-    pub qname: &'a str,
+    pub qname: Cow<'a, str>,
     /// Whether or not we prefer unicast responses.
     /// This is used in multicast DNS.
     pub prefer_unicast: bool,
@@ -14,6 +23,52 @@ struct Question<'a> {
     pub qclass: QueryClass,
 }
 
+/// Fields of the OPT pseudo-RR (RFC 6891)
+#[derive(Debug, Clone, Copy)]
+struct Edns {
+    udp: u16,
+    extrcode: u8,
+    version: u8,
+    flags: u16,
+}
+
+/// Order of the records within each RRset, see `Builder::rrset_order`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RRsetOrder {
+    /// The order the records were added in
+    Keep,
+    /// Rotated left by this many records, e.g. a counter incremented for
+    /// every response for round-robin load distribution
+    Rotate(usize),
+    /// Shuffled with a generator seeded with this value, so the same seed
+    /// always gives the same order
+    Shuffle(u64),
+}
+
+impl RRsetOrder {
+    /// Shuffles with a seed from the OS random number generator
+    #[cfg(feature = "rand")]
+    pub fn random() -> RRsetOrder {
+        use rand::RngCore;
+        RRsetOrder::Shuffle(rand::rngs::OsRng.next_u64())
+    }
+}
+
+/// What to do about RRsets whose records have different TTLs, see
+/// `Builder::rrset_ttl`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RRsetTtl {
+    /// Write the TTLs as they were added
+    Ignore,
+    /// Give all records of an RRset the lowest TTL among them
+    Clamp,
+}
+
+/// Names a packet may point to and their offsets in the packet
+///
+/// Returned by `Builder::build_with_compression_map`.
+pub type CompressionMap = Vec<(String, u16)>;
+
 /// Allows to build a DNS packet
 ///
 /// Both query and answer packets may be built with this interface, although,
@@ -25,40 +80,230 @@ pub struct Builder<'a> {
     answers: Vec<ResourceRecord<'a>>,
     nameservers: Vec<ResourceRecord<'a>>,
     additional: Vec<ResourceRecord<'a>>,
+    opt: Option<Edns>,
+    /// Encoded EDNS options, the RDATA of the OPT record
+    edns_options: Vec<u8>,
+    rrset_order: RRsetOrder,
+    rrset_ttl: RRsetTtl,
 }
 
 impl<'a> Builder<'a> {
     /// Builds the builder content into a vector-represented packet
+    ///
+    /// Names are compressed: owner names always, and names inside RDATA
+    /// for the types RFC 3597 section 4 permits (NS, CNAME, PTR, MX, SOA).
     pub fn build(&self) -> Result<Vec<u8>, Vec<u8>> {
+        Ok(self.write_packet().0)
+    }
+
+    /// Builds the packet, returning an error for invalid names
+    ///
+    /// Names are checked again, also in records that came from
+    /// `from_packet`, and `Name::check` failures are returned as
+    /// `Error::InvalidName` instead of panicking.
+    pub fn try_build(&self) -> Result<Vec<u8>, Error> {
+        for question in &self.questions {
+            Name::check(&question.qname)?;
+        }
+        for record in self.records() {
+            check_names(record)?;
+        }
+        Ok(self.write_packet().0)
+    }
+
+    /// Checks that all records of each RRset have the same TTL
+    ///
+    /// An RRset is the records with the same owner name (ignoring case),
+    /// class and type in any section, and RFC 2181 section 5.2 forbids
+    /// different TTLs within one. RRSIG records are left out, as
+    /// signatures over different types legitimately differ. This is
+    /// independent of the `rrset_ttl` setting, call it before building
+    /// to refuse such packets.
+    pub fn check_rrset_ttls(&self) -> Result<(), Error> {
+        let mut seen: Vec<(RRsetKey, u32)> = Vec::new();
+        for record in self.records() {
+            let key = match rrset_key(record) {
+                Some(key) => key,
+                None => continue,
+            };
+            match seen.iter().find(|s| s.0 == key) {
+                Some(&(_, ttl)) if ttl != record.ttl => {
+                    return Err(Error::InconsistentTtl(key.0, key.2));
+                }
+                Some(_) => {}
+                None => seen.push((key, record.ttl)),
+            }
+        }
+        Ok(())
+    }
+
+    fn records(&self) -> impl Iterator<Item = &ResourceRecord<'a>> {
+        self.answers.iter()
+            .chain(self.nameservers.iter())
+            .chain(self.additional.iter())
+    }
+
+    /// The lowest TTL of each RRset, for `RRsetTtl::Clamp`
+    fn min_rrset_ttls(&self) -> Vec<(RRsetKey, u32)> {
+        let mut ttls: Vec<(RRsetKey, u32)> = Vec::new();
+        for record in self.records() {
+            let key = match rrset_key(record) {
+                Some(key) => key,
+                None => continue,
+            };
+            match ttls.iter_mut().find(|t| t.0 == key) {
+                Some(entry) => entry.1 = entry.1.min(record.ttl),
+                None => ttls.push((key, record.ttl)),
+            }
+        }
+        ttls
+    }
+
+    /// Builds the packet and returns the names it may point to as well
+    ///
+    /// These are the lowercase name suffixes written out in full, with
+    /// their offsets in the packet, sorted by offset. Meant for tools that
+    /// show how the packet is laid out.
+    pub fn build_with_compression_map(&self)
+        -> Result<(Vec<u8>, CompressionMap), Vec<u8>>
+    {
+        let (buf, names) = self.write_packet();
+        Ok((buf, names.entries()))
+    }
+
+    fn write_packet(&self) -> (Vec<u8>, Compressor) {
         let mut buf = Vec::with_capacity(512);
         buf.extend([0u8; 12].iter());
-        self.head.write(&mut buf[..12]);
+        let mut head = self.head;
+        if self.opt.is_some() {
+            head.additional += 1;
+        }
+        head.write(&mut buf[..12]);
+        let mut names = Compressor::new();
 
         for question in &self.questions {
-            Builder::write_name(&mut buf, question.qname);
+            names.write_name(&mut buf, &question.qname);
             buf.write_u16::<BigEndian>(question.qtype as u16).unwrap();
             let prefer_unicast: u16 = if question.prefer_unicast { 0x8000 } else { 0x0000 };
             buf.write_u16::<BigEndian>(question.qclass as u16 | prefer_unicast).unwrap();
         }
 
-        self.answers.iter().for_each(|record| Builder::append_resource_record(&mut buf, record));
-        self.nameservers.iter().for_each(|record| Builder::append_resource_record(&mut buf, record));
-        self.additional.iter().for_each(|record| Builder::append_resource_record(&mut buf, record));
+        let mut rng = match self.rrset_order {
+            RRsetOrder::Shuffle(seed) => seed,
+            _ => 0,
+        };
+        let mut records = Vec::with_capacity(self.answers.len()
+            + self.nameservers.len() + self.additional.len());
+        for section in &[&self.answers, &self.nameservers, &self.additional] {
+            let start = records.len();
+            records.extend(section.iter());
+            self.reorder_rrsets(&mut records[start..], &mut rng);
+        }
+        let min_ttls = match self.rrset_ttl {
+            RRsetTtl::Clamp => self.min_rrset_ttls(),
+            _ => Vec::new(),
+        };
+        for record in records {
+            let ttl = rrset_key(record)
+                .and_then(|key| min_ttls.iter().find(|t| t.0 == key))
+                .map_or(record.ttl, |t| t.1);
+            Builder::append_resource_record(&mut buf, &mut names, record,
+                ttl);
+        }
+        if let Some(opt) = self.opt {
+            buf.push(0); // root name
+            buf.write_u16::<BigEndian>(Type::OPT as u16).unwrap();
+            buf.write_u16::<BigEndian>(opt.udp).unwrap();
+            buf.write_u8(opt.extrcode).unwrap();
+            buf.write_u8(opt.version).unwrap();
+            buf.write_u16::<BigEndian>(opt.flags).unwrap();
+            buf.write_u16::<BigEndian>(self.edns_options.len() as u16)
+                .unwrap();
+            buf.extend_from_slice(&self.edns_options);
+        }
 
-        return Ok(buf)
+        (buf, names)
     }
 
-    fn append_resource_record(buf: &mut Vec<u8>, record: &ResourceRecord) {
-        Builder::write_name(buf, &record.name.to_string());
+    /// Estimates the size of the packet `build` would produce
+    ///
+    /// Owner names are measured with compression as `build` does it, but
+    /// names inside RDATA are counted uncompressed, so the estimate may
+    /// be a bit larger than the packet and is never smaller.
+    pub fn estimate_size(&self) -> usize {
+        let mut names = Compressor::new();
+        let mut len = 12;
+        for question in &self.questions {
+            len += names.measure_name(len, &question.qname) + 4;
+        }
+        let records = self.answers.iter()
+            .chain(self.nameservers.iter())
+            .chain(self.additional.iter());
+        for record in records {
+            len += names.measure_name(len, &record.name.str_val) + 10;
+            len += record.data.rdata_length() as usize;
+        }
+        if self.opt.is_some() {
+            len += 11 + self.edns_options.len();
+        }
+        len
+    }
+
+    /// Builds the packet prefixed with its two-byte length for use on TCP
+    ///
+    /// See RFC 1035 section 4.2.2. Packets larger than 65535 bytes can't
+    /// be framed and are returned as an error.
+    pub fn build_tcp(&self) -> Result<Vec<u8>, Vec<u8>> {
+        let packet = self.build()?;
+        if packet.len() > 0xFFFF {
+            return Err(packet);
+        }
+        let mut buf = Vec::with_capacity(packet.len() + 2);
+        buf.write_u16::<BigEndian>(packet.len() as u16).unwrap();
+        buf.extend(packet);
+        Ok(buf)
+    }
+
+    fn append_resource_record(buf: &mut Vec<u8>, names: &mut Compressor,
+        record: &ResourceRecord, ttl: u32)
+    {
+        names.write_name(buf, &record.name.to_string());
 
         let data = &record.data;
-        let type_code = data.type_code();
+        buf.write_u16::<BigEndian>(data.raw_type_code()).unwrap();
+        let cache_flush: u16 = if record.multicast_unique { 0x8000 } else { 0x0000 };
+        buf.write_u16::<BigEndian>(u16::from(record.cls) | cache_flush).unwrap();
+        buf.write_u32::<BigEndian>(ttl).unwrap();
+        let rdlen_pos = buf.len();
+        buf.write_u16::<BigEndian>(0).unwrap();
+        record.data.write_compressed(buf, names);
+        let rdlen = buf.len() - rdlen_pos - 2;
+        BigEndian::write_u16(&mut buf[rdlen_pos..], rdlen as u16);
+    }
 
-        buf.write_u16::<BigEndian>(type_code as u16).unwrap();
-        buf.write_u16::<BigEndian>(record.cls as u16).unwrap();
-        buf.write_u32::<BigEndian>(record.ttl).unwrap();
-        buf.write_u16::<BigEndian>(record.data.rdata_length()).unwrap();
-        buf.extend(record.data.to_bytes().iter());
+    /// Reorders runs of adjacent records of the same RRset
+    fn reorder_rrsets(&self, records: &mut [&ResourceRecord], rng: &mut u64) {
+        let mut start = 0;
+        while start < records.len() {
+            let first = records[start];
+            let len = records[start..].iter()
+                .take_while(|r| r.cls == first.cls
+                    && r.data.raw_type_code() == first.data.raw_type_code()
+                    && r.name.eq_ignore_case(&first.name.str_val))
+                .count();
+            let set = &mut records[start..start+len];
+            match self.rrset_order {
+                RRsetOrder::Keep => {}
+                RRsetOrder::Rotate(n) => set.rotate_left(n % len),
+                RRsetOrder::Shuffle(_) => {
+                    for i in (1..len).rev() {
+                        let j = (splitmix64(rng) % (i as u64 + 1)) as usize;
+                        set.swap(i, j);
+                    }
+                }
+            }
+            start += len;
+        }
     }
 
     /// Creates a new query
@@ -67,7 +312,7 @@ impl<'a> Builder<'a> {
     /// the questions section with `add_question`
     pub fn new(id: u16, recursion: bool) -> Builder<'a> {
         let head = Header {
-            id: id,
+            id,
             query: true,
             opcode: Opcode::StandardQuery,
             authoritative: false,
@@ -76,6 +321,7 @@ impl<'a> Builder<'a> {
             recursion_available: false,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: ResponseCode::NoError,
             questions: 0,
             answers: 0,
@@ -88,12 +334,320 @@ impl<'a> Builder<'a> {
             questions: Vec::new(),
             nameservers: Vec::new(),
             additional: Vec::new(),
+            opt: None,
+            edns_options: Vec::new(),
+            rrset_order: RRsetOrder::Keep,
+            rrset_ttl: RRsetTtl::Ignore,
+        }
+    }
+
+    /// Creates a response skeleton for the query
+    ///
+    /// Copies the ID, opcode, RD and CD flags and the questions. Answers
+    /// and EDNS are left for the caller to add.
+    pub fn response_to(query: &Packet) -> Builder<'a> {
+        let mut bld = Builder::new(query.header.id,
+                                   query.header.recursion_desired);
+        bld.head.query = false;
+        bld.head.opcode = query.header.opcode;
+        bld.head.checking_disabled = query.header.checking_disabled;
+        for question in &query.questions {
+            bld.push_question(Cow::Owned(question.qname.to_string()),
+                question.prefer_unicast, question.qtype, question.qclass);
+        }
+        bld
+    }
+
+    /// Creates an unsolicited multicast DNS response
+    ///
+    /// The ID is zero and the authoritative bit is set, as RFC 6762
+    /// section 18 requires of responses sent to the multicast group.
+    /// Used for announcements and goodbyes, see `goodbye`.
+    pub fn mdns_response() -> Builder<'a> {
+        let mut bld = Builder::new(0, false);
+        bld.head.query = false;
+        bld.head.authoritative = true;
+        bld
+    }
+
+    /// Creates an mDNS probe for the records a host wants to claim
+    ///
+    /// As RFC 6762 section 8.1 describes, there is an ANY question for
+    /// each owner name, with the QU bit set, and the proposed records go
+    /// to the authority section so that simultaneous probes can be told
+    /// apart. The cache-flush bit of the records is cleared, it has no
+    /// meaning in queries. Send three probes `mdns::PROBE_INTERVAL`
+    /// apart, then announce with `mdns_announcement`. This panics on
+    /// invalid names like `answer`.
+    pub fn mdns_probe<I>(records: I) -> Builder<'a>
+        where I: IntoIterator<Item = ResourceRecord<'a>>
+    {
+        let mut bld = Builder::new(0, false);
+        for mut record in records {
+            check_names(&record).expect("invalid domain name");
+            let asked = bld.questions.iter()
+                .any(|q| record.name.eq_ignore_case(&q.qname));
+            if !asked {
+                bld.push_question(Cow::Owned(record.name.to_string()), true,
+                    QueryType::All, QueryClass::IN);
+            }
+            record.multicast_unique = false;
+            bld.nameservers.push(record);
         }
+        if bld.nameservers.len() > 65535 {
+            panic!("Too many nameservers");
+        }
+        bld.head.nameservers = bld.nameservers.len() as u16;
+        bld
+    }
+
+    /// Creates an mDNS announcement of the records (RFC 6762 section 8.3)
+    ///
+    /// An unsolicited response, as with `mdns_response`, with all the
+    /// records in the answer section. Set `multicast_unique` on the
+    /// records the host has probed for, so that caches flush stale data.
+    /// Send it at least twice, `mdns::ANNOUNCE_INTERVAL` apart. This
+    /// panics on invalid names like `answer`.
+    pub fn mdns_announcement<I>(records: I) -> Builder<'a>
+        where I: IntoIterator<Item = ResourceRecord<'a>>
+    {
+        let mut bld = Builder::mdns_response();
+        for record in records {
+            check_names(&record).expect("invalid domain name");
+            bld.answers.push(record);
+        }
+        if bld.answers.len() > 65535 {
+            panic!("Too many answers");
+        }
+        bld.head.answers = bld.answers.len() as u16;
+        bld
+    }
+
+    /// Creates a builder that re-serializes the packet, e.g. to forward it
+    ///
+    /// The header, questions and records are kept as they are. The OPT
+    /// record is copied with `edns_from`, so options the crate doesn't
+    /// know are passed on too.
+    pub fn from_packet(packet: Packet<'a>) -> Builder<'a> {
+        let mut bld = Builder::new(packet.header.id, false);
+        bld.head = packet.header;
+        bld.head.questions = 0;
+        for question in &packet.questions {
+            bld.push_question(Cow::Owned(question.qname.to_string()),
+                question.prefer_unicast, question.qtype, question.qclass);
+        }
+        bld.head.answers = packet.answers.len() as u16;
+        bld.head.nameservers = packet.nameservers.len() as u16;
+        bld.head.additional = packet.additional.len() as u16;
+        bld.answers = packet.answers;
+        bld.nameservers = packet.nameservers;
+        bld.additional = packet.additional;
+        if let Some(ref opt) = packet.opt {
+            bld.edns_from(opt);
+        }
+        bld
+    }
+
+    /// Creates a query for a CHAOS class TXT record such as `id.server`
+    ///
+    /// Servers answer these with information about themselves, see
+    /// `version_bind` and `hostname_bind`. Recursion isn't desired, the
+    /// information is about the server queried.
+    pub fn chaos_txt(id: u16, qname: &'a str) -> Builder<'a> {
+        let mut bld = Builder::new(id, false);
+        bld.question(qname, false, QueryType::TXT, QueryClass::CH);
+        bld
+    }
+
+    /// Creates a `version.bind CH TXT` query asking for the server version
+    pub fn version_bind(id: u16) -> Builder<'a> {
+        Builder::chaos_txt(id, "version.bind")
+    }
+
+    /// Creates a `hostname.bind CH TXT` query asking for the server's name
+    ///
+    /// Useful to tell apart the instances behind an anycast address.
+    pub fn hostname_bind(id: u16) -> Builder<'a> {
+        Builder::chaos_txt(id, "hostname.bind")
+    }
+
+    /// Creates an AXFR query for the zone (RFC 5936)
+    pub fn axfr_query(id: u16, zone: &'a str) -> Builder<'a> {
+        let mut bld = Builder::new(id, false);
+        bld.question(zone, false, QueryType::AXFR, QueryClass::IN);
+        bld
+    }
+
+    /// Creates an IXFR query for the changes since `current_serial`
+    ///
+    /// As RFC 1995 section 3 requires, the SOA record of the version the
+    /// client has is sent in the authority section. Only its serial is
+    /// looked at, the other fields are filled with the zone name and
+    /// zeros.
+    pub fn ixfr_query(id: u16, zone: &'a str, current_serial: u32)
+        -> Builder<'a>
+    {
+        let mut bld = Builder::new(id, false);
+        bld.question(zone, false, QueryType::IXFR, QueryClass::IN);
+        let soa = Soa::new(Name::from_string(zone), Name::from_string(zone),
+            current_serial, 0, 0, 0, 0);
+        bld.nameserver(zone, Class::IN, RData::SOA(soa), false, 0);
+        bld
+    }
+
+    /// Creates a DNS-SD query for the service types in `domain`
+    ///
+    /// Asks for the PTR records of `_services._dns-sd._udp.<domain>`
+    /// (RFC 6763 section 9), parse the response with
+    /// `dnssd::service_types`. Recursion is desired, mDNS responders
+    /// ignore the bit. This panics if the resulting name isn't valid, as
+    /// `question` does.
+    pub fn dnssd_services_query(id: u16, domain: &str) -> Builder<'a> {
+        let mut bld = Builder::new(id, true);
+        let qname = dnssd::services_name(domain);
+        Name::check(&qname).expect("invalid domain name");
+        bld.push_question(Cow::Owned(qname), false, QueryType::PTR,
+            QueryClass::IN);
+        bld
+    }
+
+    /// Sets the RCODE of the header
+    ///
+    /// Extended response codes (above 15) need the upper bits in the
+    /// OPT record, see `edns`.
+    pub fn response_code(&mut self, code: ResponseCode) -> &Builder<'a> {
+        self.head.response_code = code;
+        self
+    }
+
+    /// Adds an OPT pseudo-RR with the given fields
+    ///
+    /// The record is always written last in the additional section.
+    /// Options are added with `edns_option`.
+    pub fn edns(&mut self, udp: u16, extrcode: u8, version: u8, flags: u16)
+        -> &Builder<'a>
+    {
+        self.opt = Some(Edns { udp, extrcode, version, flags });
+        self
+    }
+
+    /// Sets the order of the records within each RRset in the packet
+    ///
+    /// Only records of an RRset that are next to each other are
+    /// reordered, use `answers` to group them. The default is to keep
+    /// the order the records were added in.
+    pub fn rrset_order(&mut self, order: RRsetOrder) -> &Builder<'a> {
+        self.rrset_order = order;
+        self
+    }
+
+    /// Sets how records of an RRset with different TTLs are handled
+    ///
+    /// RFC 2181 section 5.2 requires the same TTL for all records of an
+    /// RRset. The default is `RRsetTtl::Ignore`, writing the TTLs as
+    /// given. `answers` always clamps the records it adds. To refuse such
+    /// packets instead, see `check_rrset_ttls`.
+    pub fn rrset_ttl(&mut self, policy: RRsetTtl) -> &Builder<'a> {
+        self.rrset_ttl = policy;
+        self
+    }
+
+    /// Adds an OPT pseudo-RR copied from a parsed packet
+    ///
+    /// All the options are copied unchanged, including those with codes
+    /// the crate doesn't understand, and replace any added before.
+    pub fn edns_from(&mut self, opt: &Opt) -> &Builder<'a> {
+        self.edns(opt.udp, opt.extrcode, opt.version, opt.flags);
+        self.edns_options.clear();
+        if let RData::Unknown(_, data) = opt.data {
+            self.edns_options.extend_from_slice(data);
+        }
+        self
+    }
+
+    /// Appends an option to the OPT pseudo-RR
+    ///
+    /// Options are written in the order they're added, and only if the
+    /// packet has the OPT record, see `edns`.
+    ///
+    /// # Panics
+    ///
+    /// When the options no longer fit the 65535 bytes of RDATA.
+    pub fn edns_option(&mut self, code: u16, data: &[u8]) -> &Builder<'a> {
+        assert!(self.edns_options.len() + 4 + data.len() <= 65535,
+            "EDNS options too long");
+        self.edns_options.write_u16::<BigEndian>(code).unwrap();
+        self.edns_options.write_u16::<BigEndian>(data.len() as u16).unwrap();
+        self.edns_options.extend_from_slice(data);
+        self
+    }
+
+    /// Appends an option defined by the application to the OPT pseudo-RR
+    ///
+    /// Takes any type implementing `edns::EdnsOption`, or an option
+    /// parsed by an `edns::OptionRegistry`.
+    pub fn edns_custom_option(&mut self, option: &dyn CustomOption)
+        -> &Builder<'a>
+    {
+        self.edns_option(option.code(), &option.option_data())
+    }
+
+    /// Creates a new query with an unpredictable ID
+    ///
+    /// Returns the ID too, to match it against the response.
+    #[cfg(feature = "rand")]
+    pub fn new_random_id(recursion: bool) -> (Builder<'a>, Id) {
+        let id = Id::random();
+        (Builder::new(id.0, recursion), id)
     }
 
     /// question adds a new DNS question to this packet
+    ///
+    /// With the `idna` feature Unicode names are converted to A-labels
+    /// (punycode). The root is written as `"."` or `""`, a trailing dot
+    /// is optional. This panics if the name isn't valid (see
+    /// `Name::check`) or isn't a valid IDN, use `try_question` to get an
+    /// error instead.
     pub fn question(&mut self, qname: &'a str, prefer_unicast: bool,
-        qtype: QueryType, qclass: QueryClass) -> &Builder {
+        qtype: QueryType, qclass: QueryClass) -> &Builder<'a> {
+        let qname = Builder::ascii_name(qname)
+            .expect("invalid domain name");
+        self.push_question(qname, prefer_unicast, qtype, qclass)
+    }
+
+    /// Adds a question, returning an error for invalid names
+    ///
+    /// Returns `Error::InvalidName` if `Name::check` rejects the name and
+    /// `Error::InvalidIdn` if the conversion of a Unicode name to A-labels
+    /// fails.
+    pub fn try_question(&mut self, qname: &'a str, prefer_unicast: bool,
+        qtype: QueryType, qclass: QueryClass)
+        -> Result<&Builder<'a>, Error>
+    {
+        let qname = Builder::ascii_name(qname)?;
+        Ok(self.push_question(qname, prefer_unicast, qtype, qclass))
+    }
+
+    #[cfg(feature = "idna")]
+    fn ascii_name(name: &str) -> Result<Cow<'_, str>, Error> {
+        if name.is_ascii() {
+            Name::check(name)?;
+            return Ok(Cow::Borrowed(name));
+        }
+        let name = idna::domain_to_ascii(name)
+            .map_err(|_| Error::InvalidIdn)?;
+        Name::check(&name)?;
+        Ok(Cow::Owned(name))
+    }
+
+    #[cfg(not(feature = "idna"))]
+    fn ascii_name(name: &str) -> Result<Cow<'_, str>, Error> {
+        Name::check(name)?;
+        Ok(Cow::Borrowed(name))
+    }
+
+    fn push_question(&mut self, qname: Cow<'a, str>, prefer_unicast: bool,
+        qtype: QueryType, qclass: QueryClass) -> &Builder<'a> {
         if self.head.questions == 65535 {
             panic!("Too many questions");
         }
@@ -111,8 +665,21 @@ impl<'a> Builder<'a> {
     }
 
     /// Appends an answer to the packet
+    ///
+    /// This panics if the owner name or a name in the RDATA isn't valid
+    /// (see `Name::check`), use `try_answer` to get an error instead.
     pub fn answer(&mut self, qname: &'a str, cls: Class, data: RData<'a>, 
-        multicast_unique: bool, ttl: u32) -> &Builder {
+        multicast_unique: bool, ttl: u32) -> &Builder<'a> {
+        self.try_answer(qname, cls, data, multicast_unique, ttl)
+            .expect("invalid domain name")
+    }
+
+    /// Appends an answer, returning `Error::InvalidName` for invalid
+    /// owner or RDATA names
+    pub fn try_answer(&mut self, qname: &'a str, cls: Class,
+        data: RData<'a>, multicast_unique: bool, ttl: u32)
+        -> Result<&Builder<'a>, Error>
+    {
         let answer = ResourceRecord {
             name: Name::from_string(qname),
             cls,
@@ -120,15 +687,75 @@ impl<'a> Builder<'a> {
             multicast_unique,
             ttl
         };
+        check_names(&answer)?;
         self.answers.push(answer);
         self.head.answers += 1;
 
+        Ok(self)
+    }
+
+    /// Adds answers, arranged into proper RRsets
+    ///
+    /// Together with the answers added before, records with the same
+    /// owner name (ignoring case), class and type form an RRset. Members
+    /// of an RRset are kept next to each other, in the order the sets
+    /// were first seen. Exact duplicates are dropped, and all members
+    /// get the lowest TTL of the set, as RFC 2181 section 5 requires.
+    /// RRSIG records are kept as they are, each where it was first seen.
+    /// This panics on invalid names like `answer`.
+    pub fn answers<I>(&mut self, records: I) -> &Builder<'a>
+        where I: IntoIterator<Item = ResourceRecord<'a>>
+    {
+        let mut all = mem::take(&mut self.answers);
+        for record in records {
+            check_names(&record).expect("invalid domain name");
+            all.push(record);
+        }
+        self.answers = rrsets(all);
+        if self.answers.len() > 65535 {
+            panic!("Too many answers");
+        }
+        self.head.answers = self.answers.len() as u16;
         self
     }
 
+    /// Appends a known answer to a multicast DNS query
+    ///
+    /// Per RFC 6762 section 7.1 a querier lists the records it already
+    /// knows in the answer section of the query, so that responders can
+    /// suppress them. The `ttl` should be the remaining TTL of the cached
+    /// record.
+    pub fn known_answer(&mut self, qname: &'a str, cls: Class,
+        data: RData<'a>, ttl: u32) -> &Builder<'a> {
+        self.answer(qname, cls, data, false, ttl)
+    }
+
+    /// Appends an mDNS goodbye for the record, an answer with TTL 0
+    ///
+    /// Per RFC 6762 section 10.1 a responder announces that a record is
+    /// going away by multicasting it with a TTL of zero. Receivers see
+    /// such records with `ResourceRecord::is_goodbye`.
+    pub fn goodbye(&mut self, qname: &'a str, cls: Class, data: RData<'a>,
+        multicast_unique: bool) -> &Builder<'a> {
+        self.answer(qname, cls, data, multicast_unique, 0)
+    }
+
     /// Appends a nameserver record to the packet
+    ///
+    /// This panics on invalid names like `answer`, use `try_nameserver`
+    /// to get an error instead.
     pub fn nameserver(&mut self, qname: &'a str, cls: Class, data: RData<'a>, 
-        multicast_unique: bool, ttl: u32) -> &Builder {
+        multicast_unique: bool, ttl: u32) -> &Builder<'a> {
+        self.try_nameserver(qname, cls, data, multicast_unique, ttl)
+            .expect("invalid domain name")
+    }
+
+    /// Appends a nameserver record, returning `Error::InvalidName` for
+    /// invalid owner or RDATA names
+    pub fn try_nameserver(&mut self, qname: &'a str, cls: Class,
+        data: RData<'a>, multicast_unique: bool, ttl: u32)
+        -> Result<&Builder<'a>, Error>
+    {
         let ns = ResourceRecord {
             name: Name::from_string(qname),
             cls,
@@ -136,15 +763,29 @@ impl<'a> Builder<'a> {
             multicast_unique,
             ttl
         };
+        check_names(&ns)?;
         self.nameservers.push(ns);
         self.head.nameservers += 1;
 
-        self
+        Ok(self)
     }
 
     /// Appends additional data to the packet
+    ///
+    /// This panics on invalid names like `answer`, use `try_additional`
+    /// to get an error instead.
     pub fn additional(&mut self, qname: &'a str, cls: Class, data: RData<'a>, 
-        multicast_unique: bool, ttl: u32) -> &Builder {
+        multicast_unique: bool, ttl: u32) -> &Builder<'a> {
+        self.try_additional(qname, cls, data, multicast_unique, ttl)
+            .expect("invalid domain name")
+    }
+
+    /// Appends additional data, returning `Error::InvalidName` for
+    /// invalid owner or RDATA names
+    pub fn try_additional(&mut self, qname: &'a str, cls: Class,
+        data: RData<'a>, multicast_unique: bool, ttl: u32)
+        -> Result<&Builder<'a>, Error>
+    {
         let record = ResourceRecord {
             name: Name::from_string(qname),
             cls,
@@ -152,28 +793,99 @@ impl<'a> Builder<'a> {
             multicast_unique,
             ttl
         };
+        check_names(&record)?;
         self.additional.push(record);
         self.head.additional += 1;
 
-        self
+        Ok(self)
     }
+}
 
-    fn write_name(buf: &mut Vec<u8>, name: &str) {
-        for part in name.split('.') {
-            assert!(part.len() < 63);
-            let ln = part.len() as u8;
-            buf.push(ln);
-            buf.extend(part.as_bytes());
+/// The SplitMix64 generator, enough to shuffle records
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Checks the owner name and the names in the RDATA with `Name::check`
+fn check_names(record: &ResourceRecord) -> Result<(), Error> {
+    Name::check(&record.name.str_val)?;
+    for name in record.data.names() {
+        Name::check(&name.str_val)?;
+    }
+    Ok(())
+}
+
+/// Lowercase owner name, class and type of an RRset
+type RRsetKey = (String, u16, u16);
+
+/// The RRset a record belongs to, `None` for RRSIG records
+fn rrset_key(record: &ResourceRecord) -> Option<RRsetKey> {
+    let typ = record.data.raw_type_code();
+    if typ == Type::RRSIG as u16 {
+        return None;
+    }
+    let name = record.name.str_val.trim_end_matches('.').to_ascii_lowercase();
+    Some((name, u16::from(record.cls), typ))
+}
+
+/// Members of an RRset with their RDATA, see `rrsets`
+///
+/// The key is `None` for an RRSIG record, which stays on its own.
+struct RRset<'a> {
+    key: Option<RRsetKey>,
+    members: Vec<(ResourceRecord<'a>, Vec<u8>)>,
+}
+
+/// Groups records into RRsets, see `Builder::answers`
+fn rrsets(records: Vec<ResourceRecord>) -> Vec<ResourceRecord> {
+    let mut sets: Vec<RRset> = Vec::new();
+    for record in records {
+        let key = rrset_key(&record);
+        let rdata = record.data.to_bytes();
+        let set = match key {
+            Some(_) => sets.iter_mut().find(|s| s.key == key),
+            None => None,
+        };
+        match set {
+            Some(set) => {
+                if set.members.iter().all(|m| m.1 != rdata) {
+                    set.members.push((record, rdata));
+                }
+            }
+            None => sets.push(RRset {
+                key,
+                members: vec![(record, rdata)],
+            }),
         }
-        buf.push(0);
     }
+    let mut result = Vec::new();
+    for set in sets {
+        let ttl = set.members.iter().map(|m| m.0.ttl).min().unwrap_or(0);
+        for (mut record, _) in set.members {
+            record.ttl = ttl;
+            result.push(record);
+        }
+    }
+    result
 }
 
 #[cfg(test)]
 mod test {
+    use std::borrow::Cow;
+    use std::net::Ipv4Addr;
+
     use QueryType as QT;
     use QueryClass as QC;
-    use super::Builder;
+    use Class as C;
+    use {Name, Packet, RData, ResourceRecord, Type};
+    use Error;
+    use rdata::{Cname, Mx, Ptr, Rrsig, Srv};
+    use dnssec::Algorithm;
+    use super::{Builder, RRsetOrder, RRsetTtl};
 
     #[test]
     fn build_query() {
@@ -184,6 +896,31 @@ mod test {
         assert_eq!(&bld.build().unwrap()[..], &result[..]);
     }
 
+    #[test]
+    fn root_and_trailing_dot() {
+        let mut bld = Builder::new(1, false);
+        bld.question(".", false, QT::NS, QC::IN);
+        bld.question("example.com.", false, QT::A, QC::IN);
+        bld.answer("example.com.", C::IN,
+            RData::CNAME(Cname(Name::from_string("."))), false, 60);
+        let data = bld.build().unwrap();
+        assert_eq!(&data[12..], &b"\x00\x00\x02\x00\x01\
+            \x07example\x03com\x00\x00\x01\x00\x01\
+            \xc0\x11\x00\x05\x00\x01\x00\x00\x00\x3c\x00\x01\x00"[..]);
+
+        assert_eq!(bld.try_question("a..example", false, QT::A, QC::IN)
+            .err(), Some(Error::InvalidName("empty label")));
+    }
+
+    #[test]
+    fn build_tcp_query() {
+        let mut bld = Builder::new(1573, true);
+        bld.question("example.com", false, QT::A, QC::IN);
+        let result = b"\x00\x1d\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                      \x07example\x03com\x00\x00\x01\x00\x01";
+        assert_eq!(&bld.build_tcp().unwrap()[..], &result[..]);
+    }
+
     #[test]
     fn build_unicast_query() {
         let mut bld = Builder::new(1573, true);
@@ -193,6 +930,41 @@ mod test {
         assert_eq!(&bld.build().unwrap()[..], &result[..]);
     }
 
+    #[test]
+    fn build_query_with_known_answers() {
+        let mut bld = Builder::new(0, false);
+        bld.question("_http._tcp.local", false, QT::PTR, QC::IN);
+        bld.known_answer("_http._tcp.local", C::IN,
+            RData::PTR(Ptr::new(Name::from_string("web._http._tcp.local"))),
+            4500);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert!(packet.header.query);
+        assert_eq!(packet.header.answers, 1);
+        assert_eq!(&packet.answers[0].name.to_string()[..],
+            "_http._tcp.local");
+        assert_eq!(packet.answers[0].ttl, 4500);
+        assert!(!packet.answers[0].multicast_unique);
+        match packet.answers[0].data {
+            RData::PTR(ref ptr) => {
+                assert_eq!(&ptr.0.to_string()[..], "web._http._tcp.local");
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn build_cache_flush_answer() {
+        let mut bld = Builder::new(0, false);
+        bld.answer("host.local", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()),
+            true, 120);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert!(packet.answers[0].multicast_unique);
+        assert_eq!(packet.answers[0].cls, C::IN);
+    }
+
     #[test]
     fn build_srv_query() {
         let mut bld = Builder::new(23513, true);
@@ -201,4 +973,304 @@ mod test {
             \x0c_xmpp-server\x04_tcp\x05gmail\x03com\x00\x00!\x00\x01";
         assert_eq!(&bld.build().unwrap()[..], &result[..]);
     }
+
+    #[test]
+    fn compress_rdata_names() {
+        let mut bld = Builder::new(1, false);
+        bld.question("www.example.com", false, QT::A, QC::IN);
+        bld.answer("www.example.com", C::IN, RData::CNAME(Cname::new(
+            Name::from_string("web.example.com"))), false, 60);
+        bld.answer("example.com", C::IN, RData::MX(Mx::new(10,
+            Name::from_string("web.example.com"))), false, 60);
+        let data = bld.build().unwrap();
+        // question name in full, everything else compressed
+        assert_eq!(data.len(), 12 + 17 + 4 + (2 + 10 + 6) + (2 + 10 + 4));
+
+        let packet = Packet::parse(&data).unwrap();
+        match packet.answers[0].data {
+            RData::CNAME(ref cname) => {
+                assert_eq!(&cname.0.to_string()[..], "web.example.com");
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        match packet.answers[1].data {
+            RData::MX(ref mx) => {
+                assert_eq!(mx.preference, 10);
+                assert_eq!(&mx.exchange.to_string()[..], "web.example.com");
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn no_compression_in_srv_target() {
+        assert!(Type::MX.allows_rdata_compression());
+        assert!(!Type::SRV.allows_rdata_compression());
+        assert!(!Type::NSEC.allows_rdata_compression());
+
+        let mut bld = Builder::new(1, false);
+        bld.answer("host.example.com", C::IN, RData::CNAME(Cname::new(
+            Name::from_string("www.example.com"))), false, 60);
+        bld.answer("_sip._tcp.example.com", C::IN, RData::SRV(Srv::new(
+            10, 60, 5060, Name::from_string("host.example.com"))), false, 60);
+        let data = bld.build().unwrap();
+        let target = b"\x04host\x07example\x03com\x00";
+        assert!(data.ends_with(target));
+
+        let packet = Packet::parse(&data).unwrap();
+        match packet.answers[1].data {
+            RData::SRV(ref srv) => {
+                assert_eq!(&srv.target.to_string()[..], "host.example.com");
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn forward_unknown_edns_options() {
+        let mut bld = Builder::new(7, true);
+        bld.question("example.com", false, QT::A, QC::IN);
+        bld.answer("example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.edns(1232, 0, 0, 0x8000);
+        bld.edns_option(65001, b"private");
+        bld.edns_option(12, b"\x00\x00");
+        let original = bld.build().unwrap();
+
+        let packet = Packet::parse(&original).unwrap();
+        let forwarded = Builder::from_packet(packet).build().unwrap();
+        assert_eq!(forwarded, original);
+    }
+
+    #[test]
+    fn zone_transfer_queries() {
+        let data = Builder::axfr_query(1, "example.com").build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.questions[0].qtype, QT::AXFR);
+        assert!(!packet.header.recursion_desired);
+        assert!(packet.nameservers.is_empty());
+
+        let data = Builder::ixfr_query(2, "example.com", 2024010101)
+            .build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.questions[0].qtype, QT::IXFR);
+        assert_eq!(packet.header.nameservers, 1);
+        let soa = &packet.nameservers[0];
+        assert_eq!(&soa.name.to_string()[..], "example.com");
+        match soa.data {
+            RData::SOA(ref soa) => assert_eq!(soa.serial, 2024010101),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn answer_rrsets() {
+        let record = |name, ip: [u8; 4], ttl| ResourceRecord {
+            name: Name::from_string(name),
+            multicast_unique: false,
+            cls: C::IN,
+            ttl,
+            data: RData::A(Ipv4Addr::from(ip).into()),
+        };
+        let mut bld = Builder::new(1, false);
+        bld.answer("a.example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 300);
+        let rrsig = |ttl, key_tag| ResourceRecord {
+            name: Name::from_string("a.example.com"),
+            multicast_unique: false,
+            cls: C::IN,
+            ttl,
+            data: RData::RRSIG(Rrsig {
+                type_covered: Type::A as u16,
+                algorithm: Algorithm::Ed25519,
+                labels: 3,
+                original_ttl: 300,
+                expiration: 2,
+                inception: 1,
+                key_tag,
+                signer: Name::from_string("example.com"),
+                signature: Cow::Borrowed(b"sig"),
+            }),
+        };
+        bld.answers(vec![
+            rrsig(300, 1),
+            record("b.example.com", [192, 0, 2, 2], 60),
+            record("A.example.com.", [192, 0, 2, 3], 120),
+            rrsig(30, 2),
+            record("a.example.com", [192, 0, 2, 1], 300),
+            record("b.example.com", [192, 0, 2, 2], 60),
+        ]);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        let answers: Vec<_> = packet.answers.iter()
+            .map(|r| (r.name.to_string(), r.ttl, r.data.type_code()))
+            .collect();
+        // Compression reuses the first spelling of the owner
+        assert_eq!(answers, [
+            ("a.example.com".to_string(), 120, Type::A),
+            ("a.example.com".to_string(), 120, Type::A),
+            ("a.example.com".to_string(), 300, Type::RRSIG),
+            ("b.example.com".to_string(), 60, Type::A),
+            ("a.example.com".to_string(), 30, Type::RRSIG),
+        ]);
+        assert_eq!(packet.answers[1].data.to_string(), "192.0.2.3");
+    }
+
+    #[test]
+    fn rrset_order() {
+        let mut bld = Builder::new(1, false);
+        for i in 1..5 {
+            bld.answer("example.com", C::IN,
+                RData::A(Ipv4Addr::new(192, 0, 2, i).into()), false, 60);
+        }
+        bld.answer("example.com", C::IN, RData::MX(Mx::new(10,
+            Name::from_string("mail.example.com"))), false, 60);
+        let order = |bld: &Builder| {
+            let data = bld.build().unwrap();
+            let packet = Packet::parse(&data).unwrap();
+            packet.answers.iter().map(|r| r.data.to_string())
+                .collect::<Vec<_>>().join(" ")
+        };
+        let keep = order(&bld);
+        assert_eq!(keep,
+            "192.0.2.1 192.0.2.2 192.0.2.3 192.0.2.4 10 mail.example.com.");
+        bld.rrset_order(RRsetOrder::Rotate(5));
+        assert_eq!(order(&bld),
+            "192.0.2.2 192.0.2.3 192.0.2.4 192.0.2.1 10 mail.example.com.");
+        bld.rrset_order(RRsetOrder::Shuffle(42));
+        let shuffled = order(&bld);
+        assert_eq!(order(&bld), shuffled);
+        assert!(shuffled.ends_with("10 mail.example.com."));
+        let mut sorted: Vec<_> = shuffled.split(' ').collect();
+        sorted.sort();
+        let mut expected: Vec<_> = keep.split(' ').collect();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn rrset_ttl() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 300);
+        bld.answer("www.example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 9).into()), false, 30);
+        bld.additional("EXAMPLE.com.", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 2).into()), false, 60);
+        let ttls = |data: Vec<u8>| {
+            let packet = Packet::parse(&data).unwrap();
+            packet.answers.iter().chain(packet.additional.iter())
+                .map(|r| r.ttl).collect::<Vec<_>>()
+        };
+        assert_eq!(ttls(bld.build().unwrap()), vec![300, 30, 60]);
+        assert_eq!(bld.check_rrset_ttls(),
+            Err(Error::InconsistentTtl("example.com".into(), 1)));
+
+        bld.rrset_ttl(RRsetTtl::Clamp);
+        assert_eq!(ttls(bld.build().unwrap()), vec![60, 30, 60]);
+
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.answer("example.com", C::IN, RData::MX(Mx::new(10,
+            Name::from_string("mail.example.com"))), false, 300);
+        assert_eq!(bld.check_rrset_ttls(), Ok(()));
+    }
+
+    #[test]
+    fn longest_edns_option() {
+        let mut bld = Builder::new(1, false);
+        bld.edns(65535, 0, 0, 0);
+        bld.edns_option(65001, &[0; 65531]);
+        let data = bld.build().unwrap();
+        assert_eq!(data.len(), 12 + 11 + 65535);
+    }
+
+    #[test]
+    #[should_panic(expected = "EDNS options too long")]
+    fn too_long_edns_options() {
+        let mut bld = Builder::new(1, false);
+        bld.edns_option(65001, &[0; 40000]);
+        bld.edns_option(65002, &[0; 40000]);
+    }
+
+    #[test]
+    fn invalid_names() {
+        let a = || RData::A(Ipv4Addr::new(192, 0, 2, 1).into());
+        let cname = |name| RData::CNAME(Cname(Name::from_string(name)));
+        let mut bld = Builder::new(1, false);
+        assert_eq!(bld.try_answer("a..b", C::IN, a(), false, 60).err(),
+            Some(Error::InvalidName("empty label")));
+        assert!(bld.try_nameserver("example..", C::IN, a(), false, 60)
+            .is_err());
+        assert!(bld.try_additional("example.com", C::IN,
+            cname(".example.com"), false, 60).is_err());
+        assert!(bld.try_answer("example.com.", C::IN, cname("."), false, 60)
+            .is_ok());
+        assert_eq!(bld.records().count(), 1);
+        assert!(bld.try_build().is_ok());
+
+        let response = bld.build().unwrap();
+        let mut packet = Packet::parse(&response).unwrap();
+        packet.answers[0].name = Name::from_string("a..b");
+        assert!(Builder::from_packet(packet).try_build().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid domain name")]
+    fn answer_with_invalid_name() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("a..b", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+    }
+
+    #[test]
+    fn estimate_size() {
+        let mut bld = Builder::new(1, false);
+        assert_eq!(bld.estimate_size(), 12);
+        bld.question("example.com", false, QT::MX, QC::IN);
+        bld.answer("example.com", C::IN, RData::MX(Mx::new(10,
+            Name::from_string("mail.example.com"))), false, 60);
+        bld.answer("www.example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.edns(1232, 0, 0, 0);
+        bld.edns_option(10, b"12345678");
+        let size = bld.build().unwrap().len();
+        let estimate = bld.estimate_size();
+        assert!(estimate >= size);
+        // The MX exchange is counted in full, 18 bytes instead of `mail`
+        // and a pointer
+        assert_eq!(estimate - size, 18 - 7);
+    }
+
+    #[test]
+    fn compression_map() {
+        let mut bld = Builder::new(1, false);
+        bld.question("www.Example.com", false, QT::A, QC::IN);
+        bld.answer("mail.example.com", C::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        let (data, map) = bld.build_with_compression_map().unwrap();
+        assert_eq!(data, bld.build().unwrap());
+        assert_eq!(map, [
+            ("www.example.com".to_string(), 12),
+            ("example.com".to_string(), 16),
+            ("com".to_string(), 24),
+            ("mail.example.com".to_string(), 33),
+        ]);
+    }
+
+    #[cfg(feature = "idna")]
+    #[test]
+    fn build_idn_query() {
+        let mut bld = Builder::new(1, true);
+        bld.question("bücher.example", false, QT::A, QC::IN);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(&packet.questions[0].qname.to_string()[..],
+            "xn--bcher-kva.example");
+
+        let mut bld = Builder::new(1, true);
+        assert!(matches!(bld.try_question("\u{0301}x.example", false,
+            QT::A, QC::IN), Err(Error::InvalidIdn)));
+    }
 }
@@ -0,0 +1,244 @@
+use std::ops::Range;
+
+use byteorder::{BigEndian, ByteOrder};
+use bytes::Bytes;
+
+use {Packet, Header, Name, RData, Type, Class, QueryType, QueryClass, Error};
+use parser::OPT_RR_START;
+
+/// A DNS packet that owns its buffer
+///
+/// The packet is parsed once by `Packet::parse_bytes`. Names are decoded
+/// into strings and the RDATA of every record is kept as a `Bytes`
+/// sub-slice of the reference-counted buffer, so the packet can be stored
+/// and sent between tasks without borrowing from the receive buffer and
+/// without copying the record data.
+#[derive(Debug, Clone)]
+#[allow(missing_docs)]  // same fields as in Packet
+pub struct BytesPacket {
+    data: Bytes,
+    pub header: Header,
+    pub questions: Vec<BytesQuestion>,
+    pub answers: Vec<BytesRecord>,
+    pub nameservers: Vec<BytesRecord>,
+    pub additional: Vec<BytesRecord>,
+    pub opt: Option<BytesOpt>,
+}
+
+/// A question of a `BytesPacket`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]  // same fields as in Question
+pub struct BytesQuestion {
+    pub qname: String,
+    pub prefer_unicast: bool,
+    pub qtype: QueryType,
+    pub qclass: QueryClass,
+}
+
+/// A resource record of a `BytesPacket`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytesRecord {
+    /// Name of the record, decompressed
+    pub name: String,
+    /// The cache-flush bit of multicast DNS
+    pub multicast_unique: bool,
+    /// Class of the record
+    pub cls: Class,
+    /// Time to live in seconds
+    pub ttl: u32,
+    /// Type of the RDATA
+    pub typ: Type,
+    /// The RDATA as found in the packet
+    ///
+    /// Names in it may be compressed, use `BytesPacket::rdata` to decode it.
+    pub rdata: Bytes,
+}
+
+/// The OPT pseudo-record of a `BytesPacket`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]  // same fields as in opt::Record
+pub struct BytesOpt {
+    pub udp: u16,
+    pub extrcode: u8,
+    pub version: u8,
+    pub flags: u16,
+    /// The EDNS options, i.e. the RDATA of the record
+    pub options: Bytes,
+}
+
+impl<'a> Packet<'a> {
+    /// Parse a packet stored in a `Bytes` buffer and keep the buffer
+    ///
+    /// The returned packet has no lifetime, the RDATA of its records
+    /// shares the buffer.
+    pub fn parse_bytes(data: Bytes) -> Result<BytesPacket, Error> {
+        let packet = Packet::parse(&data)?;
+        let mut offset = Header::size();
+        let questions = packet.questions.iter().map(|q| {
+            offset += q.qname.byte_len() + 4;
+            BytesQuestion {
+                qname: q.qname.to_string(),
+                prefer_unicast: q.prefer_unicast,
+                qtype: q.qtype,
+                qclass: q.qclass,
+            }
+        }).collect();
+        let mut answers = Vec::with_capacity(packet.answers.len());
+        for rr in &packet.answers {
+            let range = rdata_range(&data, &mut offset)?;
+            answers.push(BytesRecord::new(&data, rr, range));
+        }
+        let mut nameservers = Vec::with_capacity(packet.nameservers.len());
+        for rr in &packet.nameservers {
+            let range = rdata_range(&data, &mut offset)?;
+            nameservers.push(BytesRecord::new(&data, rr, range));
+        }
+        let mut additional = Vec::with_capacity(packet.additional.len());
+        let mut opt = None;
+        let mut records = packet.additional.iter();
+        for _ in 0..packet.header.additional {
+            let is_opt = data[offset..].starts_with(&OPT_RR_START);
+            let range = rdata_range(&data, &mut offset)?;
+            match (is_opt, packet.opt.as_ref()) {
+                (true, Some(rr)) => {
+                    opt = Some(BytesOpt {
+                        udp: rr.udp,
+                        extrcode: rr.extrcode,
+                        version: rr.version,
+                        flags: rr.flags,
+                        options: data.slice(range),
+                    });
+                }
+                _ => {
+                    let rr = records.next().ok_or(Error::UnexpectedEOF)?;
+                    additional.push(BytesRecord::new(&data, rr, range));
+                }
+            }
+        }
+        Ok(BytesPacket {
+            header: packet.header,
+            questions,
+            answers,
+            nameservers,
+            additional,
+            opt,
+            data,
+        })
+    }
+}
+
+/// Steps over the record at `offset` and returns the range of its RDATA
+fn rdata_range(data: &[u8], offset: &mut usize)
+    -> Result<Range<usize>, Error>
+{
+    let start = *offset + Name::skip(&data[*offset..], data)? + 10;
+    if start > data.len() {
+        return Err(Error::UnexpectedEOF);
+    }
+    let rdlen = BigEndian::read_u16(&data[start-2..start]) as usize;
+    *offset = start + rdlen;
+    if *offset > data.len() {
+        return Err(Error::UnexpectedEOF);
+    }
+    Ok(start..*offset)
+}
+
+impl BytesRecord {
+    fn new(data: &Bytes, rr: &::ResourceRecord, range: Range<usize>)
+        -> BytesRecord
+    {
+        BytesRecord {
+            name: rr.name.to_string(),
+            multicast_unique: rr.multicast_unique,
+            cls: rr.cls,
+            ttl: rr.ttl,
+            typ: rr.data.type_code(),
+            rdata: data.slice(range),
+        }
+    }
+}
+
+impl BytesPacket {
+    /// Decodes the RDATA of a record of this packet
+    ///
+    /// Compressed names in it are resolved against this packet's buffer,
+    /// so `record` must come from this packet.
+    pub fn rdata<'a>(&'a self, record: &'a BytesRecord)
+        -> Result<RData<'a>, Error>
+    {
+        // UPDATE prerequisites and RRset deletions (RFC 2136) carry no
+        // RDATA, see `Packet::update_ops`
+        if record.rdata.is_empty() &&
+            (record.cls == Class::ANY || record.cls == Class::NONE)
+        {
+            return Ok(RData::Unknown(record.typ, &record.rdata));
+        }
+        RData::parse(record.typ, &record.rdata, &self.data)
+    }
+
+    /// Returns the underlying buffer
+    pub fn bytes(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+impl From<BytesPacket> for Bytes {
+    fn from(packet: BytesPacket) -> Bytes {
+        packet.data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use {Packet, RData, Type, Class, QueryType, Error};
+
+    #[test]
+    fn parse_bytes() {
+        let response = Bytes::from_static(b"\x06%\x81\x80\x00\x01\x00\x01\
+            \x00\x00\x00\x00\x07example\x03com\x00\x00\x01\x00\x01\
+            \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\x00\x04]\xb8\xd8\"");
+        let packet = Packet::parse_bytes(response.clone()).unwrap();
+        assert_eq!(packet.header.id, 1573);
+        assert_eq!(packet.questions[0].qname, "example.com");
+        assert_eq!(packet.questions[0].qtype, QueryType::A);
+        let answer = &packet.answers[0];
+        assert_eq!(answer.name, "example.com");
+        assert_eq!(answer.cls, Class::IN);
+        assert_eq!(answer.ttl, 1272);
+        assert_eq!(answer.typ, Type::A);
+        assert_eq!(answer.rdata, response.slice(41..45));
+        // shares the buffer rather than copying it
+        assert_eq!(answer.rdata.as_ptr(), response[41..].as_ptr());
+        match packet.rdata(answer).unwrap() {
+            RData::A(addr) => {
+                assert_eq!(addr.0.octets(), [93, 184, 216, 34]);
+            }
+            x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn compressed_rdata() {
+        let response = Bytes::from_static(b"\x00\x01\x81\x80\x00\x01\x00\x01\
+            \x00\x00\x00\x01\x07example\x03com\x00\x00\x05\x00\x01\
+            \xc0\x0c\x00\x05\x00\x01\x00\x00\x00\x3c\x00\x06\x03www\xc0\x0c\
+            \x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x00");
+        let packet = Packet::parse_bytes(response).unwrap();
+        assert_eq!(packet.additional.len(), 0);
+        assert_eq!(packet.opt.as_ref().unwrap().udp, 4096);
+        match packet.rdata(&packet.answers[0]).unwrap() {
+            RData::CNAME(name) => {
+                assert_eq!(name.0.to_string(), "www.example.com");
+            }
+            x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn parse_bytes_invalid() {
+        assert!(matches!(Packet::parse_bytes(Bytes::from_static(b"\x00")),
+            Err(Error::HeaderTooShort)));
+    }
+}
@@ -0,0 +1,155 @@
+use std::io;
+
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use {Builder};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Framing {
+    Datagram,
+    Tcp,
+}
+
+/// A `tokio-util` codec for DNS messages
+///
+/// Decoding yields raw messages (without the TCP length prefix) which can
+/// be parsed with `Packet::parse`. Both `Builder`s and already serialized
+/// messages can be encoded.
+///
+/// Use `DnsCodec::udp()` with `UdpFramed` and `DnsCodec::tcp()` with
+/// `Framed` over a TCP or TLS stream.
+#[derive(Debug, Clone, Copy)]
+pub struct DnsCodec {
+    framing: Framing,
+}
+
+impl DnsCodec {
+    /// Codec for UDP, one message per datagram
+    pub fn udp() -> DnsCodec {
+        DnsCodec { framing: Framing::Datagram }
+    }
+
+    /// Codec for TCP, messages are prefixed with the two-byte length
+    pub fn tcp() -> DnsCodec {
+        DnsCodec { framing: Framing::Tcp }
+    }
+
+    fn encode_message(&self, message: &[u8], dst: &mut BytesMut)
+        -> Result<(), io::Error>
+    {
+        match self.framing {
+            Framing::Datagram => {
+                dst.reserve(message.len());
+            }
+            Framing::Tcp => {
+                if message.len() > 0xFFFF {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                        "DNS message is too long for TCP framing"));
+                }
+                dst.reserve(message.len() + 2);
+                dst.put_u16(message.len() as u16);
+            }
+        }
+        dst.put_slice(message);
+        Ok(())
+    }
+}
+
+impl Decoder for DnsCodec {
+    type Item = BytesMut;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut)
+        -> Result<Option<BytesMut>, io::Error>
+    {
+        match self.framing {
+            Framing::Datagram => {
+                if src.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some(src.split()))
+            }
+            Framing::Tcp => {
+                if src.len() < 2 {
+                    return Ok(None);
+                }
+                let len = BigEndian::read_u16(&src[..2]) as usize;
+                if src.len() < len + 2 {
+                    src.reserve(len + 2 - src.len());
+                    return Ok(None);
+                }
+                let mut message = src.split_to(len + 2);
+                Ok(Some(message.split_off(2)))
+            }
+        }
+    }
+}
+
+impl<'a, 'b> Encoder<&'b Builder<'a>> for DnsCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &'b Builder<'a>, dst: &mut BytesMut)
+        -> Result<(), io::Error>
+    {
+        let message = item.build().map_err(|_| io::Error::new(
+            io::ErrorKind::InvalidInput, "truncated packet"))?;
+        self.encode_message(&message, dst)
+    }
+}
+
+impl Encoder<Vec<u8>> for DnsCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut)
+        -> Result<(), io::Error>
+    {
+        self.encode_message(&item, dst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use {Builder, Packet};
+    use QueryType as QT;
+    use QueryClass as QC;
+    use super::DnsCodec;
+
+    #[test]
+    fn tcp_roundtrip() {
+        let mut bld = Builder::new(1573, true);
+        bld.question("example.com", false, QT::A, QC::IN);
+        let mut codec = DnsCodec::tcp();
+        let mut buf = BytesMut::new();
+        codec.encode(&bld, &mut buf).unwrap();
+        codec.encode(&bld, &mut buf).unwrap();
+        assert_eq!(&buf[..2], b"\x00\x1d");
+
+        let mut partial = buf.split_to(10);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        partial.unsplit(buf);
+        for _ in 0..2 {
+            let message = codec.decode(&mut partial).unwrap().unwrap();
+            let packet = Packet::parse(&message).unwrap();
+            assert_eq!(packet.header.id, 1573);
+        }
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+    }
+
+    #[test]
+    fn udp_roundtrip() {
+        let mut bld = Builder::new(1573, true);
+        bld.question("example.com", false, QT::A, QC::IN);
+        let mut codec = DnsCodec::udp();
+        let mut buf = BytesMut::new();
+        codec.encode(bld.build().unwrap(), &mut buf).unwrap();
+        assert_eq!(buf.len(), 29);
+        let message = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(Packet::parse(&message).unwrap().header.id, 1573);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+/// Pointers can only address the first 16 KiB of a message
+const MAX_OFFSET: usize = 0x3FFF;
+
+/// Dictionary of names already written to a message (RFC 1035 4.1.4)
+///
+/// Keys are lowercase name suffixes, values are their offsets from the
+/// start of the message.
+#[derive(Debug, Default)]
+pub struct Compressor {
+    names: HashMap<String, u16>,
+}
+
+impl Compressor {
+    pub fn new() -> Compressor {
+        Compressor::default()
+    }
+
+    /// Writes the name, replacing the longest known suffix with a pointer
+    ///
+    /// The suffixes written out in full are remembered for later names.
+    ///
+    /// # Panics
+    ///
+    /// When `Name::check` rejects the name, callers check beforehand.
+    pub fn write_name(&mut self, buf: &mut Vec<u8>, name: &str) {
+        let name = name.strip_suffix('.').unwrap_or(name);
+        assert!(!name.ends_with('.'), "empty label in domain name");
+        let mut rest = name;
+        while !rest.is_empty() {
+            let key = rest.to_ascii_lowercase();
+            if let Some(&offset) = self.names.get(&key) {
+                buf.write_u16::<BigEndian>(0xC000 | offset).unwrap();
+                return;
+            }
+            if buf.len() <= MAX_OFFSET {
+                self.names.insert(key, buf.len() as u16);
+            }
+            let (label, tail) = match rest.find('.') {
+                Some(pos) => (&rest[..pos], &rest[pos+1..]),
+                None => (rest, ""),
+            };
+            assert!(!label.is_empty(), "empty label in domain name");
+            assert!(label.len() <= 63, "label is longer than 63 bytes");
+            buf.push(label.len() as u8);
+            buf.extend(label.as_bytes());
+            rest = tail;
+        }
+        buf.push(0);
+    }
+
+    /// The suffixes written so far and their offsets, by offset
+    pub fn entries(&self) -> Vec<(String, u16)> {
+        let mut entries: Vec<_> = self.names.iter()
+            .map(|(name, &offset)| (name.clone(), offset))
+            .collect();
+        entries.sort_by_key(|&(_, offset)| offset);
+        entries
+    }
+
+    /// Returns how long the name would be if written at `offset`
+    ///
+    /// The suffixes are remembered as if the name was written.
+    pub fn measure_name(&mut self, offset: usize, name: &str) -> usize {
+        let name = name.strip_suffix('.').unwrap_or(name);
+        let mut rest = name;
+        let mut len = 0;
+        while !rest.is_empty() {
+            let key = rest.to_ascii_lowercase();
+            if self.names.contains_key(&key) {
+                return len + 2;
+            }
+            if offset + len <= MAX_OFFSET {
+                self.names.insert(key, (offset + len) as u16);
+            }
+            let (label, tail) = match rest.find('.') {
+                Some(pos) => (&rest[..pos], &rest[pos+1..]),
+                None => (rest, ""),
+            };
+            len += label.len() + 1;
+            rest = tail;
+        }
+        len + 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compressor;
+
+    #[test]
+    fn suffix_pointers() {
+        let mut buf = vec![0; 12];
+        let mut names = Compressor::new();
+        names.write_name(&mut buf, "www.example.com");
+        names.write_name(&mut buf, "mail.EXAMPLE.com.");
+        names.write_name(&mut buf, "www.example.com");
+        assert_eq!(&buf[12..], &b"\x03www\x07example\x03com\x00\
+            \x04mail\xc0\x10\xc0\x0c"[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty label in domain name")]
+    fn double_trailing_dot() {
+        // `Name::check` rejects it too
+        assert!(::Name::check("example..").is_err());
+        Compressor::new().write_name(&mut Vec::new(), "example..");
+    }
+}
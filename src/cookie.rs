@@ -0,0 +1,166 @@
+//! Server side of DNS Cookies (RFC 7873, RFC 9018)
+//!
+//! Server cookies are computed with the interoperable algorithm of
+//! RFC 9018, so all servers of an anycast set sharing the secret accept
+//! each other's cookies.
+
+use std::hash::Hasher;
+use std::net::IpAddr;
+
+use siphasher::sip::SipHasher24;
+
+/// EDNS option code of the COOKIE option
+pub const OPTION_CODE: u16 = 10;
+/// Length of the client cookie
+pub const CLIENT_COOKIE_LEN: usize = 8;
+/// Length of a server cookie produced by `server_cookie`
+pub const SERVER_COOKIE_LEN: usize = 16;
+
+const VERSION: u8 = 1;
+/// Cookies older than this are rejected
+const MAX_AGE: i32 = 3600;
+/// Cookies older than this are accepted but should be replaced
+const REFRESH_AGE: i32 = 1800;
+/// Allowed clock skew for cookies from the future
+const MAX_SKEW: i32 = 300;
+
+/// Result of checking the server cookie sent by a client
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CookieCheck {
+    /// The cookie is valid
+    Valid,
+    /// The cookie is valid but old, a fresh one should be sent back
+    Refresh,
+    /// Wrong length, version or hash, or the cookie has expired
+    Invalid,
+}
+
+/// Splits the data of a COOKIE option into client and server cookie
+///
+/// Returns `None` if the lengths are invalid: the client cookie is always
+/// 8 bytes and the server cookie, if present, is 8 to 32 bytes.
+pub fn split_option(data: &[u8]) -> Option<(&[u8], Option<&[u8]>)> {
+    match data.len() {
+        CLIENT_COOKIE_LEN => Some((data, None)),
+        16..=40 => Some((&data[..CLIENT_COOKIE_LEN],
+                         Some(&data[CLIENT_COOKIE_LEN..]))),
+        _ => None,
+    }
+}
+
+fn hash(client_cookie: &[u8], header: &[u8], client_ip: IpAddr,
+    secret: &[u8; 16])
+    -> [u8; 8]
+{
+    let mut hasher = SipHasher24::new_with_key(secret);
+    hasher.write(client_cookie);
+    hasher.write(header);
+    match client_ip {
+        IpAddr::V4(ip) => hasher.write(&ip.octets()),
+        IpAddr::V6(ip) => hasher.write(&ip.octets()),
+    }
+    hasher.finish().to_le_bytes()
+}
+
+/// Computes the server cookie for a client
+///
+/// `timestamp` is the current time as seconds since the UNIX epoch
+/// (truncated to 32 bits).
+pub fn server_cookie(client_cookie: &[u8; 8], client_ip: IpAddr,
+    secret: &[u8; 16], timestamp: u32)
+    -> [u8; SERVER_COOKIE_LEN]
+{
+    let mut cookie = [0; SERVER_COOKIE_LEN];
+    cookie[0] = VERSION;
+    cookie[4..8].copy_from_slice(&timestamp.to_be_bytes());
+    let hash = hash(client_cookie, &cookie[..8], client_ip, secret);
+    cookie[8..].copy_from_slice(&hash);
+    cookie
+}
+
+/// Checks a server cookie received from a client
+///
+/// The cookie must have been produced by `server_cookie` with the same
+/// secret for the same client cookie and address, at most an hour
+/// before `now` (or five minutes after, to allow for clock skew between
+/// servers). Timestamps are compared with serial number arithmetic.
+pub fn validate_server_cookie(client_cookie: &[u8; 8], server_cookie: &[u8],
+    client_ip: IpAddr, secret: &[u8; 16], now: u32)
+    -> CookieCheck
+{
+    if server_cookie.len() != SERVER_COOKIE_LEN
+        || server_cookie[0] != VERSION
+    {
+        return CookieCheck::Invalid;
+    }
+    let mut timestamp = [0; 4];
+    timestamp.copy_from_slice(&server_cookie[4..8]);
+    let age = now.wrapping_sub(u32::from_be_bytes(timestamp)) as i32;
+    if !(-MAX_SKEW..=MAX_AGE).contains(&age) {
+        return CookieCheck::Invalid;
+    }
+    let expected = hash(client_cookie, &server_cookie[..8], client_ip, secret);
+    // constant time comparison
+    let diff = expected.iter().zip(&server_cookie[8..])
+        .fold(0, |acc, (a, b)| acc | (a ^ b));
+    if diff != 0 {
+        CookieCheck::Invalid
+    } else if age > REFRESH_AGE {
+        CookieCheck::Refresh
+    } else {
+        CookieCheck::Valid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::{server_cookie, validate_server_cookie, split_option};
+    use super::CookieCheck::*;
+
+    const CLIENT: [u8; 8] = *b"\x24\x64\xc4\xab\xcf\x10\xc9\x57";
+    const SECRET: [u8; 16] = *b"\xe5\xe9\x73\xe5\xa6\xb2\xa4\x3f\
+                                \x48\xe7\xdc\x84\x9e\x37\xbf\xcf";
+
+    fn ip() -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(198, 51, 100, 100))
+    }
+
+    #[test]
+    fn rfc9018_vector() {
+        // Appendix A.1
+        let cookie = server_cookie(&CLIENT, ip(), &SECRET, 1559731985);
+        assert_eq!(&cookie[..], &b"\x01\x00\x00\x00\x5c\xf7\x9f\x11\
+            \x1f\x81\x30\xc3\xee\xe2\x94\x80"[..]);
+    }
+
+    #[test]
+    fn validation() {
+        let ts = 1559731985;
+        let cookie = server_cookie(&CLIENT, ip(), &SECRET, ts);
+        let check = |now| validate_server_cookie(&CLIENT, &cookie, ip(),
+                                                 &SECRET, now);
+        assert_eq!(check(ts), Valid);
+        assert_eq!(check(ts - 200), Valid);
+        assert_eq!(check(ts + 2000), Refresh);
+        assert_eq!(check(ts + 4000), Invalid);
+        assert_eq!(check(ts - 400), Invalid);
+
+        let other = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 101));
+        assert_eq!(validate_server_cookie(&CLIENT, &cookie, other,
+                                          &SECRET, ts), Invalid);
+        assert_eq!(validate_server_cookie(&CLIENT, &cookie[..8], ip(),
+                                          &SECRET, ts), Invalid);
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(split_option(&CLIENT), Some((&CLIENT[..], None)));
+        let mut data = CLIENT.to_vec();
+        data.extend(&[0; 16]);
+        assert_eq!(split_option(&data),
+            Some((&CLIENT[..], Some(&[0; 16][..]))));
+        assert_eq!(split_option(&data[..12]), None);
+    }
+}
@@ -0,0 +1,337 @@
+//! Helpers for DNS-based service discovery (RFC 6763)
+
+use std::fmt;
+use std::net::IpAddr;
+
+use {Name, Packet, ResourceRecord, RData};
+use rdata::Txt;
+
+/// Owner of the PTR records listing the service types of a domain
+/// (RFC 6763 section 9), without the domain
+pub const SERVICES_META_QUERY: &str = "_services._dns-sd._udp";
+
+/// A service type found by service type enumeration, e.g. `_ipp._tcp`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ServiceType {
+    /// Service label including the leading underscore, e.g. `_ipp`
+    pub service: String,
+    /// Either `_tcp` or `_udp`
+    pub protocol: String,
+}
+
+impl fmt::Display for ServiceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.service, self.protocol)
+    }
+}
+
+/// The name to query for the service types of `domain`, e.g.
+/// `_services._dns-sd._udp.local`
+///
+/// See `Builder::dnssd_services_query` for a complete query.
+pub fn services_name(domain: &str) -> String {
+    match domain.trim_end_matches('.') {
+        "" => SERVICES_META_QUERY.to_string(),
+        domain => format!("{}.{}", SERVICES_META_QUERY, domain),
+    }
+}
+
+/// Collects the service types from the answer to a service type
+/// enumeration query
+///
+/// Looks at PTR answers owned by `_services._dns-sd._udp.<domain>` for
+/// any domain. Each points to a name of the form
+/// `<Service>.<Protocol>.<Domain>`, targets that don't look like that
+/// are skipped, as are duplicates.
+pub fn service_types(packet: &Packet) -> Vec<ServiceType> {
+    let meta: Vec<&str> = SERVICES_META_QUERY.split('.').collect();
+    let mut types: Vec<ServiceType> = Vec::new();
+    for record in &packet.answers {
+        let ptr = match record.data {
+            RData::PTR(ref ptr) => ptr,
+            _ => continue,
+        };
+        let owner = split_labels(&record.name.str_val);
+        let is_meta = owner.len() >= meta.len() && owner.iter().zip(&meta)
+            .all(|(x, y)| x.eq_ignore_ascii_case(y));
+        if !is_meta {
+            continue;
+        }
+        let labels = split_labels(&ptr.0.str_val);
+        if labels.len() < 3 || !labels[0].starts_with('_')
+            || !(labels[1].eq_ignore_ascii_case("_tcp")
+                 || labels[1].eq_ignore_ascii_case("_udp"))
+        {
+            continue;
+        }
+        let found = ServiceType {
+            service: labels[0].clone(),
+            protocol: labels[1].clone(),
+        };
+        let known = types.iter().any(|t| {
+            t.service.eq_ignore_ascii_case(&found.service)
+                && t.protocol.eq_ignore_ascii_case(&found.protocol)
+        });
+        if !known {
+            types.push(found);
+        }
+    }
+    types
+}
+
+/// A service instance name split into its parts
+///
+/// The name has the form `<Instance>.<Service>.<Protocol>.<Domain>`, e.g.
+/// `Office Printer._ipp._tcp.local`. The instance part is an arbitrary
+/// UTF-8 label and may contain dots.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ServiceInstanceName {
+    /// Human-readable instance name, with escapes removed
+    pub instance: String,
+    /// Service label including the leading underscore, e.g. `_ipp`
+    pub service: String,
+    /// Either `_tcp` or `_udp`
+    pub protocol: String,
+    /// The domain the service is registered in, e.g. `local`
+    pub domain: String,
+}
+
+impl ServiceInstanceName {
+    /// Splits a parsed name into service instance parts
+    ///
+    /// Returns `None` if the name doesn't look like a service instance name.
+    pub fn from_name(name: &Name) -> Option<ServiceInstanceName> {
+        ServiceInstanceName::parse(&name.to_string())
+    }
+
+    /// Splits a textual name into service instance parts
+    ///
+    /// Dots and backslashes in the instance label may be escaped with
+    /// a backslash, as in presentation format. Unescaped dots are accepted
+    /// too, because names decoded from packets aren't escaped: the service
+    /// and protocol labels are found by scanning from the right.
+    pub fn parse(name: &str) -> Option<ServiceInstanceName> {
+        let labels = split_labels(name);
+        let proto = labels.iter().rposition(|l| {
+            l.eq_ignore_ascii_case("_tcp") || l.eq_ignore_ascii_case("_udp")
+        })?;
+        if proto < 2 || proto + 1 >= labels.len()
+            || !labels[proto-1].starts_with('_')
+        {
+            return None;
+        }
+        Some(ServiceInstanceName {
+            instance: labels[..proto-1].join("."),
+            service: labels[proto-1].clone(),
+            protocol: labels[proto].clone(),
+            domain: labels[proto+1..].join("."),
+        })
+    }
+
+    /// Returns the service type, e.g. `_ipp._tcp`
+    pub fn service_type(&self) -> String {
+        format!("{}.{}", self.service, self.protocol)
+    }
+}
+
+impl fmt::Display for ServiceInstanceName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.instance.chars() {
+            if c == '.' || c == '\\' {
+                f.write_str("\\")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        write!(f, ".{}.{}.{}", self.service, self.protocol, self.domain)
+    }
+}
+
+/// A service instance with everything needed to connect to it
+#[derive(Debug, Clone)]
+pub struct ResolvedService {
+    /// The service instance name, e.g. `Office Printer._ipp._tcp.local`
+    pub instance: String,
+    /// Host name from the SRV record
+    pub target: String,
+    /// Port from the SRV record
+    pub port: u16,
+    /// Addresses of the target host found in the packet
+    pub addresses: Vec<IpAddr>,
+    /// The TXT record of the instance, if present
+    pub txt: Option<Txt>,
+}
+
+/// Collects resolved services from a response packet
+///
+/// Follows PTR records to SRV records and those to TXT and A/AAAA
+/// records, looking in both the answer and the additional sections as
+/// mDNS responders put the latter ones into additional records
+/// (RFC 6763 section 12). SRV records answering a direct query for an
+/// instance are resolved too. Instances without an SRV record in the
+/// packet are skipped.
+pub fn resolve_services(packet: &Packet) -> Vec<ResolvedService> {
+    let records = || packet.answers.iter().chain(packet.additional.iter());
+    let mut instances: Vec<String> = Vec::new();
+    for record in records() {
+        let instance = match record.data {
+            RData::PTR(ref ptr) => ptr.0.to_string(),
+            RData::SRV(..) => record.name.to_string(),
+            _ => continue,
+        };
+        if !instances.iter().any(|x| x.eq_ignore_ascii_case(&instance)) {
+            instances.push(instance);
+        }
+    }
+
+    let mut services = Vec::new();
+    for instance in instances {
+        let owned_by = |r: &&ResourceRecord| {
+            r.name.to_string().eq_ignore_ascii_case(&instance)
+        };
+        let srv = records().filter(owned_by).filter_map(|r| match r.data {
+            RData::SRV(ref srv) => Some(srv),
+            _ => None,
+        }).next();
+        let srv = match srv {
+            Some(srv) => srv,
+            None => continue,
+        };
+        let txt = records().filter(owned_by).filter_map(|r| match r.data {
+            RData::TXT(ref txt) => Some(txt.clone()),
+            _ => None,
+        }).next();
+        let target = srv.target.to_string();
+        let addresses = records()
+            .filter(|r| r.name.to_string().eq_ignore_ascii_case(&target))
+            .filter_map(|r| r.data.ip())
+            .collect();
+        services.push(ResolvedService {
+            instance,
+            target,
+            port: srv.port,
+            addresses,
+            txt,
+        });
+    }
+    services
+}
+
+fn split_labels(name: &str) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut label = String::new();
+    let mut chars = name.trim_end_matches('.').chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => label.extend(chars.next()),
+            '.' => labels.push(::std::mem::take(&mut label)),
+            _ => label.push(c),
+        }
+    }
+    labels.push(label);
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use {Builder, Class, Name, Packet, QueryType, RData};
+    use rdata::Ptr;
+    use super::{ServiceInstanceName, ServiceType, resolve_services};
+    use super::{service_types, services_name};
+
+    #[test]
+    fn parse_simple() {
+        let name = Name::from_string("Office Printer._ipp._tcp.local");
+        let sin = ServiceInstanceName::from_name(&name).unwrap();
+        assert_eq!(sin, ServiceInstanceName {
+            instance: "Office Printer".into(),
+            service: "_ipp".into(),
+            protocol: "_tcp".into(),
+            domain: "local".into(),
+        });
+        assert_eq!(sin.service_type(), "_ipp._tcp");
+        assert_eq!(sin.to_string(), "Office Printer._ipp._tcp.local");
+    }
+
+    #[test]
+    fn parse_dotted_instance() {
+        let escaped = ServiceInstanceName::parse(
+            "Web Server v2\\.0._http._tcp.example.com.").unwrap();
+        let unescaped = ServiceInstanceName::parse(
+            "Web Server v2.0._http._tcp.example.com").unwrap();
+        assert_eq!(escaped, unescaped);
+        assert_eq!(escaped.instance, "Web Server v2.0");
+        assert_eq!(escaped.domain, "example.com");
+        assert_eq!(escaped.to_string(),
+            "Web Server v2\\.0._http._tcp.example.com");
+    }
+
+    #[test]
+    fn resolve_mdns_response() {
+        // PTR answer for _http._tcp.local with SRV, TXT, A and AAAA
+        // in the additional section
+        let response = b"\x00\x00\x84\x00\x00\x00\x00\x01\x00\x00\x00\x04\
+            \x05_http\x04_tcp\x05local\x00\x00\x0c\x00\x01\x00\x00\x11\x94\
+            \x00\x06\x03web\xc0\x0c\
+            \xc0\x28\x00\x21\x80\x01\x00\x00\x00\x78\x00\x0d\
+            \x00\x00\x00\x00\x1f\x90\x04host\xc0\x17\
+            \xc0\x28\x00\x10\x80\x01\x00\x00\x11\x94\x00\x07\
+            \x06path=/\
+            \xc0\x40\x00\x01\x80\x01\x00\x00\x00\x78\x00\x04\
+            \xc0\x00\x02\x01\
+            \xc0\x40\x00\x1c\x80\x01\x00\x00\x00\x78\x00\x10\
+            \xfe\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01";
+        let packet = Packet::parse(response).unwrap();
+        let services = resolve_services(&packet);
+        assert_eq!(services.len(), 1);
+        let service = &services[0];
+        assert_eq!(service.instance, "web._http._tcp.local");
+        assert_eq!(service.target, "host.local");
+        assert_eq!(service.port, 8080);
+        assert_eq!(service.addresses, vec![
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+        ]);
+        assert_eq!(service.txt.as_ref().unwrap().attribute("path"),
+            Some(Some(&b"/"[..])));
+    }
+
+    #[test]
+    fn enumerate_service_types() {
+        assert_eq!(services_name("local."), "_services._dns-sd._udp.local");
+        assert_eq!(services_name("."), "_services._dns-sd._udp");
+
+        let query = Builder::dnssd_services_query(7, "example.com")
+            .build().unwrap();
+        let query = Packet::parse(&query).unwrap();
+        assert_eq!(query.questions[0].qname.to_string(),
+            "_services._dns-sd._udp.example.com");
+        assert_eq!(query.questions[0].qtype, QueryType::PTR);
+
+        let meta = "_services._dns-sd._udp.example.com";
+        let mut response = Builder::new(7, true);
+        for target in &["_ipp._tcp.example.com", "_http._tcp.example.com",
+                        "_IPP._TCP.example.com", "printer.example.com"] {
+            let ptr = RData::PTR(Ptr(Name::from_string(target)));
+            response.answer(meta, Class::IN, ptr, false, 60);
+        }
+        let ptr = RData::PTR(Ptr(Name::from_string("_ssh._tcp.example.com")));
+        response.answer("example.com", Class::IN, ptr, false, 60);
+        let response = response.build().unwrap();
+        let types = service_types(&Packet::parse(&response).unwrap());
+        assert_eq!(types, vec![
+            ServiceType { service: "_ipp".into(), protocol: "_tcp".into() },
+            ServiceType { service: "_http".into(), protocol: "_tcp".into() },
+        ]);
+        assert_eq!(types[1].to_string(), "_http._tcp");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(ServiceInstanceName::parse("_http._tcp.local"), None);
+        assert_eq!(ServiceInstanceName::parse("x._http._tcp"), None);
+        assert_eq!(ServiceInstanceName::parse("x.http._tcp.local"), None);
+        assert_eq!(ServiceInstanceName::parse("www.example.com"), None);
+    }
+}
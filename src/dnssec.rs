@@ -0,0 +1,402 @@
+//! DNSSEC algorithm and digest type numbers, and signatures of RRsets
+//!
+//! Values are from the IANA "DNS Security Algorithm Numbers" and
+//! "Delegation Signer (DS) Resource Record Digest Algorithms" registries,
+//! the recommendations from RFC 8624.
+
+use std::fmt;
+use std::str::FromStr;
+
+use {Error, Class, Name, RData, ResourceRecord};
+use rdata::Rrsig;
+
+/// DNSSEC algorithm of DNSKEY, RRSIG and DS records
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Algorithm {
+    /// RSA/MD5 (1)
+    RsaMd5,
+    /// DSA/SHA-1 (3)
+    Dsa,
+    /// RSA/SHA-1 (5)
+    RsaSha1,
+    /// DSA-NSEC3-SHA1 (6)
+    DsaNsec3Sha1,
+    /// RSASHA1-NSEC3-SHA1 (7)
+    RsaSha1Nsec3Sha1,
+    /// RSA/SHA-256 (8)
+    RsaSha256,
+    /// RSA/SHA-512 (10)
+    RsaSha512,
+    /// GOST R 34.10-2001 (12)
+    EccGost,
+    /// ECDSA Curve P-256 with SHA-256 (13)
+    EcdsaP256Sha256,
+    /// ECDSA Curve P-384 with SHA-384 (14)
+    EcdsaP384Sha384,
+    /// Ed25519 (15)
+    Ed25519,
+    /// Ed448 (16)
+    Ed448,
+    /// Any other algorithm number, never one of the numbers above
+    Other(u8),
+}
+
+const ALGORITHMS: [(Algorithm, u8, &str); 12] = [
+    (Algorithm::RsaMd5, 1, "RSAMD5"),
+    (Algorithm::Dsa, 3, "DSA"),
+    (Algorithm::RsaSha1, 5, "RSASHA1"),
+    (Algorithm::DsaNsec3Sha1, 6, "DSA-NSEC3-SHA1"),
+    (Algorithm::RsaSha1Nsec3Sha1, 7, "RSASHA1-NSEC3-SHA1"),
+    (Algorithm::RsaSha256, 8, "RSASHA256"),
+    (Algorithm::RsaSha512, 10, "RSASHA512"),
+    (Algorithm::EccGost, 12, "ECC-GOST"),
+    (Algorithm::EcdsaP256Sha256, 13, "ECDSAP256SHA256"),
+    (Algorithm::EcdsaP384Sha384, 14, "ECDSAP384SHA384"),
+    (Algorithm::Ed25519, 15, "ED25519"),
+    (Algorithm::Ed448, 16, "ED448"),
+];
+
+impl Algorithm {
+    /// Returns true if validators are expected to implement the algorithm
+    ///
+    /// These are the algorithms RFC 8624 marks MUST or RECOMMENDED for
+    /// validation.
+    pub fn is_supported(self) -> bool {
+        use self::Algorithm::*;
+        matches!(self, RsaSha1 | RsaSha1Nsec3Sha1 | RsaSha256 | RsaSha512 |
+            EcdsaP256Sha256 | EcdsaP384Sha384 | Ed25519)
+    }
+
+    /// Returns true if zones should no longer be signed with the algorithm
+    ///
+    /// RFC 8624 marks these MUST NOT or NOT RECOMMENDED for signing.
+    pub fn is_deprecated(self) -> bool {
+        use self::Algorithm::*;
+        matches!(self, RsaMd5 | Dsa | RsaSha1 | DsaNsec3Sha1 |
+            RsaSha1Nsec3Sha1 | EccGost)
+    }
+}
+
+impl From<u8> for Algorithm {
+    fn from(code: u8) -> Algorithm {
+        ALGORITHMS.iter()
+            .find(|&&(_, x, _)| x == code)
+            .map(|&(alg, _, _)| alg)
+            .unwrap_or(Algorithm::Other(code))
+    }
+}
+impl From<Algorithm> for u8 {
+    fn from(alg: Algorithm) -> u8 {
+        match alg {
+            Algorithm::Other(x) => x,
+            alg => ALGORITHMS.iter()
+                .find(|&&(x, _, _)| x == alg)
+                .map(|&(_, code, _)| code)
+                .expect("all named algorithms are in the table"),
+        }
+    }
+}
+
+/// The mnemonic, e.g. `ECDSAP256SHA256`, or the number for others
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match ALGORITHMS.iter().find(|&&(x, _, _)| x == *self) {
+            Some(&(_, _, name)) => f.write_str(name),
+            None => write!(f, "{}", u8::from(*self)),
+        }
+    }
+}
+
+/// Parses a mnemonic (case-insensitive) or a number
+impl FromStr for Algorithm {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Algorithm, Error> {
+        if let Ok(code) = s.parse::<u8>() {
+            return Ok(Algorithm::from(code));
+        }
+        ALGORITHMS.iter()
+            .find(|&&(_, _, name)| name.eq_ignore_ascii_case(s))
+            .map(|&(alg, _, _)| alg)
+            .ok_or(Error::InvalidText("unknown algorithm"))
+    }
+}
+
+/// Digest type of DS and CDS records
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum DigestType {
+    /// SHA-1 (1)
+    Sha1,
+    /// SHA-256 (2)
+    Sha256,
+    /// GOST R 34.11-94 (3)
+    Gost94,
+    /// SHA-384 (4)
+    Sha384,
+    /// Any other digest type, never one of the types above
+    Other(u8),
+}
+
+const DIGEST_TYPES: [(DigestType, u8, &str); 4] = [
+    (DigestType::Sha1, 1, "SHA-1"),
+    (DigestType::Sha256, 2, "SHA-256"),
+    (DigestType::Gost94, 3, "GOST R 34.11-94"),
+    (DigestType::Sha384, 4, "SHA-384"),
+];
+
+impl DigestType {
+    /// Returns true if validators are expected to implement the digest
+    ///
+    /// These are the types RFC 8624 marks MUST or RECOMMENDED for
+    /// validation.
+    pub fn is_supported(self) -> bool {
+        use self::DigestType::*;
+        matches!(self, Sha1 | Sha256 | Sha384)
+    }
+
+    /// Returns true if DS records should no longer use the digest
+    ///
+    /// RFC 8624 marks these MUST NOT for DS creation.
+    pub fn is_deprecated(self) -> bool {
+        use self::DigestType::*;
+        matches!(self, Sha1 | Gost94)
+    }
+
+    /// Length of the digest in octets, `None` for unknown types
+    pub fn digest_len(self) -> Option<usize> {
+        use self::DigestType::*;
+        match self {
+            Sha1 => Some(20),
+            Sha256 | Gost94 => Some(32),
+            Sha384 => Some(48),
+            Other(_) => None,
+        }
+    }
+}
+
+impl From<u8> for DigestType {
+    fn from(code: u8) -> DigestType {
+        DIGEST_TYPES.iter()
+            .find(|&&(_, x, _)| x == code)
+            .map(|&(typ, _, _)| typ)
+            .unwrap_or(DigestType::Other(code))
+    }
+}
+impl From<DigestType> for u8 {
+    fn from(typ: DigestType) -> u8 {
+        match typ {
+            DigestType::Other(x) => x,
+            typ => DIGEST_TYPES.iter()
+                .find(|&&(x, _, _)| x == typ)
+                .map(|&(_, code, _)| code)
+                .expect("all named digest types are in the table"),
+        }
+    }
+}
+
+/// The mnemonic, e.g. `SHA-256`, or the number for others
+impl fmt::Display for DigestType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match DIGEST_TYPES.iter().find(|&&(x, _, _)| x == *self) {
+            Some(&(_, _, name)) => f.write_str(name),
+            None => write!(f, "{}", u8::from(*self)),
+        }
+    }
+}
+
+/// Parses a mnemonic (case-insensitive) or a number
+impl FromStr for DigestType {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<DigestType, Error> {
+        if let Ok(code) = s.parse::<u8>() {
+            return Ok(DigestType::from(code));
+        }
+        DIGEST_TYPES.iter()
+            .find(|&&(_, _, name)| name.eq_ignore_ascii_case(s))
+            .map(|&(typ, _, _)| typ)
+            .ok_or(Error::InvalidText("unknown digest type"))
+    }
+}
+
+/// An RRset with the RRSIG records covering it, see `match_signatures`
+#[derive(Debug, Clone)]
+pub struct SignedRRset<'r, 'a: 'r> {
+    /// Owner name of the RRset, as in its first record
+    pub name: &'r Name<'a>,
+    /// Class of the RRset
+    pub cls: Class,
+    /// Type code of the RRset
+    pub typ: u16,
+    /// Records of the RRset, in the order they were given
+    pub records: Vec<&'r ResourceRecord<'a>>,
+    /// RRSIG records covering the RRset, empty if it's unsigned
+    pub signatures: Vec<&'r ResourceRecord<'a>>,
+}
+
+impl<'r, 'a: 'r> SignedRRset<'r, 'a> {
+    /// Returns true if no RRSIG covers the RRset
+    pub fn is_unsigned(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// The RRSIG data of the signatures
+    pub fn rrsigs(&self) -> impl Iterator<Item = &'r Rrsig<'a>> + '_ {
+        self.signatures.iter().filter_map(|r| match r.data {
+            RData::RRSIG(ref rrsig) => Some(rrsig),
+            _ => None,
+        })
+    }
+}
+
+/// RRsets paired with their signatures, returned by `match_signatures`
+#[derive(Debug, Clone)]
+pub struct SignatureMatch<'r, 'a: 'r> {
+    /// All RRsets, signed or not, in the order they were first seen
+    pub rrsets: Vec<SignedRRset<'r, 'a>>,
+    /// RRSIG records whose RRset isn't among the records
+    pub orphans: Vec<&'r ResourceRecord<'a>>,
+}
+
+impl<'r, 'a: 'r> SignatureMatch<'r, 'a> {
+    /// The RRsets no RRSIG covers
+    pub fn unsigned(&self) -> impl Iterator<Item = &SignedRRset<'r, 'a>> {
+        self.rrsets.iter().filter(|set| set.is_unsigned())
+    }
+}
+
+/// Groups records into RRsets and pairs each RRSIG with the RRset it
+/// covers
+///
+/// This is the step before validating signatures (RFC 4035 section
+/// 5.3.1): an RRSIG covers the RRset with the same owner name (ignoring
+/// case) and class whose type is the type covered. Records of an RRset
+/// don't need to be next to each other. Pass the records of one section,
+/// e.g. `packet.answers.iter()`, or of several. Nothing is verified,
+/// the signer, labels and validity period are left to the validator.
+pub fn match_signatures<'r, 'a: 'r, I>(records: I) -> SignatureMatch<'r, 'a>
+    where I: IntoIterator<Item = &'r ResourceRecord<'a>>
+{
+    let same_set = |set: &SignedRRset, record: &ResourceRecord, typ| {
+        set.typ == typ && set.cls == record.cls
+            && set.name.eq_ignore_case(&record.name.str_val)
+    };
+    let mut rrsets: Vec<SignedRRset> = Vec::new();
+    let mut rrsigs = Vec::new();
+    for record in records {
+        if let RData::RRSIG(ref rrsig) = record.data {
+            rrsigs.push((record, rrsig.type_covered));
+            continue;
+        }
+        let typ = record.data.raw_type_code();
+        match rrsets.iter_mut().find(|set| same_set(set, record, typ)) {
+            Some(set) => set.records.push(record),
+            None => rrsets.push(SignedRRset {
+                name: &record.name,
+                cls: record.cls,
+                typ,
+                records: vec![record],
+                signatures: Vec::new(),
+            }),
+        }
+    }
+    let mut orphans = Vec::new();
+    for (record, covered) in rrsigs {
+        match rrsets.iter_mut().find(|set| same_set(set, record, covered)) {
+            Some(set) => set.signatures.push(record),
+            None => orphans.push(record),
+        }
+    }
+    SignatureMatch { rrsets, orphans }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::net::Ipv4Addr;
+
+    use {Class, Error, Name, RData, ResourceRecord};
+    use rdata::{A, Rrsig};
+    use super::{Algorithm, DigestType, match_signatures};
+
+    #[test]
+    fn algorithms() {
+        for code in 0..=255u8 {
+            let alg = Algorithm::from(code);
+            assert_eq!(u8::from(alg), code);
+            assert_eq!(alg.to_string().parse::<Algorithm>(), Ok(alg));
+        }
+        assert_eq!(Algorithm::from(13), Algorithm::EcdsaP256Sha256);
+        assert_eq!("ed25519".parse::<Algorithm>(), Ok(Algorithm::Ed25519));
+        assert_eq!(Algorithm::Other(200).to_string(), "200");
+        assert_eq!("RSA".parse::<Algorithm>(),
+            Err(Error::InvalidText("unknown algorithm")));
+        assert!(Algorithm::RsaSha256.is_supported());
+        assert!(!Algorithm::RsaSha256.is_deprecated());
+        assert!(Algorithm::RsaSha1.is_supported());
+        assert!(Algorithm::RsaSha1.is_deprecated());
+        assert!(!Algorithm::Other(200).is_supported());
+    }
+
+    #[test]
+    fn digest_types() {
+        for code in 0..=255u8 {
+            let typ = DigestType::from(code);
+            assert_eq!(u8::from(typ), code);
+            assert_eq!(typ.to_string().parse::<DigestType>(), Ok(typ));
+        }
+        assert_eq!(DigestType::Sha256.to_string(), "SHA-256");
+        assert_eq!(DigestType::Sha384.digest_len(), Some(48));
+        assert!(DigestType::Sha1.is_deprecated());
+        assert!(!DigestType::Gost94.is_supported());
+    }
+
+    fn record(name: &str, data: RData<'static>) -> ResourceRecord<'static> {
+        ResourceRecord {
+            name: Name::from_string(name),
+            multicast_unique: false,
+            cls: Class::IN,
+            ttl: 300,
+            data,
+        }
+    }
+
+    fn rrsig(name: &str, type_covered: u16) -> ResourceRecord<'static> {
+        record(name, RData::RRSIG(Rrsig {
+            type_covered,
+            algorithm: Algorithm::Ed25519,
+            labels: 2,
+            original_ttl: 300,
+            expiration: 2,
+            inception: 1,
+            key_tag: 12345,
+            signer: Name::from_string("example.com"),
+            signature: Cow::Borrowed(b"sig"),
+        }))
+    }
+
+    #[test]
+    fn signatures() {
+        let a = |x| RData::A(A(Ipv4Addr::new(192, 0, 2, x)));
+        let records = vec![
+            record("www.example.com", a(1)),
+            rrsig("WWW.example.com.", 1),
+            record("mail.example.com", a(3)),
+            record("www.example.com", a(2)),
+            rrsig("www.example.com", 28),
+            rrsig("ftp.example.com", 1),
+        ];
+        let matched = match_signatures(&records);
+        assert_eq!(matched.rrsets.len(), 2);
+        let www = &matched.rrsets[0];
+        assert_eq!(www.name.to_string(), "www.example.com");
+        assert_eq!((www.cls, www.typ), (Class::IN, 1));
+        assert_eq!(www.records.len(), 2);
+        assert_eq!(www.signatures.len(), 1);
+        assert_eq!(www.rrsigs().next().unwrap().key_tag, 12345);
+        let unsigned: Vec<_> = matched.unsigned()
+            .map(|set| set.name.to_string()).collect();
+        assert_eq!(unsigned, ["mail.example.com"]);
+        let orphans: Vec<_> = matched.orphans.iter()
+            .map(|r| r.name.to_string()).collect();
+        assert_eq!(orphans, ["www.example.com", "ftp.example.com"]);
+    }
+}
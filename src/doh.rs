@@ -0,0 +1,60 @@
+//! Helpers for DNS over HTTPS (RFC 8484)
+//!
+//! The crate does no HTTP itself, these are just the encoding bits.
+//! Queries should use message ID 0 to make responses cacheable
+//! (RFC 8484 section 4.1).
+
+use base64;
+use {Error};
+
+/// Media type of DNS messages in DoH request and response bodies
+pub const CONTENT_TYPE: &str = "application/dns-message";
+
+/// Name of the query parameter carrying the message in GET requests
+pub const QUERY_PARAM: &str = "dns";
+
+/// Encodes a DNS message as the value of the `dns` GET parameter
+///
+/// This is unpadded base64url, so the result needs no further escaping
+/// in a URL.
+pub fn encode_get_param(message: &[u8]) -> String {
+    base64::encode_url(message)
+}
+
+/// Decodes the value of the `dns` GET parameter into a DNS message
+pub fn decode_get_param(param: &str) -> Result<Vec<u8>, Error> {
+    base64::decode_url(param).ok_or(Error::InvalidBase64)
+}
+
+/// Builds a full GET URL from the DoH endpoint URI template base
+///
+/// `endpoint` is e.g. `https://dns.example.com/dns-query`.
+pub fn get_url(endpoint: &str, message: &[u8]) -> String {
+    let sep = if endpoint.contains('?') { '&' } else { '?' };
+    format!("{}{}{}={}", endpoint, sep, QUERY_PARAM,
+        encode_get_param(message))
+}
+
+#[cfg(test)]
+mod test {
+    use {Builder, Error};
+    use QueryType as QT;
+    use QueryClass as QC;
+    use super::*;
+
+    #[test]
+    fn rfc8484_example() {
+        // The www.example.com A query from RFC 8484 section 4.1.1
+        let mut bld = Builder::new(0, true);
+        bld.question("www.example.com", false, QT::A, QC::IN);
+        let message = bld.build().unwrap();
+        let param = "AAABAAABAAAAAAAAA3d3dwdleGFtcGxlA2NvbQAAAQAB";
+        assert_eq!(encode_get_param(&message), param);
+        assert_eq!(decode_get_param(param).unwrap(), message);
+        assert_eq!(get_url("https://dnsserver.example.net/dns-query", &message),
+            "https://dnsserver.example.net/dns-query?dns=\
+             AAABAAABAAAAAAAAA3d3dwdleGFtcGxlA2NvbQAAAQAB");
+        assert!(matches!(decode_get_param("AAAB*"),
+            Err(Error::InvalidBase64)));
+    }
+}
@@ -0,0 +1,274 @@
+//! DNS Stateful Operations (RFC 8490)
+//!
+//! DSO messages have the `StatefulOperations` opcode, no questions or
+//! records, and carry TLVs after the header instead. They're only used on
+//! session transports such as TCP, TLS or QUIC, so add the framing with
+//! the usual two-byte length when sending.
+
+use std::io::{self, Write};
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+use {Header, Opcode, ResponseCode, Error, WireEncode};
+
+/// DSO-TYPE of the Keepalive TLV
+pub const KEEPALIVE: u16 = 1;
+
+/// DSO-TYPE of the Retry Delay TLV
+pub const RETRY_DELAY: u16 = 2;
+
+/// DSO-TYPE of the Encryption Padding TLV
+pub const ENCRYPTION_PADDING: u16 = 3;
+
+/// A DSO TLV
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tlv<'a> {
+    /// Session timeouts, in milliseconds (RFC 8490 section 7.1)
+    Keepalive {
+        /// Time the session may stay idle before the server closes it
+        inactivity_timeout: u32,
+        /// Time the session may go without traffic, `0xFFFFFFFF` is
+        /// infinity
+        keepalive_interval: u32,
+    },
+    /// Server asks the client to reconnect no earlier than this many
+    /// milliseconds (RFC 8490 section 7.2)
+    RetryDelay(u32),
+    /// Padding to hide the message size (RFC 8490 section 7.3)
+    EncryptionPadding(&'a [u8]),
+    /// TLV of a type the crate doesn't parse
+    Unknown(u16, &'a [u8]),
+}
+
+impl<'a> Tlv<'a> {
+    /// Returns the DSO-TYPE code
+    pub fn code(&self) -> u16 {
+        match *self {
+            Tlv::Keepalive { .. } => KEEPALIVE,
+            Tlv::RetryDelay(..) => RETRY_DELAY,
+            Tlv::EncryptionPadding(..) => ENCRYPTION_PADDING,
+            Tlv::Unknown(code, _) => code,
+        }
+    }
+
+    fn parse(code: u16, data: &'a [u8]) -> Result<Tlv<'a>, Error> {
+        match code {
+            KEEPALIVE if data.len() == 8 => Ok(Tlv::Keepalive {
+                inactivity_timeout: BigEndian::read_u32(&data[..4]),
+                keepalive_interval: BigEndian::read_u32(&data[4..]),
+            }),
+            RETRY_DELAY if data.len() == 4 => {
+                Ok(Tlv::RetryDelay(BigEndian::read_u32(data)))
+            }
+            KEEPALIVE | RETRY_DELAY => {
+                Err(Error::InvalidDso("wrong length of TLV"))
+            }
+            ENCRYPTION_PADDING => Ok(Tlv::EncryptionPadding(data)),
+            code => Ok(Tlv::Unknown(code, data)),
+        }
+    }
+}
+
+impl<'a> WireEncode for Tlv<'a> {
+    /// Fails with `InvalidInput` if the data is longer than 65535 bytes
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.code())?;
+        match *self {
+            Tlv::Keepalive { inactivity_timeout, keepalive_interval } => {
+                w.write_u16::<BigEndian>(8)?;
+                w.write_u32::<BigEndian>(inactivity_timeout)?;
+                w.write_u32::<BigEndian>(keepalive_interval)?;
+                Ok(12)
+            }
+            Tlv::RetryDelay(delay) => {
+                w.write_u16::<BigEndian>(4)?;
+                w.write_u32::<BigEndian>(delay)?;
+                Ok(8)
+            }
+            Tlv::EncryptionPadding(data) | Tlv::Unknown(_, data) => {
+                if data.len() > u16::MAX as usize {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                        "DSO TLV longer than 65535 bytes"));
+                }
+                w.write_u16::<BigEndian>(data.len() as u16)?;
+                w.write_all(data)?;
+                Ok(data.len() + 4)
+            }
+        }
+    }
+}
+
+/// A DSO message
+///
+/// The first TLV of a request is the primary TLV stating the operation,
+/// the rest are additional TLVs such as padding.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Message<'a> {
+    /// The header, its section counts are always zero
+    pub header: Header,
+    /// TLVs in the order of the message
+    pub tlvs: Vec<Tlv<'a>>,
+}
+
+impl<'a> Message<'a> {
+    /// Creates a request
+    ///
+    /// Use ID 0 for unidirectional messages, which get no response.
+    pub fn request(id: u16, tlvs: Vec<Tlv<'a>>) -> Message<'a> {
+        let mut header = Header::from_flags(id, 0, [0; 4]);
+        header.opcode = Opcode::StatefulOperations;
+        Message { header, tlvs }
+    }
+
+    /// Creates a response to the request
+    pub fn response(request: &Message, code: ResponseCode,
+        tlvs: Vec<Tlv<'a>>)
+        -> Message<'a>
+    {
+        let mut msg = Message::request(request.header.id, tlvs);
+        msg.header.query = false;
+        msg.header.response_code = code;
+        msg
+    }
+
+    /// Parses a DSO message
+    ///
+    /// Fails with `Error::InvalidDso` if the message has another opcode,
+    /// has questions or records, or is a request without any TLV.
+    pub fn parse(data: &'a [u8]) -> Result<Message<'a>, Error> {
+        let header = Header::parse(data)?;
+        if header.opcode != Opcode::StatefulOperations {
+            return Err(Error::InvalidDso("not a DSO message"));
+        }
+        if header.questions != 0 || header.answers != 0 ||
+            header.nameservers != 0 || header.additional != 0
+        {
+            return Err(Error::InvalidDso("sections must be empty"));
+        }
+        let mut tlvs = Vec::new();
+        let mut offset = Header::size();
+        while offset < data.len() {
+            if data.len() - offset < 4 {
+                return Err(Error::UnexpectedEOF);
+            }
+            let code = BigEndian::read_u16(&data[offset..offset+2]);
+            let len = BigEndian::read_u16(&data[offset+2..offset+4]) as usize;
+            offset += 4;
+            if data.len() - offset < len {
+                return Err(Error::UnexpectedEOF);
+            }
+            tlvs.push(Tlv::parse(code, &data[offset..offset+len])?);
+            offset += len;
+        }
+        if header.query && tlvs.is_empty() {
+            return Err(Error::InvalidDso("request has no primary TLV"));
+        }
+        Ok(Message { header, tlvs })
+    }
+
+    /// Returns the primary TLV, the first one of the message
+    pub fn primary(&self) -> Option<&Tlv<'a>> {
+        self.tlvs.first()
+    }
+
+    /// Serializes the message
+    ///
+    /// Fails with `InvalidInput` if the data of a TLV is longer than
+    /// 65535 bytes.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+impl<'a> WireEncode for Message<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        let mut buf = [0; 12];
+        let mut header = self.header;
+        header.questions = 0;
+        header.answers = 0;
+        header.nameservers = 0;
+        header.additional = 0;
+        header.write(&mut buf);
+        w.write_all(&buf)?;
+        let mut len = buf.len();
+        for tlv in &self.tlvs {
+            len += tlv.write_to(w)?;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use {Error, Packet, ResponseCode, Opcode};
+    use super::{Message, Tlv};
+
+    #[test]
+    fn keepalive() {
+        let request = Message::request(0x2a, vec![
+            Tlv::Keepalive {
+                inactivity_timeout: 15000,
+                keepalive_interval: 3600000,
+            },
+            Tlv::EncryptionPadding(b"\x00\x00\x00"),
+        ]);
+        let data = request.to_bytes().unwrap();
+        assert_eq!(&data[..], &b"\x00\x2a\x30\x00\x00\x00\x00\x00\x00\x00\
+            \x00\x00\x00\x01\x00\x08\x00\x00\x3a\x98\x00\x36\xee\x80\
+            \x00\x03\x00\x03\x00\x00\x00"[..]);
+        assert_eq!(Message::parse(&data).unwrap(), request);
+        // The TLVs are ignored by the general parser
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.header.opcode, Opcode::StatefulOperations);
+
+        let response = Message::response(&request, ResponseCode::NoError,
+            vec![Tlv::Keepalive {
+                inactivity_timeout: 30000,
+                keepalive_interval: 0xFFFFFFFF,
+            }]);
+        let data = response.to_bytes().unwrap();
+        let parsed = Message::parse(&data).unwrap();
+        assert!(!parsed.header.query);
+        assert_eq!(parsed.header.id, 0x2a);
+        assert_eq!(parsed.primary(), response.primary());
+    }
+
+    #[test]
+    fn invalid() {
+        let empty = Message::request(1, vec![]).to_bytes().unwrap();
+        assert_eq!(Message::parse(&empty),
+            Err(Error::InvalidDso("request has no primary TLV")));
+
+        let mut data = Message::request(1, vec![Tlv::RetryDelay(5)])
+            .to_bytes().unwrap();
+        assert_eq!(Message::parse(&data[..data.len()-1]),
+            Err(Error::UnexpectedEOF));
+        data[15] = 3;
+        assert_eq!(Message::parse(&data[..data.len()-1]),
+            Err(Error::InvalidDso("wrong length of TLV")));
+        data[2] = 0;
+        assert_eq!(Message::parse(&data),
+            Err(Error::InvalidDso("not a DSO message")));
+    }
+
+    #[test]
+    fn long_tlv() {
+        let padding = vec![0; 65536];
+        let request = Message::request(1, vec![
+            Tlv::RetryDelay(5),
+            Tlv::EncryptionPadding(&padding),
+        ]);
+        let err = request.to_bytes().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let request = Message::request(1, vec![
+            Tlv::RetryDelay(5),
+            Tlv::EncryptionPadding(&padding[1..]),
+        ]);
+        let data = request.to_bytes().unwrap();
+        assert_eq!(Message::parse(&data).unwrap(), request);
+    }
+}
@@ -0,0 +1,602 @@
+//! Helpers for EDNS(0) (RFC 6891)
+//!
+//! Options the crate doesn't know about, e.g. proprietary ones, can be
+//! defined by implementing `EdnsOption`. Look them up in parsed packets
+//! with `option`, or with an `OptionRegistry` when the set of options is
+//! only known at runtime, and add them with `Builder::edns_custom_option`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use {Builder, Error, Name, Packet, RData, ResponseCode};
+use rdata::opt::Record as Opt;
+
+/// The EDNS version implemented by the crate
+pub const VERSION: u8 = 0;
+
+/// Option code of the EDNS Client Subnet option (RFC 7871)
+pub const CLIENT_SUBNET: u16 = 8;
+
+/// Option code of the Report-Channel option (RFC 9567)
+pub const REPORT_CHANNEL: u16 = 18;
+
+/// UDP payload size recommended by DNS Flag Day 2020
+///
+/// Messages of this size fit in the minimum IPv6 MTU, so they don't get
+/// fragmented.
+pub const DEFAULT_UDP_SIZE: u16 = 1232;
+
+/// Largest UDP message without EDNS (RFC 1035 section 4.2.1)
+pub const MIN_UDP_SIZE: u16 = 512;
+
+/// Extended RCODE: the requested EDNS version is not supported
+pub const BADVERS: u16 = 16;
+
+/// Splits an extended RCODE into header and OPT parts
+///
+/// The lower four bits go to the header, the upper eight bits to the
+/// extended RCODE field of the OPT record.
+pub fn split_response_code(code: u16) -> (ResponseCode, u8) {
+    (ResponseCode::from((code & 0x0F) as u8), (code >> 4) as u8)
+}
+
+/// Joins the header RCODE and the OPT extended RCODE
+pub fn join_response_code(code: ResponseCode, extrcode: u8) -> u16 {
+    (extrcode as u16) << 4 | u8::from(code) as u16
+}
+
+/// Iterator over the options of an OPT record, see `options`
+#[derive(Debug, Clone)]
+pub struct Options<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Options<'a> {
+    type Item = (u16, &'a [u8]);
+    fn next(&mut self) -> Option<(u16, &'a [u8])> {
+        if self.data.len() < 4 {
+            return None;
+        }
+        let code = BigEndian::read_u16(&self.data[..2]);
+        let len = BigEndian::read_u16(&self.data[2..4]) as usize;
+        if self.data.len() - 4 < len {
+            self.data = &[];
+            return None;
+        }
+        let value = &self.data[4..4+len];
+        self.data = &self.data[4+len..];
+        Some((code, value))
+    }
+}
+
+/// Returns the option codes and data of the OPT record
+///
+/// Iteration stops at an option that is cut short.
+pub fn options<'a>(opt: &Opt<'a>) -> Options<'a> {
+    match opt.data {
+        RData::Unknown(_, data) => Options { data },
+        _ => Options { data: &[] },
+    }
+}
+
+/// An EDNS option defined by the application
+pub trait EdnsOption: fmt::Debug + Sized {
+    /// The OPTION-CODE
+    const CODE: u16;
+    /// Parses the OPTION-DATA
+    fn parse(data: &[u8]) -> Result<Self, Error>;
+    /// Encodes the OPTION-DATA
+    fn to_data(&self) -> Vec<u8>;
+}
+
+/// An option parsed by an `OptionRegistry` callback
+///
+/// Implemented for every owned type implementing `EdnsOption`.
+pub trait CustomOption: fmt::Debug {
+    /// The OPTION-CODE
+    fn code(&self) -> u16;
+    /// The OPTION-DATA
+    ///
+    /// Named differently from `EdnsOption::to_data` so both traits can be
+    /// in scope.
+    fn option_data(&self) -> Vec<u8>;
+    /// Allows downcasting to the concrete type with `downcast_ref`
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: EdnsOption + 'static> CustomOption for T {
+    fn code(&self) -> u16 {
+        T::CODE
+    }
+    fn option_data(&self) -> Vec<u8> {
+        EdnsOption::to_data(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn parse_option<T: EdnsOption + 'static>(data: &[u8])
+    -> Result<Box<dyn CustomOption>, Error>
+{
+    T::parse(data).map(|x| Box::new(x) as Box<dyn CustomOption>)
+}
+
+/// Parses the OPTION-DATA of a registered option
+pub type OptionParseFn = fn(data: &[u8])
+    -> Result<Box<dyn CustomOption>, Error>;
+
+/// An option of an OPT record, see `OptionRegistry::options`
+#[derive(Debug)]
+pub enum OptionValue<'a> {
+    /// Parsed by the registered parser
+    Custom(Box<dyn CustomOption>),
+    /// An option without a parser, with its code and data
+    Raw(u16, &'a [u8]),
+}
+
+/// A set of parsers for EDNS options, by OPTION-CODE
+///
+/// The counterpart of `rdata::RecordRegistry` for options.
+#[derive(Debug, Default, Clone)]
+pub struct OptionRegistry {
+    parsers: HashMap<u16, OptionParseFn>,
+}
+
+impl OptionRegistry {
+    /// Creates an empty registry
+    pub fn new() -> OptionRegistry {
+        OptionRegistry::default()
+    }
+
+    /// Registers a parser for the option code
+    ///
+    /// Returns the previously registered parser, if any.
+    pub fn register(&mut self, code: u16, parser: OptionParseFn)
+        -> Option<OptionParseFn>
+    {
+        self.parsers.insert(code, parser)
+    }
+
+    /// Registers a type implementing `EdnsOption` under its code
+    pub fn register_option<T: EdnsOption + 'static>(&mut self)
+        -> Option<OptionParseFn>
+    {
+        self.register(T::CODE, parse_option::<T>)
+    }
+
+    /// Returns true if there is a parser for the option code
+    pub fn is_registered(&self, code: u16) -> bool {
+        self.parsers.contains_key(&code)
+    }
+
+    /// Runs the registered parser, `None` if the code isn't registered
+    pub fn parse(&self, code: u16, data: &[u8])
+        -> Option<Result<Box<dyn CustomOption>, Error>>
+    {
+        self.parsers.get(&code).map(|parser| parser(data))
+    }
+
+    /// Parses all the options of the OPT record
+    ///
+    /// Options without a registered parser are returned raw. Fails if a
+    /// registered parser fails.
+    pub fn options<'a>(&self, opt: &Opt<'a>)
+        -> Result<Vec<OptionValue<'a>>, Error>
+    {
+        options(opt).map(|(code, data)| match self.parse(code, data) {
+            Some(result) => result.map(OptionValue::Custom),
+            None => Ok(OptionValue::Raw(code, data)),
+        }).collect()
+    }
+}
+
+/// Finds and parses the option of type `T` in the OPT record
+///
+/// Returns `None` if there is no such option.
+pub fn option<T: EdnsOption>(opt: &Opt) -> Option<Result<T, Error>> {
+    options(opt).find(|&(code, _)| code == T::CODE)
+        .map(|(_, data)| T::parse(data))
+}
+
+/// Returns the agent domain of the Report-Channel option (RFC 9567)
+///
+/// Errors met resolving a query may be reported to this domain. Returns
+/// `None` if the option is missing or isn't an uncompressed name.
+pub fn report_channel<'a>(opt: &Opt<'a>) -> Option<Name<'a>> {
+    let (_, data) = options(opt).find(|&(code, _)| code == REPORT_CHANNEL)?;
+    let name = Name::scan(data, data).ok()?;
+    if name.byte_len() != data.len() || data.len() == 1 {
+        return None;
+    }
+    Some(name)
+}
+
+/// Encodes the agent domain as the data of the Report-Channel option
+///
+/// Add it to a response with `Builder::edns_option` and `REPORT_CHANNEL`.
+pub fn report_channel_data(agent_domain: &str) -> Vec<u8> {
+    Name::from_string(agent_domain.trim_end_matches('.')).to_bytes()
+}
+
+/// The EDNS Client Subnet option (RFC 7871)
+///
+/// Resolvers send part of the client address to authoritative servers,
+/// which answer with the length of the prefix the answer is valid for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientSubnet {
+    /// The address, bits past `source_prefix` are zero
+    pub address: IpAddr,
+    /// Number of bits of the address sent
+    pub source_prefix: u8,
+    /// Number of bits of the address the answer depends on, zero in
+    /// queries
+    pub scope_prefix: u8,
+}
+
+/// Keeps the first `prefix` bits of the address
+fn truncate(address: IpAddr, prefix: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX.checked_shl(32 - prefix.min(32) as u32)
+                .unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX.checked_shl(128 - prefix.min(128) as u32)
+                .unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+fn max_prefix(address: IpAddr) -> u8 {
+    if address.is_ipv4() { 32 } else { 128 }
+}
+
+impl ClientSubnet {
+    /// Creates the option for a query, keeping `source_prefix` bits
+    ///
+    /// RFC 7871 section 11.1 recommends sending no more than 24 bits of
+    /// IPv4 and 56 bits of IPv6 addresses.
+    pub fn new(address: IpAddr, source_prefix: u8) -> ClientSubnet {
+        let source_prefix = source_prefix.min(max_prefix(address));
+        ClientSubnet {
+            address: truncate(address, source_prefix),
+            source_prefix,
+            scope_prefix: 0,
+        }
+    }
+
+    /// Parses the option data
+    ///
+    /// Returns `None` for unknown families, prefixes longer than the
+    /// address, and addresses of the wrong length or with bits set past
+    /// the source prefix, which RFC 7871 section 6 requires to be
+    /// rejected.
+    pub fn parse(data: &[u8]) -> Option<ClientSubnet> {
+        if data.len() < 4 {
+            return None;
+        }
+        let (source_prefix, scope_prefix) = (data[2], data[3]);
+        let bytes = &data[4..];
+        let address = match BigEndian::read_u16(&data[..2]) {
+            1 if bytes.len() <= 4 => {
+                let mut octets = [0; 4];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V4(octets.into())
+            }
+            2 if bytes.len() <= 16 => {
+                let mut octets = [0; 16];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V6(octets.into())
+            }
+            _ => return None,
+        };
+        let max = max_prefix(address);
+        if source_prefix > max || scope_prefix > max
+            || bytes.len() != source_prefix.div_ceil(8) as usize
+            || truncate(address, source_prefix) != address
+        {
+            return None;
+        }
+        Some(ClientSubnet { address, source_prefix, scope_prefix })
+    }
+
+    /// Encodes the option data, add it with `Builder::edns_option`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (family, octets) = match self.address {
+            IpAddr::V4(ip) => (1, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (2, ip.octets().to_vec()),
+        };
+        let len = self.source_prefix.div_ceil(8) as usize;
+        let mut data = vec![0, family, self.source_prefix, self.scope_prefix];
+        data.extend(&octets[..len]);
+        data
+    }
+
+    /// The prefix a response with this option is cached under
+    ///
+    /// The address is truncated to the scope prefix, or the source prefix
+    /// if that is shorter (RFC 7871 section 7.3.1). Look up cached
+    /// entries for a client with `covers`.
+    pub fn cache_key(&self) -> (IpAddr, u8) {
+        let prefix = self.scope_prefix.min(self.source_prefix);
+        (truncate(self.address, prefix), prefix)
+    }
+
+    /// Returns true if a response with this option can be used for the
+    /// client
+    ///
+    /// This is the case if the client address is in the prefix of
+    /// `cache_key`. A scope of zero covers every address of the family.
+    pub fn covers(&self, client: IpAddr) -> bool {
+        let (key, prefix) = self.cache_key();
+        key.is_ipv4() == client.is_ipv4() && truncate(client, prefix) == key
+    }
+}
+
+/// Returns the Client Subnet option of the OPT record, if it's valid
+pub fn client_subnet(opt: &Opt) -> Option<ClientSubnet> {
+    let (_, data) = options(opt).find(|&(code, _)| code == CLIENT_SUBNET)?;
+    ClientSubnet::parse(data)
+}
+
+/// Clamps a configured UDP payload size to what should be advertised
+///
+/// Values below 512 are meaningless (RFC 6891 section 6.2.3) and values
+/// above `DEFAULT_UDP_SIZE` risk fragmentation, so the result is between
+/// the two.
+pub fn advertised_udp_size(configured: u16) -> u16 {
+    configured.clamp(MIN_UDP_SIZE, DEFAULT_UDP_SIZE)
+}
+
+/// The largest UDP response a server may send to the query
+///
+/// This is the lesser of the size the client advertised and `server_max`,
+/// but at least 512. Without EDNS it's 512.
+pub fn effective_udp_size(query: &Packet, server_max: u16) -> u16 {
+    match query.opt {
+        Some(ref opt) => opt.udp.min(server_max).max(MIN_UDP_SIZE),
+        None => MIN_UDP_SIZE,
+    }
+}
+
+/// Returns true if a message of `len` bytes must be truncated for UDP
+///
+/// The server then sends a response with the TC bit instead, so that the
+/// client retries over TCP.
+pub fn exceeds_udp_size(len: usize, udp_size: u16) -> bool {
+    len > udp_size as usize
+}
+
+/// Returns true if the client should retry the query over TCP
+///
+/// This is the case when the response has the TC bit set, or when it's
+/// larger than the size the client advertised, which means it came
+/// through fragmented and may have been spoofed.
+pub fn should_retry_over_tcp(response: &Packet, len: usize,
+    advertised: u16) -> bool
+{
+    response.header.truncated
+        || exceeds_udp_size(len, advertised.max(MIN_UDP_SIZE))
+}
+
+/// Returns a BADVERS response if the query uses an unsupported version
+///
+/// `supported` is the highest EDNS version the server implements, it is
+/// sent back in the OPT record together with `udp_size`, as RFC 6891
+/// section 6.1.3 requires. Queries without EDNS or with a supported
+/// version yield `None` and should be answered normally.
+pub fn badvers_response<'a>(query: &Packet, supported: u8, udp_size: u16)
+    -> Option<Builder<'a>>
+{
+    let opt = query.opt.as_ref()?;
+    if opt.version <= supported {
+        return None;
+    }
+    let (code, extrcode) = split_response_code(BADVERS);
+    let mut bld = Builder::response_to(query);
+    bld.response_code(code);
+    bld.edns(udp_size, extrcode, supported, 0);
+    Some(bld)
+}
+
+#[cfg(test)]
+mod test {
+    use {Builder, Packet, ResponseCode};
+    use QueryType as QT;
+    use QueryClass as QC;
+    use super::{badvers_response, join_response_code, BADVERS, VERSION};
+    use super::{options, report_channel, report_channel_data, REPORT_CHANNEL};
+    use super::{advertised_udp_size, effective_udp_size, exceeds_udp_size};
+    use super::should_retry_over_tcp;
+    use super::{client_subnet, ClientSubnet, CLIENT_SUBNET};
+    use super::{option, EdnsOption, OptionRegistry, OptionValue};
+
+    #[test]
+    fn badvers() {
+        // query for example.com A with OPT version 1
+        let query = b"\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00\x00\x01\
+            \x07example\x03com\x00\x00\x01\x00\x01\
+            \x00\x00\x29\x10\x00\x00\x01\x00\x00\x00\x00";
+        let query = Packet::parse(query).unwrap();
+        assert_eq!(query.opt.as_ref().unwrap().version, 1);
+
+        let data = badvers_response(&query, VERSION, 1232)
+            .unwrap().build().unwrap();
+        let response = Packet::parse(&data).unwrap();
+        assert_eq!(response.header.id, 0x1234);
+        assert!(!response.header.query);
+        assert!(response.header.recursion_desired);
+        assert_eq!(response.questions.len(), 1);
+        assert_eq!(response.answers.len(), 0);
+        let opt = response.opt.unwrap();
+        assert_eq!(opt.version, 0);
+        assert_eq!(opt.udp, 1232);
+        assert_eq!(join_response_code(response.header.response_code,
+            opt.extrcode), BADVERS);
+        assert_eq!(response.header.response_code, ResponseCode::NoError);
+    }
+
+    #[test]
+    fn supported_version() {
+        let query = b"\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00\x00\x01\
+            \x07example\x03com\x00\x00\x01\x00\x01\
+            \x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x00";
+        let query = Packet::parse(query).unwrap();
+        assert!(badvers_response(&query, VERSION, 1232).is_none());
+    }
+
+    #[test]
+    fn report_channel_option() {
+        let agent = report_channel_data("a01.agent-domain.example.");
+        assert_eq!(&agent[..], &b"\x03a01\x0cagent-domain\x07example\x00"[..]);
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QT::A, QC::IN);
+        bld.edns(1232, 0, 0, 0);
+        bld.edns_option(10, b"\x01\x02\x03\x04\x05\x06\x07\x08");
+        bld.edns_option(REPORT_CHANNEL, &agent);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        let opt = packet.opt.as_ref().unwrap();
+        assert_eq!(options(opt).map(|(code, _)| code).collect::<Vec<_>>(),
+            vec![10, REPORT_CHANNEL]);
+        assert_eq!(report_channel(opt).unwrap().to_string(),
+            "a01.agent-domain.example");
+
+        let mut bld = Builder::new(1, false);
+        bld.edns(1232, 0, 0, 0);
+        bld.edns_option(REPORT_CHANNEL, b"\x03a01\xc0\x00");
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert!(report_channel(packet.opt.as_ref().unwrap()).is_none());
+    }
+
+    #[test]
+    fn udp_sizes() {
+        assert_eq!(advertised_udp_size(4096), 1232);
+        assert_eq!(advertised_udp_size(100), 512);
+        assert_eq!(advertised_udp_size(1400), 1232);
+        assert_eq!(advertised_udp_size(1000), 1000);
+
+        let mut bld = Builder::new(1, true);
+        bld.question("example.com", false, QT::A, QC::IN);
+        let data = bld.build().unwrap();
+        let query = Packet::parse(&data).unwrap();
+        assert_eq!(effective_udp_size(&query, 1232), 512);
+        bld.edns(4096, 0, 0, 0);
+        let data = bld.build().unwrap();
+        let query = Packet::parse(&data).unwrap();
+        assert_eq!(effective_udp_size(&query, 1232), 1232);
+        bld.edns(256, 0, 0, 0);
+        let data = bld.build().unwrap();
+        let query = Packet::parse(&data).unwrap();
+        assert_eq!(effective_udp_size(&query, 1232), 512);
+
+        assert!(!exceeds_udp_size(1232, 1232));
+        assert!(exceeds_udp_size(1233, 1232));
+        assert!(!should_retry_over_tcp(&query, 600, 1232));
+        assert!(should_retry_over_tcp(&query, 600, 0));
+        let mut data = data;
+        data[2] |= 0x02;
+        let response = Packet::parse(&data).unwrap();
+        assert!(should_retry_over_tcp(&response, 100, 1232));
+    }
+
+    #[test]
+    fn client_subnet_scope() {
+        let ecs = ClientSubnet::new("192.0.2.123".parse().unwrap(), 24);
+        assert_eq!(ecs.address, "192.0.2.0".parse::<::std::net::IpAddr>()
+            .unwrap());
+        assert_eq!(ecs.to_bytes(), [0, 1, 24, 0, 192, 0, 2]);
+
+        // The server answers for a /16
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QT::A, QC::IN);
+        bld.edns(1232, 0, 0, 0);
+        bld.edns_option(CLIENT_SUBNET, &[0, 1, 24, 16, 192, 0, 2]);
+        let data = bld.build().unwrap();
+        let response = Packet::parse(&data).unwrap();
+        let ecs = client_subnet(response.opt.as_ref().unwrap()).unwrap();
+        assert_eq!(ecs.cache_key(), ("192.0.0.0".parse().unwrap(), 16));
+        assert!(ecs.covers("192.0.200.1".parse().unwrap()));
+        assert!(!ecs.covers("192.1.2.1".parse().unwrap()));
+        assert!(!ecs.covers("::1".parse().unwrap()));
+
+        let global = ClientSubnet::new("2001:db8::1".parse().unwrap(), 56);
+        assert_eq!(global.to_bytes().len(), 4 + 7);
+        assert_eq!(global.cache_key().1, 0);
+        assert!(global.covers("2001:db9::1".parse().unwrap()));
+
+        // Address too long, bits past the prefix, bad family
+        assert_eq!(ClientSubnet::parse(&[0, 1, 8, 0, 192, 0]), None);
+        assert_eq!(ClientSubnet::parse(&[0, 1, 7, 0, 193]), None);
+        assert_eq!(ClientSubnet::parse(&[0, 3, 0, 0]), None);
+        assert_eq!(ClientSubnet::parse(&[0, 1, 33, 0, 1, 2, 3, 4, 5]), None);
+        assert!(ClientSubnet::parse(&[0, 2, 0, 0]).is_some());
+    }
+
+    /// A made up option carrying a 16-bit shard number
+    #[derive(Debug, PartialEq)]
+    struct Shard(u16);
+
+    impl EdnsOption for Shard {
+        const CODE: u16 = 65001;
+        fn parse(data: &[u8]) -> Result<Shard, ::Error> {
+            if data.len() != 2 {
+                return Err(::Error::WrongRdataLength);
+            }
+            Ok(Shard((data[0] as u16) << 8 | data[1] as u16))
+        }
+        fn to_data(&self) -> Vec<u8> {
+            vec![(self.0 >> 8) as u8, self.0 as u8]
+        }
+    }
+
+    #[test]
+    fn custom_options() {
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QT::A, QC::IN);
+        bld.edns(1232, 0, 0, 0);
+        bld.edns_option(REPORT_CHANNEL, &report_channel_data("a.example"));
+        bld.edns_custom_option(&Shard(0x1234));
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        let opt = packet.opt.as_ref().unwrap();
+        assert_eq!(option::<Shard>(opt).unwrap(), Ok(Shard(0x1234)));
+
+        let mut registry = OptionRegistry::new();
+        assert!(registry.register_option::<Shard>().is_none());
+        let values = registry.options(opt).unwrap();
+        assert_eq!(values.len(), 2);
+        assert!(matches!(values[0], OptionValue::Raw(REPORT_CHANNEL, _)));
+        match values[1] {
+            OptionValue::Custom(ref value) => {
+                assert_eq!(value.as_any().downcast_ref(), Some(&Shard(0x1234)));
+                // Parsed options can be forwarded
+                let mut bld = Builder::new(2, false);
+                bld.edns(1232, 0, 0, 0);
+                bld.edns_custom_option(&**value);
+                let data = bld.build().unwrap();
+                let packet = Packet::parse(&data).unwrap();
+                assert_eq!(option::<Shard>(packet.opt.as_ref().unwrap()),
+                    Some(Ok(Shard(0x1234))));
+            }
+            ref x => panic!("Wrong option {:?}", x),
+        }
+
+        let mut bld = Builder::new(1, false);
+        bld.edns(1232, 0, 0, 0);
+        bld.edns_option(Shard::CODE, &[1]);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        let opt = packet.opt.as_ref().unwrap();
+        assert!(registry.options(opt).is_err());
+        assert_eq!(option::<Shard>(opt), Some(Err(::Error::WrongRdataLength)));
+    }
+}
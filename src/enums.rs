@@ -1,3 +1,6 @@
+use std::fmt;
+use std::str::FromStr;
+
 use {Error};
 use rdata::Record;
 use rdata::*;
@@ -43,8 +46,30 @@ pub enum Type {
     SRV = srv::Record::TYPE,
     /// EDNS0 options (RFC 6891)
     OPT = opt::Record::TYPE,
+    /// DNSSEC signature (RFC 4034)
+    RRSIG = rrsig::Record::TYPE,
     /// next secure record (RFC 4034, RFC 6762)
     NSEC = nsec::Record::TYPE,
+    /// DNSSEC public key (RFC 4034)
+    DNSKEY = dnskey::Record::TYPE,
+    /// message digest for DNS zones (RFC 8976)
+    ZONEMD = zonemd::Record::TYPE,
+    /// service binding (RFC 9460)
+    SVCB = svcb::Record::TYPE,
+    /// service binding for HTTPS (RFC 9460)
+    HTTPS = https::Record::TYPE,
+    /// certification authority authorization (RFC 8659)
+    CAA = caa::Record::TYPE,
+    /// naming authority pointer (RFC 3403)
+    NAPTR = naptr::Record::TYPE,
+    /// TLS certificate association (RFC 6698)
+    TLSA = tlsa::Record::TYPE,
+    /// SSH key fingerprint (RFC 4255)
+    SSHFP = sshfp::Record::TYPE,
+    /// geographical location (RFC 1876)
+    LOC = loc::Record::TYPE,
+    /// all types, only in UPDATE prerequisites and deletions (RFC 2136)
+    ANY = all::Record::TYPE,
 }
 
 /// The QTYPE value according to RFC 1035
@@ -87,6 +112,22 @@ pub enum QueryType {
     AAAA = aaaa::Record::TYPE,
     /// service record (RFC 2782)
     SRV = srv::Record::TYPE,
+    /// service binding (RFC 9460)
+    SVCB = svcb::Record::TYPE,
+    /// service binding for HTTPS (RFC 9460)
+    HTTPS = https::Record::TYPE,
+    /// certification authority authorization (RFC 8659)
+    CAA = caa::Record::TYPE,
+    /// naming authority pointer (RFC 3403)
+    NAPTR = naptr::Record::TYPE,
+    /// TLS certificate association (RFC 6698)
+    TLSA = tlsa::Record::TYPE,
+    /// SSH key fingerprint (RFC 4255)
+    SSHFP = sshfp::Record::TYPE,
+    /// geographical location (RFC 1876)
+    LOC = loc::Record::TYPE,
+    /// A request for an incremental transfer of a zone (RFC 1995)
+    IXFR = 251,
     /// A request for a transfer of an entire zone
     AXFR = axfr::Record::TYPE,
     /// A request for mailbox-related records (MB, MG or MR)
@@ -99,17 +140,25 @@ pub enum QueryType {
 
 
 /// The CLASS value according to RFC 1035
+///
+/// Convert from and to the numeric code with `From`.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Class {
     /// the Internet
-    IN = 1,
+    IN,
     /// the CSNET class (Obsolete - used only for examples in some obsolete
     /// RFCs)
-    CS = 2,
+    CS,
     /// the CHAOS class
-    CH = 3,
+    CH,
     /// Hesiod [Dyer 87]
-    HS = 4,
+    HS,
+    /// Used in UPDATE prerequisites and deletions (RFC 2136)
+    NONE,
+    /// Used in UPDATE prerequisites and deletions (RFC 2136)
+    ANY,
+    /// Any other class code, never one of the codes above
+    Other(u16),
 }
 
 /// The QCLASS value according to RFC 1035
@@ -124,6 +173,8 @@ pub enum QueryClass {
     CH = 3,
     /// Hesiod [Dyer 87]
     HS = 4,
+    /// No class, only meaningful in UPDATE messages (RFC 2136)
+    None = 254,
     /// Any class
     Any = 255,
 }
@@ -137,6 +188,10 @@ pub enum Opcode {
     InverseQuery,
     /// Server status request
     ServerStatusRequest,
+    /// Dynamic update (RFC 2136), see `Packet::update_ops`
+    Update,
+    /// DNS Stateful Operations (RFC 8490), see the `dso` module
+    StatefulOperations,
     /// Reserved opcode for future use
     Reserved(u16),
 }
@@ -163,22 +218,55 @@ impl From<u16> for Opcode {
             0 => StandardQuery,
             1 => InverseQuery,
             2 => ServerStatusRequest,
+            5 => Update,
+            6 => StatefulOperations,
             x => Reserved(x),
         }
     }
 }
-impl Into<u16> for Opcode {
-    fn into(self) -> u16 {
+impl From<Opcode> for u16 {
+    fn from(code: Opcode) -> u16 {
         use self::Opcode::*;
-        match self {
+        match code {
             StandardQuery => 0,
             InverseQuery => 1,
             ServerStatusRequest => 2,
+            Update => 5,
+            StatefulOperations => 6,
             Reserved(x) => x,
         }
     }
 }
 
+impl From<u16> for Class {
+    fn from(code: u16) -> Class {
+        use self::Class::*;
+        match code {
+            1   => IN,
+            2   => CS,
+            3   => CH,
+            4   => HS,
+            254 => NONE,
+            255 => ANY,
+            x   => Other(x),
+        }
+    }
+}
+impl From<Class> for u16 {
+    fn from(cls: Class) -> u16 {
+        use self::Class::*;
+        match cls {
+            IN       => 1,
+            CS       => 2,
+            CH       => 3,
+            HS       => 4,
+            NONE     => 254,
+            ANY      => 255,
+            Other(x) => x,
+        }
+    }
+}
+
 impl From<u8> for ResponseCode {
     fn from(code: u8) -> ResponseCode {
         use self::ResponseCode::*;
@@ -194,10 +282,10 @@ impl From<u8> for ResponseCode {
         }
     }
 }
-impl Into<u8> for ResponseCode {
-    fn into(self) -> u8 {
+impl From<ResponseCode> for u8 {
+    fn from(code: ResponseCode) -> u8 {
         use self::ResponseCode::*;
-        match self {
+        match code {
             NoError         => 0,
             FormatError     => 1,
             ServerFailure   => 2,
@@ -231,6 +319,14 @@ impl QueryType {
             txt::Record::TYPE       => Ok(TXT),
             aaaa::Record::TYPE      => Ok(AAAA),
             srv::Record::TYPE       => Ok(SRV),
+            svcb::Record::TYPE      => Ok(SVCB),
+            https::Record::TYPE     => Ok(HTTPS),
+            caa::Record::TYPE       => Ok(CAA),
+            naptr::Record::TYPE     => Ok(NAPTR),
+            tlsa::Record::TYPE      => Ok(TLSA),
+            sshfp::Record::TYPE     => Ok(SSHFP),
+            loc::Record::TYPE       => Ok(LOC),
+            251                     => Ok(IXFR),
             axfr::Record::TYPE      => Ok(AXFR),
             mailb::Record::TYPE     => Ok(MAILB),
             maila::Record::TYPE     => Ok(MAILA),
@@ -249,6 +345,7 @@ impl QueryClass {
             2   => Ok(CS),
             3   => Ok(CH),
             4   => Ok(HS),
+            254 => Ok(QueryClass::None),
             255 => Ok(Any),
             x   => Err(Error::InvalidQueryClass(x)),
         }
@@ -278,22 +375,203 @@ impl Type {
             aaaa::Record::TYPE      => Ok(AAAA),
             srv::Record::TYPE       => Ok(SRV),
             opt::Record::TYPE       => Ok(OPT),
+            rrsig::Record::TYPE     => Ok(RRSIG),
             nsec::Record::TYPE      => Ok(NSEC),
+            dnskey::Record::TYPE    => Ok(DNSKEY),
+            zonemd::Record::TYPE    => Ok(ZONEMD),
+            svcb::Record::TYPE      => Ok(SVCB),
+            https::Record::TYPE     => Ok(HTTPS),
+            caa::Record::TYPE       => Ok(CAA),
+            naptr::Record::TYPE     => Ok(NAPTR),
+            tlsa::Record::TYPE      => Ok(TLSA),
+            sshfp::Record::TYPE     => Ok(SSHFP),
+            loc::Record::TYPE       => Ok(LOC),
+            all::Record::TYPE       => Ok(ANY),
             x               => Err(Error::InvalidType(x as u16)),
         }
     }
+
+    /// Returns true if names in the RDATA of this type may be compressed
+    ///
+    /// Only the types defined in RFC 1035 qualify (RFC 3597 section 4).
+    /// Names in newer types, such as the SRV target or the NSEC next
+    /// name, must always be written in full because other
+    /// implementations don't decompress them.
+    pub fn allows_rdata_compression(self) -> bool {
+        use self::Type::*;
+        matches!(self, NS | MF | CNAME | SOA | MB | MG | MR | PTR | MINFO | MX)
+    }
+}
+
+const TYPES: [Type; 30] = [
+    Type::A, Type::NS, Type::MF, Type::CNAME, Type::SOA, Type::MB, Type::MG,
+    Type::MR, Type::NULL, Type::WKS, Type::PTR, Type::HINFO, Type::MINFO,
+    Type::MX, Type::TXT, Type::AAAA, Type::SRV, Type::OPT, Type::RRSIG,
+    Type::NSEC, Type::DNSKEY, Type::ZONEMD, Type::SVCB, Type::HTTPS,
+    Type::CAA, Type::NAPTR, Type::TLSA, Type::SSHFP, Type::LOC, Type::ANY,
+];
+
+const QUERY_TYPES: [QueryType; 29] = [
+    QueryType::A, QueryType::NS, QueryType::MF, QueryType::CNAME,
+    QueryType::SOA, QueryType::MB, QueryType::MG, QueryType::MR,
+    QueryType::NULL, QueryType::WKS, QueryType::PTR, QueryType::HINFO,
+    QueryType::MINFO, QueryType::MX, QueryType::TXT, QueryType::AAAA,
+    QueryType::SRV, QueryType::SVCB, QueryType::HTTPS, QueryType::CAA,
+    QueryType::NAPTR, QueryType::TLSA, QueryType::SSHFP, QueryType::LOC,
+    QueryType::IXFR, QueryType::AXFR, QueryType::MAILB, QueryType::MAILA,
+    QueryType::All,
+];
+
+const CLASSES: [Class; 6] = [
+    Class::IN, Class::CS, Class::CH, Class::HS, Class::NONE, Class::ANY,
+];
+
+const QUERY_CLASSES: [QueryClass; 6] = [
+    QueryClass::IN, QueryClass::CS, QueryClass::CH, QueryClass::HS,
+    QueryClass::None, QueryClass::Any,
+];
+
+/// Finds the value with the mnemonic `text` or the generic `PREFIXnn` form
+///
+/// Comparison is case-insensitive as mnemonics are in zone files.
+fn parse_mnemonic<T, F>(text: &str, prefix: &str, values: &[T], by_code: F)
+    -> Result<T, Error>
+    where T: fmt::Display + Copy, F: Fn(u16) -> Result<T, Error>
+{
+    if text.len() > prefix.len() && text.get(..prefix.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+    {
+        if let Ok(code) = text[prefix.len()..].parse() {
+            return by_code(code);
+        }
+    }
+    values.iter()
+        .find(|x| x.to_string().eq_ignore_ascii_case(text))
+        .cloned()
+        .ok_or(Error::InvalidText("unknown mnemonic"))
+}
+
+/// The mnemonic, e.g. `MX`
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The mnemonic, e.g. `IN`, or `CLASS` followed by the code for others
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Class::Other(x) => write!(f, "CLASS{}", x),
+            ref x => fmt::Debug::fmt(x, f),
+        }
+    }
+}
+
+/// The mnemonic, `ANY` for `All`
+impl fmt::Display for QueryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryType::All => f.write_str("ANY"),
+            ref x => fmt::Debug::fmt(x, f),
+        }
+    }
+}
+
+/// The mnemonic, `NONE` and `ANY` for `None` and `Any`
+impl fmt::Display for QueryClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryClass::None => f.write_str("NONE"),
+            QueryClass::Any => f.write_str("ANY"),
+            ref x => fmt::Debug::fmt(x, f),
+        }
+    }
+}
+
+/// Parses a mnemonic such as `MX` or the generic form `TYPE15`
+impl FromStr for Type {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Type, Error> {
+        parse_mnemonic(s, "TYPE", &TYPES, Type::parse)
+    }
+}
+
+/// Parses a mnemonic such as `SRV`, `ANY` (or `*`) or the generic form
+/// `TYPE33`
+impl FromStr for QueryType {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<QueryType, Error> {
+        if s == "*" {
+            return Ok(QueryType::All);
+        }
+        parse_mnemonic(s, "TYPE", &QUERY_TYPES, QueryType::parse)
+    }
+}
+
+/// Parses a mnemonic such as `IN` or the generic form `CLASS1`
+impl FromStr for Class {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Class, Error> {
+        parse_mnemonic(s, "CLASS", &CLASSES, Class::parse)
+    }
+}
+
+/// Parses a mnemonic such as `CH`, `ANY` (or `*`) or the generic form
+/// `CLASS3`
+impl FromStr for QueryClass {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<QueryClass, Error> {
+        if s == "*" {
+            return Ok(QueryClass::Any);
+        }
+        parse_mnemonic(s, "CLASS", &QUERY_CLASSES, QueryClass::parse)
+    }
 }
 
 impl Class {
     /// Parse a class code
+    ///
+    /// This never fails, unknown codes are kept as `Class::Other`.
     pub fn parse(code: u16) -> Result<Class, Error> {
-        use self::Class::*;
-        match code {
-            1   => Ok(IN),
-            2   => Ok(CS),
-            3   => Ok(CH),
-            4   => Ok(HS),
-            x   => Err(Error::InvalidClass(x)),
+        Ok(Class::from(code))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Type, QueryType, Class, QueryClass, Error};
+
+    #[test]
+    fn mnemonics() {
+        assert_eq!("SRV".parse::<QueryType>(), Ok(QueryType::SRV));
+        assert_eq!("aaaa".parse::<QueryType>(), Ok(QueryType::AAAA));
+        assert_eq!("ANY".parse::<QueryType>(), Ok(QueryType::All));
+        assert_eq!("*".parse::<QueryType>(), Ok(QueryType::All));
+        assert_eq!("TYPE15".parse::<QueryType>(), Ok(QueryType::MX));
+        assert_eq!("type252".parse::<QueryType>(), Ok(QueryType::AXFR));
+        assert_eq!("TYPE999".parse::<QueryType>(),
+            Err(Error::InvalidQueryType(999)));
+        assert_eq!("BOGUS".parse::<QueryType>(),
+            Err(Error::InvalidText("unknown mnemonic")));
+        assert_eq!("TYPE".parse::<QueryType>(),
+            Err(Error::InvalidText("unknown mnemonic")));
+        assert_eq!("OPT".parse::<Type>(), Ok(Type::OPT));
+
+        assert_eq!("CH".parse::<QueryClass>(), Ok(QueryClass::CH));
+        assert_eq!("CLASS255".parse::<QueryClass>(), Ok(QueryClass::Any));
+        assert_eq!("CLASS1".parse::<Class>(), Ok(Class::IN));
+        assert_eq!("ANY".parse::<Class>(), Ok(Class::ANY));
+        assert_eq!("none".parse::<QueryClass>(), Ok(QueryClass::None));
+        assert_eq!("CLASS5".parse::<Class>(), Ok(Class::Other(5)));
+        assert!("TYP\u{e9}".parse::<QueryType>().is_err());
+        assert!("CLAS\u{e9}".parse::<QueryClass>().is_err());
+        assert!("\u{e9}\u{e9}\u{e9}".parse::<Type>().is_err());
+        assert_eq!(Class::Other(5).to_string(), "CLASS5");
+        assert_eq!(u16::from(Class::from(254)), 254);
+
+        for &t in &super::QUERY_TYPES {
+            assert_eq!(t.to_string().parse::<QueryType>(), Ok(t));
         }
     }
 }
@@ -1,8 +1,10 @@
 use std::str::Utf8Error;
 
+use Type;
+
 quick_error! {
     /// Error parsing DNS packet
-    #[derive(Debug)]
+    #[derive(Debug, PartialEq, Eq, Clone)]
     pub enum Error {
         /// Invalid compression pointer not pointing backwards
         /// when parsing label
@@ -30,6 +32,11 @@ quick_error! {
         UnknownLabelFormat {
             description("label in domain name has unknown label format")
         }
+        /// Label in domain name is an extended label such as an RFC 2673
+        /// binary label, see `ParseOptions::skip_binary_labels`
+        BinaryLabelUnsupported {
+            description("binary labels are not supported")
+        }
         /// Query type code is invalid
         InvalidQueryType(code: u16) {
             description("query type code is invalid")
@@ -67,5 +74,82 @@ quick_error! {
         AdditionalOPT {
             description("additional OPT record found")
         }
+        /// Invalid base64url encoded data
+        InvalidBase64 {
+            description("invalid base64url encoded data")
+        }
+        /// Malformed DNS stamp
+        InvalidStamp {
+            description("malformed DNS stamp")
+        }
+        /// Zone transfer violates the protocol
+        InvalidZoneTransfer(reason: &'static str) {
+            description("zone transfer violates the protocol")
+            display("invalid zone transfer: {}", reason)
+        }
+        /// Message violates DNS Stateful Operations (RFC 8490)
+        InvalidDso(reason: &'static str) {
+            description("invalid DSO message")
+            display("invalid DSO message: {}", reason)
+        }
+        /// Domain name can't be converted to ASCII (IDNA)
+        InvalidIdn {
+            description("invalid internationalized domain name")
+        }
+        /// Domain name can't be encoded
+        InvalidName(reason: &'static str) {
+            description("invalid domain name")
+            display("invalid domain name: {}", reason)
+        }
+        /// Names in the packet decompress to too much data
+        DecompressionLimit {
+            description("decompression limit exceeded")
+        }
+        /// Text is not valid presentation format
+        InvalidText(reason: &'static str) {
+            description("invalid presentation format")
+            display("invalid presentation format: {}", reason)
+        }
+        /// Parsed RDATA is shorter than RDLENGTH (strict mode only)
+        TrailingRdata(typ: Type) {
+            description("trailing data after RDATA")
+            display("trailing data after {} RDATA", typ)
+        }
+        /// Records don't form a valid zone
+        InvalidZone(reason: &'static str) {
+            description("records don't form a valid zone")
+            display("invalid zone: {}", reason)
+        }
+        /// Malformed SvcParams of an SVCB or HTTPS record (RFC 9460)
+        InvalidSvcParams(reason: &'static str) {
+            description("malformed SvcParams")
+            display("malformed SvcParams: {}", reason)
+        }
+        /// CAA property is malformed or can't be processed (RFC 8659)
+        InvalidCaa(reason: &'static str) {
+            description("invalid CAA property")
+            display("invalid CAA property: {}", reason)
+        }
+        /// NAPTR rewrite rule or ENUM number is malformed (RFC 3403)
+        InvalidNaptr(reason: &'static str) {
+            description("invalid NAPTR rule")
+            display("invalid NAPTR rule: {}", reason)
+        }
+        /// Location can't be encoded in a LOC record (RFC 1876)
+        InvalidLoc(reason: &'static str) {
+            description("invalid location")
+            display("invalid location: {}", reason)
+        }
+        /// Records of an RRset have different TTLs (RFC 2181 section 5.2),
+        /// with the owner name and type of the RRset
+        InconsistentTtl(name: String, typ: u16) {
+            description("RRset with different TTLs")
+            display("RRset {} type {} has different TTLs", name, typ)
+        }
+        /// Malformed UPDATE message or update record (RFC 2136)
+        InvalidUpdate(reason: &'static str) {
+            description("invalid UPDATE message")
+            display("invalid UPDATE message: {}", reason)
+        }
     }
 }
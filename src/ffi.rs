@@ -0,0 +1,333 @@
+//! C interface to the parser
+//!
+//! Enabled with the `ffi` feature. Build a shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` and use the
+//! declarations from `include/dns_parser.h`.
+//!
+//! A packet is parsed with `dns_parse`, which copies the bytes, so the
+//! caller's buffer may be reused right away. Pointers returned by the
+//! accessors point into the packet and stay valid until `dns_packet_free`.
+//!
+//! # Safety
+//!
+//! All functions check their pointer arguments for null, otherwise
+//! pointers must be valid: `data` must point to `len` readable bytes,
+//! packets must come from `dns_parse` and not be freed yet, and `out`
+//! must point to writable memory of the right type.
+#![allow(clippy::missing_safety_doc)]  // covered by the module docs
+
+use std::mem::ManuallyDrop;
+use std::os::raw::c_int;
+use std::ptr;
+use std::slice;
+
+use {Packet, ResourceRecord, RData};
+
+/// The function succeeded
+pub const DNS_OK: c_int = 0;
+/// A required pointer argument was null
+pub const DNS_ERR_NULL: c_int = -1;
+/// The packet is malformed
+pub const DNS_ERR_MALFORMED: c_int = -2;
+/// Index or section is out of range
+pub const DNS_ERR_RANGE: c_int = -3;
+
+/// The answer section, for `dns_packet_record_count` and `dns_packet_record`
+pub const DNS_SECTION_ANSWER: c_int = 1;
+/// The authority section
+pub const DNS_SECTION_AUTHORITY: c_int = 2;
+/// The additional section
+pub const DNS_SECTION_ADDITIONAL: c_int = 3;
+
+/// A parsed packet, opaque to C
+#[derive(Debug)]
+pub struct DnsPacket {
+    // Borrows from `data`, dropped before `data` is freed
+    packet: ManuallyDrop<Packet<'static>>,
+    rdata: [Vec<Vec<u8>>; 3],
+    /// The copy of the bytes, from `Box::into_raw`
+    data: *mut [u8],
+}
+
+impl Drop for DnsPacket {
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.packet);
+            drop(Box::from_raw(self.data));
+        }
+    }
+}
+
+/// The header of a packet
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[allow(missing_docs)]
+pub struct DnsHeader {
+    pub id: u16,
+    /// The second 16 bits of the header: QR, opcode, flags and RCODE
+    pub flags: u16,
+    pub questions: u16,
+    pub answers: u16,
+    pub nameservers: u16,
+    pub additional: u16,
+}
+
+/// A question, the name is not NUL-terminated
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[allow(missing_docs)]
+pub struct DnsQuestion {
+    pub name: *const u8,
+    pub name_len: usize,
+    pub qtype: u16,
+    pub qclass: u16,
+    pub prefer_unicast: bool,
+}
+
+/// A resource record, the name is not NUL-terminated
+///
+/// `rdata` is the uncompressed wire form of the RDATA.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[allow(missing_docs)]
+pub struct DnsRecord {
+    pub name: *const u8,
+    pub name_len: usize,
+    pub rtype: u16,
+    pub rclass: u16,
+    pub ttl: u32,
+    pub rdata: *const u8,
+    pub rdata_len: usize,
+}
+
+fn rdata_bytes(record: &ResourceRecord) -> Vec<u8> {
+    match record.data {
+        RData::Unknown(_, data) => data.to_vec(),
+        ref data => data.to_bytes(),
+    }
+}
+
+impl DnsPacket {
+    fn parse(data: &[u8]) -> Option<DnsPacket> {
+        let data = Box::into_raw(Box::<[u8]>::from(data));
+        // The bytes are only freed by `drop`, after the packet
+        let bytes: &'static [u8] = unsafe { &*data };
+        let packet = match Packet::parse(bytes) {
+            Ok(packet) => packet,
+            Err(_) => {
+                drop(unsafe { Box::from_raw(data) });
+                return None;
+            }
+        };
+        let rdata = [
+            packet.answers.iter().map(rdata_bytes).collect(),
+            packet.nameservers.iter().map(rdata_bytes).collect(),
+            packet.additional.iter().map(rdata_bytes).collect(),
+        ];
+        Some(DnsPacket { packet: ManuallyDrop::new(packet), rdata, data })
+    }
+
+    fn section(&self, section: c_int) -> Option<usize> {
+        match section {
+            DNS_SECTION_ANSWER => Some(0),
+            DNS_SECTION_AUTHORITY => Some(1),
+            DNS_SECTION_ADDITIONAL => Some(2),
+            _ => None,
+        }
+    }
+
+    fn records(&self, index: usize) -> &[ResourceRecord<'static>] {
+        match index {
+            0 => &self.packet.answers,
+            1 => &self.packet.nameservers,
+            _ => &self.packet.additional,
+        }
+    }
+}
+
+/// Parses `len` bytes at `data`
+///
+/// On success stores a new packet to `*out`, which must be released with
+/// `dns_packet_free`.
+#[no_mangle]
+pub unsafe extern "C" fn dns_parse(data: *const u8, len: usize,
+    out: *mut *mut DnsPacket)
+    -> c_int
+{
+    if data.is_null() || out.is_null() {
+        return DNS_ERR_NULL;
+    }
+    match DnsPacket::parse(slice::from_raw_parts(data, len)) {
+        Some(packet) => {
+            *out = Box::into_raw(Box::new(packet));
+            DNS_OK
+        }
+        None => {
+            *out = ptr::null_mut();
+            DNS_ERR_MALFORMED
+        }
+    }
+}
+
+/// Releases a packet returned by `dns_parse`, null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_free(packet: *mut DnsPacket) {
+    if !packet.is_null() {
+        drop(Box::from_raw(packet));
+    }
+}
+
+/// Stores the header of the packet to `*out`
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_header(packet: *const DnsPacket,
+    out: *mut DnsHeader)
+    -> c_int
+{
+    if packet.is_null() || out.is_null() {
+        return DNS_ERR_NULL;
+    }
+    let header = &(&*packet).packet.header;
+    *out = DnsHeader {
+        id: header.id,
+        flags: header.flags_u16(),
+        questions: header.questions,
+        answers: header.answers,
+        nameservers: header.nameservers,
+        additional: header.additional,
+    };
+    DNS_OK
+}
+
+/// Returns the number of questions, 0 for a null packet
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_question_count(packet: *const DnsPacket)
+    -> usize
+{
+    if packet.is_null() {
+        return 0;
+    }
+    (&*packet).packet.questions.len()
+}
+
+/// Stores the question at `index` to `*out`
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_question(packet: *const DnsPacket,
+    index: usize, out: *mut DnsQuestion)
+    -> c_int
+{
+    if packet.is_null() || out.is_null() {
+        return DNS_ERR_NULL;
+    }
+    let packet = &*packet;
+    let question = match packet.packet.questions.get(index) {
+        Some(question) => question,
+        None => return DNS_ERR_RANGE,
+    };
+    *out = DnsQuestion {
+        name: question.qname.str_val.as_ptr(),
+        name_len: question.qname.str_val.len(),
+        qtype: question.qtype as u16,
+        qclass: question.qclass as u16,
+        prefer_unicast: question.prefer_unicast,
+    };
+    DNS_OK
+}
+
+/// Returns the number of records in a section, 0 for a bad argument
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_record_count(packet: *const DnsPacket,
+    section: c_int)
+    -> usize
+{
+    if packet.is_null() {
+        return 0;
+    }
+    let packet = &*packet;
+    match packet.section(section) {
+        Some(idx) => packet.records(idx).len(),
+        None => 0,
+    }
+}
+
+/// Stores the record at `index` of a section to `*out`
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_record(packet: *const DnsPacket,
+    section: c_int, index: usize, out: *mut DnsRecord)
+    -> c_int
+{
+    if packet.is_null() || out.is_null() {
+        return DNS_ERR_NULL;
+    }
+    let packet = &*packet;
+    let idx = match packet.section(section) {
+        Some(idx) => idx,
+        None => return DNS_ERR_RANGE,
+    };
+    let record = match packet.records(idx).get(index) {
+        Some(record) => record,
+        None => return DNS_ERR_RANGE,
+    };
+    let rdata = &packet.rdata[idx][index];
+    *out = DnsRecord {
+        name: record.name.str_val.as_ptr(),
+        name_len: record.name.str_val.len(),
+        rtype: record.data.raw_type_code(),
+        rclass: u16::from(record.cls),
+        ttl: record.ttl,
+        rdata: rdata.as_ptr(),
+        rdata_len: rdata.len(),
+    };
+    DNS_OK
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+    use std::slice;
+
+    use super::*;
+
+    #[test]
+    fn parse_and_read() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04\x5d\xb8\xd8\x22";
+        unsafe {
+            let mut packet = ptr::null_mut();
+            assert_eq!(dns_parse(response.as_ptr(), response.len(),
+                                 &mut packet), DNS_OK);
+            let mut header = DnsHeader { id: 0, flags: 0, questions: 0,
+                answers: 0, nameservers: 0, additional: 0 };
+            assert_eq!(dns_packet_header(packet, &mut header), DNS_OK);
+            assert_eq!(header.id, 1573);
+            assert_eq!(header.flags, 0x8180);
+            assert_eq!(dns_packet_question_count(packet), 1);
+
+            let mut q = DnsQuestion { name: ptr::null(), name_len: 0,
+                qtype: 0, qclass: 0, prefer_unicast: false };
+            assert_eq!(dns_packet_question(packet, 0, &mut q), DNS_OK);
+            assert_eq!(slice::from_raw_parts(q.name, q.name_len),
+                b"example.com");
+            assert_eq!(dns_packet_question(packet, 1, &mut q), DNS_ERR_RANGE);
+
+            assert_eq!(dns_packet_record_count(packet, DNS_SECTION_ANSWER), 1);
+            let mut rr = DnsRecord { name: ptr::null(), name_len: 0,
+                rtype: 0, rclass: 0, ttl: 0, rdata: ptr::null(),
+                rdata_len: 0 };
+            assert_eq!(dns_packet_record(packet, DNS_SECTION_ANSWER, 0,
+                                         &mut rr), DNS_OK);
+            assert_eq!((rr.rtype, rr.rclass, rr.ttl), (1, 1, 1272));
+            assert_eq!(slice::from_raw_parts(rr.rdata, rr.rdata_len),
+                b"\x5d\xb8\xd8\x22");
+            assert_eq!(dns_packet_record(packet, 7, 0, &mut rr),
+                DNS_ERR_RANGE);
+            dns_packet_free(packet);
+
+            assert_eq!(dns_parse(response.as_ptr(), 20, &mut packet),
+                DNS_ERR_MALFORMED);
+            assert!(packet.is_null());
+            assert_eq!(dns_parse(ptr::null(), 0, &mut packet), DNS_ERR_NULL);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! A minimal `bitflags`-like macro for the flag words of the protocol
+
+/// Defines a newtype over an integer with named flag constants
+///
+/// Unknown bits are kept, so flags survive a round trip unchanged.
+macro_rules! typed_flags {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident: $bits:ty {
+            $(
+                $(#[$flag_meta:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(PartialEq, Eq, Clone, Copy, Hash, Default)]
+        pub struct $name($bits);
+
+        impl $name {
+            $(
+                $(#[$flag_meta])*
+                pub const $flag: $name = $name($value);
+            )*
+
+            /// No flags set
+            pub fn empty() -> $name {
+                $name(0)
+            }
+            /// Creates the flags from the raw value, keeping unknown bits
+            pub fn from_bits(bits: $bits) -> $name {
+                $name(bits)
+            }
+            /// The raw value
+            pub fn bits(self) -> $bits {
+                self.0
+            }
+            /// Returns true if no flag is set
+            pub fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+            /// Returns true if all the flags in `other` are set
+            pub fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+            /// Sets the flags in `other`
+            pub fn insert(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+            /// Clears the flags in `other`
+            pub fn remove(&mut self, other: $name) {
+                self.0 &= !other.0;
+            }
+            /// Sets or clears the flags in `other`
+            pub fn set(&mut self, other: $name, value: bool) {
+                if value {
+                    self.insert(other);
+                } else {
+                    self.remove(other);
+                }
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = $name;
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl ::std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl ::std::ops::BitAnd for $name {
+            type Output = $name;
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+
+        /// Lists the names of the flags set, e.g. `Flags(RD | RA)`
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, "{}(", stringify!($name))?;
+                let mut rest = self.0;
+                let mut first = true;
+                $(
+                    if self.contains($name::$flag) && $value != 0 {
+                        if !first {
+                            f.write_str(" | ")?;
+                        }
+                        f.write_str(stringify!($flag))?;
+                        first = false;
+                        rest &= !$value;
+                    }
+                )*
+                if rest != 0 {
+                    if !first {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{:#x}", rest)?;
+                }
+                f.write_str(")")
+            }
+        }
+    };
+}
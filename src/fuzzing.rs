@@ -0,0 +1,426 @@
+//! `Arbitrary` implementations for structured fuzzing
+//!
+//! Everything generated here is valid: names consist of lowercase
+//! letters, digits and hyphens, character-strings fit their length
+//! prefix and TTLs fit in 31 bits. So a `BuilderInput` always builds
+//! into a packet that parses, and rebuilding the parsed packet yields the
+//! same bytes, which is the invariant fuzz targets usually check.
+use std::borrow::Cow;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use arbitrary::{Arbitrary, Unstructured, Result};
+
+use {Builder, Class, Name, QueryClass, QueryType, RData};
+use rdata::{A, Aaaa, Caa, Cname, Dnskey, DnskeyFlags, Hinfo, Https, Loc};
+use rdata::{Mx, Naptr, Ns, Ptr, Rrsig, Soa, Srv, Sshfp, Svcb, Tlsa, Txt};
+use rdata::Zonemd;
+
+const LABEL_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-";
+
+fn name_string(u: &mut Unstructured) -> Result<String> {
+    let labels = u.int_in_range(1..=4)?;
+    let mut name = String::new();
+    for i in 0..labels {
+        if i > 0 {
+            name.push('.');
+        }
+        for _ in 0..u.int_in_range(1..=20)? {
+            name.push(*u.choose(LABEL_CHARS)? as char);
+        }
+    }
+    Ok(name)
+}
+
+fn character_string<'a>(u: &mut Unstructured<'a>) -> Result<&'a [u8]> {
+    let len = u.int_in_range(0..=255)?;
+    u.bytes(len)
+}
+
+/// Between `min` and `max` bytes, for keys, digests and the like
+fn blob<'a>(u: &mut Unstructured<'a>, min: usize, max: usize)
+    -> Result<&'a [u8]>
+{
+    let len = u.int_in_range(min..=max)?;
+    u.bytes(len)
+}
+
+impl<'a> Arbitrary<'a> for Name<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Name::from_owned(name_string(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Class {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        use Class::*;
+        u.choose(&[IN, CS, CH, HS, NONE, ANY]).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for QueryClass {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        use QueryClass::*;
+        u.choose(&[IN, CS, CH, HS, QueryClass::None, Any]).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for QueryType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        use QueryType::*;
+        u.choose(&[A, NS, MF, CNAME, SOA, MB, MG, MR, NULL, WKS, PTR,
+                   HINFO, MINFO, MX, TXT, AAAA, SRV, SVCB, HTTPS, CAA,
+                   NAPTR, TLSA, SSHFP, LOC, IXFR, AXFR, MAILB, MAILA,
+                   All]).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for A {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(A(Ipv4Addr::from(u32::arbitrary(u)?)))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Aaaa {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Aaaa(Ipv6Addr::from(u128::arbitrary(u)?)))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Cname<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Cname(Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Ns<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Ns(Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Ptr<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Ptr(Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Hinfo<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Hinfo::new(character_string(u)?, character_string(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Mx<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Mx::new(u16::arbitrary(u)?, Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Soa<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Soa {
+            primary_ns: Name::arbitrary(u)?,
+            mailbox: Name::arbitrary(u)?,
+            serial: u32::arbitrary(u)?,
+            refresh: u32::arbitrary(u)?,
+            retry: u32::arbitrary(u)?,
+            expire: u32::arbitrary(u)?,
+            minimum_ttl: u32::arbitrary(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Srv<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Srv::new(u16::arbitrary(u)?, u16::arbitrary(u)?,
+            u16::arbitrary(u)?, Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Txt {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(0..=600)?;
+        Ok(Txt::from_bytes(u.bytes(len)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Rrsig<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Rrsig {
+            type_covered: u16::arbitrary(u)?,
+            algorithm: u8::arbitrary(u)?.into(),
+            labels: u8::arbitrary(u)?,
+            original_ttl: u32::arbitrary(u)?,
+            expiration: u32::arbitrary(u)?,
+            inception: u32::arbitrary(u)?,
+            key_tag: u16::arbitrary(u)?,
+            signer: Name::arbitrary(u)?,
+            signature: Cow::Borrowed(blob(u, 0, 256)?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Dnskey<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut record = Dnskey::new(DnskeyFlags::from_bits(
+            u16::arbitrary(u)?), u8::arbitrary(u)?.into(), blob(u, 0, 256)?);
+        record.protocol = u8::arbitrary(u)?;
+        Ok(record)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Zonemd<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // The digest is at least 12 octets long
+        Ok(Zonemd::new(u32::arbitrary(u)?, u8::arbitrary(u)?,
+            u8::arbitrary(u)?, blob(u, 12, 64)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Svcb<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut record = Svcb::new(u16::arbitrary(u)?, Name::arbitrary(u)?);
+        for _ in 0..u.int_in_range(0..=4)? {
+            record = record.with_param(u16::arbitrary(u)?, blob(u, 0, 64)?);
+        }
+        Ok(record)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Https<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Https::from(Svcb::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Caa<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut tag = String::new();
+        for _ in 0..u.int_in_range(1..=15)? {
+            tag.push(*u.choose(b"abcdefghijklmnopqrstuvwxyz0123456789")?
+                     as char);
+        }
+        Ok(Caa::new(u8::arbitrary(u)?, tag, blob(u, 0, 256)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Naptr<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Naptr::new(u16::arbitrary(u)?, u16::arbitrary(u)?,
+            character_string(u)?, character_string(u)?,
+            character_string(u)?, Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Tlsa<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Tlsa::new(u8::arbitrary(u)?, u8::arbitrary(u)?,
+            u8::arbitrary(u)?, blob(u, 1, 64)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Sshfp<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Sshfp::new(u8::arbitrary(u)?.into(), u8::arbitrary(u)?.into(),
+            blob(u, 1, 64)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Loc {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Loc {
+            version: u8::arbitrary(u)?,
+            size: u8::arbitrary(u)?,
+            horizontal_precision: u8::arbitrary(u)?,
+            vertical_precision: u8::arbitrary(u)?,
+            latitude: u32::arbitrary(u)?,
+            longitude: u32::arbitrary(u)?,
+            altitude: u32::arbitrary(u)?,
+        })
+    }
+}
+
+/// Generates the record types the crate parses
+///
+/// `Unknown` and `Custom` are never generated.
+impl<'a> Arbitrary<'a> for RData<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=19)? {
+            0 => RData::A(A::arbitrary(u)?),
+            1 => RData::AAAA(Aaaa::arbitrary(u)?),
+            2 => RData::CNAME(Cname::arbitrary(u)?),
+            3 => RData::HINFO(Hinfo::arbitrary(u)?),
+            4 => RData::MX(Mx::arbitrary(u)?),
+            5 => RData::NS(Ns::arbitrary(u)?),
+            6 => RData::PTR(Ptr::arbitrary(u)?),
+            7 => RData::SOA(Soa::arbitrary(u)?),
+            8 => RData::SRV(Srv::arbitrary(u)?),
+            9 => RData::TXT(Txt::arbitrary(u)?),
+            10 => RData::RRSIG(Rrsig::arbitrary(u)?),
+            11 => RData::DNSKEY(Dnskey::arbitrary(u)?),
+            12 => RData::ZONEMD(Zonemd::arbitrary(u)?),
+            13 => RData::SVCB(Svcb::arbitrary(u)?),
+            14 => RData::HTTPS(Https::arbitrary(u)?),
+            15 => RData::CAA(Caa::arbitrary(u)?),
+            16 => RData::NAPTR(Naptr::arbitrary(u)?),
+            17 => RData::TLSA(Tlsa::arbitrary(u)?),
+            18 => RData::SSHFP(Sshfp::arbitrary(u)?),
+            _ => RData::LOC(Loc::arbitrary(u)?),
+        })
+    }
+}
+
+/// Arguments of a `Builder::question` call
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub struct QuestionInput {
+    pub qname: String,
+    pub prefer_unicast: bool,
+    pub qtype: QueryType,
+    pub qclass: QueryClass,
+}
+
+impl<'a> Arbitrary<'a> for QuestionInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(QuestionInput {
+            qname: name_string(u)?,
+            prefer_unicast: bool::arbitrary(u)?,
+            qtype: QueryType::arbitrary(u)?,
+            qclass: QueryClass::arbitrary(u)?,
+        })
+    }
+}
+
+/// Arguments of a `Builder::answer` (or `nameserver`, `additional`) call
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub struct RecordInput<'a> {
+    pub name: String,
+    pub cls: Class,
+    pub data: RData<'a>,
+    pub multicast_unique: bool,
+    pub ttl: u32,
+}
+
+impl<'a> Arbitrary<'a> for RecordInput<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(RecordInput {
+            name: name_string(u)?,
+            cls: Class::arbitrary(u)?,
+            data: RData::arbitrary(u)?,
+            multicast_unique: bool::arbitrary(u)?,
+            ttl: u.int_in_range(0..=i32::MAX as u32)?,
+        })
+    }
+}
+
+/// Everything needed to drive a `Builder`
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub struct BuilderInput<'a> {
+    pub id: u16,
+    pub recursion: bool,
+    pub questions: Vec<QuestionInput>,
+    pub answers: Vec<RecordInput<'a>>,
+    pub nameservers: Vec<RecordInput<'a>>,
+    pub additional: Vec<RecordInput<'a>>,
+}
+
+impl<'a> Arbitrary<'a> for BuilderInput<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(BuilderInput {
+            id: u16::arbitrary(u)?,
+            recursion: bool::arbitrary(u)?,
+            questions: Arbitrary::arbitrary(u)?,
+            answers: Arbitrary::arbitrary(u)?,
+            nameservers: Arbitrary::arbitrary(u)?,
+            additional: Arbitrary::arbitrary(u)?,
+        })
+    }
+}
+
+impl<'a> BuilderInput<'a> {
+    /// Feeds the input to a `Builder` and returns the result of `build`
+    pub fn build(self) -> ::std::result::Result<Vec<u8>, Vec<u8>> {
+        let mut names = Vec::new();
+        let mut records = Vec::new();
+        for (section, list) in vec![self.answers, self.nameservers,
+                                    self.additional].into_iter().enumerate()
+        {
+            for record in list {
+                names.push(record.name);
+                records.push((section, record.cls, record.data,
+                              record.multicast_unique, record.ttl));
+            }
+        }
+        let mut builder = Builder::new(self.id, self.recursion);
+        for q in &self.questions {
+            builder.question(&q.qname, q.prefer_unicast, q.qtype, q.qclass);
+        }
+        for (name, (section, cls, data, unique, ttl))
+            in names.iter().zip(records)
+        {
+            match section {
+                0 => builder.answer(name, cls, data, unique, ttl),
+                1 => builder.nameserver(name, cls, data, unique, ttl),
+                _ => builder.additional(name, cls, data, unique, ttl),
+            };
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use {Packet, Builder};
+    use super::BuilderInput;
+
+    #[test]
+    fn build_parse_rebuild() {
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut data = vec![0u8; 4096];
+        for _ in 0..200 {
+            for byte in data.iter_mut() {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                *byte = seed as u8;
+            }
+            let mut u = Unstructured::new(&data);
+            let input = BuilderInput::arbitrary(&mut u).unwrap();
+            let bytes = input.build().expect("input builds");
+            let packet = Packet::parse(&bytes).unwrap();
+            let qnames = packet.questions.iter()
+                .map(|q| q.qname.to_string()).collect::<Vec<_>>();
+            let sections = vec![packet.answers, packet.nameservers,
+                                packet.additional];
+            let names = sections.iter()
+                .map(|s| s.iter().map(|rr| rr.name.to_string())
+                          .collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let mut builder = Builder::new(packet.header.id,
+                packet.header.recursion_desired);
+            for (q, qname) in packet.questions.iter().zip(&qnames) {
+                builder.question(qname, q.prefer_unicast, q.qtype, q.qclass);
+            }
+            for (i, (section, names)) in sections.into_iter().zip(&names)
+                .enumerate()
+            {
+                for (rr, name) in section.into_iter().zip(names) {
+                    let (cls, unique, ttl) =
+                        (rr.cls, rr.multicast_unique, rr.ttl);
+                    match i {
+                        0 => builder.answer(name, cls, rr.data, unique, ttl),
+                        1 => builder.nameserver(name, cls, rr.data,
+                                                unique, ttl),
+                        _ => builder.additional(name, cls, rr.data,
+                                                unique, ttl),
+                    };
+                }
+            }
+            assert_eq!(builder.build().unwrap(), bytes);
+        }
+    }
+}
@@ -15,9 +15,38 @@ mod flag {
     pub const RESPONSE_CODE_MASK:  u16 = 0b0000_0000_0000_1111;
 }
 
+typed_flags! {
+    /// The single-bit flags of the header
+    ///
+    /// An alternative to the boolean fields of `Header`, see
+    /// `Header::flags`. Opcode and RCODE aren't part of it.
+    pub struct HeaderFlags: u16 {
+        /// The message is a response
+        const QR = flag::QUERY;
+        /// Authoritative answer
+        const AA = flag::AUTHORITATIVE;
+        /// The message is truncated
+        const TC = flag::TRUNCATED;
+        /// Recursion desired
+        const RD = flag::RECURSION_DESIRED;
+        /// Recursion available
+        const RA = flag::RECURSION_AVAILABLE;
+        /// The reserved Z bit
+        const Z = flag::RESERVED_MASK;
+        /// Authentic data (RFC 4035)
+        const AD = flag::AUTHENTICATED_DATA;
+        /// Checking disabled (RFC 4035)
+        const CD = flag::CHECKING_DISABLED;
+    }
+}
+
 /// Represents parsed header of the packet
+///
+/// Fields may be added, so outside of this crate a header can't be built
+/// with a struct literal. Use `Header::from_flags` and set the fields.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[allow(missing_docs)] // fields are from the spec I think
+#[non_exhaustive]
 pub struct Header {
     pub id: u16,
     pub query: bool,
@@ -28,6 +57,11 @@ pub struct Header {
     pub recursion_available: bool,
     pub authenticated_data: bool,
     pub checking_disabled: bool,
+    /// The Z bit, must be zero (RFC 1035, RFC 6840)
+    ///
+    /// `parse` rejects packets with the bit set, it is only there for
+    /// `from_flags` to preserve it.
+    pub reserved: bool,
     pub response_code: ResponseCode,
     pub questions: u16,
     pub answers: u16,
@@ -45,8 +79,21 @@ impl Header {
         if flags & flag::RESERVED_MASK != 0 {
             return Err(Error::ReservedBitsAreNonZero);
         }
-        let header = Header {
-            id: BigEndian::read_u16(&data[..2]),
+        let header = Header::from_flags(BigEndian::read_u16(&data[..2]), flags, [
+            BigEndian::read_u16(&data[4..6]),
+            BigEndian::read_u16(&data[6..8]),
+            BigEndian::read_u16(&data[8..10]),
+            BigEndian::read_u16(&data[10..12]),
+        ]);
+        Ok(header)
+    }
+    /// Creates a header from the ID, the raw flags word and section counts
+    ///
+    /// Counts are in the order of sections: questions, answers,
+    /// nameservers and additional. Unlike `parse`, this accepts the Z bit.
+    pub fn from_flags(id: u16, flags: u16, counts: [u16; 4]) -> Header {
+        Header {
+            id,
             query: flags & flag::QUERY == 0,
             opcode: ((flags & flag::OPCODE_MASK)
                      >> flag::OPCODE_MASK.trailing_zeros()).into(),
@@ -56,23 +103,16 @@ impl Header {
             recursion_available: flags & flag::RECURSION_AVAILABLE != 0,
             authenticated_data: flags & flag::AUTHENTICATED_DATA != 0,
             checking_disabled: flags & flag::CHECKING_DISABLED != 0,
+            reserved: flags & flag::RESERVED_MASK != 0,
             response_code: From::from((flags&flag::RESPONSE_CODE_MASK) as u8),
-            questions: BigEndian::read_u16(&data[4..6]),
-            answers: BigEndian::read_u16(&data[6..8]),
-            nameservers: BigEndian::read_u16(&data[8..10]),
-            additional: BigEndian::read_u16(&data[10..12]),
-        };
-        Ok(header)
-    }
-    /// Write a header to a buffer slice
-    ///
-    /// # Panics
-    ///
-    /// When buffer size is not exactly 12 bytes
-    pub fn write(&self, data: &mut [u8]) {
-        if data.len() != 12 {
-            panic!("Header size is exactly 12 bytes");
+            questions: counts[0],
+            answers: counts[1],
+            nameservers: counts[2],
+            additional: counts[3],
         }
+    }
+    /// Returns the flags word (the second 16 bits of the header)
+    pub fn flags_u16(&self) -> u16 {
         let mut flags = 0u16;
         flags |= Into::<u16>::into(self.opcode)
             << flag::OPCODE_MASK.trailing_zeros();
@@ -82,8 +122,40 @@ impl Header {
         if self.recursion_desired { flags |= flag::RECURSION_DESIRED; }
         if self.recursion_available { flags |= flag::RECURSION_AVAILABLE; }
         if self.truncated { flags |= flag::TRUNCATED; }
+        if self.authenticated_data { flags |= flag::AUTHENTICATED_DATA; }
+        if self.checking_disabled { flags |= flag::CHECKING_DISABLED; }
+        if self.reserved { flags |= flag::RESERVED_MASK; }
+        flags
+    }
+    /// Returns the single-bit flags as a set
+    pub fn flags(&self) -> HeaderFlags {
+        HeaderFlags(self.flags_u16() &
+            !(flag::OPCODE_MASK | flag::RESPONSE_CODE_MASK))
+    }
+    /// Sets the boolean fields from the set of flags
+    ///
+    /// Opcode and RCODE are left unchanged.
+    pub fn set_flags(&mut self, flags: HeaderFlags) {
+        self.query = !flags.contains(HeaderFlags::QR);
+        self.authoritative = flags.contains(HeaderFlags::AA);
+        self.truncated = flags.contains(HeaderFlags::TC);
+        self.recursion_desired = flags.contains(HeaderFlags::RD);
+        self.recursion_available = flags.contains(HeaderFlags::RA);
+        self.reserved = flags.contains(HeaderFlags::Z);
+        self.authenticated_data = flags.contains(HeaderFlags::AD);
+        self.checking_disabled = flags.contains(HeaderFlags::CD);
+    }
+    /// Write a header to a buffer slice
+    ///
+    /// # Panics
+    ///
+    /// When buffer size is not exactly 12 bytes
+    pub fn write(&self, data: &mut [u8]) {
+        if data.len() != 12 {
+            panic!("Header size is exactly 12 bytes");
+        }
         BigEndian::write_u16(&mut data[..2], self.id);
-        BigEndian::write_u16(&mut data[2..4], flags);
+        BigEndian::write_u16(&mut data[2..4], self.flags_u16());
         BigEndian::write_u16(&mut data[4..6], self.questions);
         BigEndian::write_u16(&mut data[6..8], self.answers);
         BigEndian::write_u16(&mut data[8..10], self.nameservers);
@@ -103,9 +175,9 @@ impl Header {
 #[cfg(test)]
 mod test {
 
-    use {Header};
+    use {Header, HeaderFlags};
     use Opcode::*;
-    use ResponseCode::NoError;
+    use ResponseCode::{NoError, NameError};
 
     #[test]
     fn parse_example_query() {
@@ -122,6 +194,7 @@ mod test {
             recursion_available: false,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 0,
@@ -147,6 +220,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 1,
@@ -170,6 +244,7 @@ mod test {
             recursion_available: false,
             authenticated_data: true,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 0,
@@ -193,6 +268,7 @@ mod test {
             recursion_available: false,
             authenticated_data: false,
             checking_disabled: true,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 0,
@@ -200,4 +276,45 @@ mod test {
             additional: 0,
         });
     }
+
+    #[test]
+    fn flags_round_trip() {
+        // response with AD, CD and the Z bit set, rcode NXDOMAIN
+        let flags = 0b1000_0101_1111_0011;
+        let header = Header::from_flags(7, flags, [1, 2, 3, 4]);
+        assert!(!header.query);
+        assert!(header.authoritative);
+        assert!(header.authenticated_data);
+        assert!(header.checking_disabled);
+        assert!(header.reserved);
+        assert_eq!(header.response_code, NameError);
+        assert_eq!(header.additional, 4);
+        assert_eq!(header.flags_u16(), flags);
+
+        let mut data = [0; 12];
+        header.write(&mut data);
+        assert_eq!(&data[2..4], &[0x85, 0xf3]);
+        assert!(Header::parse(&data).is_err());
+    }
+
+    #[test]
+    fn typed_flags() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00";
+        let mut header = Header::parse(response).unwrap();
+        let flags = header.flags();
+        assert_eq!(flags, HeaderFlags::QR | HeaderFlags::RD | HeaderFlags::RA);
+        assert_eq!(format!("{:?}", flags), "HeaderFlags(QR | RD | RA)");
+        assert!(!flags.contains(HeaderFlags::AA | HeaderFlags::RD));
+
+        let mut flags = HeaderFlags::empty();
+        flags.insert(HeaderFlags::RD | HeaderFlags::CD);
+        flags.set(HeaderFlags::CD, false);
+        header.set_flags(flags);
+        assert!(header.query);
+        assert!(header.recursion_desired);
+        assert!(!header.recursion_available);
+        assert!(!header.checking_disabled);
+        assert_eq!(header.flags(), HeaderFlags::RD);
+        assert_eq!(header.flags_u16(), 0x0100);
+    }
 }
@@ -0,0 +1,55 @@
+use std::fmt;
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+/// DNS message ID
+///
+/// Query IDs should be unpredictable, otherwise responses to the query
+/// are easy to spoof (RFC 5452 section 9.2). `Id::random` draws them
+/// from the operating system CSPRNG.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Id(pub u16);
+
+impl Id {
+    /// Generates a random ID using the OS random number generator
+    pub fn random() -> Id {
+        Id(OsRng.next_u32() as u16)
+    }
+}
+
+impl From<u16> for Id {
+    fn from(id: u16) -> Id {
+        Id(id)
+    }
+}
+
+impl From<Id> for u16 {
+    fn from(id: Id) -> u16 {
+        id.0
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Builder, Packet};
+    use super::Id;
+
+    #[test]
+    fn random_ids() {
+        // 16 bits is too few to assert uniqueness, but 32 equal IDs in a row
+        // would mean the generator is broken
+        let ids: Vec<_> = (0..32).map(|_| Id::random()).collect();
+        assert!(ids.iter().any(|&x| x != ids[0]));
+
+        let (bld, id) = Builder::new_random_id(true);
+        let data = bld.build().unwrap();
+        assert_eq!(Packet::parse(&data).unwrap().header.id, u16::from(id));
+    }
+}
@@ -0,0 +1,106 @@
+use smallvec::SmallVec;
+
+use std::ops::Range;
+
+use {Header, Packet, Question, ResourceRecord, Error, ParseOptions, Section};
+use {SkippedSection};
+use parser::{parse_into, Sections};
+use rdata::opt::Record as Opt;
+
+/// Questions stored inline, almost every packet has exactly one
+pub type InlineQuestions<'a> = SmallVec<[Question<'a>; 1]>;
+/// Records stored inline up to a handful per section
+pub type InlineRecords<'a> = SmallVec<[ResourceRecord<'a>; 4]>;
+
+/// A packet whose sections are stored inline when they are small
+///
+/// Returned by `Packet::parse_inline`. A section spills to the heap only
+/// when it has more entries than fit inline, so parsing a typical query
+/// or response doesn't allocate for the sections at all. Decoded names
+/// and TXT data are still allocated on the heap.
+#[derive(Debug)]
+#[allow(missing_docs)]  // same fields as in Packet
+pub struct InlinePacket<'a> {
+    pub header: Header,
+    pub questions: InlineQuestions<'a>,
+    pub answers: InlineRecords<'a>,
+    pub nameservers: InlineRecords<'a>,
+    pub additional: InlineRecords<'a>,
+    pub opt: Option<Opt<'a>>,
+    pub skipped: Vec<SkippedSection>,
+}
+
+#[derive(Default)]
+struct InlineSections<'a> {
+    questions: InlineQuestions<'a>,
+    answers: InlineRecords<'a>,
+    nameservers: InlineRecords<'a>,
+    additional: InlineRecords<'a>,
+    skipped: Vec<SkippedSection>,
+}
+
+impl<'a> Sections<'a> for InlineSections<'a> {
+    fn reserve(&mut self, header: &Header) {
+        self.questions.reserve(header.questions as usize);
+        self.answers.reserve(header.answers as usize);
+        self.nameservers.reserve(header.nameservers as usize);
+        self.additional.reserve(header.additional as usize);
+    }
+    fn question(&mut self, question: Question<'a>) {
+        self.questions.push(question);
+    }
+    fn record(&mut self, section: Section, record: ResourceRecord<'a>) {
+        match section {
+            Section::Question => unreachable!(),
+            Section::Answer => self.answers.push(record),
+            Section::Authority => self.nameservers.push(record),
+            Section::Additional => self.additional.push(record),
+        }
+    }
+    fn skipped(&mut self, section: Section, range: Range<usize>) {
+        self.skipped.push(SkippedSection { section, range });
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Parse a packet keeping small sections inline
+    pub fn parse_inline(data: &'a [u8]) -> Result<InlinePacket<'a>, Error> {
+        Packet::parse_inline_with_options(data, &ParseOptions::default())
+    }
+
+    /// Same as `parse_inline` but with non-default settings
+    pub fn parse_inline_with_options(data: &'a [u8], options: &ParseOptions)
+        -> Result<InlinePacket<'a>, Error>
+    {
+        let mut sections = InlineSections::default();
+        let (header, opt) = parse_into(data, options, &mut sections)?;
+        Ok(InlinePacket {
+            header,
+            questions: sections.questions,
+            answers: sections.answers,
+            nameservers: sections.nameservers,
+            additional: sections.additional,
+            opt,
+            skipped: sections.skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Packet, RData};
+
+    #[test]
+    fn parse_inline() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04\x5d\xb8\xd8\x22";
+        let packet = Packet::parse_inline(response).unwrap();
+        assert_eq!(packet.header.id, 1573);
+        assert!(!packet.questions.spilled());
+        assert!(!packet.answers.spilled());
+        assert_eq!(&packet.questions[0].qname.to_string()[..], "example.com");
+        assert!(matches!(packet.answers[0].data, RData::A(..)));
+    }
+}
@@ -1,4 +1,4 @@
-#![recursion_limit="100"]
+#![recursion_limit="256"]
 //! The network-agnostic DNS parser library
 //!
 //! [Documentation](https://docs.rs/dns-parser) |
@@ -19,21 +19,89 @@ extern crate byteorder;
 #[cfg(test)] #[macro_use] extern crate matches;
 #[macro_use(quick_error)] extern crate quick_error;
 #[cfg(feature = "with-serde")] #[macro_use] extern crate serde_derive;
+#[cfg(feature = "tokio")] extern crate tokio_util;
+#[cfg(feature = "bytes")] extern crate bytes;
+#[cfg(feature = "idna")] extern crate idna;
+#[cfg(feature = "rand")] extern crate rand;
+#[cfg(feature = "siphash")] extern crate siphasher;
+#[cfg(any(feature = "zonemd", feature = "crypto"))] extern crate sha2;
+#[cfg(feature = "bumpalo")] extern crate bumpalo;
+#[cfg(feature = "smallvec")] extern crate smallvec;
+#[cfg(feature = "arbitrary")] extern crate arbitrary;
+#[cfg(feature = "trace")] #[macro_use] extern crate log;
+#[cfg(feature = "proptest")] extern crate proptest;
+#[cfg(feature = "wasm")] extern crate wasm_bindgen;
+#[cfg(feature = "wasm")] extern crate js_sys;
 
+#[macro_use] mod flags;
+#[macro_use] mod trace;
 mod enums;
 mod structs;
 mod name;
 mod parser;
+mod packet;
+mod validate;
+mod salvage;
 mod error;
 mod header;
 mod builder;
+mod stream;
+mod base64;
+mod compress;
+mod wire;
+mod pretty;
+mod response;
+mod qmin;
+mod update;
+#[cfg(test)] mod robustness;
+#[cfg(feature = "tokio")] mod codec;
+#[cfg(feature = "bytes")] mod bytes_packet;
+#[cfg(feature = "rand")] mod id;
+#[cfg(feature = "bumpalo")] mod arena;
+#[cfg(feature = "smallvec")] mod inline;
 
 pub mod rdata;
+pub mod mdns;
+pub mod dnssd;
+pub mod doh;
+pub mod edns;
+pub mod dso;
+pub mod dnssec;
+pub mod stamp;
+pub mod xfr;
+pub mod bailiwick;
+pub mod visit;
+#[cfg(feature = "siphash")] pub mod cookie;
+#[cfg(feature = "zonemd")] pub mod zonemd;
+#[cfg(feature = "arbitrary")] pub mod fuzzing;
+#[cfg(feature = "proptest")] pub mod strategy;
+#[cfg(feature = "ffi")] pub mod ffi;
+#[cfg(feature = "wasm")] pub mod wasm;
+pub mod zone;
 
 pub use enums::{Type, QueryType, Class, QueryClass, ResponseCode, Opcode};
-pub use structs::{Question, ResourceRecord, Packet};
+pub use structs::{Question, ResourceRecord, Packet, SkippedSection};
+pub use parser::{ParseOptions};
 pub use name::{Name};
+#[cfg(feature = "idna")] pub use name::{DisplayUnicode};
+pub use qmin::{Minimizer, MinimizedQuery};
 pub use error::{Error};
-pub use header::{Header};
+pub use header::{Header, HeaderFlags};
+pub use validate::{Section, Violation};
+pub use salvage::{SalvagedPacket, SalvageError};
 pub use rdata::{RData};
-pub use builder::{Builder};
+pub use builder::{Builder, RRsetOrder, RRsetTtl, CompressionMap};
+pub use stream::{StreamDecoder};
+pub use wire::{WireEncode};
+pub use pretty::{Dig};
+pub use response::{Referral, ResponseKind, Negative, NegativeResponse};
+pub use response::{CacheEntry, CachedData, ServiceCandidate};
+pub use update::{UpdateOp};
+pub use packet::{Records, RecordsOf, TypedRecord, TypedRecords};
+#[cfg(feature = "tokio")] pub use codec::{DnsCodec};
+#[cfg(feature = "bytes")] pub use bytes_packet::{BytesPacket, BytesQuestion};
+#[cfg(feature = "bytes")] pub use bytes_packet::{BytesRecord, BytesOpt};
+#[cfg(feature = "rand")] pub use id::{Id};
+#[cfg(feature = "bumpalo")] pub use arena::{ArenaPacket};
+#[cfg(feature = "smallvec")]
+pub use inline::{InlinePacket, InlineQuestions, InlineRecords};
@@ -0,0 +1,256 @@
+//! Helpers for multicast DNS (RFC 6762)
+//!
+//! The wire format of mDNS is the same as for unicast DNS, these are the
+//! few bits of semantics responders and queriers need on top of it.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use {Packet, ResourceRecord};
+
+/// The UDP port multicast DNS is sent to and from
+pub const PORT: u16 = 5353;
+
+/// Maximum TTL of records in a response to a legacy unicast query
+pub const LEGACY_UNICAST_MAX_TTL: u32 = 10;
+
+/// Time between probes (RFC 6762 section 8.1)
+pub const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Number of probes sent before a name may be claimed
+pub const PROBE_COUNT: usize = 3;
+
+/// Time between the first two announcements (RFC 6762 section 8.3)
+pub const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// TTL a cache keeps a goodbye record with (RFC 6762 section 10.1)
+///
+/// Records received with TTL 0 are deleted one second later, so that
+/// a responder can still rescue them.
+pub const GOODBYE_TTL: u32 = 1;
+
+/// The way a responder must send its response to a query
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ResponseMode {
+    /// Send the response to the multicast group
+    Multicast,
+    /// Unicast responses were requested with the QU bit
+    ///
+    /// The responder may still multicast records that it has not
+    /// multicast recently (RFC 6762 section 5.4).
+    Unicast,
+    /// The query came from a simple resolver not listening on port 5353
+    ///
+    /// The response must be unicast back to the source, repeat the query
+    /// ID and the question, use TTLs no greater than
+    /// `LEGACY_UNICAST_MAX_TTL` and must not set the cache-flush bit
+    /// (RFC 6762 section 6.7).
+    LegacyUnicast,
+}
+
+/// Returns true if a query sent from `source_port` is a legacy unicast one
+pub fn is_legacy_unicast(source_port: u16) -> bool {
+    source_port != PORT
+}
+
+/// Returns true if any question of the packet has the QU bit set
+pub fn wants_unicast_response(packet: &Packet) -> bool {
+    packet.questions.iter().any(|q| q.prefer_unicast)
+}
+
+/// Determines how a query received from `source_port` must be answered
+pub fn response_mode(packet: &Packet, source_port: u16) -> ResponseMode {
+    if is_legacy_unicast(source_port) {
+        ResponseMode::LegacyUnicast
+    } else if wants_unicast_response(packet) {
+        ResponseMode::Unicast
+    } else {
+        ResponseMode::Multicast
+    }
+}
+
+/// Clamps a record TTL for use in a legacy unicast response
+pub fn legacy_unicast_ttl(ttl: u32) -> u32 {
+    ttl.min(LEGACY_UNICAST_MAX_TTL)
+}
+
+/// Compares two records for the simultaneous probe tiebreak
+///
+/// Records are compared by class, without the cache-flush bit, then by
+/// type and then by the bytes of the uncompressed RDATA, as RFC 6762
+/// section 8.2 defines. The greater record is lexicographically later.
+pub fn compare_records(a: &ResourceRecord, b: &ResourceRecord) -> Ordering {
+    u16::from(a.cls).cmp(&u16::from(b.cls))
+        .then(a.data.raw_type_code().cmp(&b.data.raw_type_code()))
+        .then_with(|| a.data.to_bytes().cmp(&b.data.to_bytes()))
+}
+
+/// Breaks the tie between our probe and a simultaneous one for the name
+///
+/// `ours` and `theirs` are the records proposed for the same name in the
+/// authority sections of the two probes. Both are sorted and compared
+/// record by record with `compare_records`, the first difference decides
+/// and if one list runs out first the other one is greater (RFC 6762
+/// section 8.2.1).
+///
+/// `Greater` means we win and keep probing. `Less` means we lose, and
+/// must wait one second and probe again. `Equal` means both hosts
+/// proposed the same records, so there is no conflict.
+pub fn tiebreak(ours: &[ResourceRecord], theirs: &[ResourceRecord])
+    -> Ordering
+{
+    let mut ours: Vec<_> = ours.iter().collect();
+    let mut theirs: Vec<_> = theirs.iter().collect();
+    ours.sort_by(|a, b| compare_records(a, b));
+    theirs.sort_by(|a, b| compare_records(a, b));
+    for (a, b) in ours.iter().zip(&theirs) {
+        match compare_records(a, b) {
+            Ordering::Equal => {}
+            order => return order,
+        }
+    }
+    ours.len().cmp(&theirs.len())
+}
+
+#[cfg(test)]
+mod test {
+    use {Builder, Packet};
+    use QueryType as QT;
+    use QueryClass as QC;
+    use super::*;
+
+    #[test]
+    fn modes() {
+        let mut bld = Builder::new(0, false);
+        bld.question("host.local", false, QT::A, QC::IN);
+        let qm = bld.build().unwrap();
+        let qm = Packet::parse(&qm).unwrap();
+
+        let mut bld = Builder::new(0, false);
+        bld.question("host.local", true, QT::A, QC::IN);
+        let qu = bld.build().unwrap();
+        let qu = Packet::parse(&qu).unwrap();
+
+        assert!(!wants_unicast_response(&qm));
+        assert!(wants_unicast_response(&qu));
+        assert_eq!(response_mode(&qm, PORT), ResponseMode::Multicast);
+        assert_eq!(response_mode(&qu, PORT), ResponseMode::Unicast);
+        assert_eq!(response_mode(&qm, 49152), ResponseMode::LegacyUnicast);
+        assert_eq!(response_mode(&qu, 49152), ResponseMode::LegacyUnicast);
+    }
+
+    #[test]
+    fn goodbye() {
+        use std::net::Ipv4Addr;
+        use {Class, RData};
+
+        let mut bld = Builder::mdns_response();
+        bld.answer("host.local", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), true, 120);
+        bld.goodbye("host.local", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 2).into()), true);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.header.id, 0);
+        assert!(!packet.header.query && packet.header.authoritative);
+        let goodbyes: Vec<_> = packet.answers.iter()
+            .map(|r| r.is_goodbye()).collect();
+        assert_eq!(goodbyes, [false, true]);
+        assert!(packet.answers[1].multicast_unique);
+    }
+
+    #[test]
+    fn probe_and_announce() {
+        use std::net::Ipv4Addr;
+        use {Class, Name, RData, ResourceRecord};
+        use rdata::Ptr;
+
+        let records = || vec![
+            ResourceRecord {
+                name: Name::from_string("host.local"),
+                multicast_unique: true,
+                cls: Class::IN,
+                ttl: 120,
+                data: RData::A(Ipv4Addr::new(192, 0, 2, 1).into()),
+            },
+            ResourceRecord {
+                name: Name::from_string("HOST.local"),
+                multicast_unique: true,
+                cls: Class::IN,
+                ttl: 120,
+                data: RData::A(Ipv4Addr::new(192, 0, 2, 2).into()),
+            },
+            ResourceRecord {
+                name: Name::from_string("1.2.0.192.in-addr.arpa"),
+                multicast_unique: true,
+                cls: Class::IN,
+                ttl: 120,
+                data: RData::PTR(Ptr(Name::from_string("host.local"))),
+            },
+        ];
+
+        let data = Builder::mdns_probe(records()).build().unwrap();
+        let probe = Packet::parse(&data).unwrap();
+        assert!(probe.header.query);
+        let questions: Vec<_> = probe.questions.iter()
+            .map(|q| (q.qname.to_string(), q.qtype, q.prefer_unicast))
+            .collect();
+        assert_eq!(questions, [
+            ("host.local".to_string(), QT::All, true),
+            ("1.2.0.192.in-addr.arpa".to_string(), QT::All, true),
+        ]);
+        assert_eq!(probe.nameservers.len(), 3);
+        assert!(probe.answers.is_empty());
+        assert!(probe.nameservers.iter().all(|r| !r.multicast_unique));
+
+        let data = Builder::mdns_announcement(records()).build().unwrap();
+        let announcement = Packet::parse(&data).unwrap();
+        assert!(!announcement.header.query);
+        assert!(announcement.header.authoritative);
+        assert!(announcement.questions.is_empty());
+        assert_eq!(announcement.answers.len(), 3);
+        assert!(announcement.answers.iter().all(|r| r.multicast_unique));
+    }
+
+    #[test]
+    fn tiebreak_records() {
+        use std::net::Ipv4Addr;
+        use {Class, Name, RData, ResourceRecord};
+        use rdata::Hinfo;
+
+        fn a(last: u8) -> ResourceRecord<'static> {
+            ResourceRecord {
+                name: Name::from_string("host.local"),
+                multicast_unique: true,
+                cls: Class::IN,
+                ttl: 120,
+                data: RData::A(Ipv4Addr::new(169, 254, 99, last).into()),
+            }
+        }
+        let hinfo = ResourceRecord {
+            name: Name::from_string("host.local"),
+            multicast_unique: false,
+            cls: Class::IN,
+            ttl: 120,
+            data: RData::HINFO(Hinfo::new(b"x86", b"Linux")),
+        };
+
+        // The example of RFC 6762 section 8.2
+        assert_eq!(compare_records(&a(200), &a(201)), Ordering::Less);
+        // Type A (1) sorts before HINFO (13), whatever the RDATA
+        assert_eq!(compare_records(&a(255), &hinfo), Ordering::Less);
+
+        assert_eq!(tiebreak(&[a(201)], &[a(200)]), Ordering::Greater);
+        assert_eq!(tiebreak(&[a(200)], &[a(201)]), Ordering::Less);
+        assert_eq!(tiebreak(&[a(2), a(1)], &[a(1), a(2)]), Ordering::Equal);
+        // After the same first records, more records win
+        assert_eq!(tiebreak(&[a(1)], &[a(1), a(2)]), Ordering::Less);
+        assert_eq!(tiebreak(&[a(1), hinfo], &[a(1)]), Ordering::Greater);
+    }
+
+    #[test]
+    fn ttl_clamp() {
+        assert_eq!(legacy_unicast_ttl(4500), 10);
+        assert_eq!(legacy_unicast_ttl(5), 5);
+    }
+}
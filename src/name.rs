@@ -1,5 +1,5 @@
 use std::fmt;
-use std::fmt::Write;
+use std::io::{self, Write};
 use std::str::from_utf8;
 
 // Deprecated since rustc 1.23
@@ -8,7 +8,11 @@ use std::ascii::AsciiExt;
 
 use byteorder::{BigEndian, ByteOrder};
 
-use {Error};
+use {Error, WireEncode};
+
+/// The extended label type of RFC 2673 binary labels, deprecated by
+/// RFC 6891 section 5
+const BINARY_LABEL: u8 = 0x41;
 
 /// The DNS name as stored in the original packet
 ///
@@ -29,6 +33,35 @@ impl<'a> Name<'a> {
     /// The `original` is the data starting a the start of a packet, so
     /// that offsets in compressed name starts from the `original`.
     pub fn scan(data: &'a[u8], original: &'a[u8]) -> Result<Name<'a>, Error> {
+        let len = Name::skip(data, original)?;
+        Ok(Name {
+            labels: &data[..len],
+            str_val: Name::to_string(&data[..len], original),
+        })
+    }
+
+    /// Validates the name at the start of `data` without decoding it
+    ///
+    /// Returns the number of bytes the name occupies in `data`, i.e. up to
+    /// and including the terminating zero or the first pointer. As with
+    /// `scan`, pointers are followed and checked against `original`, but
+    /// nothing is allocated.
+    pub fn skip(data: &[u8], original: &[u8]) -> Result<usize, Error> {
+        Name::skip_labels(data, original, false)
+    }
+
+    /// Same as `skip`, but steps over binary labels if `binary_labels`
+    /// is set
+    ///
+    /// Labels with the high bits `01` fail with
+    /// `Error::BinaryLabelUnsupported`, but if `binary_labels` is set the
+    /// length of binary labels is worked out from their bit count, so
+    /// that records with such names can be skipped. Other extended label
+    /// types still fail, their length is unknown.
+    pub(crate) fn skip_labels(data: &[u8], original: &[u8],
+        binary_labels: bool)
+        -> Result<usize, Error>
+    {
         let mut parse_data = data;
         let mut return_pos = None;
         let mut pos = 0;
@@ -55,7 +88,7 @@ impl<'a> Name<'a> {
                 // Set value for return_pos which is the pos in the original
                 // data buffer that should be used to return after validating
                 // the offsetted labels.
-                if let None = return_pos {
+                if return_pos.is_none() {
                     return_pos = Some(pos);
                 }
 
@@ -79,51 +112,99 @@ impl<'a> Name<'a> {
                 if parse_data.len() <= pos {
                     return Err(Error::UnexpectedEOF);
                 }
+            } else if byte & 0b1100_0000 == 0b0100_0000 {
+                if !binary_labels || byte != BINARY_LABEL {
+                    return Err(Error::BinaryLabelUnsupported);
+                }
+                // A count of bits, zero meaning 256, then the bits
+                let bits = match parse_data.get(pos + 1) {
+                    Some(&0) => 256,
+                    Some(&count) => count as usize,
+                    None => return Err(Error::UnexpectedEOF),
+                };
+                pos += 2 + bits.div_ceil(8);
+                if parse_data.len() <= pos {
+                    return Err(Error::UnexpectedEOF);
+                }
             } else {
                 return Err(Error::UnknownLabelFormat);
             }
             byte = parse_data[pos];
         }
         if let Some(return_pos) = return_pos {
-            return Ok(Name {
-                labels: &data[..return_pos+2], 
-                str_val: Name::to_string(data[..return_pos+2].to_vec(), original.to_vec())
-            });
+            Ok(return_pos+2)
         } else {
-            return Ok(Name {
-                labels: &data[..pos+1], 
-                str_val: Name::to_string(data[..pos+1].to_vec(), original.to_vec())
-            });
+            Ok(pos+1)
         }
     }
 
     /// Creates a Name from a raw string value
-    pub fn from_string(name: &str) -> Name {
+    ///
+    /// The name is copied, so it can be used with any lifetime.
+    pub fn from_string(name: &str) -> Name<'static> {
         Name { labels: &[], str_val: String::from(name) }
     }
 
+    /// Creates a Name owning its text
+    pub fn from_owned(name: String) -> Name<'static> {
+        Name { labels: &[], str_val: name }
+    }
+
+    /// Detaches the name from the packet it was parsed from
+    ///
+    /// The text is kept, but the name no longer knows where it was in the
+    /// packet.
+    pub fn into_owned(self) -> Name<'static> {
+        Name::from_owned(self.str_val)
+    }
+
+    /// Checks that a dotted name can be encoded on the wire
+    ///
+    /// A single trailing dot is allowed, and `""` and `"."` are the root.
+    /// Fails with `Error::InvalidName` on empty labels (as in `a..b`),
+    /// labels longer than 63 bytes and names longer than 255 bytes.
+    pub fn check(name: &str) -> Result<(), Error> {
+        let name = without_trailing_dot(name);
+        if name.is_empty() {
+            return Ok(());
+        }
+        if name.len() + 2 > 255 {
+            return Err(Error::InvalidName("name is longer than 255 bytes"));
+        }
+        for label in name.split('.') {
+            if label.is_empty() {
+                return Err(Error::InvalidName("empty label"));
+            }
+            if label.len() > 63 {
+                return Err(Error::InvalidName(
+                    "label is longer than 63 bytes"));
+            }
+        }
+        Ok(())
+    }
+
     /// Converts a Name to the on-the-wire byte representation
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::new();
-        for part in self.str_val.split('.') {
-            assert!(part.len() < 63);
-            let ln = part.len() as u8;
-            buf.push(ln);
-            buf.extend(part.as_bytes());
-        }
-        buf.push(0);
+        let mut buf = Vec::with_capacity(self.octet_length() as usize);
+        self.write_to(&mut buf).expect("writing to a Vec can't fail");
         buf
     }
 
     /// Returns the on-the-wire length in octets
     pub fn octet_length(&self) -> u16 {
-        self.str_val.len() as u16 + 2 
+        match without_trailing_dot(&self.str_val) {
+            "" => 1,
+            name => name.len() as u16 + 2,
+        }
     }
 
-    fn to_string(labels: Vec<u8>, original: Vec<u8>) -> String {
-        let mut val = String::from("");
-        let data = labels;
-        let original = original;
+    /// Decodes a name already validated by `scan`
+    ///
+    /// Works on slices of the packet and follows pointers in a loop, so
+    /// nothing is copied except the label text itself.
+    fn to_string(labels: &[u8], original: &[u8]) -> String {
+        let mut val = String::new();
+        let mut data = labels;
         let mut pos = 0;
         loop {
             let byte = data[pos];
@@ -132,34 +213,155 @@ impl<'a> Name<'a> {
             } else if byte & 0b1100_0000 == 0b1100_0000 {
                 let off = (BigEndian::read_u16(&data[pos..pos+2])
                            & !0b1100_0000_0000_0000) as usize;
-                if pos != 0 {
-                    val.write_char('.').unwrap();
-                }
-                val.extend(Name::to_string(original[off..].to_vec(), original).chars());
-                return val
+                data = &original[off..];
+                pos = 0;
             } else if byte & 0b1100_0000 == 0 {
-                if pos != 0 {
-                    val.write_char('.').unwrap();
+                if !val.is_empty() {
+                    val.push('.');
                 }
                 let end = pos + byte as usize + 1;
-                val.write_str(from_utf8(&data[pos+1..end]).unwrap()).unwrap();
+                val.push_str(from_utf8(&data[pos+1..end]).unwrap());
                 pos = end;
-                continue;
             } else {
                 unreachable!();
             }
         }
     }
 
+    /// Compares with a dotted name ignoring ASCII case
+    ///
+    /// A trailing dot on either side is ignored. Nothing is allocated.
+    pub fn eq_ignore_case(&self, other: &str) -> bool {
+        let own = self.str_val.trim_end_matches('.');
+        own.eq_ignore_ascii_case(other.trim_end_matches('.'))
+    }
+
+    /// Returns true if the name is `zone` or below it, ignoring ASCII case
+    ///
+    /// Every name is below the root, given as `""` or `"."`.
+    pub fn is_subdomain_of(&self, zone: &str) -> bool {
+        let name = self.str_val.trim_end_matches('.');
+        let zone = zone.trim_end_matches('.');
+        if zone.is_empty() {
+            return true;
+        }
+        if name.len() < zone.len()
+            || !name.is_char_boundary(name.len() - zone.len())
+        {
+            return false;
+        }
+        let (head, tail) = name.split_at(name.len() - zone.len());
+        tail.eq_ignore_ascii_case(zone)
+            && (head.is_empty() || head.ends_with('.'))
+    }
+
+    /// Offset of the name in `original` if it was parsed from it
+    pub(crate) fn offset_in(&self, original: &[u8]) -> Option<usize> {
+        let start = original.as_ptr() as usize;
+        let pos = self.labels.as_ptr() as usize;
+        if self.labels.is_empty() || pos < start
+            || pos >= start + original.len()
+        {
+            return None;
+        }
+        Some(pos - start)
+    }
+
+    /// The first label as it is on the wire, if the name was parsed from
+    /// a packet and doesn't start with a pointer
+    ///
+    /// Unlike the text, the raw label tells a dot inside a label apart
+    /// from a label separator.
+    pub(crate) fn first_wire_label(&self) -> Option<&'a [u8]> {
+        let len = *self.labels.first()? as usize;
+        if len == 0 || len & 0b1100_0000 != 0 {
+            return None;
+        }
+        self.labels.get(1..1 + len)
+    }
+
+    /// Displays the name with A-labels decoded to Unicode
+    ///
+    /// Meant for output shown to users, `xn--bcher-kva.example` displays
+    /// as `bücher.example`. Labels that aren't valid punycode are shown as
+    /// they are. The `Display` of `Name` itself always gives the exact
+    /// ASCII form.
+    #[cfg(feature = "idna")]
+    pub fn display_unicode(&self) -> DisplayUnicode<'_> {
+        DisplayUnicode(&self.str_val)
+    }
+
     /// Number of bytes serialized name occupies
     pub fn byte_len(&self) -> usize {
         self.labels.len()
     }
 }
 
+impl<'a> WireEncode for Name<'a> {
+    /// Fails with `InvalidInput` if `Name::check` rejects the name
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        Name::check(&self.str_val)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut len = 1;
+        let name = without_trailing_dot(&self.str_val);
+        if !name.is_empty() {
+            for part in name.split('.') {
+                w.write_all(&[part.len() as u8])?;
+                w.write_all(part.as_bytes())?;
+                len += part.len() + 1;
+            }
+        }
+        w.write_all(&[0])?;
+        Ok(len)
+    }
+}
+
+/// Strips one trailing dot, `"."` becomes the root `""`
+fn without_trailing_dot(name: &str) -> &str {
+    name.strip_suffix('.').unwrap_or(name)
+}
+
+/// Names compare case-insensitively, see `Name::eq_ignore_case`
+impl<'a> PartialEq<str> for Name<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.eq_ignore_case(other)
+    }
+}
+impl<'a, 'b> PartialEq<&'b str> for Name<'a> {
+    fn eq(&self, other: &&'b str) -> bool {
+        self.eq_ignore_case(other)
+    }
+}
+
+/// Formats a name with Unicode labels, see `Name::display_unicode`
+#[cfg(feature = "idna")]
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayUnicode<'n>(&'n str);
+
+#[cfg(feature = "idna")]
+impl<'n> fmt::Display for DisplayUnicode<'n> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, label) in self.0.split('.').enumerate() {
+            if i > 0 {
+                fmt.write_str(".")?;
+            }
+            let is_a_label = label.len() > 4 && label.as_bytes().get(..4)
+                .is_some_and(|p| p.eq_ignore_ascii_case(b"xn--"));
+            if is_a_label {
+                if let (unicode, Ok(())) = ::idna::domain_to_unicode(label) {
+                    fmt.write_str(&unicode)?;
+                    continue;
+                }
+            }
+            fmt.write_str(label)?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> fmt::Display for Name<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        return fmt.write_str(&self.str_val);
+        fmt.write_str(&self.str_val)
     }
 }
 impl<'a> fmt::Debug for Name<'a> {
@@ -215,4 +417,96 @@ mod test {
         assert_eq!(Name::scan(&buf[9..], buf).unwrap().labels,
             b"\x02zz\xc0\x04");
     }
+
+    #[test]
+    fn compare_with_str() {
+        let buf = b"\x07Example\x03COM\x00";
+        let name = Name::scan(&buf[..], buf).unwrap();
+        assert!(name.eq_ignore_case("example.com"));
+        assert!(name.eq_ignore_case("example.com."));
+        assert!(!name.eq_ignore_case("example.co"));
+        assert!(!name.eq_ignore_case("www.example.com"));
+        assert!(name == "EXAMPLE.com");
+        assert!(name != "example.org");
+        assert!(name == *"example.com");
+    }
+
+    #[test]
+    fn skip() {
+        let buf = b"\x02xx\x00\x02yy\xc0\x00\x01";
+        assert_eq!(Name::skip(&buf[..], buf), Ok(4));
+        assert_eq!(Name::skip(&buf[4..], buf), Ok(5));
+        assert_eq!(Name::skip(&buf[9..], buf), Err(Error::UnexpectedEOF));
+        assert_eq!(Name::skip(b"\xc0\x00", b"\xc0\x00"),
+            Err(Error::BadPointer));
+    }
+
+    #[test]
+    fn binary_labels() {
+        // \[b11010000/4].example, then an unknown extended label type
+        let buf = b"\x41\x04\xd0\x07example\x00\x42\x00";
+        assert_eq!(Name::scan(&buf[..], buf).unwrap_err(),
+            Error::BinaryLabelUnsupported);
+        assert_eq!(Name::skip_labels(&buf[..], buf, true), Ok(12));
+        assert_eq!(Name::skip_labels(&buf[12..], buf, true),
+            Err(Error::BinaryLabelUnsupported));
+        assert_eq!(Name::skip_labels(b"\x41\x00", b"", true),
+            Err(Error::UnexpectedEOF));
+        assert_eq!(Name::skip(b"\x80\x00", b"\x80\x00"),
+            Err(Error::UnknownLabelFormat));
+    }
+
+    #[test]
+    fn subdomain() {
+        let name = Name::from_string("www.Example.com");
+        assert!(name.is_subdomain_of("example.com"));
+        assert!(name.is_subdomain_of("www.example.com."));
+        assert!(name.is_subdomain_of("com"));
+        assert!(name.is_subdomain_of("."));
+        assert!(!name.is_subdomain_of("ample.com"));
+        assert!(!name.is_subdomain_of("example.org"));
+        assert!(!name.is_subdomain_of("a.www.example.com"));
+    }
+
+    #[cfg(feature = "idna")]
+    #[test]
+    fn display_unicode() {
+        let name = Name::from_string("www.xn--bcher-kva.Example");
+        assert_eq!(name.display_unicode().to_string(), "www.bücher.Example");
+        assert_eq!(name.to_string(), "www.xn--bcher-kva.Example");
+        let bad = Name::from_string("xn--a.xn--bcher-kva");
+        assert_eq!(bad.display_unicode().to_string(), "xn--a.bücher");
+        let raw = Name::from_string("abc\u{e9}.x\u{e9}.com");
+        assert_eq!(raw.display_unicode().to_string(), "abc\u{e9}.x\u{e9}.com");
+    }
+
+    #[test]
+    fn root_and_trailing_dot() {
+        use WireEncode;
+
+        for &(name, bytes) in &[
+            ("example.com.", &b"\x07example\x03com\x00"[..]),
+            ("example.com", &b"\x07example\x03com\x00"[..]),
+            (".", &b"\x00"[..]),
+            ("", &b"\x00"[..]),
+        ] {
+            let name = Name::from_string(name);
+            assert_eq!(name.to_bytes(), bytes);
+            assert_eq!(name.octet_length() as usize, bytes.len());
+        }
+
+        assert_eq!(Name::check("a..example"),
+            Err(Error::InvalidName("empty label")));
+        assert_eq!(Name::check(".example"),
+            Err(Error::InvalidName("empty label")));
+        assert_eq!(Name::check("example.."),
+            Err(Error::InvalidName("empty label")));
+        assert!(Name::check(&"a".repeat(63)).is_ok());
+        assert_eq!(Name::check(&"a".repeat(64)),
+            Err(Error::InvalidName("label is longer than 63 bytes")));
+        assert!(Name::check(&["a"; 127].join(".")).is_ok());
+        assert_eq!(Name::check(&["a"; 128].join(".")),
+            Err(Error::InvalidName("name is longer than 255 bytes")));
+        assert!(Name::from_string("a..b").write_to(&mut Vec::new()).is_err());
+    }
 }
@@ -0,0 +1,557 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::mem;
+use std::net::{IpAddr, SocketAddr};
+use std::slice;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use {Packet, ResourceRecord, Name, QueryType, Class, RData, Type, Error};
+use rdata::{FromRData, A, Aaaa, Cname, Mx, Ns, Ptr, Srv, Txt};
+
+/// Iterator over the records of one type in a section
+///
+/// Returned by `Packet::answers_of` and its variants for the other
+/// sections.
+#[derive(Debug, Clone)]
+pub struct RecordsOf<'r, 'a: 'r, T> {
+    records: slice::Iter<'r, ResourceRecord<'a>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'r, 'a, T> RecordsOf<'r, 'a, T> {
+    fn new(records: &'r [ResourceRecord<'a>]) -> RecordsOf<'r, 'a, T> {
+        RecordsOf { records: records.iter(), marker: PhantomData }
+    }
+}
+
+impl<'r, 'a, T: FromRData<'a> + 'r> Iterator for RecordsOf<'r, 'a, T> {
+    type Item = &'r T;
+    fn next(&mut self) -> Option<&'r T> {
+        self.records.by_ref().filter_map(|r| T::from_rdata(&r.data)).next()
+    }
+}
+
+/// Iterator over the records of a section, see `Packet::answers`
+///
+/// Besides iterating over all records, it can be narrowed to a single
+/// type with `of` or shortcuts such as `a` and `srv`.
+#[derive(Debug, Clone)]
+pub struct Records<'r, 'a: 'r> {
+    records: slice::Iter<'r, ResourceRecord<'a>>,
+}
+
+/// A record of a known type, with the fields of its resource record
+#[derive(Debug, Clone, Copy)]
+#[allow(missing_docs)]  // same as in ResourceRecord
+pub struct TypedRecord<'r, 'a: 'r, T: 'r> {
+    pub name: &'r Name<'a>,
+    pub cls: Class,
+    pub ttl: u32,
+    pub data: &'r T,
+}
+
+/// Iterator over the records of one type, see `Records::of`
+#[derive(Debug, Clone)]
+pub struct TypedRecords<'r, 'a: 'r, T> {
+    records: slice::Iter<'r, ResourceRecord<'a>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'r, 'a> Iterator for Records<'r, 'a> {
+    type Item = &'r ResourceRecord<'a>;
+    fn next(&mut self) -> Option<&'r ResourceRecord<'a>> {
+        self.records.next()
+    }
+}
+
+impl<'r, 'a> Records<'r, 'a> {
+    /// Keeps the records of type `T`
+    pub fn of<T: FromRData<'a>>(self) -> TypedRecords<'r, 'a, T> {
+        TypedRecords { records: self.records, marker: PhantomData }
+    }
+    /// Keeps the A records
+    pub fn a(self) -> TypedRecords<'r, 'a, A> { self.of() }
+    /// Keeps the AAAA records
+    pub fn aaaa(self) -> TypedRecords<'r, 'a, Aaaa> { self.of() }
+    /// Keeps the CNAME records
+    pub fn cname(self) -> TypedRecords<'r, 'a, Cname<'a>> { self.of() }
+    /// Keeps the MX records
+    pub fn mx(self) -> TypedRecords<'r, 'a, Mx<'a>> { self.of() }
+    /// Keeps the NS records
+    pub fn ns(self) -> TypedRecords<'r, 'a, Ns<'a>> { self.of() }
+    /// Keeps the PTR records
+    pub fn ptr(self) -> TypedRecords<'r, 'a, Ptr<'a>> { self.of() }
+    /// Keeps the SRV records
+    pub fn srv(self) -> TypedRecords<'r, 'a, Srv<'a>> { self.of() }
+    /// Keeps the TXT records
+    pub fn txt(self) -> TypedRecords<'r, 'a, Txt> { self.of() }
+}
+
+impl<'r, 'a, T: FromRData<'a> + 'r> Iterator for TypedRecords<'r, 'a, T> {
+    type Item = TypedRecord<'r, 'a, T>;
+    fn next(&mut self) -> Option<TypedRecord<'r, 'a, T>> {
+        self.records.by_ref()
+            .filter_map(|r| T::from_rdata(&r.data).map(|data| TypedRecord {
+                name: &r.name,
+                cls: r.cls,
+                ttl: r.ttl,
+                data,
+            }))
+            .next()
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Returns true if this is a minimized answer to an ANY query
+    ///
+    /// RFC 8482 lets servers answer `ANY` queries with a single synthesized
+    /// `HINFO "RFC8482" ""` record. Clients seeing such a response should
+    /// query for the specific types they need instead.
+    pub fn is_rfc8482_response(&self) -> bool {
+        self.questions.iter().any(|q| q.qtype == QueryType::All)
+            && self.answers.iter().any(|r| match r.data {
+                RData::HINFO(ref hinfo) => hinfo.is_rfc8482(),
+                _ => false,
+            })
+    }
+
+    /// Returns the text of the first CHAOS class TXT answer
+    ///
+    /// This is the answer to the queries made by `Builder::chaos_txt`,
+    /// e.g. the server version for `version.bind`. Character-strings are
+    /// joined and invalid UTF-8 is replaced.
+    pub fn chaos_txt(&self) -> Option<Cow<'_, str>> {
+        self.answers.iter()
+            .filter(|r| r.cls == Class::CH)
+            .filter_map(|r| match r.data {
+                RData::TXT(ref txt) => Some(txt.joined_lossy()),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Iterates over the answer section, e.g. `packet.answers().a()`
+    pub fn answers(&self) -> Records<'_, 'a> {
+        Records { records: self.answers.iter() }
+    }
+
+    /// Iterates over the authority section
+    pub fn nameservers(&self) -> Records<'_, 'a> {
+        Records { records: self.nameservers.iter() }
+    }
+
+    /// Iterates over the additional section, without the OPT record
+    pub fn additional(&self) -> Records<'_, 'a> {
+        Records { records: self.additional.iter() }
+    }
+
+    /// Returns the answers of type `T`, e.g. `answers_of::<rdata::A>()`
+    pub fn answers_of<T: FromRData<'a>>(&self) -> RecordsOf<'_, 'a, T> {
+        RecordsOf::new(&self.answers)
+    }
+
+    /// Returns the authority section records of type `T`
+    pub fn nameservers_of<T: FromRData<'a>>(&self) -> RecordsOf<'_, 'a, T> {
+        RecordsOf::new(&self.nameservers)
+    }
+
+    /// Returns the additional section records of type `T`
+    pub fn additional_of<T: FromRData<'a>>(&self) -> RecordsOf<'_, 'a, T> {
+        RecordsOf::new(&self.additional)
+    }
+
+    /// Returns the addresses to connect to for the SRV record
+    ///
+    /// The A and AAAA records of the target are looked up in the
+    /// additional section, where servers put them as glue, and combined
+    /// with the port. The result is empty if the server sent no glue, the
+    /// target must then be resolved separately.
+    pub fn srv_socket_addrs(&self, srv: &Srv) -> Vec<SocketAddr> {
+        self.additional.iter()
+            .filter(|r| r.name.eq_ignore_case(&srv.target.str_val))
+            .filter_map(|r| match r.data {
+                RData::A(ref a) => Some(IpAddr::V4(a.0)),
+                RData::AAAA(ref aaaa) => Some(IpAddr::V6(aaaa.0)),
+                _ => None,
+            })
+            .map(|ip| SocketAddr::new(ip, srv.port))
+            .collect()
+    }
+
+    /// Lists the compression pointers of the packet in `data`
+    ///
+    /// Returns pairs of the offset of each pointer and the offset it
+    /// points to, sorted by the pointer offset. Pointers in the names of
+    /// questions and records are found, and in RDATA of the types the
+    /// crate parses. Meant for tools that show how the packet is laid
+    /// out.
+    pub fn compression_pointers(data: &[u8])
+        -> Result<Vec<(usize, usize)>, Error>
+    {
+        let packet = Packet::parse(data)?;
+        let mut names: Vec<&Name> = packet.questions.iter()
+            .map(|q| &q.qname)
+            .collect();
+        let records = packet.answers.iter()
+            .chain(packet.nameservers.iter())
+            .chain(packet.additional.iter());
+        for record in records {
+            names.push(&record.name);
+            match record.data {
+                RData::CNAME(ref val) => names.push(&val.0),
+                RData::NS(ref val) => names.push(&val.0),
+                RData::PTR(ref val) => names.push(&val.0),
+                RData::MX(ref val) => names.push(&val.exchange),
+                RData::SRV(ref val) => names.push(&val.target),
+                RData::SOA(ref val) => {
+                    names.push(&val.primary_ns);
+                    names.push(&val.mailbox);
+                }
+                RData::RRSIG(ref val) => names.push(&val.signer),
+                _ => {}
+            }
+        }
+        let mut pointers = BTreeMap::new();
+        for mut pos in names.iter().filter_map(|n| n.offset_in(data)) {
+            // Names were validated by parsing, pointers only go backwards
+            while data[pos] != 0 {
+                if data[pos] & 0xC0 == 0xC0 {
+                    let target = ((data[pos] as usize & 0x3F) << 8)
+                        | data[pos+1] as usize;
+                    if pointers.insert(pos, target).is_some() {
+                        break;
+                    }
+                    pos = target;
+                } else {
+                    pos += data[pos] as usize + 1;
+                }
+            }
+        }
+        Ok(pointers.into_iter().collect())
+    }
+
+    /// Keeps only the answers for which `keep` returns true
+    ///
+    /// The answer count of the header is set to the number of answers
+    /// left, so the packet can be passed on with `Builder::from_packet`.
+    pub fn retain_answers<F>(&mut self, keep: F)
+        where F: FnMut(&ResourceRecord<'a>) -> bool
+    {
+        self.answers.retain(keep);
+        self.header.answers = self.answers.len() as u16;
+    }
+
+    /// Removes the additional records of the type, returns how many
+    ///
+    /// The additional count of the header is set to the records left,
+    /// counting the OPT record. That is kept separately and isn't
+    /// affected.
+    pub fn remove_additional(&mut self, typ: Type) -> usize {
+        let before = self.additional.len();
+        self.additional.retain(|r| r.data.raw_type_code() != typ as u16);
+        self.header.additional =
+            (self.additional.len() + self.opt.is_some() as usize) as u16;
+        before - self.additional.len()
+    }
+
+    /// Replaces the RDATA of the answer at `index`, returns the old one
+    ///
+    /// Returns `None` and leaves the packet alone if there is no such
+    /// answer.
+    pub fn replace_rdata(&mut self, index: usize, data: RData<'a>)
+        -> Option<RData<'a>>
+    {
+        self.answers.get_mut(index)
+            .map(|record| mem::replace(&mut record.data, data))
+    }
+
+    /// The questions and answers in a canonical form, for comparison
+    ///
+    /// Names are lowercased, including the ones in RDATA as in RFC 4034
+    /// section 6.2. Questions and answers are sorted and duplicates are
+    /// dropped, and TTLs are left out. So two responses with the same
+    /// content have the same canonical form even if their ID, TTLs,
+    /// record order or name case differ. The other sections and the
+    /// header aren't included.
+    pub fn canonical_form(&self) -> Vec<u8> {
+        let mut questions: Vec<Vec<u8>> = self.questions.iter().map(|q| {
+            let mut buf = lowercase_name(&q.qname);
+            buf.write_u16::<BigEndian>(q.qtype as u16).unwrap();
+            buf.write_u16::<BigEndian>(q.qclass as u16).unwrap();
+            buf
+        }).collect();
+        let mut answers: Vec<Vec<u8>> = self.answers.iter().map(|r| {
+            let rdata = r.data.canonical_bytes();
+            let mut buf = lowercase_name(&r.name);
+            buf.write_u16::<BigEndian>(r.data.raw_type_code()).unwrap();
+            buf.write_u16::<BigEndian>(u16::from(r.cls)).unwrap();
+            buf.write_u16::<BigEndian>(rdata.len() as u16).unwrap();
+            buf.extend(rdata);
+            buf
+        }).collect();
+        let mut buf = Vec::new();
+        for entries in [&mut questions, &mut answers].iter_mut() {
+            entries.sort();
+            entries.dedup();
+            buf.write_u16::<BigEndian>(entries.len() as u16).unwrap();
+            for entry in entries.iter() {
+                buf.extend(entry);
+            }
+        }
+        buf
+    }
+
+    /// A stable 64-bit hash of `canonical_form`, e.g. for deduplication
+    ///
+    /// The hash is FNV-1a, which is the same on every platform and
+    /// release, so it can be stored. It isn't cryptographic, don't use it
+    /// where collisions may be crafted.
+    pub fn canonical_hash(&self) -> u64 {
+        self.canonical_form().iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+            (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+}
+
+fn lowercase_name(name: &Name) -> Vec<u8> {
+    let mut buf = name.to_bytes();
+    buf.make_ascii_lowercase();
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    use {Builder, Packet, QueryType, QueryClass, Class, RData, Type};
+    use Name;
+    use rdata::{A, Mx, Ns, Rrsig, Srv, Txt};
+    use dnssec::Algorithm;
+
+    #[test]
+    fn version_bind() {
+        let query = Builder::version_bind(0x1234).build().unwrap();
+        assert_eq!(&query[..], &b"\x12\x34\x00\x00\x00\x01\x00\x00\x00\x00\
+            \x00\x00\x07version\x04bind\x00\x00\x10\x00\x03"[..]);
+        let packet = Packet::parse(&query).unwrap();
+        assert_eq!(packet.questions[0].qtype, QueryType::TXT);
+        assert_eq!(packet.questions[0].qclass, QueryClass::CH);
+        assert_eq!(packet.chaos_txt(), None);
+
+        // As answered by BIND, with the authority section NS record
+        let response = b"\x12\x34\x84\x00\x00\x01\x00\x01\x00\x01\x00\x00\
+            \x07version\x04bind\x00\x00\x10\x00\x03\
+            \xc0\x0c\x00\x10\x00\x03\x00\x00\x00\x00\x00\x0a\x099.18.19-1\
+            \xc0\x0c\x00\x02\x00\x03\x00\x00\x00\x00\x00\x02\xc0\x0c";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.chaos_txt().unwrap(), "9.18.19-1");
+    }
+
+    #[test]
+    fn canonical_hash() {
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QueryType::MX, QueryClass::IN);
+        bld.answer("example.com", Class::IN,
+            RData::MX(Mx::new(10, Name::from_string("a.example.com"))),
+            false, 300);
+        bld.answer("example.com", Class::IN,
+            RData::MX(Mx::new(20, Name::from_string("b.example.com"))),
+            false, 300);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+
+        let mut bld = Builder::new(2, false);
+        bld.question("Example.COM.", false, QueryType::MX, QueryClass::IN);
+        bld.answer("EXAMPLE.com", Class::IN,
+            RData::MX(Mx::new(20, Name::from_string("B.example.com"))),
+            false, 117);
+        bld.answer("example.com", Class::IN,
+            RData::MX(Mx::new(10, Name::from_string("a.example.com"))),
+            false, 299);
+        bld.answer("example.com", Class::IN,
+            RData::MX(Mx::new(10, Name::from_string("a.example.com"))),
+            false, 299);
+        let other_data = bld.build().unwrap();
+        let other = Packet::parse(&other_data).unwrap();
+        assert_eq!(packet.canonical_form(), other.canonical_form());
+        assert_eq!(packet.canonical_hash(), other.canonical_hash());
+
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QueryType::MX, QueryClass::IN);
+        bld.answer("example.com", Class::IN,
+            RData::MX(Mx::new(10, Name::from_string("a.example.com"))),
+            false, 300);
+        let data = bld.build().unwrap();
+        let fewer = Packet::parse(&data).unwrap();
+        assert!(fewer.canonical_hash() != packet.canonical_hash());
+    }
+
+    #[test]
+    fn canonical_signer() {
+        let hash = |signer| {
+            let mut bld = Builder::new(1, false);
+            bld.answer("example.com", Class::IN, RData::RRSIG(Rrsig {
+                type_covered: Type::MX as u16,
+                algorithm: Algorithm::Ed25519,
+                labels: 2,
+                original_ttl: 300,
+                expiration: 2,
+                inception: 1,
+                key_tag: 12345,
+                signer: Name::from_string(signer),
+                signature: Cow::Borrowed(b"Signature"),
+            }), false, 300);
+            let data = bld.build().unwrap();
+            Packet::parse(&data).unwrap().canonical_hash()
+        };
+        assert_eq!(hash("Example.COM"), hash("example.com"));
+        assert!(hash("example.net") != hash("example.com"));
+    }
+
+    #[test]
+    fn scrub_records() {
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QueryType::A, QueryClass::IN);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(10, 0, 0, 1).into()), false, 60);
+        bld.additional("example.com", Class::IN,
+            RData::TXT(Txt::from_bytes(b"hello")), false, 60);
+        bld.edns(1232, 0, 0, 0);
+        let data = bld.build().unwrap();
+        let mut packet = Packet::parse(&data).unwrap();
+
+        packet.retain_answers(|r| match r.data {
+            RData::A(ref a) => !a.0.is_private(),
+            _ => true,
+        });
+        assert_eq!(packet.answers.len(), 1);
+        assert_eq!(packet.header.answers, 1);
+        assert_eq!(packet.remove_additional(Type::TXT), 1);
+        assert_eq!(packet.header.additional, 1);
+        assert!(packet.opt.is_some());
+
+        let old = packet.replace_rdata(0,
+            RData::A(Ipv4Addr::new(192, 0, 2, 2).into()));
+        assert!(matches!(old, Some(RData::A(..))));
+        assert!(packet.replace_rdata(1, RData::A(Ipv4Addr::LOCALHOST.into()))
+            .is_none());
+
+        let data = Builder::from_packet(packet).build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.answers.len(), 1);
+        assert!(packet.additional.is_empty());
+        assert!(matches!(packet.answers[0].data,
+            RData::A(ref a) if a.0 == Ipv4Addr::new(192, 0, 2, 2)));
+    }
+
+    #[test]
+    fn scrub_added_records() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.edns(1232, 0, 0, 0);
+        let data = bld.build().unwrap();
+        let mut packet = Packet::parse(&data).unwrap();
+        // Records added by hand aren't in the counts
+        for _ in 0..2 {
+            let mut other = Packet::parse(&data).unwrap();
+            packet.answers.append(&mut other.answers);
+        }
+        let mut other = Packet::parse(&data).unwrap();
+        packet.additional.append(&mut other.answers);
+        packet.retain_answers(|_| false);
+        assert_eq!(packet.header.answers, 0);
+        assert_eq!(packet.remove_additional(Type::A), 1);
+        assert_eq!(packet.header.additional, 1);
+    }
+
+    #[test]
+    fn typed_records() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.answer("example.com", Class::IN,
+            RData::TXT(Txt::from_bytes(b"hello")), false, 60);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 2).into()), false, 60);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+
+        let addrs: Vec<_> = packet.answers_of::<A>().map(|a| a.0).collect();
+        assert_eq!(addrs, [Ipv4Addr::new(192, 0, 2, 1),
+                           Ipv4Addr::new(192, 0, 2, 2)]);
+        assert_eq!(packet.answers_of::<Txt>().count(), 1);
+        assert_eq!(packet.nameservers_of::<Ns>().count(), 0);
+        assert_eq!(packet.additional_of::<A>().count(), 0);
+    }
+
+    #[test]
+    fn typed_iterators() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.answer("_sip._tcp.example.com", Class::IN, RData::SRV(Srv::new(
+            10, 60, 5060, Name::from_string("sip.example.com"))), false, 30);
+        bld.nameserver("example.com", Class::IN,
+            RData::NS(Ns::new(Name::from_string("ns.example.com"))),
+            false, 3600);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+
+        assert_eq!(packet.answers().count(), 2);
+        let a: Vec<_> = packet.answers().a().collect();
+        assert_eq!(a.len(), 1);
+        assert!(a[0].name == "example.com");
+        assert_eq!(a[0].ttl, 60);
+        assert_eq!(a[0].data.0, Ipv4Addr::new(192, 0, 2, 1));
+        let srv = packet.answers().srv().next().unwrap();
+        assert!(srv.name == "_sip._tcp.example.com");
+        assert_eq!(srv.data.port, 5060);
+        assert_eq!(srv.ttl, 30);
+        assert_eq!(packet.answers().aaaa().count(), 0);
+        assert_eq!(packet.nameservers().ns().next().unwrap().cls, Class::IN);
+        assert_eq!(packet.additional().count(), 0);
+    }
+
+    #[test]
+    fn srv_glue() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("_sip._tcp.example.com", Class::IN, RData::SRV(Srv::new(
+            10, 60, 5060, Name::from_string("sip.example.com"))), false, 30);
+        bld.additional("SIP.example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 30);
+        bld.additional("other.example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 2).into()), false, 30);
+        bld.additional("sip.example.com", Class::IN,
+            RData::AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)
+                .into()), false, 30);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+
+        let srv = packet.answers_of::<Srv>().next().unwrap();
+        assert_eq!(packet.srv_socket_addrs(srv), [
+            "192.0.2.1:5060".parse::<SocketAddr>().unwrap(),
+            "[2001:db8::1]:5060".parse().unwrap(),
+        ]);
+    }
+
+    #[test]
+    fn compression_pointers() {
+        let mut bld = Builder::new(1, false);
+        bld.question("www.example.com", false, QueryType::MX,
+            QueryClass::IN);
+        bld.answer("www.example.com", Class::IN, RData::MX(Mx::new(10,
+            Name::from_string("mail.example.com"))), false, 60);
+        let data = bld.build().unwrap();
+        // The owner points to the question name at 12, the exchange
+        // to `example.com` at 16
+        assert_eq!(Packet::compression_pointers(&data).unwrap(),
+            [(33, 12), (52, 16)]);
+        assert!(Packet::compression_pointers(&data[..40]).is_err());
+    }
+}
@@ -1,71 +1,144 @@
-use std::i32;
+use std::ops::Range;
 
 use byteorder::{BigEndian, ByteOrder};
 
 use {Header, Packet, Error, Question, Name, QueryType, QueryClass};
-use {Type, Class, ResourceRecord, RData};
+use {Type, Class, ResourceRecord, RData, Section, SkippedSection};
 use rdata::opt::Record as Opt;
+use rdata::RecordRegistry;
 
-const OPT_RR_START: [u8; 3] = [0, 0, 41];
+pub(crate) const OPT_RR_START: [u8; 3] = [0, 0, 41];
 
-impl<'a> Packet<'a> {
-    /// Parse a full DNS Packet and return a structure that has all the
-    /// data borrowed from the passed buffer.
-    pub fn parse(data: &[u8]) -> Result<Packet, Error> {
-        let header = Header::parse(data)?;
-        let mut offset = Header::size();
-        let mut questions = Vec::with_capacity(header.questions as usize);
-        for _ in 0..header.questions {
-            let name = Name::scan(&data[offset..], data)?;
-            offset += name.byte_len();
-            if offset + 4 > data.len() {
-                return Err(Error::UnexpectedEOF);
-            }
-            let qtype = QueryType::parse(
-                BigEndian::read_u16(&data[offset..offset+2]))?;
-            offset += 2;
-
-            let (prefer_unicast, qclass) = parse_qclass_code(
-                BigEndian::read_u16(&data[offset..offset+2]))?;
-            offset += 2;
-
-            questions.push(Question {
-                qname: name,
-                qtype: qtype,
-                prefer_unicast: prefer_unicast,
-                qclass: qclass,
-            });
-        }
-        let mut answers = Vec::with_capacity(header.answers as usize);
-        for _ in 0..header.answers {
-            answers.push(parse_record(data, &mut offset)?);
-        }
-        let mut nameservers = Vec::with_capacity(header.nameservers as usize);
-        for _ in 0..header.nameservers {
-            nameservers.push(parse_record(data, &mut offset)?);
+/// Settings for `Packet::parse_with_options`
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions<'r> {
+    /// Parsers for types the crate doesn't support
+    pub registry: Option<&'r RecordRegistry>,
+    /// Limit on the total length of decompressed names
+    ///
+    /// Given as a multiple of the packet size, `None` disables the check.
+    /// Compression pointers let a small crafted packet expand to a huge
+    /// amount of name data, parsing is aborted with
+    /// `Error::DecompressionLimit` once names in the packet expand past
+    /// the limit. The default is 64, far above what real packets need.
+    pub decompression_limit: Option<usize>,
+    /// Don't parse the authority section
+    ///
+    /// The records are still checked to be well-formed, so that the
+    /// sections after them can be found, and the byte range of the
+    /// section is recorded in `Packet::skipped`.
+    pub skip_authority: bool,
+    /// Don't parse the additional section, same as `skip_authority`
+    ///
+    /// Note that the OPT record lives in the additional section, so
+    /// `Packet::opt` is always `None` when this is set.
+    pub skip_additional: bool,
+    /// Reject records with bytes left over after their RDATA
+    ///
+    /// Some broken middleboxes emit records whose RDLENGTH is larger than
+    /// the data of the type. Such records are accepted by default, in
+    /// strict mode parsing fails with `Error::TrailingRdata`. Unknown and
+    /// custom types aren't checked.
+    pub strict_rdata: bool,
+    /// Skip questions and records with binary labels instead of failing
+    ///
+    /// Binary labels (RFC 2673) were deprecated long ago and aren't
+    /// supported, by default a name with one fails parsing with
+    /// `Error::BinaryLabelUnsupported`. With this set, a question or
+    /// record with such a name, as the owner or in the RDATA, is left
+    /// out and its byte range is recorded in `Packet::skipped`, so that
+    /// analyzers can count them. Other extended label types still fail.
+    pub skip_binary_labels: bool,
+}
+
+impl<'r> Default for ParseOptions<'r> {
+    fn default() -> ParseOptions<'r> {
+        ParseOptions {
+            registry: None,
+            decompression_limit: Some(64),
+            skip_authority: false,
+            skip_additional: false,
+            strict_rdata: false,
+            skip_binary_labels: false,
         }
-        let mut additional = Vec::with_capacity(header.additional as usize);
-        let mut opt = None;
-        for _ in 0..header.additional {
-            if offset + 3 <= data.len() && data[offset..offset+3] == OPT_RR_START {
-                if opt.is_none() {
-                    opt = Some(parse_opt_record(data, &mut offset)?);
-                } else {
-                    return Err(Error::AdditionalOPT);
-                }
-            } else {
-                additional.push(parse_record(data, &mut offset)?);
+    }
+}
+
+/// Remaining decompression budget of a packet
+pub(crate) struct Budget(Option<usize>);
+
+impl Budget {
+    pub(crate) fn new(options: &ParseOptions, data: &[u8]) -> Budget {
+        Budget(options.decompression_limit
+            .map(|x| x.saturating_mul(data.len())))
+    }
+    pub(crate) fn spend(&mut self, amount: usize) -> Result<(), Error> {
+        if let Some(ref mut left) = self.0 {
+            if amount > *left {
+                return Err(Error::DecompressionLimit);
             }
+            *left -= amount;
         }
+        Ok(())
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Parse a full DNS Packet and return a structure that has all the
+    /// data borrowed from the passed buffer.
+    ///
+    /// Any input is handled gracefully: malformed or truncated packets
+    /// yield an `Error`, parsing never panics and never overflows. The
+    /// same holds for the other functions taking packet bytes
+    /// (`parse_with_options`, `parse_tcp`, `parse_salvage` and
+    /// `validate`), unless a `RecordRegistry` callback panics.
+    pub fn parse(data: &[u8]) -> Result<Packet<'_>, Error> {
+        Packet::parse_with_options(data, &ParseOptions::default())
+    }
+
+    /// Parse a packet using the registry for otherwise unsupported types
+    ///
+    /// Records of registered types are returned as `RData::Custom`.
+    pub fn parse_with_registry(data: &'a [u8], registry: &RecordRegistry)
+        -> Result<Packet<'a>, Error>
+    {
+        Packet::parse_with_options(data, &ParseOptions {
+            registry: Some(registry),
+            ..ParseOptions::default()
+        })
+    }
+
+    /// Parse a packet with non-default settings
+    pub fn parse_with_options(data: &'a [u8], options: &ParseOptions)
+        -> Result<Packet<'a>, Error>
+    {
+        let mut sections = VecSections::default();
+        let (header, opt) = parse_into(data, options, &mut sections)?;
         Ok(Packet {
-            header: header,
-            questions: questions,
-            answers: answers,
-            nameservers: nameservers,
-            additional: additional,
-            opt: opt,
+            header,
+            questions: sections.questions,
+            answers: sections.answers,
+            nameservers: sections.nameservers,
+            additional: sections.additional,
+            opt,
+            skipped: sections.skipped,
         })
     }
+
+    /// Parse a DNS packet prefixed with the two-byte length used on TCP
+    ///
+    /// This is the framing described in RFC 1035 section 4.2.2, used by
+    /// DNS over TCP and DNS over TLS. Bytes after the message are ignored.
+    pub fn parse_tcp(data: &[u8]) -> Result<Packet<'_>, Error> {
+        if data.len() < 2 {
+            return Err(Error::UnexpectedEOF);
+        }
+        let len = BigEndian::read_u16(&data[..2]) as usize;
+        if !has_bytes(data, 2, len) {
+            return Err(Error::UnexpectedEOF);
+        }
+        Packet::parse(&data[2..len+2])
+    }
 }
 
 fn parse_qclass_code(value: u16) -> Result<(bool, QueryClass), Error> {
@@ -84,46 +157,291 @@ fn parse_class_code(value: u16) -> Result<(bool, Class), Error> {
     Ok((is_unique, cls))
 }
 
+/// Receives parsed entries, allows storing sections in other containers
+pub(crate) trait Sections<'a> {
+    /// Called once with the header before any entries
+    fn reserve(&mut self, header: &Header);
+    fn question(&mut self, question: Question<'a>);
+    fn record(&mut self, section: Section, record: ResourceRecord<'a>);
+    fn skipped(&mut self, section: Section, range: Range<usize>);
+}
+
+#[derive(Default)]
+struct VecSections<'a> {
+    questions: Vec<Question<'a>>,
+    answers: Vec<ResourceRecord<'a>>,
+    nameservers: Vec<ResourceRecord<'a>>,
+    additional: Vec<ResourceRecord<'a>>,
+    skipped: Vec<SkippedSection>,
+}
+
+impl<'a> Sections<'a> for VecSections<'a> {
+    fn reserve(&mut self, header: &Header) {
+        self.questions.reserve(header.questions as usize);
+        self.answers.reserve(header.answers as usize);
+        self.nameservers.reserve(header.nameservers as usize);
+        self.additional.reserve(header.additional as usize);
+    }
+    fn question(&mut self, question: Question<'a>) {
+        self.questions.push(question);
+    }
+    fn record(&mut self, section: Section, record: ResourceRecord<'a>) {
+        match section {
+            Section::Question => unreachable!(),
+            Section::Answer => self.answers.push(record),
+            Section::Authority => self.nameservers.push(record),
+            Section::Additional => self.additional.push(record),
+        }
+    }
+    fn skipped(&mut self, section: Section, range: Range<usize>) {
+        self.skipped.push(SkippedSection { section, range });
+    }
+}
+
+/// Parses the packet passing questions and records to `sections`
+pub(crate) fn parse_into<'a, S: Sections<'a>>(data: &'a [u8],
+    options: &ParseOptions, sections: &mut S)
+    -> Result<(Header, Option<Opt<'a>>), Error>
+{
+    let mut budget = Budget::new(options, data);
+    let header = Header::parse(data)?;
+    parse_trace!(0, "header", header);
+    sections.reserve(&header);
+    let mut offset = Header::size();
+    let binary_labels = options.skip_binary_labels;
+    for _ in 0..header.questions {
+        let start = offset;
+        match parse_question(data, &mut offset) {
+            Ok(question) => {
+                budget.spend(question.qname.str_val.len())?;
+                sections.question(question);
+            }
+            Err(Error::BinaryLabelUnsupported) if binary_labels => {
+                offset = start;
+                skip_question_labels(data, &mut offset, true)?;
+                sections.skipped(Section::Question, start..offset);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    for _ in 0..header.answers {
+        parse_section_record(data, &mut offset, options, &mut budget,
+            Section::Answer, sections)?;
+    }
+    if options.skip_authority {
+        let start = offset;
+        for _ in 0..header.nameservers {
+            skip_record_labels(data, &mut offset, binary_labels)?;
+        }
+        sections.skipped(Section::Authority, start..offset);
+    } else {
+        for _ in 0..header.nameservers {
+            parse_section_record(data, &mut offset, options, &mut budget,
+                Section::Authority, sections)?;
+        }
+    }
+    let mut opt = None;
+    if options.skip_additional {
+        let start = offset;
+        for _ in 0..header.additional {
+            skip_record_labels(data, &mut offset, binary_labels)?;
+        }
+        sections.skipped(Section::Additional, start..offset);
+        return Ok((header, opt));
+    }
+    for _ in 0..header.additional {
+        if has_bytes(data, offset, 3) &&
+            data[offset..offset+3] == OPT_RR_START
+        {
+            if opt.is_none() {
+                opt = Some(parse_opt_record(data, &mut offset)?);
+            } else {
+                return Err(Error::AdditionalOPT);
+            }
+        } else {
+            parse_section_record(data, &mut offset, options, &mut budget,
+                Section::Additional, sections)?;
+        }
+    }
+    Ok((header, opt))
+}
+
+/// Parses a record into `section`, or skips it if a name in it has
+/// a binary label and `ParseOptions::skip_binary_labels` is set
+fn parse_section_record<'a, S: Sections<'a>>(data: &'a [u8],
+    offset: &mut usize, options: &ParseOptions, budget: &mut Budget,
+    section: Section, sections: &mut S)
+    -> Result<(), Error>
+{
+    let start = *offset;
+    match parse_checked_record(data, offset, options) {
+        Ok(record) => {
+            budget.spend(expanded_names_len(&record))?;
+            sections.record(section, record);
+        }
+        Err(Error::BinaryLabelUnsupported) if options.skip_binary_labels => {
+            *offset = start;
+            skip_record_labels(data, offset, true)?;
+            sections.skipped(section, start..*offset);
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// Returns true if `data` holds at least `len` bytes past `offset`
+///
+/// Never overflows, unlike comparing `offset + len` to the length.
+pub(crate) fn has_bytes(data: &[u8], offset: usize, len: usize) -> bool {
+    data.len().checked_sub(offset).is_some_and(|left| left >= len)
+}
+
+pub(crate) fn expanded_names_len(record: &ResourceRecord) -> usize {
+    record.name.str_val.len() + record.data.expanded_names_len()
+}
+
+pub(crate) fn parse_question<'a>(data: &'a [u8], offset: &mut usize)
+    -> Result<Question<'a>, Error>
+{
+    let name = Name::scan(&data[*offset..], data)?;
+    parse_trace!(*offset, "qname", name);
+    *offset += name.byte_len();
+    if !has_bytes(data, *offset, 4) {
+        return Err(Error::UnexpectedEOF);
+    }
+    let code = BigEndian::read_u16(&data[*offset..*offset+2]);
+    parse_trace!(*offset, "qtype", code);
+    let qtype = QueryType::parse(code)?;
+    *offset += 2;
+
+    let code = BigEndian::read_u16(&data[*offset..*offset+2]);
+    parse_trace!(*offset, "qclass", code);
+    let (prefer_unicast, qclass) = parse_qclass_code(code)?;
+    *offset += 2;
+
+    Ok(Question {
+        qname: name,
+        qtype,
+        prefer_unicast,
+        qclass,
+    })
+}
+
+// Checks that a question is well-formed and moves `offset` past it
+pub(crate) fn skip_question(data: &[u8], offset: &mut usize)
+    -> Result<(), Error>
+{
+    skip_question_labels(data, offset, false)
+}
+
+fn skip_question_labels(data: &[u8], offset: &mut usize,
+    binary_labels: bool)
+    -> Result<(), Error>
+{
+    *offset += Name::skip_labels(&data[*offset..], data, binary_labels)?;
+    if !has_bytes(data, *offset, 4) {
+        return Err(Error::UnexpectedEOF);
+    }
+    *offset += 4;
+    Ok(())
+}
+
+// Checks that a record is well-formed and moves `offset` past it
+pub(crate) fn skip_record(data: &[u8], offset: &mut usize) -> Result<(), Error> {
+    skip_record_labels(data, offset, false)
+}
+
+fn skip_record_labels(data: &[u8], offset: &mut usize, binary_labels: bool)
+    -> Result<(), Error>
+{
+    *offset += Name::skip_labels(&data[*offset..], data, binary_labels)?;
+    if !has_bytes(data, *offset, 10) {
+        return Err(Error::UnexpectedEOF);
+    }
+    let rdlen = BigEndian::read_u16(&data[*offset+8..*offset+10]) as usize;
+    *offset += 10;
+    if !has_bytes(data, *offset, rdlen) {
+        return Err(Error::UnexpectedEOF);
+    }
+    *offset += rdlen;
+    Ok(())
+}
+
+// Parses a record, checking the RDATA length in strict mode
+fn parse_checked_record<'a>(data: &'a [u8], offset: &mut usize,
+    options: &ParseOptions)
+    -> Result<ResourceRecord<'a>, Error>
+{
+    let start = *offset;
+    let record = parse_record(data, offset, options.registry)?;
+    if options.strict_rdata {
+        let rdlen = *offset - start - record.name.byte_len() - 10;
+        match record.data.wire_length() {
+            Some(len) if len != rdlen => {
+                return Err(Error::TrailingRdata(record.data.type_code()));
+            }
+            _ => {}
+        }
+    }
+    Ok(record)
+}
+
 // Generic function to parse answer, nameservers, and additional records.
-fn parse_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<ResourceRecord<'a>, Error> {
+pub(crate) fn parse_record<'a>(data: &'a [u8], offset: &mut usize,
+    registry: Option<&RecordRegistry>)
+    -> Result<ResourceRecord<'a>, Error>
+{
     let name = Name::scan(&data[*offset..], data)?;
+    parse_trace!(*offset, "name", name);
     *offset += name.byte_len();
-    if *offset + 10 > data.len() {
+    if !has_bytes(data, *offset, 10) {
         return Err(Error::UnexpectedEOF);
     }
-    let typ = Type::parse(
-        BigEndian::read_u16(&data[*offset..*offset+2]))?;
+    let code = BigEndian::read_u16(&data[*offset..*offset+2]);
+    parse_trace!(*offset, "type", code);
     *offset += 2;
 
     let class_code = BigEndian::read_u16(&data[*offset..*offset+2]);
+    parse_trace!(*offset, "class", class_code);
     let (multicast_unique, cls) = parse_class_code(class_code)?;
     *offset += 2;
 
     let mut ttl = BigEndian::read_u32(&data[*offset..*offset+4]);
+    parse_trace!(*offset, "ttl", ttl);
     if ttl > i32::MAX as u32 {
         ttl = 0;
     }
     *offset += 4;
     let rdlen = BigEndian::read_u16(&data[*offset..*offset+2]) as usize;
+    parse_trace!(*offset, "rdlength", rdlen);
     *offset += 2;
-    if *offset + rdlen > data.len() {
+    if !has_bytes(data, *offset, rdlen) {
         return Err(Error::UnexpectedEOF);
     }
-    let data = RData::parse(typ,
-        &data[*offset..*offset+rdlen], data)?;
+    let rdata = &data[*offset..*offset+rdlen];
+    parse_trace!(*offset, "rdata", rdata);
+    let data = match registry {
+        // UPDATE prerequisites and RRset deletions (RFC 2136) carry no
+        // RDATA, see `Packet::update_ops`
+        _ if rdlen == 0 && (cls == Class::ANY || cls == Class::NONE) => {
+            RData::Unknown(Type::parse(code)?, rdata)
+        }
+        Some(reg) => RData::parse_with_registry(code, rdata, data, reg)?,
+        None => RData::parse(Type::parse(code)?, rdata, data)?,
+    };
     *offset += rdlen;
     Ok(ResourceRecord {
-        name: name,
-        multicast_unique: multicast_unique,
-        cls: cls,
-        ttl: ttl,
-        data: data,
+        name,
+        multicast_unique,
+        cls,
+        ttl,
+        data,
     })
 }
 
 // Function to parse an RFC 6891 OPT Pseudo RR
-fn parse_opt_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<Opt<'a>, Error> {
-    if *offset + 11 > data.len() {
+pub(crate) fn parse_opt_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<Opt<'a>, Error> {
+    if !has_bytes(data, *offset, 11) {
         return Err(Error::UnexpectedEOF);
     }
     *offset += 1;
@@ -134,16 +452,21 @@ fn parse_opt_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<Opt<'a>, E
     }
     *offset += 2;
     let udp = BigEndian::read_u16(&data[*offset..*offset+2]);
+    parse_trace!(*offset, "udp payload size", udp);
     *offset += 2;
     let extrcode = data[*offset];
+    parse_trace!(*offset, "extended rcode", extrcode);
     *offset += 1;
     let version = data[*offset];
+    parse_trace!(*offset, "version", version);
     *offset += 1;
     let flags = BigEndian::read_u16(&data[*offset..*offset+2]);
+    parse_trace!(*offset, "flags", flags);
     *offset += 2;
     let rdlen = BigEndian::read_u16(&data[*offset..*offset+2]) as usize;
+    parse_trace!(*offset, "rdlength", rdlen);
     *offset += 2;
-    if *offset + rdlen > data.len() {
+    if !has_bytes(data, *offset, rdlen) {
         return Err(Error::UnexpectedEOF);
     }
     let data = RData::parse(typ,
@@ -151,11 +474,11 @@ fn parse_opt_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<Opt<'a>, E
     *offset += rdlen;
 
     Ok(Opt {
-        udp: udp,
-        extrcode: extrcode,
-        version: version,
-        flags: flags,
-        data: data,
+        udp,
+        extrcode,
+        version,
+        flags,
+        data,
     })
 }
 
@@ -163,7 +486,7 @@ fn parse_opt_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<Opt<'a>, E
 mod test {
 
     use std::net::Ipv4Addr;
-    use {Packet, Header};
+    use {Packet, Header, Error, ParseOptions, Section, SkippedSection};
     use Opcode::*;
     use ResponseCode::NoError;
     use QueryType as QT;
@@ -186,6 +509,7 @@ mod test {
             recursion_available: false,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 0,
@@ -216,6 +540,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 1,
@@ -228,7 +553,7 @@ mod test {
         assert_eq!(&packet.questions[0].qname.to_string()[..], "example.com");
         assert_eq!(packet.answers.len(), 1);
         assert_eq!(&packet.answers[0].name.to_string()[..], "example.com");
-        assert_eq!(packet.answers[0].multicast_unique, false);
+        assert!(!packet.answers[0].multicast_unique);
         assert_eq!(packet.answers[0].cls, C::IN);
         assert_eq!(packet.answers[0].ttl, 1272);
         match packet.answers[0].data {
@@ -248,7 +573,7 @@ mod test {
         let packet = Packet::parse(response).unwrap();
 
         assert_eq!(packet.answers.len(), 1);
-        assert_eq!(packet.answers[0].multicast_unique, true);
+        assert!(packet.answers[0].multicast_unique);
         assert_eq!(packet.answers[0].cls, C::IN);
     }
 
@@ -276,6 +601,7 @@ mod test {
               recursion_available: true,
               authenticated_data: false,
               checking_disabled: false,
+              reserved: false,
               response_code: NoError,
               questions: 1,
               answers: 1,
@@ -341,6 +667,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 6,
@@ -352,7 +679,7 @@ mod test {
         assert_eq!(packet.questions[0].qclass, QC::IN);
         assert_eq!(&packet.questions[0].qname.to_string()[..], "google.com");
         assert_eq!(packet.answers.len(), 6);
-        let ips = vec![
+        let ips = [
             Ipv4Addr::new(64, 233, 164, 100),
             Ipv4Addr::new(64, 233, 164, 139),
             Ipv4Addr::new(64, 233, 164, 113),
@@ -360,13 +687,13 @@ mod test {
             Ipv4Addr::new(64, 233, 164, 101),
             Ipv4Addr::new(64, 233, 164, 138),
         ];
-        for i in 0..6 {
-            assert_eq!(&packet.answers[i].name.to_string()[..], "google.com");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 239);
-            match packet.answers[i].data {
+        for (answer, ip) in packet.answers.iter().zip(ips.iter()) {
+            assert_eq!(&answer.name.to_string()[..], "google.com");
+            assert_eq!(answer.cls, C::IN);
+            assert_eq!(answer.ttl, 239);
+            match answer.data {
                 RData::A(addr) => {
-                    assert_eq!(addr.0, ips[i]);
+                    assert_eq!(addr.0, *ip);
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
@@ -388,6 +715,7 @@ mod test {
             recursion_available: false,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 0,
@@ -397,7 +725,7 @@ mod test {
         assert_eq!(packet.questions.len(), 1);
         assert_eq!(packet.questions[0].qtype, QT::SRV);
         assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(packet.questions[0].prefer_unicast, false);
+        assert!(!packet.questions[0].prefer_unicast);
         assert_eq!(&packet.questions[0].qname.to_string()[..],
             "_xmpp-server._tcp.gmail.com");
         assert_eq!(packet.answers.len(), 0);
@@ -412,7 +740,21 @@ mod test {
         assert_eq!(packet.questions.len(), 1);
         assert_eq!(packet.questions[0].qtype, QT::A);
         assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(packet.questions[0].prefer_unicast, true);
+        assert!(packet.questions[0].prefer_unicast);
+    }
+
+    #[test]
+    fn parse_tcp_query() {
+        let query = b"\x00\x1d\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                      \x07example\x03com\x00\x00\x01\x00\x01\xff\xff";
+        let packet = Packet::parse_tcp(query).unwrap();
+        assert_eq!(packet.header.id, 1573);
+        assert_eq!(&packet.questions[0].qname.to_string()[..], "example.com");
+
+        assert!(matches!(Packet::parse_tcp(&query[..1]),
+            Err(Error::UnexpectedEOF)));
+        assert!(matches!(Packet::parse_tcp(&query[..20]),
+            Err(Error::UnexpectedEOF)));
     }
 
     #[test]
@@ -431,6 +773,7 @@ mod test {
             recursion_available: false,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 0,
@@ -452,4 +795,157 @@ mod test {
             None => panic!("Missing OPT RR")
         }
     }
+
+    #[test]
+    fn decompression_limit() {
+        // Each answer's owner and CNAME target point at the previous
+        // target prefixed with one more label, so names grow linearly and
+        // the total decompressed length grows quadratically
+        let mut data = b"\x00\x01\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+            \x01x\x00\x00\x01\x00\x01".to_vec();
+        let mut prev = 12;
+        let mut count = 0;
+        while data.len() < 4000 {
+            data.push(0xc0 | (prev >> 8) as u8);
+            data.push(prev as u8);
+            data.extend(b"\x00\x05\x00\x01\x00\x00\x00\x00\x00\x04");
+            let next = data.len();
+            data.extend(b"\x01x");
+            data.push(0xc0 | (prev >> 8) as u8);
+            data.push(prev as u8);
+            prev = next;
+            count += 1;
+        }
+        data[6] = (count >> 8) as u8;
+        data[7] = count as u8;
+        let limited = ParseOptions {
+            decompression_limit: Some(8),
+            ..ParseOptions::default()
+        };
+        assert!(matches!(Packet::parse_with_options(&data, &limited),
+            Err(Error::DecompressionLimit)));
+        let unlimited = ParseOptions {
+            decompression_limit: None,
+            ..ParseOptions::default()
+        };
+        assert_eq!(Packet::parse_with_options(&data, &unlimited).unwrap()
+            .answers.len(), count);
+    }
+
+    #[test]
+    fn strict_rdata() {
+        // CNAME with two bytes after the name
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x01a\x00\x00\x05\x00\x01\x00\x00\x00\x3c\x00\x05\
+                         \x01b\x00\xde\xad";
+        let strict = ParseOptions {
+            strict_rdata: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(Packet::parse(response).unwrap().answers.len(), 1);
+        assert_eq!(Packet::parse_with_options(response, &strict).unwrap_err(),
+            Error::TrailingRdata(::Type::CNAME));
+
+        // Compressed names in RDATA
+        let response = b"\x4a\xf0\x81\x80\x00\x01\x00\x01\x00\x01\x00\x01\
+                         \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                         \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+                         \xc0\x42\x00\x02\x00\x01\x00\x01\xd5\xd3\x00\x11\
+                         \x01g\x0cgtld-servers\xc0\x42\
+                         \x01a\xc0\x55\x00\x01\x00\x01\x00\x00\xa3\x1c\
+                         \x00\x04\xc0\x05\x06\x1e";
+        assert!(Packet::parse_with_options(response, &strict).is_ok());
+    }
+
+    #[test]
+    fn skip_sections() {
+        let response = b"\x4a\xf0\x81\x80\x00\x01\x00\x01\x00\x01\x00\x01\
+                         \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                         \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+                         \xc0\x42\x00\x02\x00\x01\x00\x01\xd5\xd3\x00\x11\
+                         \x01g\x0cgtld-servers\xc0\x42\
+                         \x01a\xc0\x55\x00\x01\x00\x01\x00\x00\xa3\x1c\
+                         \x00\x04\xc0\x05\x06\x1e";
+        let options = ParseOptions {
+            skip_authority: true,
+            skip_additional: true,
+            ..ParseOptions::default()
+        };
+        let packet = Packet::parse_with_options(response, &options).unwrap();
+        assert_eq!(packet.answers.len(), 1);
+        assert_eq!(packet.nameservers.len(), 0);
+        assert_eq!(packet.additional.len(), 0);
+        assert_eq!(packet.skipped, vec![
+            SkippedSection { section: Section::Authority, range: 71..100 },
+            SkippedSection { section: Section::Additional,
+                range: 100..response.len() },
+        ]);
+
+        // Skipped records must still be well-formed
+        assert!(matches!(
+            Packet::parse_with_options(&response[..110], &options),
+            Err(Error::UnexpectedEOF)));
+    }
+
+    #[test]
+    fn skip_binary_labels() {
+        // A question and an answer owned by a name with a binary label,
+        // then a CNAME to one
+        let response = b"\x00\x01\x81\x80\x00\x01\x00\x03\x00\x00\x00\x00\
+                         \x41\x08\xc0\x01a\x00\x00\x01\x00\x01\
+                         \x01b\x00\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\
+                         \x7f\x00\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\
+                         \x7f\x00\x00\x02\
+                         \x01c\x00\x00\x05\x00\x01\x00\x00\x00\x3c\x00\x02\
+                         \xc0\x0c";
+        assert_eq!(Packet::parse(response).unwrap_err(),
+            Error::BinaryLabelUnsupported);
+        let options = ParseOptions {
+            skip_binary_labels: true,
+            ..ParseOptions::default()
+        };
+        let packet = Packet::parse_with_options(response, &options).unwrap();
+        assert_eq!(packet.questions.len(), 0);
+        assert_eq!(packet.answers.len(), 1);
+        assert_eq!(packet.answers[0].name.to_string(), "b");
+        assert_eq!(packet.skipped, vec![
+            SkippedSection { section: Section::Question, range: 12..22 },
+            SkippedSection { section: Section::Answer, range: 39..55 },
+            SkippedSection { section: Section::Answer,
+                range: 55..response.len() },
+        ]);
+    }
+
+    #[test]
+    fn update_classes() {
+        use {Builder, Type};
+        // A prerequisite "RRset exists" (class ANY) and a deletion of
+        // a single RR (class NONE)
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QT::SOA, QC::None);
+        bld.answer("www.example.com", C::ANY, RData::Unknown(Type::A, b""),
+            false, 0);
+        bld.nameserver("www.example.com", C::NONE,
+            RData::A(::rdata::A(Ipv4Addr::new(192, 0, 2, 1))), false, 0);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.questions[0].qclass, QC::None);
+        assert_eq!(packet.answers[0].cls, C::ANY);
+        assert!(matches!(packet.answers[0].data,
+            RData::Unknown(Type::A, b"")));
+        assert_eq!(packet.nameservers[0].cls, C::NONE);
+    }
+
+    #[test]
+    fn unknown_class() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+            \x07example\x03com\x00\x00\x01\x00\x01\
+            \xc0\x0c\x00\x01\x00\x09\x00\x00\x04\xf8\x00\x04\x5d\xb8\xd8\x22";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.answers[0].cls, C::Other(9));
+        assert!(!packet.answers[0].multicast_unique);
+    }
 }
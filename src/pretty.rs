@@ -0,0 +1,139 @@
+//! dig-style rendering of packets
+use std::fmt;
+
+use {Packet, ResourceRecord, Opcode, ResponseCode};
+
+/// Displays a packet the way `dig` prints responses
+///
+/// Returned by `Packet::dig`.
+#[derive(Debug)]
+pub struct Dig<'p, 'a: 'p>(&'p Packet<'a>);
+
+impl<'a> Packet<'a> {
+    /// Returns a value displaying the packet in the format of `dig`
+    pub fn dig(&self) -> Dig<'_, 'a> {
+        Dig(self)
+    }
+}
+
+fn opcode_name(opcode: Opcode) -> String {
+    match opcode {
+        Opcode::StandardQuery => "QUERY".into(),
+        Opcode::InverseQuery => "IQUERY".into(),
+        Opcode::ServerStatusRequest => "STATUS".into(),
+        Opcode::Update => "UPDATE".into(),
+        Opcode::StatefulOperations => "DSO".into(),
+        Opcode::Reserved(x) => format!("RESERVED{}", x),
+    }
+}
+
+fn rcode_name(code: ResponseCode) -> String {
+    match code {
+        ResponseCode::NoError => "NOERROR".into(),
+        ResponseCode::FormatError => "FORMERR".into(),
+        ResponseCode::ServerFailure => "SERVFAIL".into(),
+        ResponseCode::NameError => "NXDOMAIN".into(),
+        ResponseCode::NotImplemented => "NOTIMP".into(),
+        ResponseCode::Refused => "REFUSED".into(),
+        ResponseCode::Reserved(x) => format!("RESERVED{}", x),
+    }
+}
+
+fn write_record(f: &mut fmt::Formatter, rr: &ResourceRecord) -> fmt::Result {
+    writeln!(f, "{}.\t\t{}\t{}\t{}\t{}", rr.name, rr.ttl, rr.cls,
+        rr.data.type_name(), rr.data)
+}
+
+impl<'p, 'a> fmt::Display for Dig<'p, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let packet = self.0;
+        let h = &packet.header;
+        writeln!(f, ";; ->>HEADER<<- opcode: {}, status: {}, id: {}",
+            opcode_name(h.opcode), rcode_name(h.response_code), h.id)?;
+        let flags = [
+            (!h.query, "qr"),
+            (h.authoritative, "aa"),
+            (h.truncated, "tc"),
+            (h.recursion_desired, "rd"),
+            (h.recursion_available, "ra"),
+            (h.authenticated_data, "ad"),
+            (h.checking_disabled, "cd"),
+        ];
+        write!(f, ";; flags:")?;
+        for &(set, name) in &flags {
+            if set {
+                write!(f, " {}", name)?;
+            }
+        }
+        writeln!(f, "; QUERY: {}, ANSWER: {}, AUTHORITY: {}, ADDITIONAL: {}",
+            h.questions, h.answers, h.nameservers, h.additional)?;
+
+        if let Some(ref opt) = packet.opt {
+            writeln!(f)?;
+            writeln!(f, ";; OPT PSEUDOSECTION:")?;
+            write!(f, "; EDNS: version: {}, flags:", opt.version())?;
+            if opt.dnssec_ok() {
+                write!(f, " do")?;
+            }
+            writeln!(f, "; udp: {}", opt.udp)?;
+        }
+        if !packet.questions.is_empty() {
+            writeln!(f)?;
+            writeln!(f, ";; QUESTION SECTION:")?;
+            for q in &packet.questions {
+                writeln!(f, ";{}.\t\t\t{}\t{}", q.qname, q.qclass,
+                    q.qtype)?;
+            }
+        }
+        let sections = [
+            ("ANSWER", &packet.answers),
+            ("AUTHORITY", &packet.nameservers),
+            ("ADDITIONAL", &packet.additional),
+        ];
+        for &(name, records) in &sections {
+            if records.is_empty() {
+                continue;
+            }
+            writeln!(f)?;
+            writeln!(f, ";; {} SECTION:", name)?;
+            for rr in records.iter() {
+                write_record(f, rr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use Packet;
+
+    #[test]
+    fn dig_output() {
+        let response = b"\x4a\xf0\x81\x80\x00\x01\x00\x01\x00\x01\x00\x01\
+                         \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                         \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+                         \xc0\x42\x00\x02\x00\x01\x00\x01\xd5\xd3\x00\x11\
+                         \x01g\x0cgtld-servers\xc0\x42\
+                         \x01a\xc0\x55\x00\x01\x00\x01\x00\x00\xa3\x1c\
+                         \x00\x04\xc0\x05\x06\x1e";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.dig().to_string(), "\
+;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 19184
+;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 1, ADDITIONAL: 1
+
+;; QUESTION SECTION:
+;www.skype.com.\t\t\tIN\tA
+
+;; ANSWER SECTION:
+www.skype.com.\t\t3600\tIN\tCNAME\tlivecms.trafficmanager.net.
+
+;; AUTHORITY SECTION:
+net.\t\t120275\tIN\tNS\tg.gtld-servers.net.
+
+;; ADDITIONAL SECTION:
+a.gtld-servers.net.\t\t41756\tIN\tA\t192.5.6.30
+");
+    }
+}
@@ -0,0 +1,167 @@
+use QueryType;
+
+/// Number of queries that reveal one label at a time (RFC 9156 section 2.3)
+const MINIMISE_ONE_LAB: usize = 4;
+/// Maximum number of minimized queries for a name (RFC 9156 section 2.3)
+const MAX_MINIMISE_COUNT: usize = 10;
+
+/// A query to send while resolving a name with QNAME minimization
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimizedQuery {
+    /// The name to ask for
+    pub qname: String,
+    /// `QueryType::A` (or NS, see `Minimizer::ns_queries`) for the
+    /// intermediate names, the requested type for the last query
+    pub qtype: QueryType,
+}
+
+/// Generates the queries of QNAME minimization (RFC 9156)
+///
+/// Each query reveals only one more label of the target name to the
+/// servers of the current zone cut. After a referral, report the new zone
+/// with `zone_cut` and the following queries continue from there. The
+/// last query is for the full name and type. Following RFC 9156, after
+/// the first four queries the remaining labels are revealed in bigger
+/// steps, so that no name takes more than ten queries.
+///
+/// ```
+/// use dns_parser::{Minimizer, QueryType};
+///
+/// let mut queries = Minimizer::new("www.example.com", QueryType::AAAA);
+/// assert_eq!(queries.next().unwrap().qname, "com");
+/// queries.zone_cut("com");
+/// assert_eq!(queries.next().unwrap().qname, "example.com");
+/// let last = queries.next().unwrap();
+/// assert_eq!((&last.qname[..], last.qtype),
+///            ("www.example.com", QueryType::AAAA));
+/// assert_eq!(queries.next(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Minimizer {
+    labels: Vec<String>,
+    qtype: QueryType,
+    minimized_qtype: QueryType,
+    /// Labels revealed so far, counted from the root
+    revealed: usize,
+    queries: usize,
+    done: bool,
+}
+
+impl Minimizer {
+    /// Starts at the root zone
+    pub fn new(target: &str, qtype: QueryType) -> Minimizer {
+        Minimizer {
+            labels: target.trim_end_matches('.').split('.')
+                .filter(|label| !label.is_empty())
+                .map(String::from)
+                .collect(),
+            qtype,
+            minimized_qtype: QueryType::A,
+            revealed: 0,
+            queries: 0,
+            done: false,
+        }
+    }
+
+    /// Uses NS instead of A for the intermediate queries, as RFC 7816 did
+    pub fn ns_queries(mut self) -> Minimizer {
+        self.minimized_qtype = QueryType::NS;
+        self
+    }
+
+    /// Sets the zone cut learned from a referral
+    ///
+    /// Zones that aren't an ancestor of the target are ignored, and so are
+    /// zones above the names already asked for.
+    pub fn zone_cut(&mut self, zone: &str) {
+        let zone: Vec<&str> = zone.trim_end_matches('.').split('.')
+            .filter(|label| !label.is_empty())
+            .collect();
+        let matches = zone.len() <= self.labels.len()
+            && zone.iter().rev().zip(self.labels.iter().rev())
+                .all(|(a, b)| a.eq_ignore_ascii_case(b));
+        if matches && zone.len() > self.revealed {
+            self.revealed = zone.len();
+        }
+    }
+
+    fn name(&self, labels: usize) -> String {
+        self.labels[self.labels.len() - labels..].join(".")
+    }
+}
+
+impl Iterator for Minimizer {
+    type Item = MinimizedQuery;
+    fn next(&mut self) -> Option<MinimizedQuery> {
+        if self.done {
+            return None;
+        }
+        let remaining = self.labels.len().saturating_sub(self.revealed);
+        let step = if self.queries < MINIMISE_ONE_LAB {
+            1
+        } else if self.queries < MAX_MINIMISE_COUNT {
+            remaining.div_ceil(MAX_MINIMISE_COUNT - self.queries)
+        } else {
+            remaining
+        };
+        self.revealed += step.max(1);
+        self.queries += 1;
+        if self.revealed >= self.labels.len() {
+            self.done = true;
+            return Some(MinimizedQuery {
+                qname: self.name(self.labels.len()),
+                qtype: self.qtype,
+            });
+        }
+        Some(MinimizedQuery {
+            qname: self.name(self.revealed),
+            qtype: self.minimized_qtype,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use QueryType;
+    use super::Minimizer;
+
+    fn names(queries: Minimizer) -> Vec<String> {
+        queries.map(|q| q.qname).collect()
+    }
+
+    #[test]
+    fn one_label_at_a_time() {
+        let queries = Minimizer::new("a.b.example.com.", QueryType::MX);
+        assert_eq!(names(queries.clone()),
+            ["com", "example.com", "b.example.com", "a.b.example.com"]);
+        let types: Vec<_> = queries.ns_queries().map(|q| q.qtype).collect();
+        assert_eq!(types, [QueryType::NS, QueryType::NS, QueryType::NS,
+                           QueryType::MX]);
+    }
+
+    #[test]
+    fn zone_cut() {
+        let mut queries = Minimizer::new("a.b.example.com", QueryType::A);
+        queries.zone_cut("b.example.com");
+        assert_eq!(queries.next().unwrap().qname, "a.b.example.com");
+        assert_eq!(queries.next(), None);
+
+        let mut queries = Minimizer::new("a.b.example.com", QueryType::A);
+        queries.zone_cut("example.org");
+        queries.zone_cut("EXAMPLE.com");
+        assert_eq!(names(queries), ["b.example.com", "a.b.example.com"]);
+
+        assert_eq!(names(Minimizer::new(".", QueryType::NS)), [""]);
+    }
+
+    #[test]
+    fn long_names() {
+        let target = (0..30).map(|i| i.to_string()).collect::<Vec<_>>()
+            .join(".");
+        let queries = names(Minimizer::new(&target, QueryType::A));
+        assert_eq!(queries.len(), 10);
+        assert_eq!(queries[3], "26.27.28.29");
+        assert_eq!(queries[4], "21.22.23.24.25.26.27.28.29");
+        assert_eq!(queries[9], target);
+    }
+}
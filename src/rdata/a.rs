@@ -1,31 +1,80 @@
-use std::net::Ipv4Addr;
+use std::io::{self, Write};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
 
 use Error;
 use byteorder::{BigEndian, ByteOrder};
+use super::text::Fields;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Record(pub Ipv4Addr);
 
+impl Record {
+    /// Creates a new A record for `address`
+    pub fn new(address: Ipv4Addr) -> Record {
+        Record(address)
+    }
+}
+
+impl From<Ipv4Addr> for Record {
+    fn from(address: Ipv4Addr) -> Record {
+        Record(address)
+    }
+}
+
+impl From<Record> for Ipv4Addr {
+    fn from(record: Record) -> Ipv4Addr {
+        record.0
+    }
+}
+
+impl From<Record> for IpAddr {
+    fn from(record: Record) -> IpAddr {
+        IpAddr::V4(record.0)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Parses the address, e.g. `192.0.2.1`
+impl FromStr for Record {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record(fields.parse()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
 impl<'a> super::Record<'a> for Record {
 
     const TYPE: isize = 1;
 
-    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
         if rdata.len() != 4 {
             return Err(Error::WrongRdataLength);
         }
         let address = Ipv4Addr::from(BigEndian::read_u32(rdata));
         let record = Record(address);
-        Ok(super::RData::A(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
         4
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
+impl super::WireEncode for Record {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
         let num: u32 = self.0.into();
-        num.to_be_bytes().to_vec()
+        w.write_all(&num.to_be_bytes())?;
+        Ok(4)
     }
 }
 
@@ -43,4 +92,13 @@ mod test {
         assert_eq!(record.rdata_length(), 4);
         assert_eq!(record.to_bytes(), b"\x80\x08\xFF\x10")
     }
+
+    #[test]
+    fn ip_conversions() {
+        let ip = Ipv4Addr::new(192, 0, 2, 1);
+        let record = super::Record::from(ip);
+        assert_eq!(Ipv4Addr::from(record), ip);
+        assert_eq!(IpAddr::from(record), IpAddr::V4(ip));
+        assert_eq!(RData::A(record).ip(), Some(IpAddr::V4(ip)));
+    }
 }
@@ -1,16 +1,62 @@
-use std::net::Ipv6Addr;
+use std::io::{self, Write};
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::str::FromStr;
 
 use Error;
 use byteorder::{BigEndian, ByteOrder};
+use super::text::Fields;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Record(pub Ipv6Addr);
 
+impl Record {
+    /// Creates a new AAAA record for `address`
+    pub fn new(address: Ipv6Addr) -> Record {
+        Record(address)
+    }
+}
+
+impl From<Ipv6Addr> for Record {
+    fn from(address: Ipv6Addr) -> Record {
+        Record(address)
+    }
+}
+
+impl From<Record> for Ipv6Addr {
+    fn from(record: Record) -> Ipv6Addr {
+        record.0
+    }
+}
+
+impl From<Record> for IpAddr {
+    fn from(record: Record) -> IpAddr {
+        IpAddr::V6(record.0)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Parses the address, e.g. `2001:db8::1`
+impl FromStr for Record {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record(fields.parse()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
 impl<'a> super::Record<'a> for Record {
 
     const TYPE: isize = 28;
 
-    fn parse(rdata: &'a [u8], _record: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], _record: &'a [u8]) -> Result<Self, Error> {
         if rdata.len() != 16 {
             return Err(Error::WrongRdataLength);
         }
@@ -25,15 +71,18 @@ impl<'a> super::Record<'a> for Record {
             BigEndian::read_u16(&rdata[14..16]),
             );
         let record = Record(address);
-        Ok(super::RData::AAAA(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
         16
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-        unimplemented!();
+impl super::WireEncode for Record {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_all(&self.0.octets())?;
+        Ok(16)
     }
 }
 
@@ -49,6 +98,28 @@ mod test {
     use RData;
     use super::*;
 
+    #[test]
+    fn ip_conversions() {
+        use std::net::IpAddr;
+
+        let ip = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let record = Record::from(ip);
+        assert_eq!(Ipv6Addr::from(record), ip);
+        assert_eq!(IpAddr::from(record), IpAddr::V6(ip));
+        assert_eq!(RData::AAAA(record).ip(), Some(IpAddr::V6(ip)));
+    }
+
+    #[test]
+    fn format() {
+        let record = RData::AAAA(Record::new(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)));
+
+        assert_eq!(record.rdata_length(), 16);
+        assert_eq!(&record.to_bytes()[..],
+            &b"\x20\x01\x0d\xb8\x00\x00\x00\x00\
+               \x00\x00\x00\x00\x00\x00\x00\x01"[..]);
+    }
+
     #[test]
     fn parse_response() {
         let response = b"\xa9\xd9\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\x06\
@@ -66,6 +137,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 1,
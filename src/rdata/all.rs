@@ -1,3 +1,5 @@
+use std::io::{self, Write};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Record;
 
@@ -5,15 +7,17 @@ impl<'a> super::Record<'a> for Record {
 
     const TYPE: isize = 255;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, ::Error> {
         unimplemented!();
     }
 
     fn length(&self) -> u16 {
         unimplemented!();
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
+impl super::WireEncode for Record {
+    fn write_to<W: Write>(&self, _w: &mut W) -> io::Result<usize> {
         unimplemented!();
     }
 }
@@ -0,0 +1,344 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use std::str::{FromStr, from_utf8};
+use {Error, WireEncode};
+use byteorder::WriteBytesExt;
+use super::text::Fields;
+use super::write_character_string;
+
+/// The flag making CAs that don't understand the tag refuse to issue
+pub const ISSUER_CRITICAL: u8 = 0x80;
+
+/// Tags defined by RFC 8659 and RFC 9495
+pub const KNOWN_TAGS: [&str; 4] = ["issue", "issuewild", "iodef", "issuemail"];
+
+/// The CAA record (RFC 8659)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record<'a> {
+    pub flags: u8,
+    /// The property tag, 1 to 15 ASCII letters and digits
+    pub tag: Cow<'a, str>,
+    pub value: Cow<'a, [u8]>,
+}
+
+/// The value of an `issue`, `issuewild` or `issuemail` property
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Issuer<'r> {
+    /// Domain of the CA, `None` if no CA may issue
+    pub domain: Option<&'r str>,
+    /// The parameters after `;` as `(tag, value)`
+    pub parameters: Vec<(&'r str, &'r str)>,
+}
+
+impl<'r> Issuer<'r> {
+    /// Returns the value of the parameter `tag`, compared ignoring case
+    pub fn parameter(&self, tag: &str) -> Option<&'r str> {
+        self.parameters.iter()
+            .find(|&&(t, _)| t.eq_ignore_ascii_case(tag))
+            .map(|&(_, v)| v)
+    }
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new CAA record
+    ///
+    /// # Panics
+    ///
+    /// When the tag is empty, longer than 15 bytes or has characters
+    /// other than ASCII letters and digits.
+    pub fn new<T, V>(flags: u8, tag: T, value: V) -> Record<'a>
+        where T: Into<Cow<'a, str>>, V: Into<Cow<'a, [u8]>>
+    {
+        let tag = tag.into();
+        assert!(is_valid_tag(tag.as_bytes()), "invalid CAA tag");
+        Record { flags, tag, value: value.into() }
+    }
+
+    /// Copies the tag and value so that the record no longer borrows the
+    /// packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            flags: self.flags,
+            tag: Cow::Owned(self.tag.into_owned()),
+            value: Cow::Owned(self.value.into_owned()),
+        }
+    }
+
+    /// Returns true if the issuer critical flag is set
+    pub fn is_critical(&self) -> bool {
+        self.flags & ISSUER_CRITICAL != 0
+    }
+
+    /// Returns true if the tag is one of `KNOWN_TAGS`, ignoring case
+    pub fn is_known_tag(&self) -> bool {
+        KNOWN_TAGS.iter().any(|t| t.eq_ignore_ascii_case(&self.tag))
+    }
+
+    /// Returns true if the tag is `tag`, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tag.eq_ignore_ascii_case(tag)
+    }
+
+    /// Parses the value as an issuer domain followed by parameters
+    ///
+    /// This is the syntax of `issue`, `issuewild` and `issuemail`
+    /// (RFC 8659 section 4.2), e.g. `ca.example.net; account=230123`.
+    pub fn issuer(&self) -> Result<Issuer<'_>, Error> {
+        let value = from_utf8(&self.value)
+            .map_err(|_| Error::InvalidCaa("value is not ASCII"))?;
+        let (domain, parameters) = match value.find(';') {
+            Some(idx) => (&value[..idx], &value[idx+1..]),
+            None => (value, ""),
+        };
+        let domain = domain.trim_matches(is_wsp);
+        let domain = if domain.is_empty() {
+            None
+        } else if domain.split('.').all(|label| !label.is_empty() &&
+            label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'))
+        {
+            Some(domain)
+        } else {
+            return Err(Error::InvalidCaa("invalid issuer domain"));
+        };
+        let mut issuer = Issuer { domain, parameters: Vec::new() };
+        if parameters.trim_matches(is_wsp).is_empty() {
+            return Ok(issuer);
+        }
+        for parameter in parameters.split(';') {
+            let mut parts = parameter.splitn(2, '=');
+            let tag = parts.next().unwrap_or("").trim_matches(is_wsp);
+            let value = parts.next()
+                .ok_or(Error::InvalidCaa("parameter has no value"))?
+                .trim_matches(is_wsp);
+            if !is_valid_tag(tag.as_bytes()) {
+                return Err(Error::InvalidCaa("invalid parameter tag"));
+            }
+            if value.bytes().any(|b| !(0x21..=0x7e).contains(&b)) {
+                return Err(Error::InvalidCaa("invalid parameter value"));
+            }
+            issuer.parameters.push((tag, value));
+        }
+        Ok(issuer)
+    }
+
+    /// The URL of an `iodef` property
+    ///
+    /// Returns `None` for other tags and for URLs whose scheme is not
+    /// `mailto`, `http` or `https` (RFC 8659 section 4.4).
+    pub fn iodef(&self) -> Option<&str> {
+        if !self.has_tag("iodef") {
+            return None;
+        }
+        let url = from_utf8(&self.value).ok()?;
+        let scheme = &url[..url.find(':')?];
+        if ["mailto", "http", "https"].iter()
+            .any(|s| s.eq_ignore_ascii_case(scheme))
+        {
+            Some(url)
+        } else {
+            None
+        }
+    }
+
+    /// The issuers authorized by the `issue` properties of an RRset
+    ///
+    /// A malformed value is returned as an issuer without a domain, it
+    /// authorizes no CA (RFC 8659 section 4.2). An empty result means
+    /// that any CA may issue.
+    pub fn issuers<'r, I>(records: I) -> Vec<Issuer<'r>>
+        where I: IntoIterator<Item = &'r Record<'a>>, 'a: 'r
+    {
+        issuers_with_tag(records, "issue")
+    }
+
+    /// The issuers authorized for wildcard names by an RRset
+    ///
+    /// These are the `issuewild` properties or, when there are none,
+    /// the `issue` properties (RFC 8659 section 4.3).
+    pub fn issue_wild<'r, I>(records: I) -> Vec<Issuer<'r>>
+        where I: IntoIterator<Item = &'r Record<'a>>, 'a: 'r
+    {
+        let records: Vec<_> = records.into_iter().collect();
+        if records.iter().any(|r| r.has_tag("issuewild")) {
+            issuers_with_tag(records, "issuewild")
+        } else {
+            issuers_with_tag(records, "issue")
+        }
+    }
+
+    /// The first usable `iodef` URL of an RRset
+    pub fn iodef_url<'r, I>(records: I) -> Option<&'r str>
+        where I: IntoIterator<Item = &'r Record<'a>>, 'a: 'r
+    {
+        records.into_iter().filter_map(|r| r.iodef()).next()
+    }
+
+    /// Fails if a critical property of the RRset has an unknown tag
+    ///
+    /// A CA must not issue in that case (RFC 8659 section 4.1).
+    pub fn validate<'r, I>(records: I) -> Result<(), Error>
+        where I: IntoIterator<Item = &'r Record<'a>>, 'a: 'r
+    {
+        if records.into_iter().any(|r| r.is_critical() && !r.is_known_tag())
+        {
+            return Err(Error::InvalidCaa("unknown critical property"));
+        }
+        Ok(())
+    }
+}
+
+fn issuers_with_tag<'r, 'a: 'r, I>(records: I, tag: &str) -> Vec<Issuer<'r>>
+    where I: IntoIterator<Item = &'r Record<'a>>
+{
+    records.into_iter()
+        .filter(|r| r.has_tag(tag))
+        .map(|r| r.issuer().unwrap_or(Issuer {
+            domain: None,
+            parameters: Vec::new(),
+        }))
+        .collect()
+}
+
+fn is_wsp(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_valid_tag(tag: &[u8]) -> bool {
+    !tag.is_empty() && tag.len() <= 15 &&
+        tag.iter().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Formats as e.g. `0 issue "ca.example.net"`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ", self.flags, self.tag)?;
+        write_character_string(f, &self.value)
+    }
+}
+
+/// Parses e.g. `128 iodef "mailto:security@example.com"`
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let flags = fields.parse()?;
+        let tag = fields.string()?;
+        if !is_valid_tag(&tag) {
+            return Err(Error::InvalidText("invalid CAA tag"));
+        }
+        let tag = String::from_utf8(tag).expect("tag is ASCII");
+        let record = Record::new(flags, tag, fields.string()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 257;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() < 2 || rdata.len() < 2 + rdata[1] as usize {
+            return Err(Error::WrongRdataLength);
+        }
+        let tag = &rdata[2..2 + rdata[1] as usize];
+        if !is_valid_tag(tag) {
+            return Err(Error::InvalidCaa("invalid tag"));
+        }
+        Ok(Record {
+            flags: rdata[0],
+            tag: Cow::Borrowed(from_utf8(tag).expect("tag is ASCII")),
+            value: Cow::Borrowed(&rdata[2 + tag.len()..]),
+        })
+    }
+
+    fn length(&self) -> u16 {
+        2 + self.tag.len() as u16 + self.value.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u8(self.flags)?;
+        w.write_u8(self.tag.len() as u8)?;
+        w.write_all(self.tag.as_bytes())?;
+        w.write_all(&self.value)?;
+        Ok(2 + self.tag.len() + self.value.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rdata::Record as _;
+    use Error;
+    use super::*;
+
+    #[test]
+    fn issuers() {
+        let records = [
+            Record::new(0, "issue", &b"ca.example.net; account=230123"[..]),
+            Record::new(0, "Issue", &b"  ;  "[..]),
+            Record::new(0, "issuewild", &b"ca2.example.org"[..]),
+            Record::new(0, "iodef", &b"ftp://example.com/"[..]),
+            Record::new(0, "iodef", &b"mailto:security@example.com"[..]),
+            Record::new(0, "issue", &b"bad domain"[..]),
+        ];
+        let issuers = Record::issuers(&records);
+        assert_eq!(issuers.len(), 3);
+        assert_eq!(issuers[0].domain, Some("ca.example.net"));
+        assert_eq!(issuers[0].parameter("ACCOUNT"), Some("230123"));
+        assert_eq!(issuers[1], Issuer { domain: None, parameters: vec![] });
+        assert_eq!(issuers[2].domain, None);
+        assert_eq!(records[5].issuer(),
+            Err(Error::InvalidCaa("invalid issuer domain")));
+
+        let wild = Record::issue_wild(&records);
+        assert_eq!(wild.len(), 1);
+        assert_eq!(wild[0].domain, Some("ca2.example.org"));
+        assert_eq!(Record::issue_wild(&records[..2]).len(), 2);
+
+        assert_eq!(Record::iodef_url(&records),
+            Some("mailto:security@example.com"));
+        assert!(Record::validate(&records).is_ok());
+
+        let issuer = Record::new(0, "issue",
+            &b"ca.example.net; policy=ev;account=1"[..]);
+        assert_eq!(issuer.issuer().unwrap().parameters,
+            [("policy", "ev"), ("account", "1")]);
+        let issuer = Record::new(0, "issue", &b"ca.example.net; policy"[..]);
+        assert!(issuer.issuer().is_err());
+    }
+
+    #[test]
+    fn critical() {
+        let records = [
+            Record::new(ISSUER_CRITICAL, "issue", &b"ca.example.net"[..]),
+            Record::new(ISSUER_CRITICAL, "tbs", &b"Unknown"[..]),
+        ];
+        assert!(records[0].is_critical());
+        assert!(records[0].is_known_tag());
+        assert!(!records[1].is_known_tag());
+        assert!(Record::validate(&records[..1]).is_ok());
+        assert_eq!(Record::validate(&records),
+            Err(Error::InvalidCaa("unknown critical property")));
+    }
+
+    #[test]
+    fn wire_and_text() {
+        let record: Record = "128 issue \"ca.example.net; account=1\""
+            .parse().unwrap();
+        assert!(record.is_critical());
+        let bytes = record.to_bytes();
+        assert_eq!(&bytes[..7], b"\x80\x05issue");
+        assert_eq!(Record::parse(&bytes, &bytes).unwrap(), record);
+        assert_eq!(record.to_string(),
+            "128 issue \"ca.example.net; account=1\"");
+
+        assert_eq!(Record::parse(b"\x00\x00", b""),
+            Err(Error::InvalidCaa("invalid tag")));
+        assert_eq!(Record::parse(b"\x00\x05iss", b""),
+            Err(Error::WrongRdataLength));
+        assert!("0 is-sue \"x\"".parse::<Record>().is_err());
+    }
+}
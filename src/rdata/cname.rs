@@ -1,12 +1,34 @@
-use Name;
+use std::io::{self, Write};
+use std::fmt;
+use std::str::FromStr;
+
+use {Name, Error, WireEncode};
+use super::text::Fields;
 
 #[derive(Debug, Clone)]
 pub struct Record<'a>(pub Name<'a>);
 
-impl<'a> ToString for Record<'a> {
-    #[inline]
-    fn to_string(&self) -> String {
-        self.0.to_string()
+impl<'a> Record<'a> {
+    /// Creates a new CNAME record pointing to `name`
+    pub fn new(name: Name<'a>) -> Record<'a> {
+        Record(name)
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.", self.0)
+    }
+}
+
+/// Parses the name, the trailing dot is optional
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record(fields.name()?);
+        fields.end()?;
+        Ok(record)
     }
 }
 
@@ -14,18 +36,20 @@ impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 5;
 
-    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, ::Error> {
         let name = Name::scan(rdata, original)?;
         let record = Record(name);
-        Ok(super::RData::CNAME(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
         self.0.octet_length() // one for initial length octet, one for root
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-       self.0.to_bytes() 
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        self.0.write_to(w)
     }
 }
 
@@ -67,6 +91,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 6,
@@ -89,20 +114,20 @@ mod test {
             ref x => panic!("Wrong rdata {:?}", x),
         }
 
-        let ips = vec![
+        let ips = [
             Ipv4Addr::new(104, 16, 103, 204),
             Ipv4Addr::new(104, 16, 107, 204),
             Ipv4Addr::new(104, 16, 104, 204),
             Ipv4Addr::new(104, 16, 106, 204),
             Ipv4Addr::new(104, 16, 105, 204),
         ];
-        for i in 1..6 {
-            assert_eq!(&packet.answers[i].name.to_string()[..], "sstatic.net");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 102);
-            match packet.answers[i].data {
+        for (answer, ip) in packet.answers[1..].iter().zip(ips.iter()) {
+            assert_eq!(&answer.name.to_string()[..], "sstatic.net");
+            assert_eq!(answer.cls, C::IN);
+            assert_eq!(answer.ttl, 102);
+            match answer.data {
                 RData::A(addr) => {
-                    assert_eq!(addr.0, ips[i-1]);
+                    assert_eq!(addr.0, *ip);
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use {Error, WireEncode};
+use base64;
+use dnssec::Algorithm;
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+/// The only valid value of the protocol field (RFC 4034 section 2.1.2)
+pub const PROTOCOL: u8 = 3;
+
+typed_flags! {
+    /// Flags of a DNSKEY record (RFC 4034, RFC 5011)
+    pub struct Flags: u16 {
+        /// The key may sign the zone, set on every DNSSEC zone key
+        const ZONE = 0x0100;
+        /// The key has been revoked (RFC 5011)
+        const REVOKE = 0x0080;
+        /// Secure entry point, usually set on key signing keys
+        const SEP = 0x0001;
+    }
+}
+
+/// The DNSKEY record (RFC 4034)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record<'a> {
+    pub flags: Flags,
+    pub protocol: u8,
+    pub algorithm: Algorithm,
+    pub public_key: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new DNSKEY record with protocol 3
+    pub fn new<K>(flags: Flags, algorithm: Algorithm, public_key: K)
+        -> Record<'a>
+        where K: Into<Cow<'a, [u8]>>
+    {
+        Record {
+            flags,
+            protocol: PROTOCOL,
+            algorithm,
+            public_key: public_key.into(),
+        }
+    }
+
+    /// Copies the key so that the record no longer borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            public_key: Cow::Owned(self.public_key.into_owned()),
+            ..self
+        }
+    }
+
+    /// Computes the key tag identifying the key in RRSIG and DS records
+    ///
+    /// Uses the algorithm from RFC 4034 appendix B, including the special
+    /// case of RSA/MD5 keys.
+    pub fn key_tag(&self) -> u16 {
+        if self.algorithm == Algorithm::RsaMd5 {
+            let key = &self.public_key;
+            if key.len() < 3 {
+                return 0;
+            }
+            return BigEndian::read_u16(&key[key.len()-3..key.len()-1]);
+        }
+        let mut rdata = Vec::with_capacity(4 + self.public_key.len());
+        self.write_to(&mut rdata).expect("writing to a Vec can't fail");
+        let mut sum = 0u32;
+        for (i, &byte) in rdata.iter().enumerate() {
+            sum += if i & 1 == 0 { (byte as u32) << 8 } else { byte as u32 };
+        }
+        sum += (sum >> 16) & 0xFFFF;
+        sum as u16
+    }
+}
+
+/// Formats as e.g. `257 3 13 BASE64`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.flags.bits(), self.protocol,
+            u8::from(self.algorithm), base64::encode(&self.public_key))
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 48;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() < 4 {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record {
+            flags: Flags::from_bits(BigEndian::read_u16(&rdata[..2])),
+            protocol: rdata[2],
+            algorithm: Algorithm::from(rdata[3]),
+            public_key: Cow::Borrowed(&rdata[4..]),
+        })
+    }
+
+    fn length(&self) -> u16 {
+        4 + self.public_key.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.flags.bits())?;
+        w.write_u8(self.protocol)?;
+        w.write_u8(u8::from(self.algorithm))?;
+        w.write_all(&self.public_key)?;
+        Ok(4 + self.public_key.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use base64;
+    use dnssec::Algorithm;
+    use rdata::Record as _;
+    use super::*;
+
+    #[test]
+    fn rfc4034_key_tag() {
+        // The key of the DS example in RFC 4034 section 5.4
+        let key = base64::decode_url(&"AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/\
+            2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBa\
+            MbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw=="
+            .replace('+', "-").replace('/', "_")).unwrap();
+        let record = Record::new(Flags::ZONE, Algorithm::RsaSha1, key);
+        assert_eq!(record.key_tag(), 60485);
+
+        let bytes = record.to_bytes();
+        let parsed = Record::parse(&bytes, &bytes).unwrap();
+        assert_eq!(parsed, record);
+        assert!(parsed.flags.contains(Flags::ZONE));
+        assert!(!parsed.flags.contains(Flags::SEP));
+        assert!(parsed.to_string().starts_with("256 3 5 AQOeiiR0"));
+    }
+
+    #[test]
+    fn flags() {
+        let mut flags = Flags::ZONE | Flags::SEP;
+        assert_eq!(flags.bits(), 257);
+        assert_eq!(format!("{:?}", flags), "Flags(ZONE | SEP)");
+        flags.insert(Flags::REVOKE);
+        assert_eq!(flags.bits(), 385);
+        assert_eq!(format!("{:?}", Flags::from_bits(0x8101)),
+            "Flags(ZONE | SEP | 0x8000)");
+    }
+}
@@ -0,0 +1,484 @@
+//! A small matcher for the POSIX extended regular expressions of NAPTR
+//! rewrite rules (RFC 3402 section 3.2)
+//!
+//! Supports literals, `.`, anchors, bracket expressions with ranges,
+//! groups, alternation and the `*`, `+`, `?` and `{m,n}` quantifiers.
+//! Alternatives are tried in order instead of looking for the longest
+//! match, which makes no difference for the anchored expressions used
+//! in practice.
+//!
+//! The expressions come from untrusted responses, so they are compiled
+//! for a Pike VM, which runs all alternatives in lockstep instead of
+//! backtracking. Matching takes linear time in the length of the input
+//! and never recurses, the program size is limited to `MAX_PROGRAM`
+//! instructions as counted repetitions are expanded.
+
+use Error;
+
+/// Capture groups, `\1` to `\9` in the substitution
+const MAX_GROUPS: usize = 9;
+/// Instructions of a compiled program, `a{255}` takes 255
+const MAX_PROGRAM: usize = 4096;
+
+#[derive(Debug)]
+enum Node {
+    Byte(u8),
+    Any,
+    Class { ranges: Vec<(u8, u8)>, negated: bool },
+    Start,
+    End,
+    Group(Box<Node>, usize),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat { node: Box<Node>, min: u32, max: Option<u32> },
+}
+
+/// A compiled expression
+#[derive(Debug)]
+pub(crate) struct Regex {
+    program: Vec<Inst>,
+    groups: usize,
+    ignore_case: bool,
+}
+
+/// Start and end of the match and of each group
+pub(crate) type Captures = Vec<Option<(usize, usize)>>;
+
+struct Parser<'p> {
+    pattern: &'p [u8],
+    pos: usize,
+    groups: usize,
+}
+
+fn invalid(reason: &'static str) -> Error {
+    Error::InvalidNaptr(reason)
+}
+
+impl<'p> Parser<'p> {
+    fn peek(&self) -> Option<u8> {
+        self.pattern.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Result<u8, Error> {
+        let b = self.peek().ok_or(invalid("unexpected end of regexp"))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn alternation(&mut self) -> Result<Node, Error> {
+        let mut alternatives = vec![self.concatenation()?];
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            alternatives.push(self.concatenation()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alt(alternatives)
+        })
+    }
+
+    fn concatenation(&mut self) -> Result<Node, Error> {
+        let mut nodes = Vec::new();
+        while let Some(b) = self.peek() {
+            if b == b'|' || b == b')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifiers(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn atom(&mut self) -> Result<Node, Error> {
+        Ok(match self.next()? {
+            b'(' => {
+                self.groups += 1;
+                let index = self.groups;
+                let inner = self.alternation()?;
+                if self.peek() != Some(b')') {
+                    return Err(invalid("unbalanced parenthesis"));
+                }
+                self.pos += 1;
+                Node::Group(Box::new(inner), index)
+            }
+            b'[' => self.class()?,
+            b'.' => Node::Any,
+            b'^' => Node::Start,
+            b'$' => Node::End,
+            b'\\' => Node::Byte(self.next()?),
+            b'*' | b'+' | b'?' | b'{' => {
+                return Err(invalid("quantifier without operand"));
+            }
+            b => Node::Byte(b),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, Error> {
+        let negated = self.peek() == Some(b'^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        // A `]` right after the opening bracket is a literal
+        let mut first = true;
+        loop {
+            let b = self.next()?;
+            if b == b']' && !first {
+                break;
+            }
+            first = false;
+            if self.peek() == Some(b'-') &&
+                self.pattern.get(self.pos + 1).is_some_and(|&x| x != b']')
+            {
+                self.pos += 1;
+                let end = self.next()?;
+                if end < b {
+                    return Err(invalid("invalid range in bracket"));
+                }
+                ranges.push((b, end));
+            } else {
+                ranges.push((b, b));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        ::std::str::from_utf8(&self.pattern[start..self.pos]).ok()?
+            .parse().ok()
+    }
+
+    fn quantifiers(&mut self, mut node: Node) -> Result<Node, Error> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some(b'*') => (0, None),
+                Some(b'+') => (1, None),
+                Some(b'?') => (0, Some(1)),
+                Some(b'{') => {
+                    self.pos += 1;
+                    let min = self.number()
+                        .ok_or(invalid("invalid interval"))?;
+                    let max = if self.peek() == Some(b',') {
+                        self.pos += 1;
+                        self.number()
+                    } else {
+                        Some(min)
+                    };
+                    if self.peek() != Some(b'}') ||
+                        max.is_some_and(|max| max < min)
+                    {
+                        return Err(invalid("invalid interval"));
+                    }
+                    (min, max)
+                }
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+            node = Node::Repeat { node: Box::new(node), min, max };
+        }
+    }
+}
+
+/// An instruction of the compiled program
+#[derive(Debug)]
+enum Inst {
+    Byte(u8),
+    Any,
+    Class { ranges: Vec<(u8, u8)>, negated: bool },
+    Start,
+    End,
+    /// Stores the position in a capture slot
+    Save(usize),
+    /// Continues at both, the first with higher priority
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> Result<usize, Error> {
+        if self.program.len() >= MAX_PROGRAM {
+            return Err(invalid("regexp too large"));
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn patch_split(&mut self, at: usize, second: usize) {
+        if let Inst::Split(_, ref mut x) = self.program[at] {
+            *x = second;
+        }
+    }
+
+    fn node(&mut self, node: &Node) -> Result<(), Error> {
+        match *node {
+            Node::Byte(b) => { self.emit(Inst::Byte(b))?; }
+            Node::Any => { self.emit(Inst::Any)?; }
+            Node::Class { ref ranges, negated } => {
+                self.emit(Inst::Class { ranges: ranges.clone(), negated })?;
+            }
+            Node::Start => { self.emit(Inst::Start)?; }
+            Node::End => { self.emit(Inst::End)?; }
+            Node::Group(ref inner, index) => {
+                self.emit(Inst::Save(index * 2))?;
+                self.node(inner)?;
+                self.emit(Inst::Save(index * 2 + 1))?;
+            }
+            Node::Concat(ref nodes) => {
+                for node in nodes {
+                    self.node(node)?;
+                }
+            }
+            Node::Alt(ref alternatives) => {
+                let mut jumps = Vec::new();
+                let (last, rest) = alternatives.split_last()
+                    .expect("alternation has two alternatives or more");
+                for alt in rest {
+                    let split = self.emit(Inst::Split(0, 0))?;
+                    self.program[split] = Inst::Split(split + 1, 0);
+                    self.node(alt)?;
+                    jumps.push(self.emit(Inst::Jmp(0))?);
+                    let next = self.program.len();
+                    self.patch_split(split, next);
+                }
+                self.node(last)?;
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jmp(end);
+                }
+            }
+            Node::Repeat { node: ref inner, min, max } => {
+                for _ in 0..min {
+                    self.node(inner)?;
+                }
+                match max {
+                    None => {
+                        let split = self.emit(Inst::Split(0, 0))?;
+                        self.program[split] = Inst::Split(split + 1, 0);
+                        self.node(inner)?;
+                        self.emit(Inst::Jmp(split))?;
+                        let end = self.program.len();
+                        self.patch_split(split, end);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in min..max {
+                            let split = self.emit(Inst::Split(0, 0))?;
+                            self.program[split] = Inst::Split(split + 1, 0);
+                            splits.push(split);
+                            self.node(inner)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.patch_split(split, end);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A thread of the Pike VM
+struct Thread {
+    pc: usize,
+    slots: Vec<Option<usize>>,
+}
+
+/// Threads for one position, each instruction at most once
+struct Threads {
+    list: Vec<Thread>,
+    /// Generation in which each instruction was last added
+    seen: Vec<usize>,
+}
+
+impl Regex {
+    /// Compiles the expression, `ignore_case` is the `i` flag
+    pub(crate) fn new(pattern: &[u8], ignore_case: bool)
+        -> Result<Regex, Error>
+    {
+        let mut parser = Parser { pattern, pos: 0, groups: 0 };
+        let root = parser.alternation()?;
+        if parser.pos != pattern.len() {
+            return Err(invalid("unbalanced parenthesis"));
+        }
+        if parser.groups > MAX_GROUPS {
+            return Err(invalid("too many groups"));
+        }
+        let mut compiler = Compiler { program: Vec::new() };
+        compiler.node(&root)?;
+        compiler.emit(Inst::Match)?;
+        Ok(Regex {
+            program: compiler.program,
+            groups: parser.groups,
+            ignore_case,
+        })
+    }
+
+    fn byte_matches(&self, inst: &Inst, byte: u8) -> bool {
+        let test = |b: u8| match *inst {
+            Inst::Byte(x) => b == x,
+            Inst::Any => true,
+            Inst::Class { ref ranges, negated } => {
+                ranges.iter().any(|&(lo, hi)| lo <= b && b <= hi) != negated
+            }
+            _ => false,
+        };
+        if self.ignore_case {
+            test(byte.to_ascii_lowercase()) || test(byte.to_ascii_uppercase())
+        } else {
+            test(byte)
+        }
+    }
+
+    /// Adds the thread and everything reachable from it without
+    /// consuming input, in priority order
+    fn add_thread(&self, threads: &mut Threads, generation: usize,
+        text: &[u8], pos: usize, thread: Thread)
+    {
+        let mut stack = vec![thread];
+        while let Some(Thread { pc, mut slots }) = stack.pop() {
+            if threads.seen[pc] == generation {
+                continue;
+            }
+            threads.seen[pc] = generation;
+            match self.program[pc] {
+                Inst::Jmp(to) => stack.push(Thread { pc: to, slots }),
+                Inst::Split(first, second) => {
+                    stack.push(Thread { pc: second, slots: slots.clone() });
+                    stack.push(Thread { pc: first, slots });
+                }
+                Inst::Save(slot) => {
+                    slots[slot] = Some(pos);
+                    stack.push(Thread { pc: pc + 1, slots });
+                }
+                Inst::Start if pos == 0 => {
+                    stack.push(Thread { pc: pc + 1, slots });
+                }
+                Inst::End if pos == text.len() => {
+                    stack.push(Thread { pc: pc + 1, slots });
+                }
+                Inst::Start | Inst::End => {}
+                _ => threads.list.push(Thread { pc, slots }),
+            }
+        }
+    }
+
+    /// Finds the first match, group 0 is the whole match
+    ///
+    /// Among matches starting at the leftmost position the one a
+    /// backtracking matcher would find wins. Runs in time proportional
+    /// to the length of the text times the size of the program.
+    pub(crate) fn captures(&self, text: &[u8]) -> Option<Captures> {
+        let slot_count = (self.groups + 1) * 2;
+        let new_threads = || Threads {
+            list: Vec::new(),
+            seen: vec![usize::MAX; self.program.len()],
+        };
+        let mut current = new_threads();
+        let mut next = new_threads();
+        let mut matched: Option<Vec<Option<usize>>> = None;
+        for pos in 0..text.len() + 1 {
+            if matched.is_none() {
+                let mut slots = vec![None; slot_count];
+                slots[0] = Some(pos);
+                self.add_thread(&mut current, pos, text, pos,
+                    Thread { pc: 0, slots });
+            }
+            if current.list.is_empty() {
+                break;
+            }
+            for thread in current.list.drain(..) {
+                match self.program[thread.pc] {
+                    Inst::Match => {
+                        let mut slots = thread.slots;
+                        slots[1] = Some(pos);
+                        matched = Some(slots);
+                        // Threads after this one have lower priority
+                        break;
+                    }
+                    ref inst => {
+                        if pos < text.len()
+                            && self.byte_matches(inst, text[pos])
+                        {
+                            self.add_thread(&mut next, pos + 1, text,
+                                pos + 1,
+                                Thread { pc: thread.pc + 1, ..thread });
+                        }
+                    }
+                }
+            }
+            ::std::mem::swap(&mut current, &mut next);
+        }
+        matched.map(|slots| {
+            slots.chunks(2).map(|pair| match (pair[0], pair[1]) {
+                (Some(start), Some(end)) => Some((start, end)),
+                _ => None,
+            }).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Regex;
+
+    fn find(pattern: &str, text: &str) -> Option<Vec<Option<String>>> {
+        let regex = Regex::new(pattern.as_bytes(), false).unwrap();
+        regex.captures(text.as_bytes()).map(|captures| {
+            captures.iter()
+                .map(|c| c.map(|(s, e)| text[s..e].to_string()))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn matching() {
+        let some = |x: &str| Some(x.to_string());
+        assert_eq!(find("^\\+?(.*)$", "+4420"),
+            Some(vec![some("+4420"), some("4420")]));
+        assert_eq!(find("b+", "abbbc"), Some(vec![some("bbb")]));
+        assert_eq!(find("^(a|ab)c$", "abc"),
+            Some(vec![some("abc"), some("ab")]));
+        assert_eq!(find("[^0-9]{2,3}", "12abcd"), Some(vec![some("abc")]));
+        assert_eq!(find("^[]a]*$", "a]a"), Some(vec![some("a]a")]));
+        assert_eq!(find("(x)?y", "y"), Some(vec![some("y"), None]));
+        assert_eq!(find("(a*)*b", "aab"),
+            Some(vec![some("aab"), some("aa")]));
+        assert_eq!(find("^a$", "ab"), None);
+        assert!(Regex::new(b"^A.c", true).unwrap().captures(b"abc").is_some());
+
+        assert!(Regex::new(b"(a", false).is_err());
+        assert!(Regex::new(b"a)", false).is_err());
+        assert!(Regex::new(b"*a", false).is_err());
+        assert!(Regex::new(b"a{3,1}", false).is_err());
+        assert!(Regex::new(b"[z-a]", false).is_err());
+        assert!(Regex::new(b"((a{200}){200}){200}", false).is_err());
+    }
+
+    #[test]
+    fn pathological() {
+        use std::time::{Duration, Instant};
+
+        let started = Instant::now();
+        let pattern = format!("^{}x$", ".*".repeat(16));
+        assert_eq!(find(&pattern, "+441632960083"), None);
+        let pattern = format!("^{}a{}*$", "(".repeat(9), ")".repeat(9));
+        let text = "a".repeat(100_000);
+        assert!(find(&pattern, &text).is_some());
+        let text = format!("{}c", text);
+        assert_eq!(find("(a*)*b", &text), None);
+        assert_eq!(find(&format!("(a|aa){}b", "*".repeat(50)), &text), None);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
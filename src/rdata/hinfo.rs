@@ -1,19 +1,193 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use std::str::FromStr;
+use byteorder::WriteBytesExt;
+use Error;
+use super::write_character_string;
+use super::text::Fields;
 
-impl<'a> super::Record<'a> for Record {
+/// The CPU field of a synthesized answer to an ANY query (RFC 8482)
+pub const RFC8482_CPU: &[u8] = b"RFC8482";
+
+/// The HINFO (host information) record
+///
+/// Parsed records borrow their strings from the packet, records built
+/// with `new` may own them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record<'a> {
+    pub cpu: Cow<'a, [u8]>,
+    pub os: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new HINFO record from borrowed or owned strings
+    ///
+    /// # Panics
+    ///
+    /// When one of the strings is longer than 255 bytes.
+    pub fn new<C, O>(cpu: C, os: O) -> Record<'a>
+        where C: Into<Cow<'a, [u8]>>, O: Into<Cow<'a, [u8]>>
+    {
+        let record = Record { cpu: cpu.into(), os: os.into() };
+        assert!(record.cpu.len() <= 255 && record.os.len() <= 255,
+            "HINFO string too long");
+        record
+    }
+
+    /// Copies the strings so that the record no longer borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            cpu: Cow::Owned(self.cpu.into_owned()),
+            os: Cow::Owned(self.os.into_owned()),
+        }
+    }
+
+    /// Creates the conventional answer to ANY queries, `HINFO "RFC8482" ""`
+    ///
+    /// RFC 8482 allows servers to answer ANY queries with this single record
+    /// instead of every record they have for the name.
+    pub fn rfc8482() -> Record<'static> {
+        Record::new(RFC8482_CPU, &b""[..])
+    }
+
+    /// Returns true if this is a synthesized RFC 8482 answer
+    pub fn is_rfc8482(&self) -> bool {
+        *self.cpu == *RFC8482_CPU
+    }
+}
+
+fn read_string<'a>(rdata: &'a [u8], pos: &mut usize) -> Result<&'a [u8], Error> {
+    if *pos >= rdata.len() {
+        return Err(Error::WrongRdataLength);
+    }
+    let len = rdata[*pos] as usize;
+    let start = *pos + 1;
+    if rdata.len() - start < len {
+        return Err(Error::WrongRdataLength);
+    }
+    *pos = start + len;
+    Ok(&rdata[start..start+len])
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_character_string(f, &self.cpu)?;
+        f.write_str(" ")?;
+        write_character_string(f, &self.os)
+    }
+}
+
+/// Parses two character-strings, quoted or not
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record::new(fields.string()?, fields.string()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 13;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        let mut pos = 0;
+        let cpu = read_string(rdata, &mut pos)?;
+        let os = read_string(rdata, &mut pos)?;
+        if pos != rdata.len() {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record::new(cpu, os))
     }
 
     fn length(&self) -> u16 {
-        unimplemented!();
+        2 + self.cpu.len() as u16 + self.os.len() as u16
+    }
+}
+
+impl<'a> super::WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u8(self.cpu.len() as u8)?;
+        w.write_all(&self.cpu)?;
+        w.write_u8(self.os.len() as u8)?;
+        w.write_all(&self.os)?;
+        Ok(2 + self.cpu.len() + self.os.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use {Packet, Builder};
+    use QueryType as QT;
+    use QueryClass as QC;
+    use Class as C;
+    use RData;
+    use super::*;
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x8e\x6c\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+            \x0acloudflare\x03com\x00\x00\xff\x00\x01\
+            \xc0\x0c\x00\x0d\x00\x01\x00\x00\x0e\x10\x00\x09\
+            \x07RFC8482\x00";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.questions[0].qtype, QT::All);
+        match packet.answers[0].data {
+            RData::HINFO(ref hinfo) => {
+                assert_eq!(*hinfo, Record::new(b"RFC8482", b""));
+                assert!(hinfo.is_rfc8482());
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        assert!(packet.is_rfc8482_response());
+    }
+
+    #[test]
+    fn build_rfc8482_answer() {
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QT::All, QC::IN);
+        bld.answer("example.com", C::IN, RData::HINFO(Record::rfc8482()),
+            false, 3600);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        assert!(packet.is_rfc8482_response());
+    }
+
+    #[test]
+    fn owned_strings() {
+        let record: Record = "\"Intel Xeon\" Linux".parse().unwrap();
+        assert_eq!(&record.cpu[..], b"Intel Xeon");
+        assert_eq!(record, Record::new(b"Intel Xeon".to_vec(),
+            String::from("Linux").into_bytes()));
+
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", C::IN, RData::HINFO(record), false, 60);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        let owned = match packet.answers[0].data {
+            RData::HINFO(ref hinfo) => hinfo.clone().into_owned(),
+            ref x => panic!("Wrong rdata {:?}", x),
+        };
+        drop(packet);
+        assert_eq!(owned.to_string(), "\"Intel Xeon\" \"Linux\"");
+    }
+
+    #[test]
+    fn long_strings() {
+        let longest = vec![b'x'; 255];
+        let record = Record::new(&longest[..], &b""[..]);
+        assert_eq!(super::super::Record::length(&record), 257);
+        let text = format!("{} Linux", "x".repeat(256));
+        assert!(text.parse::<Record>().is_err());
     }
 
-    fn to_bytes(&self) -> Vec<u8> {
-        unimplemented!();
+    #[test]
+    #[should_panic(expected = "HINFO string too long")]
+    fn too_long_string() {
+        Record::new(vec![b'x'; 256], &b"Linux"[..]);
     }
 }
@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+use std::fmt;
+use std::ops::Deref;
+use {Error, WireEncode};
+use super::svcb;
+
+/// The HTTPS record (RFC 9460 section 9)
+///
+/// An SVCB record for the `https` scheme, all accessors are those of
+/// `svcb::Record`.
+#[derive(Debug, Clone)]
+pub struct Record<'a>(pub svcb::Record<'a>);
+
+impl<'a> Record<'a> {
+    /// Copies the target and parameters so that the record no longer
+    /// borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record(self.0.into_owned())
+    }
+}
+
+impl<'a> Deref for Record<'a> {
+    type Target = svcb::Record<'a>;
+    fn deref(&self) -> &svcb::Record<'a> {
+        &self.0
+    }
+}
+
+impl<'a> From<svcb::Record<'a>> for Record<'a> {
+    fn from(record: svcb::Record<'a>) -> Record<'a> {
+        Record(record)
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 65;
+
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
+        svcb::parse(rdata, original).map(Record)
+    }
+
+    fn length(&self) -> u16 {
+        self.0.length()
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        self.0.write_to(w)
+    }
+}
@@ -0,0 +1,256 @@
+use std::io::{self, Write};
+use std::fmt;
+use {Error, WireEncode};
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+/// Latitude and longitude of the equator and the prime meridian
+const EQUATOR: u32 = 1 << 31;
+/// Thousandths of an arc second in a degree
+const PER_DEGREE: f64 = 3_600_000.0;
+/// Altitude 0, the base is 100 000 m below the WGS84 spheroid
+const SEA_LEVEL: i64 = 10_000_000;
+
+/// Default size of 1 m (RFC 1876 section 3)
+pub const DEFAULT_SIZE: u8 = 0x12;
+/// Default horizontal precision of 10 000 m
+pub const DEFAULT_HORIZONTAL_PRECISION: u8 = 0x16;
+/// Default vertical precision of 10 m
+pub const DEFAULT_VERTICAL_PRECISION: u8 = 0x13;
+
+/// The LOC record (RFC 1876)
+///
+/// Fields are in the wire encoding, the methods convert them to degrees
+/// and meters.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Record {
+    pub version: u8,
+    pub size: u8,
+    pub horizontal_precision: u8,
+    pub vertical_precision: u8,
+    pub latitude: u32,
+    pub longitude: u32,
+    pub altitude: u32,
+}
+
+impl Record {
+    /// Creates a record from WGS84 coordinates in degrees and the
+    /// altitude in meters, with the default size and precisions
+    ///
+    /// Positions are rounded to a thousandth of an arc second, altitudes
+    /// to a centimeter.
+    pub fn from_wgs84(latitude: f64, longitude: f64, altitude: f64)
+        -> Result<Record, Error>
+    {
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(Error::InvalidLoc("latitude out of range"));
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(Error::InvalidLoc("longitude out of range"));
+        }
+        if !altitude.is_finite() {
+            return Err(Error::InvalidLoc("altitude out of range"));
+        }
+        let altitude = (altitude * 100.0).round() as i64 + SEA_LEVEL;
+        if altitude < 0 || altitude > u32::MAX as i64 {
+            return Err(Error::InvalidLoc("altitude out of range"));
+        }
+        Ok(Record {
+            version: 0,
+            size: DEFAULT_SIZE,
+            horizontal_precision: DEFAULT_HORIZONTAL_PRECISION,
+            vertical_precision: DEFAULT_VERTICAL_PRECISION,
+            latitude: encode_angle(latitude),
+            longitude: encode_angle(longitude),
+            altitude: altitude as u32,
+        })
+    }
+
+    /// Latitude in degrees, positive to the north
+    pub fn latitude_degrees(&self) -> f64 {
+        decode_angle(self.latitude)
+    }
+
+    /// Longitude in degrees, positive to the east
+    pub fn longitude_degrees(&self) -> f64 {
+        decode_angle(self.longitude)
+    }
+
+    /// Altitude in meters above the WGS84 reference spheroid
+    pub fn altitude_meters(&self) -> f64 {
+        (self.altitude as i64 - SEA_LEVEL) as f64 / 100.0
+    }
+
+    /// Diameter of the sphere enclosing the entity in meters
+    pub fn size_meters(&self) -> Option<f64> {
+        decode_size(self.size)
+    }
+
+    /// Horizontal precision in meters
+    pub fn horizontal_precision_meters(&self) -> Option<f64> {
+        decode_size(self.horizontal_precision)
+    }
+
+    /// Vertical precision in meters
+    pub fn vertical_precision_meters(&self) -> Option<f64> {
+        decode_size(self.vertical_precision)
+    }
+}
+
+fn encode_angle(degrees: f64) -> u32 {
+    (EQUATOR as i64 + (degrees * PER_DEGREE).round() as i64) as u32
+}
+
+fn decode_angle(value: u32) -> f64 {
+    (value as i64 - EQUATOR as i64) as f64 / PER_DEGREE
+}
+
+/// Decodes the size and precision format, a digit times a power of ten
+/// in centimeters
+///
+/// Returns `None` if either digit is above 9.
+pub fn decode_size(value: u8) -> Option<f64> {
+    let (mantissa, exponent) = (value >> 4, value & 0x0f);
+    if mantissa > 9 || exponent > 9 {
+        return None;
+    }
+    Some(mantissa as f64 * 10f64.powi(exponent as i32) / 100.0)
+}
+
+/// Encodes meters into the size and precision format
+///
+/// The value is truncated to a single significant digit as in the
+/// reference implementation of RFC 1876, e.g. 30.5 m becomes 30 m.
+/// Returns `None` for negative values and values above 90 000 km.
+pub fn encode_size(meters: f64) -> Option<u8> {
+    let centimeters = (meters * 100.0).round();
+    if !(0.0..=9e9).contains(&centimeters) {
+        return None;
+    }
+    let centimeters = centimeters as u64;
+    let mut exponent = 0;
+    while exponent < 9 && centimeters >= 10u64.pow(exponent + 1) {
+        exponent += 1;
+    }
+    let mantissa = centimeters / 10u64.pow(exponent);
+    Some((mantissa as u8) << 4 | exponent as u8)
+}
+
+/// Writes a position as degrees, minutes and seconds
+fn write_angle(f: &mut fmt::Formatter, value: u32, hemispheres: [char; 2])
+    -> fmt::Result
+{
+    let offset = value as i64 - EQUATOR as i64;
+    let hemisphere = if offset < 0 { hemispheres[1] } else { hemispheres[0] };
+    let value = offset.abs();
+    write!(f, "{} {} {}.{:03} {}", value / 3_600_000, value / 60_000 % 60,
+        value / 1000 % 60, value % 1000, hemisphere)
+}
+
+fn write_size(f: &mut fmt::Formatter, value: u8) -> fmt::Result {
+    match decode_size(value) {
+        Some(meters) => write!(f, " {:.2}m", meters),
+        None => write!(f, " \\# 1 {:02X}", value),
+    }
+}
+
+/// Formats as e.g. `42 21 54.000 N 71 6 18.000 W -24.00m 30.00m
+/// 10000.00m 10.00m`
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_angle(f, self.latitude, ['N', 'S'])?;
+        f.write_str(" ")?;
+        write_angle(f, self.longitude, ['E', 'W'])?;
+        write!(f, " {:.2}m", self.altitude_meters())?;
+        write_size(f, self.size)?;
+        write_size(f, self.horizontal_precision)?;
+        write_size(f, self.vertical_precision)
+    }
+}
+
+impl<'a> super::Record<'a> for Record {
+
+    const TYPE: isize = 29;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() != 16 {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record {
+            version: rdata[0],
+            size: rdata[1],
+            horizontal_precision: rdata[2],
+            vertical_precision: rdata[3],
+            latitude: BigEndian::read_u32(&rdata[4..8]),
+            longitude: BigEndian::read_u32(&rdata[8..12]),
+            altitude: BigEndian::read_u32(&rdata[12..16]),
+        })
+    }
+
+    fn length(&self) -> u16 {
+        16
+    }
+}
+
+impl WireEncode for Record {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u8(self.version)?;
+        w.write_u8(self.size)?;
+        w.write_u8(self.horizontal_precision)?;
+        w.write_u8(self.vertical_precision)?;
+        w.write_u32::<BigEndian>(self.latitude)?;
+        w.write_u32::<BigEndian>(self.longitude)?;
+        w.write_u32::<BigEndian>(self.altitude)?;
+        Ok(16)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rdata::Record as _;
+    use Error;
+    use super::*;
+
+    #[test]
+    fn coordinates() {
+        // The example of RFC 1876 section 4
+        let mut record = Record::from_wgs84(42.365, -71.105, -24.0).unwrap();
+        record.size = encode_size(30.0).unwrap();
+        assert_eq!(record.size, 0x33);
+        assert_eq!(record.to_string(),
+            "42 21 54.000 N 71 6 18.000 W -24.00m 30.00m 10000.00m 10.00m");
+        assert!((record.latitude_degrees() - 42.365).abs() < 1e-9);
+        assert!((record.longitude_degrees() + 71.105).abs() < 1e-9);
+        assert_eq!(record.altitude_meters(), -24.0);
+        assert_eq!(record.size_meters(), Some(30.0));
+        assert_eq!(record.horizontal_precision_meters(), Some(10000.0));
+        assert_eq!(record.vertical_precision_meters(), Some(10.0));
+
+        let bytes = record.to_bytes();
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(Record::parse(&bytes, &bytes).unwrap(), record);
+
+        assert_eq!(Record::from_wgs84(91.0, 0.0, 0.0).unwrap_err(),
+            Error::InvalidLoc("latitude out of range"));
+        assert!(Record::from_wgs84(0.0, -181.0, 0.0).is_err());
+        assert!(Record::from_wgs84(0.0, 0.0, -100_001.0).is_err());
+        assert_eq!(Record::from_wgs84(0.0, f64::NAN, 0.0).unwrap_err(),
+            Error::InvalidLoc("longitude out of range"));
+        assert_eq!(Record::from_wgs84(0.0, 0.0, f64::NAN).unwrap_err(),
+            Error::InvalidLoc("altitude out of range"));
+        assert_eq!(Record::from_wgs84(0.0, 0.0, f64::INFINITY).unwrap_err(),
+            Error::InvalidLoc("altitude out of range"));
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(decode_size(0x12), Some(1.0));
+        assert_eq!(decode_size(0x99), Some(9e7));
+        assert_eq!(decode_size(0xa0), None);
+        assert_eq!(encode_size(0.0), Some(0x00));
+        assert_eq!(encode_size(0.05), Some(0x50));
+        assert_eq!(encode_size(30.5), Some(0x33));
+        assert_eq!(encode_size(1.0), Some(0x12));
+        assert_eq!(encode_size(-1.0), None);
+        assert_eq!(encode_size(1e8), None);
+    }
+}
@@ -6,8 +6,13 @@ pub mod a;
 pub mod aaaa;
 pub mod all;
 pub mod axfr;
+pub mod caa;
 pub mod cname;
+pub mod dnskey;
+mod ere;
 pub mod hinfo;
+pub mod https;
+pub mod loc;
 pub mod maila;
 pub mod mailb;
 pub mod mb;
@@ -16,29 +21,57 @@ pub mod mg;
 pub mod minfo;
 pub mod mr;
 pub mod mx;
+pub mod naptr;
 pub mod ns;
 pub mod nsec;
 pub mod null;
 pub mod opt;
 pub mod ptr;
+pub mod registry;
+pub mod rrsig;
 pub mod soa;
 pub mod srv;
+pub mod sshfp;
+pub mod tlsa;
+pub mod svcb;
+mod text;
 pub mod txt;
 pub mod wks;
+pub mod zonemd;
 
-use {Type, Error};
+use std::fmt;
+use std::net::IpAddr;
+
+use std::io::{self, Write};
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use {Name, Type, Error, WireEncode};
+use compress::Compressor;
 
 pub use self::a::Record as A;
 pub use self::aaaa::Record as Aaaa;
+pub use self::caa::Record as Caa;
 pub use self::cname::Record as Cname;
+pub use self::dnskey::{Record as Dnskey, Flags as DnskeyFlags};
+pub use self::hinfo::Record as Hinfo;
+pub use self::loc::Record as Loc;
+pub use self::https::Record as Https;
 pub use self::mx::Record as Mx;
+pub use self::naptr::Record as Naptr;
 pub use self::ns::Record as Ns;
 pub use self::nsec::Record as Nsec;
 pub use self::opt::Record as Opt;
 pub use self::ptr::Record as Ptr;
+pub use self::registry::{RecordRegistry, CustomRecord};
+pub use self::rrsig::Record as Rrsig;
 pub use self::soa::Record as Soa;
 pub use self::srv::Record as Srv;
+pub use self::sshfp::Record as Sshfp;
+pub use self::tlsa::Record as Tlsa;
+pub use self::svcb::Record as Svcb;
 pub use self::txt::Record as Txt;
+pub use self::zonemd::Record as Zonemd;
 
 pub type RDataResult<'a> = Result<RData<'a>, Error>;
 
@@ -48,41 +81,183 @@ pub enum RData<'a> {
     A(A),
     AAAA(Aaaa),
     CNAME(Cname<'a>),
+    HINFO(Hinfo<'a>),
     MX(Mx<'a>),
     NS(Ns<'a>),
     PTR(Ptr<'a>),
     SOA(Soa<'a>),
     SRV(Srv<'a>),
     TXT(Txt),
+    RRSIG(Rrsig<'a>),
+    DNSKEY(Dnskey<'a>),
+    ZONEMD(Zonemd<'a>),
+    SVCB(Svcb<'a>),
+    HTTPS(Https<'a>),
+    CAA(Caa<'a>),
+    NAPTR(Naptr<'a>),
+    TLSA(Tlsa<'a>),
+    SSHFP(Sshfp<'a>),
+    LOC(Loc),
     /// Anything that can't be parsed yet
     Unknown(Type, &'a [u8]),
+    /// A type parsed by a `RecordRegistry` callback
+    Custom(Box<dyn CustomRecord>),
 }
 
-pub (crate) trait Record<'a> {
+/// RDATA of a single record type
+///
+/// Every record type of the crate implements this, and so may types
+/// defined in other crates. Owned (`'static`) types implementing it for
+/// any lifetime can be put into `RData::Custom` and registered in
+/// a `RecordRegistry` with `register_record`, which is how they are
+/// parsed from packets and written by the `Builder`.
+pub trait Record<'a>: WireEncode + Sized {
+    /// The TYPE code
     const TYPE: isize;
 
-    fn parse(rdata: &'a [u8], original: &'a [u8]) -> RDataResult<'a>;
+    /// Parses the RDATA, `original` is the whole packet for compression
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error>;
+    /// Length of the RDATA in octets
     fn length(&self) -> u16;
-    fn to_bytes(&self) -> Vec<u8>;
+    /// The wire representation of the RDATA
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.length() as usize);
+        self.write_to(&mut buf).expect("writing to a Vec can't fail");
+        buf
+    }
+}
+
+/// Record types that can be borrowed out of an `RData`
+///
+/// Used for typed access such as `Packet::answers_of::<rdata::A>()`.
+pub trait FromRData<'a> {
+    /// Returns the record if `data` holds this type
+    fn from_rdata<'r>(data: &'r RData<'a>) -> Option<&'r Self>;
+}
+
+macro_rules! from_rdata {
+    ($($variant:ident => $typ:ty,)*) => {
+        $(
+            impl<'a> FromRData<'a> for $typ {
+                fn from_rdata<'r>(data: &'r RData<'a>) -> Option<&'r Self> {
+                    match *data {
+                        RData::$variant(ref val) => Some(val),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_rdata! {
+    A => A,
+    AAAA => Aaaa,
+    CNAME => Cname<'a>,
+    HINFO => Hinfo<'a>,
+    MX => Mx<'a>,
+    NS => Ns<'a>,
+    PTR => Ptr<'a>,
+    SOA => Soa<'a>,
+    SRV => Srv<'a>,
+    TXT => Txt,
+    RRSIG => Rrsig<'a>,
+    DNSKEY => Dnskey<'a>,
+    ZONEMD => Zonemd<'a>,
+    SVCB => Svcb<'a>,
+    HTTPS => Https<'a>,
+    CAA => Caa<'a>,
+    NAPTR => Naptr<'a>,
+    TLSA => Tlsa<'a>,
+    SSHFP => Sshfp<'a>,
+    LOC => Loc,
 }
 
 impl<'a> RData<'a> {
     /// Parse an RR data and return RData enumeration
     pub fn parse(typ: Type, rdata: &'a [u8], original: &'a [u8]) -> RDataResult<'a> {
         match typ {
-            Type::A         => A::parse(rdata, original),
-            Type::AAAA      => Aaaa::parse(rdata, original),
-            Type::CNAME     => Cname::parse(rdata, original),
-            Type::NS        => Ns::parse(rdata, original),
-            Type::MX        => Mx::parse(rdata, original),
-            Type::PTR       => Ptr::parse(rdata, original),
-            Type::SOA       => Soa::parse(rdata, original),
-            Type::SRV       => Srv::parse(rdata, original),
-            Type::TXT       => Txt::parse(rdata, original),
+            Type::A         => A::parse(rdata, original).map(RData::A),
+            Type::AAAA      => Aaaa::parse(rdata, original).map(RData::AAAA),
+            Type::CNAME     => Cname::parse(rdata, original).map(RData::CNAME),
+            Type::HINFO     => Hinfo::parse(rdata, original).map(RData::HINFO),
+            Type::NS        => Ns::parse(rdata, original).map(RData::NS),
+            Type::MX        => Mx::parse(rdata, original).map(RData::MX),
+            Type::PTR       => Ptr::parse(rdata, original).map(RData::PTR),
+            Type::SOA       => Soa::parse(rdata, original).map(RData::SOA),
+            Type::SRV       => Srv::parse(rdata, original).map(RData::SRV),
+            Type::TXT       => Txt::parse(rdata, original).map(RData::TXT),
+            Type::RRSIG     => Rrsig::parse(rdata, original).map(RData::RRSIG),
+            Type::DNSKEY    => Dnskey::parse(rdata, original).map(RData::DNSKEY),
+            Type::ZONEMD    => Zonemd::parse(rdata, original).map(RData::ZONEMD),
+            Type::SVCB      => Svcb::parse(rdata, original).map(RData::SVCB),
+            Type::HTTPS     => Https::parse(rdata, original).map(RData::HTTPS),
+            Type::CAA       => Caa::parse(rdata, original).map(RData::CAA),
+            Type::NAPTR     => Naptr::parse(rdata, original).map(RData::NAPTR),
+            Type::TLSA      => Tlsa::parse(rdata, original).map(RData::TLSA),
+            Type::SSHFP     => Sshfp::parse(rdata, original).map(RData::SSHFP),
+            Type::LOC       => Loc::parse(rdata, original).map(RData::LOC),
             _               => Ok(RData::Unknown(typ, rdata)),
         }
     }
 
+    /// Parse RDATA of the given type from presentation format
+    ///
+    /// For example `10 mail.example.com.` for MX or `"v=spf1 -all"` for
+    /// TXT. Names need not end with a dot, there is no origin to make
+    /// them relative to. The types the crate doesn't parse can't be
+    /// parsed this way either.
+    pub fn from_text(typ: Type, text: &str) -> RDataResult<'static> {
+        Ok(match typ {
+            Type::A     => RData::A(text.parse()?),
+            Type::AAAA  => RData::AAAA(text.parse()?),
+            Type::CNAME => RData::CNAME(text.parse()?),
+            Type::HINFO => RData::HINFO(text.parse()?),
+            Type::CAA   => RData::CAA(text.parse()?),
+            Type::NAPTR => RData::NAPTR(text.parse()?),
+            Type::NS    => RData::NS(text.parse()?),
+            Type::MX    => RData::MX(text.parse()?),
+            Type::PTR   => RData::PTR(text.parse()?),
+            Type::SOA   => RData::SOA(text.parse()?),
+            Type::SRV   => RData::SRV(text.parse()?),
+            Type::TXT   => RData::TXT(text.parse()?),
+            _ => return Err(Error::InvalidText("type can't be parsed from text")),
+        })
+    }
+
+    /// Parse an RR data consulting the registry for unsupported types
+    ///
+    /// Types supported by the crate are parsed as with `parse`.
+    pub fn parse_with_registry(code: u16, rdata: &'a [u8], original: &'a [u8],
+        registry: &RecordRegistry)
+        -> RDataResult<'a>
+    {
+        match Type::parse(code) {
+            Ok(typ) => match RData::parse(typ, rdata, original)? {
+                RData::Unknown(..) if registry.is_registered(code) => {
+                    registry.parse(code, rdata, original)
+                        .expect("parser is registered")
+                        .map(RData::Custom)
+                }
+                data => Ok(data),
+            },
+            Err(e) => registry.parse(code, rdata, original)
+                .unwrap_or(Err(e))
+                .map(RData::Custom),
+        }
+    }
+
+    /// Returns the address carried by an A or AAAA record
+    ///
+    /// Any other record type yields `None`.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match *self {
+            RData::A(record)     => Some(record.into()),
+            RData::AAAA(record)  => Some(record.into()),
+            _                    => None,
+        }
+    }
+
     /// Returns packet type as enum
     ///
     /// Code can be converted to an integer `packet.type_code() as isize`
@@ -91,13 +266,90 @@ impl<'a> RData<'a> {
             RData::A(..)         => Type::A,
             RData::AAAA(..)      => Type::AAAA,
             RData::CNAME(..)     => Type::CNAME,
+            RData::HINFO(..)     => Type::HINFO,
             RData::NS(..)        => Type::NS,
             RData::MX(..)        => Type::MX,
             RData::PTR(..)       => Type::PTR,
             RData::SOA(..)       => Type::SOA,
             RData::SRV(..)       => Type::SRV,
             RData::TXT(..)       => Type::TXT,
+            RData::RRSIG(..)     => Type::RRSIG,
+            RData::DNSKEY(..)    => Type::DNSKEY,
+            RData::ZONEMD(..)    => Type::ZONEMD,
+            RData::SVCB(..)      => Type::SVCB,
+            RData::HTTPS(..)     => Type::HTTPS,
+            RData::CAA(..)       => Type::CAA,
+            RData::NAPTR(..)     => Type::NAPTR,
+            RData::TLSA(..)      => Type::TLSA,
+            RData::SSHFP(..)     => Type::SSHFP,
+            RData::LOC(..)       => Type::LOC,
             RData::Unknown(_t, _) => panic!("Unknown type"),
+            RData::Custom(..)    => panic!("Custom type"),
+        }
+    }
+
+    /// The names embedded in the RDATA
+    pub(crate) fn names(&self) -> Vec<&Name<'a>> {
+        match self {
+            RData::CNAME(val) => vec![&val.0],
+            RData::NS(val) => vec![&val.0],
+            RData::PTR(val) => vec![&val.0],
+            RData::MX(val) => vec![&val.exchange],
+            RData::SRV(val) => vec![&val.target],
+            RData::SOA(val) => vec![&val.primary_ns, &val.mailbox],
+            RData::RRSIG(val) => vec![&val.signer],
+            RData::SVCB(val) => vec![&val.target],
+            RData::HTTPS(val) => vec![&val.target],
+            RData::NAPTR(val) => vec![&val.replacement],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Total length of the names embedded in the RDATA, decompressed
+    pub(crate) fn expanded_names_len(&self) -> usize {
+        self.names().iter().map(|name| name.str_val.len()).sum()
+    }
+
+    /// Number of RDATA bytes the data was parsed from
+    ///
+    /// Compressed names count with their size in the packet. `None` for
+    /// unknown and custom types, which are kept as raw bytes.
+    pub(crate) fn wire_length(&self) -> Option<usize> {
+        let saved = |name: &Name| {
+            (name.octet_length() as usize).saturating_sub(name.byte_len())
+        };
+        let saved = match self {
+            RData::Unknown(..) | RData::Custom(..) => return None,
+            RData::CNAME(val) => saved(&val.0),
+            RData::NS(val) => saved(&val.0),
+            RData::PTR(val) => saved(&val.0),
+            RData::MX(val) => saved(&val.exchange),
+            RData::SRV(val) => saved(&val.target),
+            RData::SOA(val) => saved(&val.primary_ns) + saved(&val.mailbox),
+            RData::RRSIG(val) => saved(&val.signer),
+            RData::SVCB(val) => saved(&val.target),
+            RData::HTTPS(val) => saved(&val.target),
+            RData::NAPTR(val) => saved(&val.replacement),
+            _ => 0,
+        };
+        Some(self.rdata_length() as usize - saved)
+    }
+
+    /// The TYPE mnemonic, `TYPE` followed by the code for custom types
+    pub(crate) fn type_name(&self) -> String {
+        match self {
+            RData::Unknown(typ, _) => typ.to_string(),
+            RData::Custom(val)     => format!("TYPE{}", val.type_code()),
+            data                   => data.type_code().to_string(),
+        }
+    }
+
+    /// Returns the numeric TYPE code, including custom and unknown types
+    pub fn raw_type_code(&self) -> u16 {
+        match self {
+            RData::Unknown(typ, _) => *typ as u16,
+            RData::Custom(val)     => val.type_code(),
+            data                   => data.type_code() as u16,
         }
     }
 
@@ -107,29 +359,189 @@ impl<'a> RData<'a> {
             RData::A(val) => val.length(),
             RData::AAAA(val) => val.length(),
             RData::CNAME(val) => val.length(),
+            RData::HINFO(val) => val.length(),
             RData::NS(val) => val.length(),
             RData::MX(val) => val.length(),
             RData::PTR(val) => val.length(),
             RData::SOA(val) => val.length(),
             RData::SRV(val) => val.length(),
             RData::TXT(val) => val.length(),
-            RData::Unknown(_t, _) => panic!("Unknown type"),
+            RData::RRSIG(val) => val.length(),
+            RData::DNSKEY(val) => val.length(),
+            RData::ZONEMD(val) => val.length(),
+            RData::SVCB(val) => val.length(),
+            RData::HTTPS(val) => val.length(),
+            RData::CAA(val) => val.length(),
+            RData::NAPTR(val) => val.length(),
+            RData::TLSA(val) => val.length(),
+            RData::SSHFP(val) => val.length(),
+            RData::LOC(val) => val.length(),
+            RData::Unknown(_, bytes) => bytes.len() as u16,
+            RData::Custom(val) => val.to_rdata().len() as u16,
+        }
+    }
+
+    /// Writes the RDATA compressing embedded names where permitted
+    ///
+    /// See `Type::allows_rdata_compression` for the types that qualify,
+    /// everything else is written as is.
+    pub(crate) fn write_compressed(&self, buf: &mut Vec<u8>,
+        names: &mut Compressor)
+    {
+        let allowed = match self {
+            RData::Unknown(..) | RData::Custom(..) => false,
+            data => data.type_code().allows_rdata_compression(),
+        };
+        if !allowed {
+            self.write_to(buf).unwrap();
+            return;
+        }
+        match self {
+            RData::CNAME(val) => names.write_name(buf, &val.0.to_string()),
+            RData::NS(val) => names.write_name(buf, &val.0.to_string()),
+            RData::PTR(val) => names.write_name(buf, &val.0.to_string()),
+            RData::MX(val) => {
+                buf.write_u16::<BigEndian>(val.preference).unwrap();
+                names.write_name(buf, &val.exchange.to_string());
+            }
+            RData::SOA(val) => {
+                names.write_name(buf, &val.primary_ns.to_string());
+                names.write_name(buf, &val.mailbox.to_string());
+                for &x in &[val.serial, val.refresh, val.retry,
+                            val.expire, val.minimum_ttl]
+                {
+                    buf.write_u32::<BigEndian>(x).unwrap();
+                }
+            }
+            data => {
+                data.write_to(buf).unwrap();
+            }
         }
     }
 
-    /// Returns the RDATA value 
+    /// Returns the RDATA value
     pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.rdata_length() as usize);
+        self.write_to(&mut buf).expect("writing to a Vec can't fail");
+        buf
+    }
+
+    /// The RDATA with the embedded names lowercased (RFC 4034 section 6.2)
+    pub(crate) fn canonical_bytes(&self) -> Vec<u8> {
+        let mut buf = self.to_bytes();
+        // Names are written uncompressed and label lengths are below 64,
+        // so only label characters are affected by lowercasing
+        let len = buf.len();
+        let names = match *self {
+            RData::CNAME(..) | RData::NS(..) | RData::PTR(..) => 0..len,
+            RData::MX(..) => 2..len,
+            RData::SRV(..) => 6..len,
+            RData::SOA(..) => 0..len - 20,
+            RData::NAPTR(ref val) => {
+                len - val.replacement.octet_length() as usize..len
+            }
+            RData::RRSIG(ref val) => {
+                18..18 + val.signer.octet_length() as usize
+            }
+            _ => 0..0,
+        };
+        buf[names].make_ascii_lowercase();
+        buf
+    }
+}
+
+/// Writes a character-string quoted, escaping as in RFC 1035 section 5.1
+pub(crate) fn write_character_string(f: &mut fmt::Formatter, bytes: &[u8])
+    -> fmt::Result
+{
+    f.write_str("\"")?;
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+            0x20..=0x7e => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\{:03}", b)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// Writes RDATA in the generic format of RFC 3597 section 5
+fn write_generic(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    write!(f, "\\# {}", bytes.len())?;
+    if !bytes.is_empty() {
+        f.write_str(" ")?;
+        for b in bytes {
+            write!(f, "{:02X}", b)?;
+        }
+    }
+    Ok(())
+}
+
+/// Presentation format of the RDATA as used in zone files
+///
+/// Types the crate can't interpret are written in the generic `\#`
+/// format of RFC 3597.
+impl<'a> fmt::Display for RData<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            RData::A(val) => val.to_bytes(),
-            RData::AAAA(val) => val.to_bytes(),
-            RData::CNAME(val) => val.to_bytes(),
-            RData::NS(val) => val.to_bytes(),
-            RData::MX(val) => val.to_bytes(),
-            RData::PTR(val) => val.to_bytes(),
-            RData::SOA(val) => val.to_bytes(),
-            RData::SRV(val) => val.to_bytes(),
-            RData::TXT(val) => val.to_bytes(),
-            RData::Unknown(_t, _) => panic!("Unknown type"),
+            RData::A(val) => val.fmt(f),
+            RData::AAAA(val) => val.fmt(f),
+            RData::CNAME(val) => val.fmt(f),
+            RData::HINFO(val) => val.fmt(f),
+            RData::NS(val) => val.fmt(f),
+            RData::MX(val) => val.fmt(f),
+            RData::PTR(val) => val.fmt(f),
+            RData::SOA(val) => val.fmt(f),
+            RData::SRV(val) => val.fmt(f),
+            RData::TXT(val) => val.fmt(f),
+            RData::RRSIG(val) => val.fmt(f),
+            RData::DNSKEY(val) => val.fmt(f),
+            RData::ZONEMD(val) => val.fmt(f),
+            RData::SVCB(val) => val.fmt(f),
+            RData::HTTPS(val) => val.fmt(f),
+            RData::CAA(val) => val.fmt(f),
+            RData::NAPTR(val) => val.fmt(f),
+            RData::TLSA(val) => val.fmt(f),
+            RData::SSHFP(val) => val.fmt(f),
+            RData::LOC(val) => val.fmt(f),
+            RData::Unknown(_, bytes) => write_generic(f, bytes),
+            RData::Custom(val) => write_generic(f, &val.to_rdata()),
+        }
+    }
+}
+
+impl<'a> WireEncode for RData<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        match self {
+            RData::A(val) => val.write_to(w),
+            RData::AAAA(val) => val.write_to(w),
+            RData::CNAME(val) => val.write_to(w),
+            RData::HINFO(val) => val.write_to(w),
+            RData::NS(val) => val.write_to(w),
+            RData::MX(val) => val.write_to(w),
+            RData::PTR(val) => val.write_to(w),
+            RData::SOA(val) => val.write_to(w),
+            RData::SRV(val) => val.write_to(w),
+            RData::TXT(val) => val.write_to(w),
+            RData::RRSIG(val) => val.write_to(w),
+            RData::DNSKEY(val) => val.write_to(w),
+            RData::ZONEMD(val) => val.write_to(w),
+            RData::SVCB(val) => val.write_to(w),
+            RData::HTTPS(val) => val.write_to(w),
+            RData::CAA(val) => val.write_to(w),
+            RData::NAPTR(val) => val.write_to(w),
+            RData::TLSA(val) => val.write_to(w),
+            RData::SSHFP(val) => val.write_to(w),
+            RData::LOC(val) => val.write_to(w),
+            RData::Unknown(_, bytes) => {
+                w.write_all(bytes)?;
+                Ok(bytes.len())
+            }
+            RData::Custom(val) => {
+                let data = val.to_rdata();
+                w.write_all(&data)?;
+                Ok(data.len())
+            }
         }
     }
 }
@@ -1,5 +1,9 @@
-use {Name, Error};
-use byteorder::{BigEndian, ByteOrder};
+use std::io::{self, Write};
+use std::fmt;
+use std::str::FromStr;
+use {Name, Error, WireEncode};
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use super::text::Fields;
 
 #[derive(Debug, Clone)]
 pub struct Record<'a> {
@@ -7,11 +11,35 @@ pub struct Record<'a> {
     pub exchange: Name<'a>,
 }
 
+impl<'a> Record<'a> {
+    /// Creates a new MX record
+    pub fn new(preference: u16, exchange: Name<'a>) -> Record<'a> {
+        Record { preference, exchange }
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}.", self.preference, self.exchange)
+    }
+}
+
+/// Parses e.g. `10 mail.example.com.`
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record::new(fields.parse()?, fields.name()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 15;
 
-    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
         if rdata.len() < 3 {
             return Err(Error::WrongRdataLength);
         }
@@ -19,15 +47,18 @@ impl<'a> super::Record<'a> for Record<'a> {
             preference: BigEndian::read_u16(&rdata[..2]),
             exchange: Name::scan(&rdata[2..], original)?,
         };
-        Ok(super::RData::MX(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
-        unimplemented!();
+        2 + self.exchange.octet_length()
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-        unimplemented!();
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.preference)?;
+        Ok(2 + self.exchange.write_to(w)?)
     }
 }
 
@@ -43,6 +74,16 @@ mod test {
     use RData;
     use super::*;
 
+    #[test]
+    fn format() {
+        let mx = RData::MX(Record::new(10,
+            Name::from_string("mail.example.com")));
+
+        assert_eq!(mx.rdata_length(), 20);
+        assert_eq!(&mx.to_bytes()[..],
+            &b"\x00\x0a\x04mail\x07example\x03com\x00"[..]);
+    }
+
     #[test]
     fn parse_response() {
         let response = b"\xe3\xe8\x81\x80\x00\x01\x00\x05\x00\x00\x00\x00\
@@ -64,6 +105,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 5,
@@ -83,18 +125,28 @@ mod test {
             (&20, "alt2.gmail-smtp-in.l.google.com"),
             (&30, "alt3.gmail-smtp-in.l.google.com"),
         ];
-        for i in 0..5 {
-            assert_eq!(&packet.answers[i].name.to_string()[..],
+        for (answer, item) in packet.answers.iter().zip(items.iter()) {
+            assert_eq!(&answer.name.to_string()[..],
             "gmail.com");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 1148);
-            match &packet.answers[i].data {
+            assert_eq!(answer.cls, C::IN);
+            assert_eq!(answer.ttl, 1148);
+            match &answer.data {
                 RData::MX( Record { preference, exchange }) => {
-                    assert_eq!(preference, items[i].0);
-                    assert_eq!(exchange.to_string(), (items[i].1).to_string());
+                    assert_eq!(preference, item.0);
+                    assert_eq!(exchange.to_string(), (item.1).to_string());
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
         }
     }
+
+    #[test]
+    fn presentation_format() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+            \x07example\x03com\x00\x00\x0f\x00\x01\x00\x00\x0e\x10\x00\x09\
+            \x00\x0a\x04mail\xc0\x0c";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.answers[0].to_string(),
+            "example.com. 3600 IN MX 10 mail.example.com.");
+    }
 }
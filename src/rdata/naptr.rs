@@ -0,0 +1,363 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use std::str::FromStr;
+use {Name, Error, WireEncode};
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use super::ere::Regex;
+use super::text::Fields;
+use super::write_character_string;
+
+/// The zone holding ENUM records (RFC 6116)
+pub const ENUM_DOMAIN: &str = "e164.arpa";
+
+/// What to do with the output of a rule, derived from its flags
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Action {
+    /// `S`: look up SRV records for the replacement
+    Srv,
+    /// `A`: look up A and AAAA records for the replacement
+    Address,
+    /// `U`: the output of the regexp is the URI to use
+    Uri,
+    /// `P`: the rest is up to the application protocol
+    Protocol,
+    /// No flags: look up NAPTR records for the output
+    Continue,
+    /// Only flags the crate doesn't know, or conflicting ones
+    Unknown,
+}
+
+/// The NAPTR record (RFC 3403)
+#[derive(Debug, Clone)]
+pub struct Record<'a> {
+    pub order: u16,
+    pub preference: u16,
+    pub flags: Cow<'a, [u8]>,
+    pub services: Cow<'a, [u8]>,
+    pub regexp: Cow<'a, [u8]>,
+    pub replacement: Name<'a>,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new NAPTR record
+    ///
+    /// Either `regexp` is empty or `replacement` is the root name.
+    ///
+    /// # Panics
+    ///
+    /// When one of the strings is longer than 255 bytes.
+    pub fn new<F, S, R>(order: u16, preference: u16, flags: F,
+        services: S, regexp: R, replacement: Name<'a>)
+        -> Record<'a>
+        where F: Into<Cow<'a, [u8]>>, S: Into<Cow<'a, [u8]>>,
+              R: Into<Cow<'a, [u8]>>
+    {
+        let record = Record {
+            order,
+            preference,
+            flags: flags.into(),
+            services: services.into(),
+            regexp: regexp.into(),
+            replacement,
+        };
+        assert!(record.flags.len() <= 255 && record.services.len() <= 255
+            && record.regexp.len() <= 255, "NAPTR string too long");
+        record
+    }
+
+    /// Copies the strings and the replacement so that the record no
+    /// longer borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            order: self.order,
+            preference: self.preference,
+            flags: Cow::Owned(self.flags.into_owned()),
+            services: Cow::Owned(self.services.into_owned()),
+            regexp: Cow::Owned(self.regexp.into_owned()),
+            replacement: self.replacement.into_owned(),
+        }
+    }
+
+    /// Interprets the `S`, `A`, `U` and `P` flags (RFC 3404 section 4.3)
+    ///
+    /// Flags are case-insensitive. `S`, `A` and `U` are mutually
+    /// exclusive, records having more than one are `Unknown`.
+    pub fn action(&self) -> Action {
+        let has = |flag: u8| {
+            self.flags.iter().any(|b| b.eq_ignore_ascii_case(&flag))
+        };
+        let terminal: Vec<_> = [(b'S', Action::Srv), (b'A', Action::Address),
+                                (b'U', Action::Uri)]
+            .iter()
+            .filter(|&&(flag, _)| has(flag))
+            .map(|&(_, action)| action)
+            .collect();
+        match terminal.len() {
+            1 => terminal[0],
+            0 if has(b'P') => Action::Protocol,
+            0 if self.flags.is_empty() => Action::Continue,
+            _ => Action::Unknown,
+        }
+    }
+
+    /// Returns true if the rule ends the lookup, i.e. for `S`, `A`, `U`
+    /// and `P` flags
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self.action(), Action::Continue | Action::Unknown)
+    }
+
+    /// Applies the rewrite rule of the regexp field to `input`
+    ///
+    /// `input` is the application's string, for ENUM the E.164 number
+    /// without visual separators such as `+4420794600000`. Returns
+    /// `None` if the regexp is empty, in which case the replacement
+    /// applies, or if it doesn't match. The substitution may refer to
+    /// groups as `\1` to `\9`, the `i` flag makes the match
+    /// case-insensitive. Fails with `Error::InvalidNaptr` if the rule is
+    /// malformed or the regexp compiles to too large a program.
+    pub fn rewrite(&self, input: &str) -> Result<Option<String>, Error> {
+        if self.regexp.is_empty() {
+            return Ok(None);
+        }
+        let (pattern, substitution, ignore_case) =
+            split_regexp(&self.regexp)?;
+        let regex = Regex::new(&pattern, ignore_case)?;
+        let captures = match regex.captures(input.as_bytes()) {
+            Some(captures) => captures,
+            None => return Ok(None),
+        };
+        let group = |index: usize| captures.get(index).and_then(|&c| c)
+            .map(|(start, end)| &input.as_bytes()[start..end]);
+        let (start, end) = captures[0].expect("match is captured");
+        let mut output = input.as_bytes()[..start].to_vec();
+        let mut bytes = substitution.iter();
+        while let Some(&b) = bytes.next() {
+            match (b, bytes.as_slice().first()) {
+                (b'\\', Some(&d)) if d.is_ascii_digit() => {
+                    bytes.next();
+                    let index = (d - b'0') as usize;
+                    if index == 0 || index >= captures.len() {
+                        return Err(Error::InvalidNaptr(
+                            "backreference to a missing group"));
+                    }
+                    output.extend_from_slice(group(index).unwrap_or(b""));
+                }
+                (b'\\', Some(&x)) => {
+                    bytes.next();
+                    output.push(x);
+                }
+                (b, _) => output.push(b),
+            }
+        }
+        output.extend_from_slice(&input.as_bytes()[end..]);
+        String::from_utf8(output)
+            .map(Some)
+            .map_err(|_| Error::InvalidNaptr("rewrite result is not UTF-8"))
+    }
+}
+
+/// Splits `!pattern!substitution!flags` at the delimiter
+///
+/// The delimiter is the first character and can be escaped with a
+/// backslash in the pattern and the substitution (RFC 3402 section 3.2).
+fn split_regexp(regexp: &[u8]) -> Result<(Vec<u8>, Vec<u8>, bool), Error> {
+    let delimiter = regexp[0];
+    if delimiter.is_ascii_digit() || delimiter == b'\\' || delimiter == b'i' {
+        return Err(Error::InvalidNaptr("invalid regexp delimiter"));
+    }
+    let mut parts = vec![Vec::new()];
+    let mut bytes = regexp[1..].iter();
+    while let Some(&b) = bytes.next() {
+        let part = parts.last_mut().unwrap();
+        match b {
+            b'\\' => match bytes.next() {
+                Some(&x) if x == delimiter => part.push(x),
+                Some(&x) => part.extend_from_slice(&[b'\\', x]),
+                None => return Err(Error::InvalidNaptr("dangling escape")),
+            },
+            b if b == delimiter => parts.push(Vec::new()),
+            b => part.push(b),
+        }
+    }
+    if parts.len() != 3 {
+        return Err(Error::InvalidNaptr("regexp needs three delimiters"));
+    }
+    let flags = parts.pop().unwrap();
+    let ignore_case = match &flags[..] {
+        b"" => false,
+        b"i" => true,
+        _ => return Err(Error::InvalidNaptr("unknown regexp flag")),
+    };
+    let substitution = parts.pop().unwrap();
+    Ok((parts.pop().unwrap(), substitution, ignore_case))
+}
+
+/// Converts an E.164 number to its ENUM domain name (RFC 6116 section 2.4)
+///
+/// The number must start with `+` and have at most 15 digits. Spaces,
+/// dashes, dots and parentheses are ignored, e.g. `+44 20 7946 0000`
+/// becomes `0.0.0.0.6.4.9.7.0.2.4.4.e164.arpa`.
+pub fn enum_name(number: &str) -> Result<Name<'static>, Error> {
+    let digits = number.strip_prefix('+')
+        .ok_or(Error::InvalidNaptr("E.164 number must start with +"))?;
+    let mut name = String::with_capacity(2 * digits.len() + 9);
+    for c in digits.chars().rev() {
+        match c {
+            '0'..='9' => {
+                name.push(c);
+                name.push('.');
+            }
+            ' ' | '-' | '.' | '(' | ')' => {}
+            _ => return Err(Error::InvalidNaptr("invalid E.164 number")),
+        }
+    }
+    if name.is_empty() || name.len() > 30 {
+        return Err(Error::InvalidNaptr("invalid E.164 number"));
+    }
+    name.push_str(ENUM_DOMAIN);
+    Ok(Name::from_owned(name))
+}
+
+/// Formats as e.g. `100 10 "U" "E2U+sip" "!^.*$!sip:info@example.com!" .`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ", self.order, self.preference)?;
+        for string in &[&self.flags, &self.services, &self.regexp] {
+            write_character_string(f, string)?;
+            f.write_str(" ")?;
+        }
+        write!(f, "{}.", self.replacement)
+    }
+}
+
+/// Parses e.g. `100 10 "S" "SIP+D2U" "" _sip._udp.example.com.`
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record::new(fields.parse()?, fields.parse()?,
+            fields.string()?, fields.string()?, fields.string()?,
+            fields.name()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 35;
+
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() < 4 {
+            return Err(Error::WrongRdataLength);
+        }
+        let mut pos = 4;
+        let mut strings = [&rdata[..0]; 3];
+        for string in &mut strings {
+            let len = *rdata.get(pos).ok_or(Error::WrongRdataLength)? as usize;
+            *string = rdata.get(pos + 1..pos + 1 + len)
+                .ok_or(Error::WrongRdataLength)?;
+            pos += 1 + len;
+        }
+        if pos >= rdata.len() {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record {
+            order: BigEndian::read_u16(&rdata[..2]),
+            preference: BigEndian::read_u16(&rdata[2..4]),
+            flags: Cow::Borrowed(strings[0]),
+            services: Cow::Borrowed(strings[1]),
+            regexp: Cow::Borrowed(strings[2]),
+            replacement: Name::scan(&rdata[pos..], original)?,
+        })
+    }
+
+    fn length(&self) -> u16 {
+        7 + self.flags.len() as u16 + self.services.len() as u16 +
+            self.regexp.len() as u16 + self.replacement.octet_length()
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.order)?;
+        w.write_u16::<BigEndian>(self.preference)?;
+        let mut len = 4;
+        for string in &[&self.flags, &self.services, &self.regexp] {
+            w.write_u8(string.len() as u8)?;
+            w.write_all(string)?;
+            len += 1 + string.len();
+        }
+        Ok(len + self.replacement.write_to(w)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rdata::Record as _;
+    use {Name, Error};
+    use super::*;
+
+    fn record(flags: &str, regexp: &str) -> Record<'static> {
+        Record::new(100, 10, flags.as_bytes().to_vec(), &b"E2U+sip"[..],
+            regexp.as_bytes().to_vec(), Name::from_string(""))
+    }
+
+    #[test]
+    fn actions() {
+        assert_eq!(record("u", "").action(), Action::Uri);
+        assert_eq!(record("S", "").action(), Action::Srv);
+        assert_eq!(record("AP", "").action(), Action::Address);
+        assert_eq!(record("P", "").action(), Action::Protocol);
+        assert_eq!(record("", "").action(), Action::Continue);
+        assert_eq!(record("SU", "").action(), Action::Unknown);
+        assert_eq!(record("X", "").action(), Action::Unknown);
+        assert!(record("U", "").is_terminal());
+        assert!(!record("", "").is_terminal());
+    }
+
+    #[test]
+    fn rewrite() {
+        let rule = record("U", "!^.*$!sip:info@example.com!");
+        assert_eq!(rule.rewrite("+4420794600000").unwrap().unwrap(),
+            "sip:info@example.com");
+        let rule = record("U", "!^\\+44(.*)$!sip:\\1@example.co.uk!");
+        assert_eq!(rule.rewrite("+4420794600000").unwrap().unwrap(),
+            "sip:20794600000@example.co.uk");
+        assert_eq!(rule.rewrite("+3312345").unwrap(), None);
+        let rule = record("", "/SIP/sips/i");
+        assert_eq!(rule.rewrite("sip:a@b").unwrap().unwrap(), "sips:a@b");
+        let rule = record("", "#a\\#b#x\\1#");
+        assert_eq!(rule.rewrite("a#b").unwrap_err(),
+            Error::InvalidNaptr("backreference to a missing group"));
+        assert_eq!(record("", "").rewrite("x").unwrap(), None);
+        assert!(record("", "!a!b").rewrite("a").is_err());
+        assert!(record("", "!a!b!g").rewrite("a").is_err());
+        assert!(record("", "1a1b1").rewrite("a").is_err());
+    }
+
+    #[test]
+    fn enum_names() {
+        assert_eq!(enum_name("+44 20 7946-0000").unwrap().to_string(),
+            "0.0.0.0.6.4.9.7.0.2.4.4.e164.arpa");
+        assert_eq!(enum_name("+1").unwrap().to_string(), "1.e164.arpa");
+        assert!(enum_name("4420").is_err());
+        assert!(enum_name("+").is_err());
+        assert!(enum_name("+44x").is_err());
+        assert!(enum_name("+1234567890123456").is_err());
+    }
+
+    #[test]
+    fn wire_and_text() {
+        let text = "100 10 \"S\" \"SIP+D2U\" \"\" _sip._udp.example.com.";
+        let record: Record = text.parse().unwrap();
+        assert_eq!(record.to_string(), text);
+        let bytes = record.to_bytes();
+        assert_eq!(bytes.len(), record.length() as usize);
+        let parsed = Record::parse(&bytes, &bytes).unwrap();
+        assert_eq!(parsed.services, &b"SIP+D2U"[..]);
+        assert_eq!(parsed.replacement.to_string(), "_sip._udp.example.com");
+        assert!(Record::parse(&bytes[..10], &bytes).is_err());
+    }
+}
@@ -1,12 +1,34 @@
-use Name;
+use std::io::{self, Write};
+use std::fmt;
+use std::str::FromStr;
+
+use {Name, Error, WireEncode};
+use super::text::Fields;
 
 #[derive(Debug, Clone)]
 pub struct Record<'a>(pub Name<'a>);
 
-impl<'a> ToString for Record<'a> {
-    #[inline]
-    fn to_string(&self) -> String {
-        self.0.to_string()
+impl<'a> Record<'a> {
+    /// Creates a new NS record pointing to `name`
+    pub fn new(name: Name<'a>) -> Record<'a> {
+        Record(name)
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.", self.0)
+    }
+}
+
+/// Parses the name, the trailing dot is optional
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record(fields.name()?);
+        fields.end()?;
+        Ok(record)
     }
 }
 
@@ -14,18 +36,20 @@ impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 2;
 
-    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, ::Error> {
         let name = Name::scan(rdata, original)?;
         let record = Record(name);
-        Ok(super::RData::NS(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
         self.0.octet_length() 
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-        self.0.to_bytes()
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        self.0.write_to(w)
     }
 }
 
@@ -62,6 +86,7 @@ mod test {
              recursion_available: true,
              authenticated_data: false,
              checking_disabled: false,
+             reserved: false,
              response_code: NoError,
              questions: 1,
              answers: 1,
@@ -1,3 +1,5 @@
+use std::io::{self, Write};
+
 /// RFC 6891 OPT RR
 #[derive(Debug)]
 pub struct Record<'a> {
@@ -8,19 +10,73 @@ pub struct Record<'a> {
     pub data: super::RData<'a>,
 }
 
+/// The DNSSEC OK bit of the EDNS flags (RFC 3225)
+pub const DNSSEC_OK: u16 = 0x8000;
+
+impl<'a> Record<'a> {
+    /// The upper eight bits of the RCODE, see `edns::join_response_code`
+    pub fn extended_rcode(&self) -> u8 {
+        self.extrcode
+    }
+
+    /// The EDNS version of the sender
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns true if the sender can handle DNSSEC records (RFC 3225)
+    pub fn dnssec_ok(&self) -> bool {
+        self.flags & DNSSEC_OK != 0
+    }
+
+    /// The EDNS flags, including the DO bit
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// The TTL field as sent on the wire, combining the extended RCODE,
+    /// version and flags
+    pub fn ttl(&self) -> u32 {
+        (self.extrcode as u32) << 24 | (self.version as u32) << 16
+            | self.flags as u32
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 41;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, ::Error> {
         unimplemented!();
     }
 
     fn length(&self) -> u16 {
         unimplemented!();
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
+impl<'a> super::WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, _w: &mut W) -> io::Result<usize> {
         unimplemented!();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use Packet;
+
+    #[test]
+    fn ttl_fields() {
+        // query for example.com A with OPT extended RCODE 1, version 2, DO
+        let query = b"\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00\x00\x01\
+            \x07example\x03com\x00\x00\x01\x00\x01\
+            \x00\x00\x29\x10\x00\x01\x02\x80\x00\x00\x00";
+        let packet = Packet::parse(query).unwrap();
+        let opt = packet.opt.unwrap();
+        assert_eq!(opt.extended_rcode(), 1);
+        assert_eq!(opt.version(), 2);
+        assert!(opt.dnssec_ok());
+        assert_eq!(opt.flags(), 0x8000);
+        assert_eq!(opt.ttl(), 0x0102_8000);
+    }
+}
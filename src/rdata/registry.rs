@@ -0,0 +1,209 @@
+//! Application-defined record types
+//!
+//! Types the crate doesn't know about (e.g. from the private use range
+//! 65280-65534) can be parsed by registering a callback for the type code
+//! in a `RecordRegistry` and parsing packets with
+//! `Packet::parse_with_registry`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+
+use {Error};
+use super::Record;
+
+/// Private use range of TYPE values (RFC 6895)
+pub const PRIVATE_USE: ::std::ops::RangeInclusive<u16> = 65280..=65534;
+
+/// RDATA of a type parsed by a `RecordRegistry` callback
+///
+/// Implemented for every owned type implementing `Record`.
+pub trait CustomRecord: fmt::Debug {
+    /// The TYPE code of the record
+    fn type_code(&self) -> u16;
+    /// The wire representation of the RDATA
+    ///
+    /// Named differently from `Record::to_bytes` so both traits can be
+    /// in scope.
+    fn to_rdata(&self) -> Vec<u8>;
+    /// Allows downcasting to the concrete type with `downcast_ref`
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T> CustomRecord for T
+    where T: for<'a> Record<'a> + fmt::Debug + 'static
+{
+    fn type_code(&self) -> u16 {
+        T::TYPE as u16
+    }
+    fn to_rdata(&self) -> Vec<u8> {
+        Record::to_bytes(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn parse_record<T>(rdata: &[u8], original: &[u8])
+    -> Result<Box<dyn CustomRecord>, Error>
+    where T: for<'a> Record<'a> + fmt::Debug + 'static
+{
+    T::parse(rdata, original).map(|x| Box::new(x) as Box<dyn CustomRecord>)
+}
+
+/// Parses RDATA of a registered type
+///
+/// Arguments are the same as for `RData::parse`: the RDATA itself and the
+/// whole packet for resolving compressed names.
+pub type ParseFn = fn(rdata: &[u8], original: &[u8])
+    -> Result<Box<dyn CustomRecord>, Error>;
+
+/// A set of parsers for type codes not supported by the crate
+///
+/// Built-in record types always take precedence, the registry is only
+/// consulted for codes that would otherwise be unknown.
+#[derive(Debug, Default, Clone)]
+pub struct RecordRegistry {
+    parsers: HashMap<u16, ParseFn>,
+}
+
+impl RecordRegistry {
+    /// Creates an empty registry
+    pub fn new() -> RecordRegistry {
+        RecordRegistry::default()
+    }
+
+    /// Registers a parser for the type code
+    ///
+    /// Returns the previously registered parser, if any.
+    pub fn register(&mut self, code: u16, parser: ParseFn)
+        -> Option<ParseFn>
+    {
+        self.parsers.insert(code, parser)
+    }
+
+    /// Registers a type implementing `Record` under its TYPE code
+    pub fn register_record<T>(&mut self) -> Option<ParseFn>
+        where T: for<'a> Record<'a> + fmt::Debug + 'static
+    {
+        self.register(T::TYPE as u16, parse_record::<T>)
+    }
+
+    /// Returns true if there is a parser for the type code
+    pub fn is_registered(&self, code: u16) -> bool {
+        self.parsers.contains_key(&code)
+    }
+
+    /// Runs the registered parser, `None` if the code isn't registered
+    pub fn parse(&self, code: u16, rdata: &[u8], original: &[u8])
+        -> Option<Result<Box<dyn CustomRecord>, Error>>
+    {
+        self.parsers.get(&code).map(|parser| parser(rdata, original))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::any::Any;
+    use std::io::{self, Write};
+    use std::net::Ipv4Addr;
+
+    use byteorder::{BigEndian, ByteOrder};
+
+    use {Builder, Packet, RData, Error, WireEncode};
+    use Class as C;
+    use rdata::{A, Record};
+    use super::{CustomRecord, RecordRegistry, PRIVATE_USE};
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+
+    impl CustomRecord for Counter {
+        fn type_code(&self) -> u16 { 65300 }
+        fn to_rdata(&self) -> Vec<u8> {
+            let mut buf = vec![0; 4];
+            BigEndian::write_u32(&mut buf, self.0);
+            buf
+        }
+        fn as_any(&self) -> &dyn Any { self }
+    }
+
+    fn parse_counter(rdata: &[u8], _original: &[u8])
+        -> Result<Box<dyn CustomRecord>, Error>
+    {
+        if rdata.len() != 4 {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Box::new(Counter(BigEndian::read_u32(rdata))))
+    }
+
+    // A type defined the same way a downstream crate would
+    #[derive(Debug, PartialEq)]
+    struct Tag(String);
+
+    impl<'a> Record<'a> for Tag {
+        const TYPE: isize = 65301;
+
+        fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+            String::from_utf8(rdata.to_vec()).map(Tag)
+                .map_err(|_| Error::WrongRdataLength)
+        }
+        fn length(&self) -> u16 {
+            self.0.len() as u16
+        }
+    }
+
+    impl WireEncode for Tag {
+        fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+            w.write_all(self.0.as_bytes())?;
+            Ok(self.0.len())
+        }
+    }
+
+    #[test]
+    fn record_trait() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", C::IN,
+            RData::Custom(Box::new(Tag("blue".into()))), false, 60);
+        let data = bld.build().unwrap();
+
+        let mut registry = RecordRegistry::new();
+        registry.register_record::<Tag>();
+        assert!(registry.is_registered(65301));
+        let packet = Packet::parse_with_registry(&data, &registry).unwrap();
+        match packet.answers[0].data {
+            RData::Custom(ref record) => {
+                assert_eq!(record.as_any().downcast_ref::<Tag>(),
+                    Some(&Tag("blue".into())));
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert!(PRIVATE_USE.contains(&65300));
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", C::IN,
+            RData::Custom(Box::new(Counter(17))), false, 60);
+        bld.answer("example.com", C::IN,
+            RData::A(A::new(Ipv4Addr::new(192, 0, 2, 1))), false, 60);
+        let data = bld.build().unwrap();
+
+        assert!(matches!(Packet::parse(&data), Err(Error::InvalidType(65300))));
+
+        let mut registry = RecordRegistry::new();
+        assert!(registry.register(65300, parse_counter).is_none());
+        let packet = Packet::parse_with_registry(&data, &registry).unwrap();
+        match packet.answers[0].data {
+            RData::Custom(ref record) => {
+                assert_eq!(record.type_code(), 65300);
+                assert_eq!(record.as_any().downcast_ref::<Counter>(),
+                    Some(&Counter(17)));
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        assert_eq!(packet.answers[0].data.raw_type_code(), 65300);
+        assert!(matches!(packet.answers[1].data, RData::A(..)));
+    }
+}
@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use {Name, Error, WireEncode};
+use dnssec::Algorithm;
+use base64;
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+/// The RRSIG (DNSSEC signature) record (RFC 4034)
+#[derive(Debug, Clone)]
+pub struct Record<'a> {
+    pub type_covered: u16,
+    pub algorithm: Algorithm,
+    pub labels: u8,
+    pub original_ttl: u32,
+    pub expiration: u32,
+    pub inception: u32,
+    pub key_tag: u16,
+    pub signer: Name<'a>,
+    pub signature: Cow<'a, [u8]>,
+}
+
+/// Where a point in time is relative to the validity period of an RRSIG
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Validity {
+    /// Between inception and expiration
+    Valid,
+    /// Before the inception
+    NotYetValid,
+    /// After the expiration
+    Expired,
+}
+
+/// Converts a 32-bit DNSSEC timestamp into a `SystemTime`
+///
+/// The timestamps wrap around every 136 years, so they are interpreted
+/// with serial number arithmetic (RFC 4034 section 3.1.5, RFC 1982) as
+/// the time closest to `now`, which is at most 68 years away.
+pub fn timestamp_to_system_time(timestamp: u32, now: SystemTime)
+    -> SystemTime
+{
+    let now = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs())
+        .unwrap_or(0);
+    let delta = timestamp.wrapping_sub(now as u32) as i32;
+    let secs = (now as i64).saturating_add(delta as i64).max(0);
+    UNIX_EPOCH + Duration::from_secs(secs as u64)
+}
+
+impl<'a> Record<'a> {
+    /// Copies the signer and signature so that the record no longer
+    /// borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            signer: self.signer.into_owned(),
+            signature: Cow::Owned(self.signature.into_owned()),
+            ..self
+        }
+    }
+
+    /// The inception as the time closest to `now`
+    pub fn inception_time(&self, now: SystemTime) -> SystemTime {
+        timestamp_to_system_time(self.inception, now)
+    }
+
+    /// The expiration as the time closest to `now`
+    pub fn expiration_time(&self, now: SystemTime) -> SystemTime {
+        timestamp_to_system_time(self.expiration, now)
+    }
+
+    /// Checks `now` against the validity period of the signature
+    ///
+    /// `skew` extends the period on both ends to allow for clocks that
+    /// are a bit off. A skew too large to add to a time stretches that
+    /// end indefinitely.
+    pub fn validity(&self, now: SystemTime, skew: Duration) -> Validity {
+        let inception = self.inception_time(now);
+        let expiration = self.expiration_time(now);
+        if now.checked_add(skew).is_some_and(|t| t < inception) {
+            Validity::NotYetValid
+        } else if expiration.checked_add(skew).is_some_and(|t| t < now) {
+            Validity::Expired
+        } else {
+            Validity::Valid
+        }
+    }
+}
+
+/// Writes a timestamp as `YYYYMMDDHHmmSS` (RFC 4034 section 3.2)
+fn write_timestamp(f: &mut fmt::Formatter, timestamp: u32) -> fmt::Result {
+    // Civil date from days since the epoch, as in Howard Hinnant's
+    // `civil_from_days`, simplified for dates after 1970
+    let days = timestamp / 86400 + 719468;
+    let secs = timestamp % 86400;
+    let era = days / 146097;
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    write!(f, "{:04}{:02}{:02}{:02}{:02}{:02}", year, month, day,
+        secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats as e.g. `A 8 2 3600 20240131000000 20240101000000 12345
+/// example. BASE64`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match ::Type::parse(self.type_covered) {
+            Ok(typ) => write!(f, "{} ", typ)?,
+            Err(_) => write!(f, "TYPE{} ", self.type_covered)?,
+        }
+        write!(f, "{} {} {} ", u8::from(self.algorithm), self.labels,
+            self.original_ttl)?;
+        write_timestamp(f, self.expiration)?;
+        f.write_str(" ")?;
+        write_timestamp(f, self.inception)?;
+        write!(f, " {} {}. {}", self.key_tag, self.signer,
+            base64::encode(&self.signature))
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 46;
+
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() < 19 {
+            return Err(Error::WrongRdataLength);
+        }
+        let signer = Name::scan(&rdata[18..], original)?;
+        let signature = Cow::Borrowed(&rdata[18 + signer.byte_len()..]);
+        Ok(Record {
+            type_covered: BigEndian::read_u16(&rdata[..2]),
+            algorithm: Algorithm::from(rdata[2]),
+            labels: rdata[3],
+            original_ttl: BigEndian::read_u32(&rdata[4..8]),
+            expiration: BigEndian::read_u32(&rdata[8..12]),
+            inception: BigEndian::read_u32(&rdata[12..16]),
+            key_tag: BigEndian::read_u16(&rdata[16..18]),
+            signer,
+            signature,
+        })
+    }
+
+    fn length(&self) -> u16 {
+        18 + self.signer.octet_length() + self.signature.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.type_covered)?;
+        w.write_u8(u8::from(self.algorithm))?;
+        w.write_u8(self.labels)?;
+        w.write_u32::<BigEndian>(self.original_ttl)?;
+        w.write_u32::<BigEndian>(self.expiration)?;
+        w.write_u32::<BigEndian>(self.inception)?;
+        w.write_u16::<BigEndian>(self.key_tag)?;
+        let len = self.signer.write_to(w)?;
+        w.write_all(&self.signature)?;
+        Ok(18 + len + self.signature.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use {Name, RData};
+    use rdata::Record as _;
+    use super::*;
+
+    fn rrsig(inception: u32, expiration: u32) -> Record<'static> {
+        Record {
+            type_covered: 1,
+            algorithm: Algorithm::EcdsaP256Sha256,
+            labels: 2,
+            original_ttl: 3600,
+            expiration,
+            inception,
+            key_tag: 12345,
+            signer: Name::from_string("example.com"),
+            signature: Cow::Borrowed(b"\x01\x02\x03\x04"),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let record = rrsig(1704067200, 1706745600);
+        let bytes = record.to_bytes();
+        assert_eq!(bytes.len(), record.length() as usize);
+        let parsed = Record::parse(&bytes, &bytes).unwrap();
+        assert_eq!(parsed.signer.to_string(), "example.com");
+        assert_eq!(&parsed.signature[..], b"\x01\x02\x03\x04");
+        assert_eq!(RData::RRSIG(parsed).to_string(),
+            "A 13 2 3600 20240201000000 20240101000000 12345 example.com. \
+             AQIDBA==");
+    }
+
+    #[test]
+    fn validity() {
+        let now = UNIX_EPOCH + Duration::from_secs(1705000000);
+        let skew = Duration::from_secs(300);
+        let record = rrsig(1704067200, 1706745600);
+        assert_eq!(record.validity(now, skew), Validity::Valid);
+        let record = rrsig(1705000100, 1706745600);
+        assert_eq!(record.validity(now, skew), Validity::Valid);
+        assert_eq!(record.validity(now, Duration::from_secs(0)),
+            Validity::NotYetValid);
+        let record = rrsig(1704067200, 1704999000);
+        assert_eq!(record.validity(now, skew), Validity::Expired);
+    }
+
+    #[test]
+    fn huge_skew() {
+        let now = UNIX_EPOCH + Duration::from_secs(1705000000);
+        let record = rrsig(1705000100, 1706745600);
+        assert_eq!(record.validity(now, Duration::MAX), Validity::Valid);
+        let record = rrsig(1704067200, 1704999000);
+        assert_eq!(record.validity(now, Duration::MAX), Validity::Valid);
+    }
+
+    #[test]
+    fn wraparound() {
+        // Shortly before the 32-bit timestamps wrap in 2106
+        let now = UNIX_EPOCH + Duration::from_secs(0xFFFF_FF00);
+        let record = rrsig(0xFFFF_0000, 0x0001_0000);
+        assert_eq!(record.expiration_time(now),
+            UNIX_EPOCH + Duration::from_secs(0x1_0001_0000));
+        assert_eq!(record.inception_time(now),
+            UNIX_EPOCH + Duration::from_secs(0xFFFF_0000));
+        assert_eq!(record.validity(now, Duration::from_secs(0)),
+            Validity::Valid);
+    }
+}
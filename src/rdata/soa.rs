@@ -1,5 +1,9 @@
-use {Name, Error};
-use byteorder::{BigEndian, ByteOrder};
+use std::io::{self, Write};
+use std::fmt;
+use std::str::{FromStr, from_utf8};
+use {Name, Error, WireEncode};
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use super::text::Fields;
 
 /// The SOA (Start of Authority) record
 #[derive(Debug, Clone)]
@@ -13,11 +17,115 @@ pub struct Record<'a> {
     pub minimum_ttl: u32,
 }
 
+impl<'a> Record<'a> {
+    /// Creates a new SOA record
+    pub fn new(primary_ns: Name<'a>, mailbox: Name<'a>, serial: u32,
+        refresh: u32, retry: u32, expire: u32, minimum_ttl: u32)
+        -> Record<'a>
+    {
+        Record {
+            primary_ns,
+            mailbox,
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum_ttl,
+        }
+    }
+
+    /// How long a negative answer may be cached (RFC 2308 section 5)
+    ///
+    /// This is the lesser of the TTL of the SOA record itself, which the
+    /// RDATA doesn't carry, and the MINIMUM field.
+    pub fn negative_ttl(&self, record_ttl: u32) -> u32 {
+        record_ttl.min(self.minimum_ttl)
+    }
+
+    /// The mailbox as an email address, e.g. `hostmaster@example.com`
+    ///
+    /// The first label of the mailbox is the local part (RFC 1035 section
+    /// 8), so a dot in it is either inside the label on the wire or
+    /// escaped as `\.` in text. Returns `None` if the mailbox has fewer
+    /// than two labels.
+    pub fn rname_email(&self) -> Option<String> {
+        let text = self.mailbox.str_val.trim_end_matches('.');
+        let wire_label = self.mailbox.first_wire_label()
+            .and_then(|label| from_utf8(label).ok());
+        let (local, domain) = match wire_label {
+            Some(local) => (local.to_string(), text.get(local.len() + 1..)?),
+            None => split_local_part(text)?,
+        };
+        if local.is_empty() || domain.is_empty() {
+            return None;
+        }
+        Some(format!("{}@{}", local, domain))
+    }
+
+    /// Converts an email address into a mailbox name
+    ///
+    /// This is the inverse of `rname_email`. Fails with
+    /// `Error::InvalidName` if there is no `@`, if either part is empty or
+    /// if the local part contains a dot: names are kept as dotted text,
+    /// which has no way to encode a dot inside a label on the wire.
+    pub fn email_to_rname(email: &str) -> Result<Name<'static>, Error> {
+        let at = email.rfind('@')
+            .ok_or(Error::InvalidName("email address without @"))?;
+        let (local, domain) = (&email[..at], &email[at + 1..]);
+        if local.is_empty() || domain.trim_end_matches('.').is_empty() {
+            return Err(Error::InvalidName("empty part in email address"));
+        }
+        if local.contains('.') {
+            return Err(Error::InvalidName("dot in the local part"));
+        }
+        let name = format!("{}.{}", local, domain);
+        Name::check(&name)?;
+        Ok(Name::from_owned(name))
+    }
+}
+
+/// Splits a textual mailbox at the first dot that isn't escaped
+fn split_local_part(text: &str) -> Option<(String, &str)> {
+    let mut local = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => local.extend(chars.next().map(|(_, c)| c)),
+            '.' => return Some((local, &text[i + 1..])),
+            _ => local.push(c),
+        }
+    }
+    None
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}. {}. {} {} {} {} {}",
+            self.primary_ns, self.mailbox, self.serial, self.refresh,
+            self.retry, self.expire, self.minimum_ttl)
+    }
+}
+
+/// Parses the names followed by five numbers
+///
+/// Numbers must be plain seconds, units such as `1h` are not supported.
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record::new(fields.name()?, fields.name()?,
+            fields.parse()?, fields.parse()?, fields.parse()?,
+            fields.parse()?, fields.parse()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 6;
 
-    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
         let mut pos = 0;
         let primary_name_server = Name::scan(rdata, original)?;
         pos += primary_name_server.byte_len();
@@ -28,22 +136,31 @@ impl<'a> super::Record<'a> for Record<'a> {
         }
         let record = Record {
             primary_ns: primary_name_server,
-            mailbox: mailbox,
+            mailbox,
             serial: BigEndian::read_u32(&rdata[pos..(pos+4)]),
             refresh: BigEndian::read_u32(&rdata[(pos+4)..(pos+8)]),
             retry: BigEndian::read_u32(&rdata[(pos+8)..(pos+12)]),
             expire: BigEndian::read_u32(&rdata[(pos+12)..(pos+16)]),
             minimum_ttl: BigEndian::read_u32(&rdata[(pos+16)..(pos+20)]),
         };
-        Ok(super::RData::SOA(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
-        unimplemented!();
+        self.primary_ns.octet_length() + self.mailbox.octet_length() + 20
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-        unimplemented!();
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        let mut len = self.primary_ns.write_to(w)?;
+        len += self.mailbox.write_to(w)?;
+        w.write_u32::<BigEndian>(self.serial)?;
+        w.write_u32::<BigEndian>(self.refresh)?;
+        w.write_u32::<BigEndian>(self.retry)?;
+        w.write_u32::<BigEndian>(self.expire)?;
+        w.write_u32::<BigEndian>(self.minimum_ttl)?;
+        Ok(len + 20)
     }
 }
 
@@ -57,6 +174,50 @@ mod test {
     use QueryClass as QC;
     use Class as C;
     use RData;
+    use Name;
+
+    #[test]
+    fn format() {
+        let soa = RData::SOA(super::Record::new(
+            Name::from_string("ns.example.com"),
+            Name::from_string("admin.example.com"),
+            2019010101, 7200, 3600, 1209600, 300));
+
+        assert_eq!(soa.rdata_length(), 55);
+        assert_eq!(&soa.to_bytes()[..],
+            &b"\x02ns\x07example\x03com\x00\x05admin\x07example\x03com\x00\
+               \x78\x57\xa6\x35\x00\x00\x1c\x20\x00\x00\x0e\x10\
+               \x00\x12\x75\x00\x00\x00\x01\x2c"[..]);
+    }
+
+    #[test]
+    fn mailbox_email() {
+        use Error;
+        let soa = |mailbox: Name<'static>| super::Record::new(
+            Name::from_string("ns.example.com"), mailbox, 1, 2, 3, 4, 5);
+
+        let mailbox = super::Record::email_to_rname("hostmaster@example.com")
+            .unwrap();
+        assert_eq!(mailbox.to_string(), "hostmaster.example.com");
+        assert_eq!(soa(mailbox).rname_email().unwrap(),
+            "hostmaster@example.com");
+        let escaped = Name::from_string("john\\.doe.example.com.");
+        assert_eq!(soa(escaped).rname_email().unwrap(),
+            "john.doe@example.com");
+        assert_eq!(soa(Name::from_string("admin")).rname_email(), None);
+
+        let rdata = b"\x02ns\x00\x08john.doe\x07example\x03com\x00\
+                      \x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x03\
+                      \x00\x00\x00\x04\x00\x00\x00\x05";
+        let parsed = <super::Record as ::rdata::Record>::parse(rdata, rdata)
+            .unwrap();
+        assert_eq!(parsed.rname_email().unwrap(), "john.doe@example.com");
+
+        assert_eq!(super::Record::email_to_rname("john.doe@example.com")
+            .unwrap_err(), Error::InvalidName("dot in the local part"));
+        assert!(super::Record::email_to_rname("example.com").is_err());
+        assert!(super::Record::email_to_rname("admin@").is_err());
+    }
 
      #[test]
      fn parse_response() {
@@ -76,6 +237,7 @@ mod test {
               recursion_available: true,
               authenticated_data: false,
               checking_disabled: false,
+              reserved: false,
               response_code: NameError,
               questions: 1,
               answers: 0,
@@ -91,7 +253,7 @@ mod test {
           assert_eq!(packet.nameservers.len(), 1);
           assert_eq!(&packet.nameservers[0].name.to_string()[..], "youtube.com");
           assert_eq!(packet.nameservers[0].cls, C::IN);
-          assert_eq!(packet.nameservers[0].multicast_unique, false);
+          assert!(!packet.nameservers[0].multicast_unique);
           assert_eq!(packet.nameservers[0].ttl, 10800);
           match packet.nameservers[0].data {
               RData::SOA(ref soa_rec) => {
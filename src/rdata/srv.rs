@@ -1,5 +1,9 @@
-use {Name, Error};
-use byteorder::{BigEndian, ByteOrder};
+use std::io::{self, Write};
+use std::fmt;
+use std::str::FromStr;
+use {Name, Error, WireEncode};
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use super::text::Fields;
 
 #[derive(Debug, Clone)]
 pub struct Record<'a> {
@@ -9,11 +13,39 @@ pub struct Record<'a> {
     pub target: Name<'a>,
 }
 
+impl<'a> Record<'a> {
+    /// Creates a new SRV record pointing to `target:port`
+    pub fn new(priority: u16, weight: u16, port: u16, target: Name<'a>)
+        -> Record<'a>
+    {
+        Record { priority, weight, port, target }
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}.",
+            self.priority, self.weight, self.port, self.target)
+    }
+}
+
+/// Parses e.g. `10 5 5060 sip.example.com.`
+impl FromStr for Record<'static> {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record<'static>, Error> {
+        let mut fields = Fields::new(s)?;
+        let record = Record::new(fields.parse()?, fields.parse()?,
+                                 fields.parse()?, fields.name()?);
+        fields.end()?;
+        Ok(record)
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
 
     const TYPE: isize = 33;
 
-    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
         if rdata.len() < 7 {
             return Err(Error::WrongRdataLength);
         }
@@ -23,15 +55,20 @@ impl<'a> super::Record<'a> for Record<'a> {
             port: BigEndian::read_u16(&rdata[4..6]),
             target: Name::scan(&rdata[6..], original)?,
         };
-        Ok(super::RData::SRV(record))
+        Ok(record)
     }
 
     fn length(&self) -> u16 {
-        unimplemented!();
+        6 + self.target.octet_length()
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-        unimplemented!();
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.priority)?;
+        w.write_u16::<BigEndian>(self.weight)?;
+        w.write_u16::<BigEndian>(self.port)?;
+        Ok(6 + self.target.write_to(w)?)
     }
 }
 
@@ -44,9 +81,22 @@ mod test {
     use QueryType as QT;
     use QueryClass as QC;
     use Class as C;
-    use RData;
+    use {RData, WireEncode};
     use super::*;
 
+    #[test]
+    fn format() {
+        let srv = RData::SRV(Record::new(10, 60, 5060,
+            Name::from_string("sip.example.com")));
+
+        assert_eq!(srv.rdata_length(), 23);
+        assert_eq!(&srv.to_bytes()[..],
+            &b"\x00\x0a\x00\x3c\x13\xc4\x03sip\x07example\x03com\x00"[..]);
+        let mut buf = b"prefix".to_vec();
+        assert_eq!(srv.write_to(&mut buf).unwrap(), 23);
+        assert_eq!(&buf[6..], &srv.to_bytes()[..]);
+    }
+
     #[test]
     fn parse_response() {
         let response = b"[\xd9\x81\x80\x00\x01\x00\x05\x00\x00\x00\x00\
@@ -72,6 +122,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 5,
@@ -91,17 +142,17 @@ mod test {
             (&20, &0, &5269, "alt2.xmpp-server.l.google.com"),
             (&20, &0, &5269, "alt4.xmpp-server.l.google.com"),
         ];
-        for i in 0..5 {
-            assert_eq!(&packet.answers[i].name.to_string()[..],
+        for (answer, item) in packet.answers.iter().zip(items.iter()) {
+            assert_eq!(&answer.name.to_string()[..],
                 "_xmpp-server._tcp.gmail.com");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 900);
-            match &packet.answers[i].data {
+            assert_eq!(answer.cls, C::IN);
+            assert_eq!(answer.ttl, 900);
+            match &answer.data {
                 RData::SRV(Record { priority, weight, port, target }) => {
-                    assert_eq!(priority, items[i].0);
-                    assert_eq!(weight, items[i].1);
-                    assert_eq!(port, items[i].2);
-                    assert_eq!(target.to_string(), (items[i].3).to_string());
+                    assert_eq!(priority, item.0);
+                    assert_eq!(weight, item.1);
+                    assert_eq!(port, item.2);
+                    assert_eq!(target.to_string(), (item.3).to_string());
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
@@ -0,0 +1,315 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use {Error, WireEncode};
+use byteorder::WriteBytesExt;
+#[cfg(feature = "crypto")] use byteorder::{BigEndian, ByteOrder};
+#[cfg(feature = "crypto")] use sha2::{Digest, Sha256};
+
+/// Public key algorithm of an SSHFP record
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Algorithm {
+    /// RSA (1)
+    Rsa,
+    /// DSA (2)
+    Dsa,
+    /// ECDSA (3, RFC 6594)
+    Ecdsa,
+    /// Ed25519 (4, RFC 7479)
+    Ed25519,
+    /// Ed448 (6, RFC 8709)
+    Ed448,
+    /// Any other algorithm number, never one of the numbers above
+    Other(u8),
+}
+
+const ALGORITHMS: [(Algorithm, u8, &[&str]); 5] = [
+    (Algorithm::Rsa, 1, &["ssh-rsa"]),
+    (Algorithm::Dsa, 2, &["ssh-dss"]),
+    (Algorithm::Ecdsa, 3, &["ecdsa-sha2-nistp256", "ecdsa-sha2-nistp384",
+                            "ecdsa-sha2-nistp521"]),
+    (Algorithm::Ed25519, 4, &["ssh-ed25519"]),
+    (Algorithm::Ed448, 6, &["ssh-ed448"]),
+];
+
+impl Algorithm {
+    /// The SSH public key types of the algorithm, e.g. `ssh-ed25519`
+    ///
+    /// Empty for unknown algorithms.
+    pub fn key_types(self) -> &'static [&'static str] {
+        ALGORITHMS.iter()
+            .find(|&&(x, _, _)| x == self)
+            .map(|&(_, _, types)| types)
+            .unwrap_or(&[])
+    }
+
+    /// The algorithm of an SSH public key type, e.g. `ssh-rsa`
+    pub fn from_key_type(key_type: &str) -> Option<Algorithm> {
+        ALGORITHMS.iter()
+            .find(|&&(_, _, types)| types.contains(&key_type))
+            .map(|&(alg, _, _)| alg)
+    }
+}
+
+impl From<u8> for Algorithm {
+    fn from(code: u8) -> Algorithm {
+        ALGORITHMS.iter()
+            .find(|&&(_, x, _)| x == code)
+            .map(|&(alg, _, _)| alg)
+            .unwrap_or(Algorithm::Other(code))
+    }
+}
+impl From<Algorithm> for u8 {
+    fn from(alg: Algorithm) -> u8 {
+        match alg {
+            Algorithm::Other(x) => x,
+            alg => ALGORITHMS.iter()
+                .find(|&&(x, _, _)| x == alg)
+                .map(|&(_, code, _)| code)
+                .expect("all named algorithms are in the table"),
+        }
+    }
+}
+
+/// Hash algorithm of the fingerprint
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum FingerprintType {
+    /// SHA-1 (1)
+    Sha1,
+    /// SHA-256 (2, RFC 6594)
+    Sha256,
+    /// Any other type, never one of the types above
+    Other(u8),
+}
+
+impl From<u8> for FingerprintType {
+    fn from(code: u8) -> FingerprintType {
+        match code {
+            1 => FingerprintType::Sha1,
+            2 => FingerprintType::Sha256,
+            x => FingerprintType::Other(x),
+        }
+    }
+}
+impl From<FingerprintType> for u8 {
+    fn from(typ: FingerprintType) -> u8 {
+        match typ {
+            FingerprintType::Sha1 => 1,
+            FingerprintType::Sha256 => 2,
+            FingerprintType::Other(x) => x,
+        }
+    }
+}
+
+/// The SSHFP record (RFC 4255)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record<'a> {
+    pub algorithm: Algorithm,
+    pub fingerprint_type: FingerprintType,
+    pub fingerprint: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new SSHFP record
+    pub fn new<F>(algorithm: Algorithm, fingerprint_type: FingerprintType,
+        fingerprint: F)
+        -> Record<'a>
+        where F: Into<Cow<'a, [u8]>>
+    {
+        Record { algorithm, fingerprint_type, fingerprint: fingerprint.into() }
+    }
+
+    /// Copies the fingerprint so that the record no longer borrows the
+    /// packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            fingerprint: Cow::Owned(self.fingerprint.into_owned()),
+            ..self
+        }
+    }
+
+    /// Returns true if the record is the fingerprint of the host key
+    ///
+    /// `host_key_blob` is the public key in SSH wire format, as sent by
+    /// the server and base64 encoded in `known_hosts`. Its key type must
+    /// belong to the algorithm of the record. Unknown algorithms and
+    /// fingerprint types never match.
+    #[cfg(feature = "crypto")]
+    pub fn matches(&self, host_key_blob: &[u8]) -> bool {
+        let key_type = match key_type(host_key_blob) {
+            Some(key_type) => key_type,
+            None => return false,
+        };
+        if Algorithm::from_key_type(key_type) != Some(self.algorithm) {
+            return false;
+        }
+        match self.fingerprint_type {
+            FingerprintType::Sha1 => {
+                sha1(host_key_blob)[..] == self.fingerprint[..]
+            }
+            FingerprintType::Sha256 => {
+                Sha256::digest(host_key_blob)[..] == self.fingerprint[..]
+            }
+            FingerprintType::Other(_) => false,
+        }
+    }
+}
+
+/// The key type, the first string of an SSH public key blob (RFC 4253)
+#[cfg(feature = "crypto")]
+fn key_type(blob: &[u8]) -> Option<&str> {
+    let len = BigEndian::read_u32(blob.get(..4)?) as usize;
+    ::std::str::from_utf8(blob.get(4..4usize.checked_add(len)?)?).ok()
+}
+
+/// SHA-1 (RFC 3174), only used for SSHFP fingerprints
+#[cfg(feature = "crypto")]
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] =
+        [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            w[i] = BigEndian::read_u32(&block[i * 4..]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i-3] ^ w[i-8] ^ w[i-14] ^ w[i-16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e)
+                .wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (x, y) in h.iter_mut().zip(&[a, b, c, d, e]) {
+            *x = x.wrapping_add(*y);
+        }
+    }
+    let mut digest = [0u8; 20];
+    for (chunk, x) in digest.chunks_mut(4).zip(&h) {
+        BigEndian::write_u32(chunk, *x);
+    }
+    digest
+}
+
+/// Formats as e.g. `4 2 C1D2...`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ", u8::from(self.algorithm),
+            u8::from(self.fingerprint_type))?;
+        for byte in self.fingerprint.iter() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 44;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() < 3 {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record {
+            algorithm: Algorithm::from(rdata[0]),
+            fingerprint_type: FingerprintType::from(rdata[1]),
+            fingerprint: Cow::Borrowed(&rdata[2..]),
+        })
+    }
+
+    fn length(&self) -> u16 {
+        2 + self.fingerprint.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u8(u8::from(self.algorithm))?;
+        w.write_u8(u8::from(self.fingerprint_type))?;
+        w.write_all(&self.fingerprint)?;
+        Ok(2 + self.fingerprint.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rdata::Record as _;
+    use super::*;
+
+    #[test]
+    fn algorithms() {
+        assert_eq!(Algorithm::from(4), Algorithm::Ed25519);
+        assert_eq!(u8::from(Algorithm::Ed448), 6);
+        assert_eq!(Algorithm::from(5), Algorithm::Other(5));
+        assert_eq!(Algorithm::from_key_type("ecdsa-sha2-nistp384"),
+            Some(Algorithm::Ecdsa));
+        assert_eq!(Algorithm::from_key_type("ssh-foo"), None);
+        assert_eq!(Algorithm::Rsa.key_types(), ["ssh-rsa"]);
+        assert!(Algorithm::Other(9).key_types().is_empty());
+        assert_eq!(FingerprintType::from(2), FingerprintType::Sha256);
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn sha1_vectors() {
+        assert_eq!(&sha1(b"abc")[..],
+            b"\xa9\x99\x3e\x36\x47\x06\x81\x6a\xba\x3e\
+              \x25\x71\x78\x50\xc2\x6c\x9c\xd0\xd8\x9d");
+        assert_eq!(&sha1(b"")[..],
+            b"\xda\x39\xa3\xee\x5e\x6b\x4b\x0d\x32\x55\
+              \xbf\xef\x95\x60\x18\x90\xaf\xd8\x07\x09");
+        let two_blocks =
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(&sha1(two_blocks)[..],
+            b"\x84\x98\x3e\x44\x1c\x3b\xd2\x6e\xba\xae\
+              \x4a\xa1\xf9\x51\x29\xe5\xe5\x46\x70\xf1");
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn matches() {
+        let mut blob =
+            b"\x00\x00\x00\x0bssh-ed25519\x00\x00\x00\x20".to_vec();
+        blob.extend_from_slice(&[7; 32]);
+        let record = Record::new(Algorithm::Ed25519, FingerprintType::Sha256,
+            Sha256::digest(&blob).to_vec());
+        assert!(record.matches(&blob));
+        let record = Record::new(Algorithm::Ed25519, FingerprintType::Sha1,
+            sha1(&blob).to_vec());
+        assert!(record.matches(&blob));
+        let record = Record::new(Algorithm::Rsa, FingerprintType::Sha1,
+            sha1(&blob).to_vec());
+        assert!(!record.matches(&blob));
+        let record = Record::new(Algorithm::Ed25519, FingerprintType::Sha1,
+            sha1(&blob).to_vec());
+        assert!(!record.matches(&blob[..10]));
+    }
+
+    #[test]
+    fn wire() {
+        let record = Record::new(Algorithm::Ecdsa, FingerprintType::Sha1,
+            &b"\x12\xab"[..]);
+        let bytes = record.to_bytes();
+        assert_eq!(bytes, b"\x03\x01\x12\xab");
+        assert_eq!(Record::parse(&bytes, &bytes).unwrap(), record);
+        assert_eq!(record.to_string(), "3 1 12AB");
+    }
+}
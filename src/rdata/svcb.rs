@@ -0,0 +1,469 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use {Name, Error, WireEncode};
+use base64;
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use super::write_character_string;
+
+/// Keys the other SvcParams must be understood to use the record
+pub const MANDATORY: u16 = 0;
+/// Application protocols (ALPN ids) supported by the endpoint
+pub const ALPN: u16 = 1;
+/// The default protocol of the scheme isn't supported
+pub const NO_DEFAULT_ALPN: u16 = 2;
+/// Port to connect to instead of the scheme's default
+pub const PORT: u16 = 3;
+/// IPv4 addresses of the target
+pub const IPV4HINT: u16 = 4;
+/// ECHConfigList for Encrypted Client Hello
+pub const ECH: u16 = 5;
+/// IPv6 addresses of the target
+pub const IPV6HINT: u16 = 6;
+
+/// The ECHConfig version of the TLS ECH draft deployed today
+pub const ECH_VERSION: u16 = 0xfe0d;
+
+const KEY_NAMES: [&str; 7] = [
+    "mandatory", "alpn", "no-default-alpn", "port",
+    "ipv4hint", "ech", "ipv6hint",
+];
+
+/// The SVCB record (RFC 9460)
+///
+/// HTTPS records share the format, see `rdata::https`. Priority 0 is
+/// AliasMode, in which the record carries no parameters.
+#[derive(Debug, Clone)]
+pub struct Record<'a> {
+    pub priority: u16,
+    pub target: Name<'a>,
+    /// SvcParams in wire format, keys in strictly increasing order
+    params: Cow<'a, [u8]>,
+}
+
+/// Iterator over the `(key, value)` pairs of SvcParams
+#[derive(Debug, Clone)]
+pub struct Params<'r> {
+    data: &'r [u8],
+}
+
+/// A single ECHConfig of an ECHConfigList
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EchConfig<'r> {
+    /// Version, `ECH_VERSION` for configs usable today
+    pub version: u16,
+    /// The version specific contents, without the length prefix
+    pub contents: &'r [u8],
+    /// The whole ECHConfig including version and length
+    pub bytes: &'r [u8],
+}
+
+impl<'a> Record<'a> {
+    /// Creates a record without SvcParams
+    ///
+    /// Add parameters with `with_param`.
+    pub fn new(priority: u16, target: Name<'a>) -> Record<'a> {
+        Record { priority, target, params: Cow::Borrowed(&[]) }
+    }
+
+    /// Sets the parameter `key` replacing any previous value
+    ///
+    /// # Panics
+    ///
+    /// When the value is longer than 65535 bytes.
+    pub fn with_param(self, key: u16, value: &[u8]) -> Record<'a> {
+        assert!(value.len() <= 0xFFFF, "SvcParam value too long");
+        let mut params = Vec::with_capacity(
+            self.params.len() + 4 + value.len());
+        let mut inserted = false;
+        for (k, v) in self.params() {
+            if k > key && !inserted {
+                write_param(&mut params, key, value);
+                inserted = true;
+            }
+            if k != key {
+                write_param(&mut params, k, v);
+            }
+        }
+        if !inserted {
+            write_param(&mut params, key, value);
+        }
+        Record { params: Cow::Owned(params), ..self }
+    }
+
+    /// Copies the target and parameters so that the record no longer
+    /// borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            priority: self.priority,
+            target: self.target.into_owned(),
+            params: Cow::Owned(self.params.into_owned()),
+        }
+    }
+
+    /// Iterates over SvcParams as `(key, value)` in key order
+    pub fn params(&self) -> Params<'_> {
+        Params { data: &self.params }
+    }
+
+    /// Returns the value of the parameter `key`
+    pub fn param(&self, key: u16) -> Option<&[u8]> {
+        self.params().find(|&(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns true for AliasMode records, which have priority 0
+    ///
+    /// Their target is another name to look up SVCB records for, or the
+    /// root if the service doesn't exist (RFC 9460 section 2.4.2).
+    pub fn is_alias(&self) -> bool {
+        self.priority == 0
+    }
+
+    /// Keys listed in the `mandatory` parameter
+    pub fn mandatory(&self) -> Vec<u16> {
+        self.param(MANDATORY)
+            .map(|v| v.chunks(2).filter(|x| x.len() == 2)
+                .map(BigEndian::read_u16).collect())
+            .unwrap_or_default()
+    }
+
+    /// ALPN protocol ids of the `alpn` parameter, e.g. `b"h2"`
+    ///
+    /// Empty if there is no such parameter or it's malformed.
+    pub fn alpn(&self) -> Vec<&[u8]> {
+        self.param(ALPN).and_then(alpn_ids).unwrap_or_default()
+    }
+
+    /// Returns true if the `no-default-alpn` parameter is present
+    pub fn no_default_alpn(&self) -> bool {
+        self.param(NO_DEFAULT_ALPN).is_some()
+    }
+
+    /// The port of the `port` parameter
+    pub fn port(&self) -> Option<u16> {
+        self.param(PORT).filter(|v| v.len() == 2).map(BigEndian::read_u16)
+    }
+
+    /// Addresses of the `ipv4hint` parameter
+    pub fn ipv4hint(&self) -> Vec<Ipv4Addr> {
+        self.param(IPV4HINT)
+            .map(|v| v.chunks(4).filter(|x| x.len() == 4)
+                .map(|x| Ipv4Addr::new(x[0], x[1], x[2], x[3])).collect())
+            .unwrap_or_default()
+    }
+
+    /// Addresses of the `ipv6hint` parameter
+    pub fn ipv6hint(&self) -> Vec<Ipv6Addr> {
+        self.param(IPV6HINT)
+            .map(|v| v.chunks(16).filter(|x| x.len() == 16)
+                .map(|x| {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(x);
+                    Ipv6Addr::from(octets)
+                })
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if a client implementing the keys defined in RFC 9460
+    /// may use this ServiceMode record
+    ///
+    /// Records requiring other keys through `mandatory`, and those with
+    /// `no-default-alpn` but no `alpn`, must be skipped (RFC 9460 sections
+    /// 7.1.1 and 8).
+    pub fn is_compatible(&self) -> bool {
+        let known = |key: u16| key > MANDATORY && key <= IPV6HINT;
+        self.mandatory().into_iter().all(known) &&
+            !(self.no_default_alpn() && self.param(ALPN).is_none())
+    }
+
+    /// Returns the raw ECHConfigList of the `ech` parameter
+    ///
+    /// This is what TLS libraries take to enable Encrypted Client Hello.
+    pub fn ech(&self) -> Option<&[u8]> {
+        self.param(ECH)
+    }
+
+    /// Splits the ECHConfigList of the `ech` parameter into configs
+    ///
+    /// Returns an empty list when there is no `ech` parameter. Configs
+    /// of unknown versions are included, clients skip them.
+    pub fn ech_configs(&self) -> Result<Vec<EchConfig<'_>>, Error> {
+        match self.ech() {
+            Some(list) => parse_ech_config_list(list),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl<'r> Iterator for Params<'r> {
+    type Item = (u16, &'r [u8]);
+    fn next(&mut self) -> Option<(u16, &'r [u8])> {
+        // Framing is validated on parse and by `with_param`
+        if self.data.len() < 4 {
+            return None;
+        }
+        let key = BigEndian::read_u16(&self.data[..2]);
+        let len = BigEndian::read_u16(&self.data[2..4]) as usize;
+        let value = &self.data[4..4 + len];
+        self.data = &self.data[4 + len..];
+        Some((key, value))
+    }
+}
+
+fn write_param(buf: &mut Vec<u8>, key: u16, value: &[u8]) {
+    buf.write_u16::<BigEndian>(key).unwrap();
+    buf.write_u16::<BigEndian>(value.len() as u16).unwrap();
+    buf.extend_from_slice(value);
+}
+
+/// Checks the SvcParams framing and the key order (RFC 9460 section 2.2)
+fn check_params(mut data: &[u8]) -> Result<(), Error> {
+    let mut last = None;
+    while !data.is_empty() {
+        if data.len() < 4 {
+            return Err(Error::InvalidSvcParams("truncated parameter"));
+        }
+        let key = BigEndian::read_u16(&data[..2]);
+        let len = BigEndian::read_u16(&data[2..4]) as usize;
+        if data.len() < 4 + len {
+            return Err(Error::InvalidSvcParams("truncated parameter"));
+        }
+        if last.is_some_and(|last| key <= last) {
+            return Err(Error::InvalidSvcParams("keys out of order"));
+        }
+        last = Some(key);
+        data = &data[4 + len..];
+    }
+    Ok(())
+}
+
+/// Splits the `alpn` value into ids, each prefixed with its length
+fn alpn_ids(mut value: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut ids = Vec::new();
+    while let Some((&len, tail)) = value.split_first() {
+        ids.push(tail.get(..len as usize)?);
+        value = &tail[len as usize..];
+    }
+    Some(ids)
+}
+
+/// Parses an ECHConfigList into its configs
+///
+/// The list is prefixed with its length and every config with its
+/// version and the length of its contents.
+pub fn parse_ech_config_list(list: &[u8])
+    -> Result<Vec<EchConfig<'_>>, Error>
+{
+    if list.len() < 2 ||
+        BigEndian::read_u16(&list[..2]) as usize != list.len() - 2
+    {
+        return Err(Error::InvalidSvcParams("bad ECHConfigList length"));
+    }
+    let mut data = &list[2..];
+    let mut configs = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 4 {
+            return Err(Error::InvalidSvcParams("truncated ECHConfig"));
+        }
+        let len = BigEndian::read_u16(&data[2..4]) as usize;
+        if data.len() < 4 + len {
+            return Err(Error::InvalidSvcParams("truncated ECHConfig"));
+        }
+        configs.push(EchConfig {
+            version: BigEndian::read_u16(&data[..2]),
+            contents: &data[4..4 + len],
+            bytes: &data[..4 + len],
+        });
+        data = &data[4 + len..];
+    }
+    if configs.is_empty() {
+        return Err(Error::InvalidSvcParams("empty ECHConfigList"));
+    }
+    Ok(configs)
+}
+
+/// Writes the value of a known key, `None` if it has to be quoted
+fn write_value(f: &mut fmt::Formatter, key: u16, value: &[u8])
+    -> Option<fmt::Result>
+{
+    let list = |f: &mut fmt::Formatter, items: Vec<String>| {
+        f.write_str(&items.join(","))
+    };
+    Some(match key {
+        MANDATORY if value.len().is_multiple_of(2) => list(f,
+            value.chunks(2).map(|x| key_name(BigEndian::read_u16(x)))
+                .collect()),
+        ALPN => {
+            let ids = alpn_ids(value)?;
+            let plain = |id: &&[u8]| id.iter().all(|&b| b.is_ascii_graphic()
+                && b != b',' && b != b'\\' && b != b'"');
+            if !ids.iter().all(plain) {
+                return None;
+            }
+            list(f, ids.iter()
+                .map(|id| String::from_utf8_lossy(id).into_owned())
+                .collect())
+        }
+        PORT if value.len() == 2 => {
+            write!(f, "{}", BigEndian::read_u16(value))
+        }
+        IPV4HINT if value.len().is_multiple_of(4) => list(f,
+            value.chunks(4)
+                .map(|x| Ipv4Addr::new(x[0], x[1], x[2], x[3]).to_string())
+                .collect()),
+        IPV6HINT if value.len().is_multiple_of(16) => list(f,
+            value.chunks(16)
+                .map(|x| {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(x);
+                    Ipv6Addr::from(octets).to_string()
+                })
+                .collect()),
+        ECH => f.write_str(&base64::encode(value)),
+        _ => return None,
+    })
+}
+
+fn key_name(key: u16) -> String {
+    match KEY_NAMES.get(key as usize) {
+        Some(name) => name.to_string(),
+        None => format!("key{}", key),
+    }
+}
+
+/// Formats as e.g. `1 . alpn=h2,h3 port=8443 ech=BASE64`
+///
+/// Values that have no dedicated syntax or don't fit it are written as
+/// quoted character-strings, e.g. `key667="hello"`.
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}.", self.priority, self.target)?;
+        for (key, value) in self.params() {
+            write!(f, " {}", key_name(key))?;
+            if key == NO_DEFAULT_ALPN && value.is_empty() {
+                continue;
+            }
+            f.write_str("=")?;
+            if let Some(result) = write_value(f, key, value) {
+                result?;
+            } else {
+                write_character_string(f, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the RDATA shared by SVCB and HTTPS
+pub(crate) fn parse<'a>(rdata: &'a [u8], original: &'a [u8])
+    -> Result<Record<'a>, Error>
+{
+    if rdata.len() < 3 {
+        return Err(Error::WrongRdataLength);
+    }
+    let target = Name::scan(&rdata[2..], original)?;
+    let params = &rdata[2 + target.byte_len()..];
+    check_params(params)?;
+    Ok(Record {
+        priority: BigEndian::read_u16(&rdata[..2]),
+        target,
+        params: Cow::Borrowed(params),
+    })
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 64;
+
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> Result<Self, Error> {
+        parse(rdata, original)
+    }
+
+    fn length(&self) -> u16 {
+        2 + self.target.octet_length() + self.params.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u16::<BigEndian>(self.priority)?;
+        let len = self.target.write_to(w)?;
+        w.write_all(&self.params)?;
+        Ok(2 + len + self.params.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rdata::Record as _;
+    use {Name, Error};
+    use super::*;
+
+    fn ech_config_list() -> Vec<u8> {
+        b"\x00\x0e\xfe\x0d\x00\x03abc\x00\x99\x00\x03xyz".to_vec()
+    }
+
+    #[test]
+    fn ech() {
+        let list = ech_config_list();
+        let record = Record::new(1, Name::from_string(""))
+            .with_param(ECH, &list)
+            .with_param(ALPN, b"\x02h2\x02h3");
+        let bytes = record.to_bytes();
+        assert_eq!(bytes.len(), record.length() as usize);
+        let parsed = Record::parse(&bytes, &bytes).unwrap();
+        assert_eq!(parsed.params().map(|(k, _)| k).collect::<Vec<_>>(),
+            vec![ALPN, ECH]);
+        assert_eq!(parsed.ech(), Some(&list[..]));
+        let configs = parsed.ech_configs().unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].version, ECH_VERSION);
+        assert_eq!(configs[0].contents, b"abc");
+        assert_eq!(configs[0].bytes, b"\xfe\x0d\x00\x03abc");
+        assert_eq!(configs[1].version, 0x99);
+        assert_eq!(parsed.to_string(),
+            "1 . alpn=h2,h3 ech=AA7+DQADYWJjAJkAA3h5eg==");
+
+        assert!(Record::new(1, Name::from_string("")).ech_configs()
+            .unwrap().is_empty());
+        assert!(matches!(parse_ech_config_list(&list[..list.len() - 1]),
+            Err(Error::InvalidSvcParams(_))));
+        assert!(matches!(parse_ech_config_list(b"\x00\x00"),
+            Err(Error::InvalidSvcParams(_))));
+    }
+
+    #[test]
+    fn params() {
+        let record = Record::new(16, Name::from_string("svc.example.com"))
+            .with_param(PORT, b"\x20\xfb")
+            .with_param(667, b"hello")
+            .with_param(IPV4HINT, b"\xc0\x00\x02\x01")
+            .with_param(NO_DEFAULT_ALPN, b"")
+            .with_param(PORT, b"\x01\xbb");
+        assert_eq!(record.param(PORT), Some(&b"\x01\xbb"[..]));
+        assert_eq!(record.port(), Some(443));
+        assert_eq!(record.ipv4hint(), [Ipv4Addr::new(192, 0, 2, 1)]);
+        assert!(record.ipv6hint().is_empty());
+        assert!(record.no_default_alpn());
+        assert!(!record.is_alias());
+        assert!(!record.is_compatible());
+        let record = record.with_param(ALPN, b"\x02h3");
+        assert_eq!(record.alpn(), [&b"h3"[..]]);
+        assert!(record.is_compatible());
+        let record = record.with_param(MANDATORY, b"\x00\x03\x02\x9b");
+        assert_eq!(record.mandatory(), [PORT, 667]);
+        assert!(!record.is_compatible());
+        assert_eq!(record.to_string(), "16 svc.example.com. \
+            mandatory=port,key667 alpn=h3 no-default-alpn port=443 \
+            ipv4hint=192.0.2.1 key667=\"hello\"");
+
+        assert!(matches!(
+            Record::parse(b"\x00\x01\x00\x00\x03\x00\x00\x00\x01\x00\x00", b""),
+            Err(Error::InvalidSvcParams("keys out of order"))));
+        assert!(matches!(
+            Record::parse(b"\x00\x01\x00\x00\x03\x00\x02\x01", b""),
+            Err(Error::InvalidSvcParams("truncated parameter"))));
+    }
+}
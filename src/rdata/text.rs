@@ -0,0 +1,199 @@
+//! Reading RDATA from presentation format (RFC 1035 section 5.1)
+
+use std::str::{FromStr, from_utf8};
+use std::vec;
+
+use {Name, Error};
+
+struct Token {
+    bytes: Vec<u8>,
+    /// Quoted or has escapes, so can't be a name
+    literal: bool,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut bytes = text.bytes().peekable();
+    loop {
+        while bytes.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            bytes.next();
+        }
+        if bytes.peek().is_none() {
+            return Ok(tokens);
+        }
+        let quoted = bytes.peek() == Some(&b'"');
+        if quoted {
+            bytes.next();
+        }
+        let mut token = Token { bytes: Vec::new(), literal: quoted };
+        loop {
+            let b = match bytes.next() {
+                Some(b) => b,
+                None if quoted => {
+                    return Err(Error::InvalidText("unterminated string"));
+                }
+                None => break,
+            };
+            match b {
+                b'"' if quoted => break,
+                b'"' => return Err(Error::InvalidText("misplaced quote")),
+                b if !quoted && b.is_ascii_whitespace() => break,
+                b'\\' => {
+                    token.literal = true;
+                    let first = bytes.next()
+                        .ok_or(Error::InvalidText("dangling escape"))?;
+                    if !first.is_ascii_digit() {
+                        token.bytes.push(first);
+                        continue;
+                    }
+                    let mut value = (first - b'0') as u32;
+                    for _ in 0..2 {
+                        match bytes.next() {
+                            Some(d) if d.is_ascii_digit() => {
+                                value = value * 10 + (d - b'0') as u32;
+                            }
+                            _ => return Err(Error::InvalidText(
+                                "escape needs three digits")),
+                        }
+                    }
+                    if value > 255 {
+                        return Err(Error::InvalidText("escape is above 255"));
+                    }
+                    token.bytes.push(value as u8);
+                }
+                b => token.bytes.push(b),
+            }
+        }
+        tokens.push(token);
+    }
+}
+
+/// Whitespace-separated fields of RDATA text
+pub(crate) struct Fields {
+    tokens: vec::IntoIter<Token>,
+}
+
+impl Fields {
+    pub(crate) fn new(text: &str) -> Result<Fields, Error> {
+        Ok(Fields { tokens: tokenize(text)?.into_iter() })
+    }
+
+    fn token(&mut self) -> Result<Token, Error> {
+        self.tokens.next().ok_or(Error::InvalidText("missing field"))
+    }
+
+    /// A domain name, the trailing dot is optional
+    ///
+    /// There's no origin, so relative names are taken as absolute.
+    pub(crate) fn name(&mut self) -> Result<Name<'static>, Error> {
+        let token = self.token()?;
+        if token.literal {
+            return Err(Error::InvalidText("names can't be quoted or escaped"));
+        }
+        let text = from_utf8(&token.bytes)
+            .map_err(|_| Error::InvalidText("name is not ASCII"))?;
+        let text = match text {
+            "." => "",
+            x if x.ends_with('.') => &x[..x.len()-1],
+            x => x,
+        };
+        if text.len() > 253 {
+            return Err(Error::InvalidText("name is too long"));
+        }
+        if !text.is_empty() {
+            for label in text.split('.') {
+                if label.is_empty() || label.len() > 63 || !label.is_ascii() {
+                    return Err(Error::InvalidText("invalid label"));
+                }
+            }
+        }
+        Ok(Name::from_owned(text.to_string()))
+    }
+
+    /// A number or an address
+    pub(crate) fn parse<T: FromStr>(&mut self) -> Result<T, Error> {
+        let token = self.token()?;
+        from_utf8(&token.bytes).ok()
+            .and_then(|x| x.parse().ok())
+            .ok_or(Error::InvalidText("invalid number or address"))
+    }
+
+    /// A character-string, quoted or not
+    pub(crate) fn string(&mut self) -> Result<Vec<u8>, Error> {
+        let token = self.token()?;
+        if token.bytes.len() > 255 {
+            return Err(Error::InvalidText("string is longer than 255 bytes"));
+        }
+        Ok(token.bytes)
+    }
+
+    /// Returns true if all fields were consumed
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tokens.as_slice().is_empty()
+    }
+
+    /// Fails if there are fields left
+    pub(crate) fn end(self) -> Result<(), Error> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidText("too many fields"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Error, RData, Type};
+    use super::Fields;
+
+    #[test]
+    fn fields() {
+        let mut f = Fields::new(r#" 10 "a b\"c" x\032y  \255 mail. "#)
+            .unwrap();
+        assert_eq!(f.parse::<u16>(), Ok(10));
+        assert_eq!(f.string().unwrap(), b"a b\"c");
+        assert_eq!(f.string().unwrap(), b"x y");
+        assert_eq!(f.string().unwrap(), b"\xff");
+        assert_eq!(f.name().unwrap().to_string(), "mail");
+        assert!(f.end().is_ok());
+
+        assert_eq!(Fields::new("\"abc").err(),
+            Some(Error::InvalidText("unterminated string")));
+        assert_eq!(Fields::new("\\25").err(),
+            Some(Error::InvalidText("escape needs three digits")));
+        assert!(Fields::new("a..b").unwrap().name().is_err());
+        assert!(Fields::new("\"a.b\"").unwrap().name().is_err());
+    }
+
+    #[test]
+    fn rdata_round_trip() {
+        let cases = [
+            (Type::A, "192.0.2.1"),
+            (Type::AAAA, "2001:db8::1"),
+            (Type::CNAME, "www.example.com."),
+            (Type::NS, "ns1.example.com."),
+            (Type::PTR, "host.example.com."),
+            (Type::MX, "10 mail.example.com."),
+            (Type::SRV, "10 5 5060 sip.example.com."),
+            (Type::SOA, "ns.example.com. admin.example.com. 2024010101 \
+                         7200 3600 1209600 300"),
+            (Type::TXT, "\"v=spf1 -all\" \"second \\\"part\\\"\""),
+            (Type::HINFO, "\"Intel Xeon\" \"Linux\""),
+            (Type::CAA, "0 issue \"ca.example.net\""),
+            (Type::NAPTR, "100 10 \"U\" \"E2U+sip\" \
+                           \"!^.*$!sip:info@example.com!\" ."),
+        ];
+        for &(typ, text) in &cases {
+            let data = RData::from_text(typ, text).unwrap();
+            assert_eq!(data.type_code(), typ);
+            assert_eq!(data.to_string(), text);
+        }
+        assert_eq!(RData::from_text(Type::MX, "mail.example.com.")
+            .unwrap_err(), Error::InvalidText("invalid number or address"));
+        assert_eq!(RData::from_text(Type::A, "192.0.2.1 x").unwrap_err(),
+            Error::InvalidText("too many fields"));
+        assert!(RData::from_text(Type::HINFO, "a").is_err());
+        assert!(RData::from_text(Type::WKS, "a b").is_err());
+    }
+}
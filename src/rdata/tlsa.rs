@@ -0,0 +1,254 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use {Error, WireEncode};
+use byteorder::WriteBytesExt;
+#[cfg(feature = "crypto")] use sha2::{Digest, Sha256, Sha512};
+
+/// Certificate usage: CA constraint, validated with PKIX
+pub const USAGE_PKIX_TA: u8 = 0;
+/// Certificate usage: service certificate, validated with PKIX
+pub const USAGE_PKIX_EE: u8 = 1;
+/// Certificate usage: trust anchor asserted by the domain
+pub const USAGE_DANE_TA: u8 = 2;
+/// Certificate usage: the service certificate asserted by the domain
+pub const USAGE_DANE_EE: u8 = 3;
+/// Selector: the full certificate
+pub const SELECTOR_FULL: u8 = 0;
+/// Selector: the SubjectPublicKeyInfo of the certificate
+pub const SELECTOR_SPKI: u8 = 1;
+/// Matching type: the selected content itself
+pub const MATCH_EXACT: u8 = 0;
+/// Matching type: SHA-256 of the selected content
+pub const MATCH_SHA256: u8 = 1;
+/// Matching type: SHA-512 of the selected content
+pub const MATCH_SHA512: u8 = 2;
+
+/// The TLSA record (RFC 6698)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record<'a> {
+    pub usage: u8,
+    pub selector: u8,
+    pub matching_type: u8,
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new TLSA record
+    pub fn new<D>(usage: u8, selector: u8, matching_type: u8, data: D)
+        -> Record<'a>
+        where D: Into<Cow<'a, [u8]>>
+    {
+        Record { usage, selector, matching_type, data: data.into() }
+    }
+
+    /// Copies the data so that the record no longer borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            data: Cow::Owned(self.data.into_owned()),
+            ..self
+        }
+    }
+
+    /// Returns true if the DER encoded certificate matches the record
+    ///
+    /// Selects the certificate or its SubjectPublicKeyInfo and compares
+    /// it, or its hash, to the data of the record. Only the matching is
+    /// done here, what a match means depends on `usage` (RFC 6698
+    /// section 2.1.1). Unknown selectors and matching types, and
+    /// certificates that aren't valid DER, never match.
+    #[cfg(feature = "crypto")]
+    pub fn matches(&self, cert_der: &[u8]) -> bool {
+        let selected = match self.selector {
+            SELECTOR_FULL => cert_der,
+            SELECTOR_SPKI => match subject_public_key_info(cert_der) {
+                Some(spki) => spki,
+                None => return false,
+            },
+            _ => return false,
+        };
+        match self.matching_type {
+            MATCH_EXACT => selected == &self.data[..],
+            MATCH_SHA256 => Sha256::digest(selected)[..] == self.data[..],
+            MATCH_SHA512 => Sha512::digest(selected)[..] == self.data[..],
+            _ => false,
+        }
+    }
+}
+
+/// Splits a DER element off `data`, returns the tag, the whole element
+/// and the rest
+#[cfg(feature = "crypto")]
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    let (header, len) = if first < 0x80 {
+        (2, first)
+    } else {
+        let octets = first & 0x7f;
+        if octets == 0 || octets > 4 {
+            return None;
+        }
+        let len = data.get(2..2 + octets)?.iter()
+            .fold(0usize, |len, &b| len << 8 | b as usize);
+        (2 + octets, len)
+    };
+    let end = header.checked_add(len)?;
+    let element = data.get(..end)?;
+    Some((tag, element, &data[end..]))
+}
+
+/// Content of a DER SEQUENCE element
+#[cfg(feature = "crypto")]
+fn der_sequence(element: &[u8]) -> Option<&[u8]> {
+    let (tag, element, _) = der_element(element)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let header = if element[1] < 0x80 { 2 } else { 2 + (element[1] & 0x7f) };
+    Some(&element[header as usize..])
+}
+
+/// Finds the DER encoded SubjectPublicKeyInfo of an X.509 certificate
+///
+/// It's the seventh field of the TBSCertificate, or the sixth if the
+/// optional version is missing (RFC 5280 section 4.1).
+#[cfg(feature = "crypto")]
+fn subject_public_key_info(cert_der: &[u8]) -> Option<&[u8]> {
+    let tbs = der_sequence(der_sequence(cert_der)?)?;
+    let (tag, _, rest) = der_element(tbs)?;
+    // serialNumber, signature, issuer, validity and subject come first
+    let mut fields = if tag == 0xa0 { rest } else { tbs };
+    for _ in 0..5 {
+        fields = der_element(fields)?.2;
+    }
+    let (tag, spki, _) = der_element(fields)?;
+    if tag != 0x30 {
+        return None;
+    }
+    Some(spki)
+}
+
+/// Formats as e.g. `3 1 1 0D6FCE...`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} ", self.usage, self.selector, self.matching_type)?;
+        for byte in self.data.iter() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 52;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        if rdata.len() < 4 {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record {
+            usage: rdata[0],
+            selector: rdata[1],
+            matching_type: rdata[2],
+            data: Cow::Borrowed(&rdata[3..]),
+        })
+    }
+
+    fn length(&self) -> u16 {
+        3 + self.data.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u8(self.usage)?;
+        w.write_u8(self.selector)?;
+        w.write_u8(self.matching_type)?;
+        w.write_all(&self.data)?;
+        Ok(3 + self.data.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rdata::Record as _;
+    use super::*;
+
+    #[cfg(feature = "crypto")]
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut element = vec![tag];
+        if content.len() < 0x80 {
+            element.push(content.len() as u8);
+        } else {
+            element.extend_from_slice(&[0x82, (content.len() >> 8) as u8,
+                                        content.len() as u8]);
+        }
+        element.extend_from_slice(content);
+        element
+    }
+
+    /// A certificate with the structure of X.509, but no real contents
+    #[cfg(feature = "crypto")]
+    fn certificate(version: bool) -> (Vec<u8>, Vec<u8>) {
+        let algorithm = der(0x30, &der(0x06, b"\x2a\x86\x48\xce\x3d\x02\x01"));
+        let spki = der(0x30, &[algorithm.clone(),
+                               der(0x03, &[0x04; 200])].concat());
+        let mut tbs = Vec::new();
+        if version {
+            tbs.extend(der(0xa0, &der(0x02, b"\x02")));
+        }
+        tbs.extend(der(0x02, b"\x01"));
+        tbs.extend(&algorithm);
+        tbs.extend(der(0x30, b"issuer"));
+        tbs.extend(der(0x30, b"validity"));
+        tbs.extend(der(0x30, b"subject"));
+        tbs.extend(&spki);
+        tbs.extend(der(0xa3, b"extensions"));
+        let cert = der(0x30, &[der(0x30, &tbs), algorithm,
+                               der(0x03, b"\x00signature")].concat());
+        (cert, spki)
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn spki() {
+        let (cert, spki) = certificate(true);
+        assert_eq!(subject_public_key_info(&cert), Some(&spki[..]));
+        let (cert, spki) = certificate(false);
+        assert_eq!(subject_public_key_info(&cert), Some(&spki[..]));
+        assert_eq!(subject_public_key_info(&cert[..cert.len() - 1]), None);
+        assert_eq!(subject_public_key_info(b"\x30\x00"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn matches() {
+        let (cert, spki) = certificate(true);
+        let sha256 = Sha256::digest(&spki).to_vec();
+        let record = Record::new(USAGE_DANE_EE, SELECTOR_SPKI, MATCH_SHA256,
+                                 sha256.clone());
+        assert!(record.matches(&cert));
+        assert!(!record.matches(&certificate(false).0[1..]));
+        let record = Record::new(USAGE_DANE_TA, SELECTOR_FULL, MATCH_SHA512,
+                                 Sha512::digest(&cert).to_vec());
+        assert!(record.matches(&cert));
+        let record = Record::new(USAGE_DANE_EE, SELECTOR_SPKI, MATCH_EXACT,
+                                 spki);
+        assert!(record.matches(&cert));
+        let record = Record::new(USAGE_DANE_EE, SELECTOR_SPKI, 3, sha256);
+        assert!(!record.matches(&cert));
+    }
+
+    #[test]
+    fn wire() {
+        let record = Record::new(USAGE_DANE_EE, SELECTOR_SPKI, MATCH_SHA256,
+                                 &b"\x0d\x6f"[..]);
+        let bytes = record.to_bytes();
+        assert_eq!(bytes, b"\x03\x01\x01\x0d\x6f");
+        assert_eq!(Record::parse(&bytes, &bytes).unwrap(), record);
+        assert_eq!(record.to_string(), "3 1 1 0D6F");
+        assert!(Record::parse(b"\x03\x01\x01", b"").is_err());
+    }
+}
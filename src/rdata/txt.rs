@@ -1,4 +1,11 @@
+use std::io::{self, Write};
+use std::fmt;
+use std::borrow::Cow;
+use std::str::{from_utf8, FromStr};
+
 use Error;
+use super::write_character_string;
+use super::text::Fields;
 
 const SEGMENT_LENGTH: usize = 255;
 
@@ -15,29 +22,113 @@ pub struct RecordIter<'a> {
 impl<'a> Iterator for RecordIter<'a> {
     type Item = &'a [u8];
     fn next(&mut self) -> Option<&'a [u8]> {
-        if self.bytes.len() >= 1 {
+        if !self.bytes.is_empty() {
             let len = self.bytes[0] as usize;
-            debug_assert!(self.bytes.len() >= len+1);
+            debug_assert!(self.bytes.len() > len);
             let (head, tail) = self.bytes[1..].split_at(len);
             self.bytes = tail;
             return Some(head);
         }
-        return None;
+        None
+    }
+}
+
+/// Iterator over DNS-SD `key=value` attributes (RFC 6763 section 6)
+#[derive(Debug)]
+pub struct AttributeIter<'a> {
+    chunks: RecordIter<'a>,
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = (&'a str, Option<&'a [u8]>);
+    fn next(&mut self) -> Option<(&'a str, Option<&'a [u8]>)> {
+        for chunk in &mut self.chunks {
+            let (key, value) = match chunk.iter().position(|&b| b == b'=') {
+                Some(pos) => (&chunk[..pos], Some(&chunk[pos+1..])),
+                None => (chunk, None),
+            };
+            // Empty strings and strings with a missing key are ignored,
+            // keys are printable US-ASCII only
+            if key.is_empty() || !key.iter().all(|b| (0x20..0x7F).contains(b)) {
+                continue;
+            }
+            return Some((from_utf8(key).unwrap(), value));
+        }
+        None
     }
 }
 
 impl Record {
 
     // Returns iterator over text chunks
-    pub fn iter(&self) -> RecordIter {
+    pub fn iter(&self) -> RecordIter<'_> {
         RecordIter {
             bytes: &self.bytes,
         }
     }
 
+    /// Returns all text chunks concatenated together
+    ///
+    /// Borrows the data when the record consists of a single chunk.
+    pub fn joined(&self) -> Cow<'_, [u8]> {
+        let mut chunks = self.iter();
+        match (chunks.next(), chunks.next()) {
+            (None, _) => Cow::Borrowed(&[]),
+            (Some(chunk), None) => Cow::Borrowed(chunk),
+            _ => Cow::Owned(self.iter().flat_map(|x| x.iter().cloned())
+                .collect()),
+        }
+    }
+
+    /// Same as `joined` but decoded as UTF-8, replacing invalid sequences
+    pub fn joined_lossy(&self) -> Cow<'_, str> {
+        match self.joined() {
+            Cow::Borrowed(bytes) => String::from_utf8_lossy(bytes),
+            Cow::Owned(bytes) => match String::from_utf8(bytes) {
+                Ok(text) => Cow::Owned(text),
+                Err(e) => Cow::Owned(
+                    String::from_utf8_lossy(e.as_bytes()).into_owned()),
+            },
+        }
+    }
+
+    /// Returns iterator over DNS-SD `key=value` attributes
+    ///
+    /// Each chunk is split on the first `=`. A chunk without `=` is
+    /// a boolean attribute and yields `None` as the value. Keys are
+    /// case-insensitive, use `attribute` to look one up.
+    pub fn attributes(&self) -> AttributeIter<'_> {
+        AttributeIter {
+            chunks: self.iter(),
+        }
+    }
+
+    /// Looks up DNS-SD attribute by case-insensitive key
+    ///
+    /// Returns `None` when the key is absent, `Some(None)` for a boolean
+    /// attribute and `Some(Some(value))` otherwise. Only the first
+    /// occurrence of a key is considered, as RFC 6763 requires.
+    pub fn attribute(&self, key: &str) -> Option<Option<&[u8]>> {
+        self.attributes()
+            .find(|&(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Record {
+        Record::from_bytes(s.as_bytes())
+    }
+
+    /// Creates a record from arbitrary bytes
+    ///
+    /// The data is split into as many 255-byte character-strings as needed.
+    /// Empty data becomes a single empty character-string, as TXT RDATA
+    /// can't be empty.
+    pub fn from_bytes(bytes: &[u8]) -> Record {
+        if bytes.is_empty() {
+            return Record { bytes: vec![0] };
+        }
         let mut result: Vec<u8> = Vec::new();
-        let bytes = s.as_bytes();
         let byte_len = bytes.len();
 
         let mut pos = 0;
@@ -55,13 +146,56 @@ impl Record {
 
         Record{ bytes: result }
     }
+
+    /// Creates a record with one character-string per segment
+    ///
+    /// Segment boundaries are kept exactly as given. Fails with
+    /// `WrongRdataLength` when a segment is longer than 255 bytes.
+    pub fn from_segments<'a, I>(segments: I) -> Result<Record, Error>
+        where I: IntoIterator<Item=&'a [u8]>
+    {
+        let mut result: Vec<u8> = Vec::new();
+        for segment in segments {
+            if segment.len() > SEGMENT_LENGTH {
+                return Err(Error::WrongRdataLength);
+            }
+            result.push(segment.len() as u8);
+            result.extend_from_slice(segment);
+        }
+        Ok(Record{ bytes: result })
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, chunk) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write_character_string(f, chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses one or more character-strings, quoted or not
+impl FromStr for Record {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Record, Error> {
+        let mut fields = Fields::new(s)?;
+        let mut segments = vec![fields.string()?];
+        while !fields.is_empty() {
+            segments.push(fields.string()?);
+        }
+        Record::from_segments(segments.iter().map(|x| &x[..]))
+    }
 }
 
 impl<'a> super::Record<'a> for Record {
 
     const TYPE: isize = 16;
 
-    fn parse(rdata: &[u8], _original: &[u8]) -> super::RDataResult<'a> {
+    fn parse(rdata: &[u8], _original: &[u8]) -> Result<Self, Error> {
         // Just a quick check that record is valid
         let len = rdata.len();
         if len < 1 {
@@ -76,17 +210,20 @@ impl<'a> super::Record<'a> for Record {
             }
             pos += rdlen;
         }
-        Ok(super::RData::TXT(Record {
+        Ok(Record {
             bytes: rdata.to_vec(),
-        }))
+        })
     }
 
     fn length(&self) -> u16 {
         self.bytes.len() as u16
     }
+}
 
-    fn to_bytes(&self) -> Vec<u8> {
-        self.bytes.clone()
+impl super::WireEncode for Record {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_all(&self.bytes)?;
+        Ok(self.bytes.len())
     }
 }
 
@@ -102,6 +239,7 @@ mod test {
     use QueryClass as QC;
     use Class as C;
     use RData;
+    use Error;
     use rdata::Record;
 
     #[test]
@@ -110,6 +248,60 @@ mod test {
         assert_eq!(record.to_bytes(), b"\x0Ethis is a test")
     }
 
+    #[test]
+    fn test_from_bytes() {
+        let data = [0xFFu8; 300];
+        let record = super::Record::from_bytes(&data);
+        assert_eq!(record.iter().map(|x| x.len()).collect::<Vec<_>>(),
+            [255, 45]);
+        assert!(record.iter().flat_map(|x| x.iter()).all(|&b| b == 0xFF));
+        assert_eq!(super::Record::from_bytes(b"").to_bytes(), b"\x00");
+    }
+
+    #[test]
+    fn test_from_segments() {
+        let record = super::Record::from_segments(
+            vec![&b"v=DKIM1; k=rsa; "[..], &b""[..], &b"\x00\x01"[..]])
+            .unwrap();
+        assert_eq!(record.to_bytes(),
+            b"\x10v=DKIM1; k=rsa; \x00\x02\x00\x01");
+
+        let long = [b'a'; 256];
+        assert!(matches!(super::Record::from_segments(vec![&long[..]]),
+            Err(Error::WrongRdataLength)));
+    }
+
+    #[test]
+    fn joined() {
+        use std::borrow::Cow;
+
+        let single = super::Record::from_str("v=spf1 -all");
+        assert!(matches!(single.joined(), Cow::Borrowed(b"v=spf1 -all")));
+
+        let multiple = super::Record::from_segments(
+            vec![&b"v=DKIM1; "[..], &b"p=\xff"[..]]).unwrap();
+        assert_eq!(&multiple.joined()[..], b"v=DKIM1; p=\xff");
+        assert_eq!(multiple.joined_lossy(), "v=DKIM1; p=\u{FFFD}");
+    }
+
+    #[test]
+    fn dnssd_attributes() {
+        let record = super::Record {
+            bytes: b"\x09txtvers=1\x07Path=/x\x03rw!\x00\x04=bad\x06empty=\
+                     \x0apath=other".to_vec(),
+        };
+        assert_eq!(record.attributes().collect::<Vec<_>>(), vec![
+            ("txtvers", Some(&b"1"[..])),
+            ("Path", Some(&b"/x"[..])),
+            ("rw!", None),
+            ("empty", Some(&b""[..])),
+            ("path", Some(&b"other"[..])),
+        ]);
+        assert_eq!(record.attribute("PATH"), Some(Some(&b"/x"[..])));
+        assert_eq!(record.attribute("rw!"), Some(None));
+        assert_eq!(record.attribute("missing"), None);
+    }
+
     #[test]
     fn parse_response_multiple_strings() {
         let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
@@ -130,6 +322,7 @@ mod test {
             recursion_available: true,
             authenticated_data: false,
             checking_disabled: false,
+            reserved: false,
             response_code: NoError,
             questions: 1,
             answers: 1,
@@ -142,7 +335,7 @@ mod test {
         assert_eq!(&packet.questions[0].qname.to_string()[..], "facebook.com");
         assert_eq!(packet.answers.len(), 1);
         assert_eq!(&packet.answers[0].name.to_string()[..], "facebook.com");
-        assert_eq!(packet.answers[0].multicast_unique, false);
+        assert!(!packet.answers[0].multicast_unique);
         assert_eq!(packet.answers[0].cls, C::IN);
         assert_eq!(packet.answers[0].ttl, 86333);
         match packet.answers[0].data {
@@ -160,4 +353,13 @@ mod test {
             ref x => panic!("Wrong rdata {:?}", x),
         }
     }
+
+    #[test]
+    fn presentation_format() {
+        let txt = super::Record::from_bytes(b"say \"hi\"\\\x01");
+        assert_eq!(txt.to_string(), r#""say \"hi\"\\\001""#);
+        let long = super::Record::from_bytes(&[b'a'; 256][..]);
+        assert_eq!(long.to_string(),
+            format!("\"{}\" \"a\"", "a".repeat(255)));
+    }
 }
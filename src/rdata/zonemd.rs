@@ -0,0 +1,82 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::fmt;
+use {Error, WireEncode};
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+/// The SIMPLE scheme of ZONEMD (RFC 8976 section 2.2.2)
+pub const SCHEME_SIMPLE: u8 = 1;
+/// The SHA-384 hash algorithm of ZONEMD
+pub const HASH_SHA384: u8 = 1;
+/// The SHA-512 hash algorithm of ZONEMD
+pub const HASH_SHA512: u8 = 2;
+
+/// The ZONEMD (message digest for DNS zones) record (RFC 8976)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record<'a> {
+    pub serial: u32,
+    pub scheme: u8,
+    pub hash_algorithm: u8,
+    pub digest: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new ZONEMD record
+    pub fn new<D>(serial: u32, scheme: u8, hash_algorithm: u8, digest: D)
+        -> Record<'a>
+        where D: Into<Cow<'a, [u8]>>
+    {
+        Record { serial, scheme, hash_algorithm, digest: digest.into() }
+    }
+
+    /// Copies the digest so that the record no longer borrows the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            digest: Cow::Owned(self.digest.into_owned()),
+            ..self
+        }
+    }
+}
+
+/// Formats as e.g. `2018031900 1 1 FEBE3D...`
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} ", self.serial, self.scheme, self.hash_algorithm)?;
+        for byte in self.digest.iter() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+
+    const TYPE: isize = 63;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> Result<Self, Error> {
+        // The digest is at least 12 octets long
+        if rdata.len() < 18 {
+            return Err(Error::WrongRdataLength);
+        }
+        Ok(Record {
+            serial: BigEndian::read_u32(&rdata[..4]),
+            scheme: rdata[4],
+            hash_algorithm: rdata[5],
+            digest: Cow::Borrowed(&rdata[6..]),
+        })
+    }
+
+    fn length(&self) -> u16 {
+        6 + self.digest.len() as u16
+    }
+}
+
+impl<'a> WireEncode for Record<'a> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        w.write_u32::<BigEndian>(self.serial)?;
+        w.write_u8(self.scheme)?;
+        w.write_u8(self.hash_algorithm)?;
+        w.write_all(&self.digest)?;
+        Ok(6 + self.digest.len())
+    }
+}
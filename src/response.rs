@@ -0,0 +1,650 @@
+//! Helpers for resolvers looking at responses
+
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
+
+use {Packet, Name, Class, RData, ResourceRecord, ResponseCode};
+use rdata::{Soa, Svcb};
+
+/// Longest chain of SVCB AliasMode records followed
+const MAX_ALIAS_CHAIN: usize = 8;
+
+/// What a response means to a resolver, see `Packet::response_kind`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ResponseKind {
+    /// The answer section holds the answer
+    Answer,
+    /// A delegation to the nameservers of a zone closer to the name
+    Referral,
+    /// The name doesn't exist
+    NxDomain,
+    /// The name exists but has no records of the type
+    NoData,
+    /// The server isn't authoritative for the name and doesn't know who is
+    Lame,
+    /// The server failed, or rejected the query as malformed
+    ServFail,
+}
+
+/// The two kinds of negative responses
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Negative {
+    /// The name doesn't exist
+    NxDomain,
+    /// The name exists but has no records of the type
+    NoData,
+}
+
+/// A negative response as described by RFC 2308
+///
+/// Returned by `Packet::negative_response`.
+#[derive(Debug, Clone, Copy)]
+pub struct NegativeResponse<'r, 'a: 'r> {
+    /// NXDOMAIN or NODATA
+    pub kind: Negative,
+    /// The type of the response from RFC 2308 sections 2.1 and 2.2
+    ///
+    /// 1: SOA and NS records in the authority section, 2: SOA only,
+    /// 3: nothing, 4: NS records only (NXDOMAIN only, the same NODATA
+    /// response is a referral).
+    pub response_type: u8,
+    /// The SOA record of the zone, used for negative caching
+    pub soa: Option<&'r ResourceRecord<'a>>,
+}
+
+impl<'r, 'a> NegativeResponse<'r, 'a> {
+    /// How long the response may be cached
+    ///
+    /// Responses without the SOA record must not be cached, this returns
+    /// `None` for those (RFC 2308 section 5).
+    pub fn ttl(&self) -> Option<u32> {
+        let record = self.soa?;
+        match record.data {
+            RData::SOA(ref soa) => Some(soa.negative_ttl(record.ttl)),
+            _ => None,
+        }
+    }
+
+    /// The SOA RDATA
+    pub fn soa_data(&self) -> Option<&'r Soa<'a>> {
+        match self.soa?.data {
+            RData::SOA(ref soa) => Some(soa),
+            _ => None,
+        }
+    }
+}
+
+/// The data of a cache entry, see `CacheEntry`
+#[derive(Debug, Clone, Copy)]
+pub enum CachedData<'r, 'a: 'r> {
+    /// A record
+    Record(&'r RData<'a>),
+    /// The name doesn't exist, or has no records of the type, as proven
+    /// by the SOA record of the zone
+    Negative(Negative, &'r Soa<'a>),
+}
+
+/// A record or negative answer to store in a cache
+///
+/// Returned by `Packet::cache_entries`.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheEntry<'r, 'a: 'r> {
+    /// The owner name, for negative entries the name that has no data
+    pub name: &'r Name<'a>,
+    /// The TYPE code, for negative entries the question type
+    ///
+    /// An NXDOMAIN entry applies to every type of the name.
+    pub typ: u16,
+    /// The class of the record
+    pub cls: Class,
+    /// When the entry must be dropped from the cache
+    pub expires: SystemTime,
+    /// The record data or the negative answer
+    pub data: CachedData<'r, 'a>,
+}
+
+/// A delegation to the nameservers of a child zone
+///
+/// Returned by `Packet::referral`.
+#[derive(Debug, Clone)]
+pub struct Referral<'r, 'a: 'r> {
+    /// The delegated zone, the owner of the NS records
+    pub zone: &'r Name<'a>,
+    /// Names of the nameservers of the zone
+    pub nameservers: Vec<&'r Name<'a>>,
+    /// Addresses of the nameservers from the additional section
+    pub glue: Vec<(&'r Name<'a>, IpAddr)>,
+}
+
+impl<'r, 'a> Referral<'r, 'a> {
+    /// Returns the glue addresses of the nameserver
+    pub fn addresses_of<'s>(&'s self, nameserver: &'s Name)
+        -> impl Iterator<Item = IpAddr> + 's
+    {
+        self.glue.iter()
+            .filter(move |&&(name, _)| {
+                name.eq_ignore_case(&nameserver.str_val)
+            })
+            .map(|&(_, ip)| ip)
+    }
+}
+
+/// An endpoint to connect to, see `Packet::service_candidates`
+#[derive(Debug, Clone)]
+pub struct ServiceCandidate<'r, 'a: 'r> {
+    /// SvcPriority, lower is preferred, 0 for the fallback to the origin
+    pub priority: u16,
+    /// The name to connect to, the owner of the record for a `.` target
+    pub target: &'r Name<'a>,
+    /// The `port` parameter, or the default port of the scheme
+    pub port: u16,
+    /// The `alpn` parameter
+    pub alpn: Vec<&'r [u8]>,
+    /// False if the default protocol of the scheme may not be used
+    pub default_alpn: bool,
+    /// Addresses of the target from A and AAAA records, or the hints of
+    /// the record if the response has none
+    pub addresses: Vec<IpAddr>,
+    /// The ECHConfigList of the `ech` parameter
+    pub ech: Option<&'r [u8]>,
+}
+
+impl<'a> Packet<'a> {
+    /// Follows CNAME records in the answers starting at `name`
+    fn cname_target<'r>(&'r self, mut name: &'r Name<'a>) -> &'r Name<'a> {
+        // Each CNAME is followed at most once, so loops end
+        for _ in 0..self.answers.len() {
+            let target = self.answers.iter()
+                .find(|r| r.name.eq_ignore_case(&name.str_val))
+                .and_then(|r| match r.data {
+                    RData::CNAME(ref cname) => Some(&cname.0),
+                    _ => None,
+                });
+            match target {
+                Some(target) => name = target,
+                None => break,
+            }
+        }
+        name
+    }
+
+    /// SVCB and HTTPS records of `name` in the answer and additional
+    /// sections
+    fn svcb_records<'r>(&'r self, name: &Name) -> Vec<&'r Svcb<'a>> {
+        self.answers.iter().chain(self.additional.iter())
+            .filter(|r| r.name.eq_ignore_case(&name.str_val))
+            .filter_map(|r| match r.data {
+                RData::SVCB(ref svcb) => Some(svcb),
+                RData::HTTPS(ref https) => Some(&https.0),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Addresses of `name` in the answer and additional sections
+    fn addresses_of(&self, name: &Name) -> Vec<IpAddr> {
+        let name = self.cname_target(name);
+        self.answers.iter().chain(self.additional.iter())
+            .filter(|r| r.name.eq_ignore_case(&name.str_val))
+            .filter_map(|r| r.data.ip())
+            .collect()
+    }
+
+    /// Endpoints for the service of the first question, in the order to
+    /// try them
+    ///
+    /// Merges the SVCB or HTTPS records of a response with the addresses
+    /// in it as described in RFC 9460 section 3. AliasMode records are
+    /// followed, records the client can't use (see
+    /// `Svcb::is_compatible`) are skipped and the rest are sorted by
+    /// priority. Without usable ServiceMode records the result is the
+    /// origin on `default_port`. An alias to the root, or a chain of
+    /// aliases that doesn't end in the response, yields no candidates.
+    ///
+    /// Records of equal priority keep their order, clients should
+    /// shuffle them. Candidates without addresses need A and AAAA
+    /// queries for their target.
+    pub fn service_candidates(&self, default_port: u16)
+        -> Vec<ServiceCandidate<'_, 'a>>
+    {
+        let mut owner = match self.questions.first() {
+            Some(question) => self.cname_target(&question.qname),
+            None => return Vec::new(),
+        };
+        let mut records = self.svcb_records(owner);
+        for _ in 0..MAX_ALIAS_CHAIN {
+            match records.iter().find(|r| r.is_alias()) {
+                Some(alias) if alias.target.str_val.is_empty() => {
+                    return Vec::new();
+                }
+                Some(alias) => owner = self.cname_target(&alias.target),
+                None => break,
+            }
+            records = self.svcb_records(owner);
+        }
+        if records.iter().any(|r| r.is_alias()) {
+            return Vec::new();
+        }
+        records.retain(|r| r.is_compatible());
+        records.sort_by_key(|r| r.priority);
+        if records.is_empty() {
+            return vec![ServiceCandidate {
+                priority: 0,
+                target: owner,
+                port: default_port,
+                alpn: Vec::new(),
+                default_alpn: true,
+                addresses: self.addresses_of(owner),
+                ech: None,
+            }];
+        }
+        records.into_iter()
+            .map(|record| {
+                let target = if record.target.str_val.is_empty() {
+                    owner
+                } else {
+                    &record.target
+                };
+                let mut addresses = self.addresses_of(target);
+                if addresses.is_empty() {
+                    addresses.extend(record.ipv4hint().into_iter()
+                        .map(IpAddr::V4));
+                    addresses.extend(record.ipv6hint().into_iter()
+                        .map(IpAddr::V6));
+                }
+                ServiceCandidate {
+                    priority: record.priority,
+                    target,
+                    port: record.port().unwrap_or(default_port),
+                    alpn: record.alpn(),
+                    default_alpn: !record.no_default_alpn(),
+                    addresses,
+                    ech: record.ech(),
+                }
+            })
+            .collect()
+    }
+
+    /// Classifies the response by RCODE, header flags and sections
+    ///
+    /// REFUSED is considered lame, as are responses without answers that
+    /// neither refer to a zone closer to the name nor carry the SOA of
+    /// the zone, e.g. upward referrals. Error codes other than NXDOMAIN and
+    /// REFUSED are treated as a server failure.
+    pub fn response_kind(&self) -> ResponseKind {
+        match self.header.response_code {
+            ResponseCode::NoError => {}
+            ResponseCode::NameError => return ResponseKind::NxDomain,
+            ResponseCode::Refused => return ResponseKind::Lame,
+            _ => return ResponseKind::ServFail,
+        }
+        let has_soa = self.nameservers.iter()
+            .any(|r| matches!(r.data, RData::SOA(..)));
+        if !self.answers.is_empty() {
+            ResponseKind::Answer
+        } else if self.referral().is_some() {
+            ResponseKind::Referral
+        } else if self.header.authoritative || has_soa {
+            ResponseKind::NoData
+        } else {
+            ResponseKind::Lame
+        }
+    }
+
+    /// Returns the details if this is an NXDOMAIN or NODATA response
+    ///
+    /// A NODATA response has no answers except maybe CNAME records
+    /// leading to the name that has no data. Without SOA and NS records
+    /// it's only taken as NODATA if it's authoritative.
+    pub fn negative_response(&self) -> Option<NegativeResponse<'_, 'a>> {
+        let kind = match self.header.response_code {
+            ResponseCode::NameError => Negative::NxDomain,
+            ResponseCode::NoError => Negative::NoData,
+            _ => return None,
+        };
+        let soa = self.nameservers.iter()
+            .find(|r| matches!(r.data, RData::SOA(..)));
+        let has_ns = self.nameservers.iter()
+            .any(|r| matches!(r.data, RData::NS(..)));
+        let response_type = match (soa.is_some(), has_ns) {
+            (true, true) => 1,
+            (true, false) => 2,
+            (false, false) => 3,
+            (false, true) => 4,
+        };
+        if kind == Negative::NoData {
+            let only_cnames = self.answers.iter()
+                .all(|r| matches!(r.data, RData::CNAME(..)));
+            if !only_cnames || response_type == 4
+                || (response_type == 3 && !self.header.authoritative)
+            {
+                return None;
+            }
+        }
+        Some(NegativeResponse { kind, response_type, soa })
+    }
+
+    /// The records and negative answers of the response, for a cache
+    ///
+    /// Every record of the answer and authority sections is returned,
+    /// expiring after its TTL counted from `received_at`. For an NXDOMAIN
+    /// or NODATA response with the SOA record (see `negative_response`)
+    /// there is a negative entry for each question, with the negative TTL
+    /// of RFC 2308 section 5. It is for the name at the end of the CNAME
+    /// chain in the answers, if there is one. Records aren't checked
+    /// against the question or zone, see `bailiwick::sanitize` for that.
+    pub fn cache_entries(&self, received_at: SystemTime)
+        -> Vec<CacheEntry<'_, 'a>>
+    {
+        let mut entries: Vec<_> = self.answers.iter()
+            .chain(self.nameservers.iter())
+            .map(|r| CacheEntry {
+                name: &r.name,
+                typ: r.data.raw_type_code(),
+                cls: r.cls,
+                expires: r.expires_at(received_at),
+                data: CachedData::Record(&r.data),
+            })
+            .collect();
+        let negative = match self.negative_response() {
+            Some(negative) => negative,
+            None => return entries,
+        };
+        let (soa_record, soa, ttl) = match (negative.soa, negative.soa_data(),
+                                            negative.ttl()) {
+            (Some(record), Some(soa), Some(ttl)) => (record, soa, ttl),
+            _ => return entries,
+        };
+        for question in &self.questions {
+            entries.push(CacheEntry {
+                name: self.cname_target(&question.qname),
+                typ: question.qtype as u16,
+                cls: soa_record.cls,
+                expires: received_at + Duration::from_secs(ttl as u64),
+                data: CachedData::Negative(negative.kind, soa),
+            });
+        }
+        entries
+    }
+
+    /// Returns the delegation if this response is a referral
+    ///
+    /// A referral is a non-authoritative response without answers that
+    /// carries NS records in the authority section. The NS records must be
+    /// for a zone containing the question name, others are ignored. Only
+    /// glue for the listed nameservers is returned.
+    pub fn referral(&self) -> Option<Referral<'_, 'a>> {
+        if self.header.response_code != ResponseCode::NoError
+            || self.header.authoritative
+            || !self.answers.is_empty()
+        {
+            return None;
+        }
+        let zone = self.nameservers.iter()
+            .filter(|r| matches!(r.data, RData::NS(..)))
+            .map(|r| &r.name)
+            .find(|zone| self.questions.iter()
+                .all(|q| q.qname.is_subdomain_of(&zone.str_val)))?;
+        let nameservers: Vec<_> = self.nameservers.iter()
+            .filter(|r| r.name.eq_ignore_case(&zone.str_val))
+            .filter_map(|r| match r.data {
+                RData::NS(ref ns) => Some(&ns.0),
+                _ => None,
+            })
+            .collect();
+        let glue = self.additional.iter()
+            .filter(|r| nameservers.iter()
+                .any(|ns| ns.eq_ignore_case(&r.name.str_val)))
+            .filter_map(|r| match r.data {
+                RData::A(ref a) => Some((&r.name, IpAddr::V4(a.0))),
+                RData::AAAA(ref aaaa) => Some((&r.name, IpAddr::V6(aaaa.0))),
+                _ => None,
+            })
+            .collect();
+        Some(Referral { zone, nameservers, glue })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use {Builder, Packet, Name, Class, RData, QueryType, QueryClass};
+    use ResponseCode;
+    use rdata::{Ns, Soa};
+    use super::{Negative, ResponseKind, CachedData};
+
+    fn referral(zone: &str) -> Vec<u8> {
+        let mut bld = Builder::new(1, false);
+        bld.question("www.example.com", false, QueryType::A,
+            QueryClass::IN);
+        bld.nameserver(zone, Class::IN,
+            RData::NS(Ns::new(Name::from_string("a.iana-servers.net"))),
+            false, 172800);
+        bld.nameserver(zone, Class::IN,
+            RData::NS(Ns::new(Name::from_string("b.iana-servers.net"))),
+            false, 172800);
+        bld.additional("a.iana-servers.net", Class::IN,
+            RData::A(Ipv4Addr::new(199, 43, 135, 53).into()), false, 172800);
+        bld.additional("www.example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 172800);
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn delegation() {
+        let data = referral("example.com");
+        let packet = Packet::parse(&data).unwrap();
+        let referral = packet.referral().unwrap();
+        assert!(*referral.zone == "example.com");
+        assert_eq!(referral.nameservers.len(), 2);
+        assert_eq!(referral.glue.len(), 1);
+        let ns = referral.nameservers[0];
+        assert_eq!(referral.addresses_of(ns).collect::<Vec<_>>(),
+            [IpAddr::V4(Ipv4Addr::new(199, 43, 135, 53))]);
+        assert_eq!(referral.addresses_of(referral.nameservers[1]).count(), 0);
+    }
+
+    #[test]
+    fn unrelated_zone() {
+        let data = referral("example.org");
+        let packet = Packet::parse(&data).unwrap();
+        assert!(packet.referral().is_none());
+    }
+
+    #[test]
+    fn response_kinds() {
+        let data = referral("example.com");
+        assert_eq!(Packet::parse(&data).unwrap().response_kind(),
+            ResponseKind::Referral);
+        let data = referral("example.org");
+        assert_eq!(Packet::parse(&data).unwrap().response_kind(),
+            ResponseKind::Lame);
+
+        let response = |code, answer: bool, soa: bool| {
+            let mut bld = Builder::new(1, false);
+            bld.question("www.example.com", false, QueryType::A,
+                QueryClass::IN);
+            bld.response_code(code);
+            if answer {
+                bld.answer("www.example.com", Class::IN,
+                    RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+            }
+            if soa {
+                bld.nameserver("example.com", Class::IN, RData::SOA(Soa::new(
+                    Name::from_string("ns.example.com"),
+                    Name::from_string("admin.example.com"),
+                    1, 7200, 3600, 1209600, 300)), false, 300);
+            }
+            bld.build().unwrap()
+        };
+        let kind = |data: Vec<u8>| Packet::parse(&data).unwrap()
+            .response_kind();
+        assert_eq!(kind(response(ResponseCode::NoError, true, false)),
+            ResponseKind::Answer);
+        assert_eq!(kind(response(ResponseCode::NoError, false, true)),
+            ResponseKind::NoData);
+        assert_eq!(kind(response(ResponseCode::NoError, false, false)),
+            ResponseKind::Lame);
+        assert_eq!(kind(response(ResponseCode::NameError, false, true)),
+            ResponseKind::NxDomain);
+        assert_eq!(kind(response(ResponseCode::Refused, false, false)),
+            ResponseKind::Lame);
+        assert_eq!(kind(response(ResponseCode::ServerFailure, false, false)),
+            ResponseKind::ServFail);
+    }
+
+    #[test]
+    fn negative_responses() {
+        let response = |code, ns: bool, aa: bool| {
+            let mut bld = Builder::new(1, false);
+            bld.question("www.example.com", false, QueryType::A,
+                QueryClass::IN);
+            bld.response_code(code);
+            bld.nameserver("example.com", Class::IN, RData::SOA(Soa::new(
+                Name::from_string("ns.example.com"),
+                Name::from_string("admin.example.com"),
+                1, 7200, 3600, 1209600, 300)), false, 600);
+            if ns {
+                bld.nameserver("example.com", Class::IN,
+                    RData::NS(Ns::new(Name::from_string("ns.example.com"))),
+                    false, 600);
+            }
+            let mut data = bld.build().unwrap();
+            if aa {
+                data[2] |= 0x04;
+            }
+            data
+        };
+
+        let data = response(ResponseCode::NameError, true, true);
+        let packet = Packet::parse(&data).unwrap();
+        let negative = packet.negative_response().unwrap();
+        assert_eq!(negative.kind, Negative::NxDomain);
+        assert_eq!(negative.response_type, 1);
+        assert_eq!(negative.ttl(), Some(300));
+        assert_eq!(negative.soa_data().unwrap().serial, 1);
+
+        let data = response(ResponseCode::NoError, false, true);
+        let packet = Packet::parse(&data).unwrap();
+        let negative = packet.negative_response().unwrap();
+        assert_eq!(negative.kind, Negative::NoData);
+        assert_eq!(negative.response_type, 2);
+
+        let data = referral("example.com");
+        assert!(Packet::parse(&data).unwrap().negative_response().is_none());
+        let data = response(ResponseCode::ServerFailure, false, false);
+        assert!(Packet::parse(&data).unwrap().negative_response().is_none());
+    }
+
+    #[test]
+    fn cache_entries() {
+        use std::time::{Duration, UNIX_EPOCH};
+        use rdata::Cname;
+
+        let mut bld = Builder::new(1, false);
+        bld.question("www.example.com", false, QueryType::AAAA,
+            QueryClass::IN);
+        bld.response_code(ResponseCode::NameError);
+        bld.answer("www.example.com", Class::IN,
+            RData::CNAME(Cname(Name::from_string("web.example.com"))),
+            false, 3600);
+        bld.nameserver("example.com", Class::IN, RData::SOA(Soa::new(
+            Name::from_string("ns.example.com"),
+            Name::from_string("admin.example.com"),
+            1, 7200, 3600, 1209600, 300)), false, 600);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+
+        let received = UNIX_EPOCH + Duration::from_secs(1000);
+        let entries = packet.cache_entries(received);
+        assert_eq!(entries.len(), 3);
+        assert!(*entries[0].name == "www.example.com");
+        assert_eq!(entries[0].typ, 5);
+        assert_eq!(entries[0].expires, received + Duration::from_secs(3600));
+        assert!(matches!(entries[0].data, CachedData::Record(RData::CNAME(_))));
+        assert!(*entries[1].name == "example.com");
+        assert_eq!(entries[1].typ, 6);
+
+        let negative = &entries[2];
+        assert!(*negative.name == "web.example.com");
+        assert_eq!(negative.typ, QueryType::AAAA as u16);
+        assert_eq!(negative.cls, Class::IN);
+        assert_eq!(negative.expires, received + Duration::from_secs(300));
+        match negative.data {
+            CachedData::Negative(Negative::NxDomain, soa) => {
+                assert_eq!(soa.serial, 1);
+            }
+            ref x => panic!("Wrong data {:?}", x),
+        }
+
+        let data = referral("example.com");
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.cache_entries(received).len(), 2);
+    }
+
+    #[test]
+    fn service_candidates() {
+        use rdata::{svcb, Https, Svcb};
+
+        let service = |priority, target| Svcb::new(priority,
+            Name::from_string(target));
+        let https = |record: Svcb<'static>| RData::HTTPS(Https(record));
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QueryType::HTTPS, QueryClass::IN);
+        bld.answer("example.com", Class::IN,
+            https(service(0, "svc.example.net")), false, 300);
+        bld.answer("svc.example.net", Class::IN, https(service(2, "")
+            .with_param(svcb::ALPN, b"\x02h3")
+            .with_param(svcb::PORT, b"\x20\xfb")
+            .with_param(svcb::IPV4HINT, b"\xc0\x00\x02\x01")), false, 300);
+        bld.answer("svc.example.net", Class::IN,
+            https(service(1, "pool.example.net")
+            .with_param(svcb::ALPN, b"\x02h2")
+            .with_param(svcb::NO_DEFAULT_ALPN, b"")
+            .with_param(svcb::ECH, b"\x00\x00")), false, 300);
+        bld.answer("svc.example.net", Class::IN, https(service(1, "")
+            .with_param(svcb::MANDATORY, b"\x00\x63")
+            .with_param(99, b"")), false, 300);
+        bld.additional("pool.example.net", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 10).into()), false, 300);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+
+        let candidates = packet.service_candidates(443);
+        assert_eq!(candidates.len(), 2);
+        assert!(*candidates[0].target == "pool.example.net");
+        assert_eq!(candidates[0].port, 443);
+        assert_eq!(candidates[0].alpn, [&b"h2"[..]]);
+        assert!(!candidates[0].default_alpn);
+        assert_eq!(candidates[0].addresses,
+            [IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))]);
+        assert_eq!(candidates[0].ech, Some(&b"\x00\x00"[..]));
+        assert!(*candidates[1].target == "svc.example.net");
+        assert_eq!(candidates[1].priority, 2);
+        assert_eq!(candidates[1].port, 8443);
+        assert!(candidates[1].default_alpn);
+        assert_eq!(candidates[1].addresses,
+            [IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))]);
+
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QueryType::HTTPS, QueryClass::IN);
+        bld.answer("example.com", Class::IN, RData::A(
+            Ipv4Addr::new(192, 0, 2, 7).into()), false, 300);
+        let data = bld.build().unwrap();
+        let packet = Packet::parse(&data).unwrap();
+        let candidates = packet.service_candidates(443);
+        assert_eq!(candidates.len(), 1);
+        assert!(*candidates[0].target == "example.com");
+        assert_eq!(candidates[0].addresses,
+            [IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7))]);
+
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QueryType::HTTPS, QueryClass::IN);
+        bld.answer("example.com", Class::IN, https(service(0, "")),
+            false, 300);
+        let data = bld.build().unwrap();
+        assert!(Packet::parse(&data).unwrap().service_candidates(443)
+            .is_empty());
+    }
+}
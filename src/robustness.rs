@@ -0,0 +1,126 @@
+//! Checks that parsing arbitrary bytes never panics
+//!
+//! Every function taking packet bytes from the network must return an
+//! error rather than panic, index out of bounds or overflow. This runs
+//! them on truncations and byte mutations of real packets.
+use std::panic;
+
+use {Packet, ParseOptions};
+
+const PACKETS: &[&[u8]] = &[
+    // www.skype.com A, with authority and additional records
+    b"\x4a\xf0\x81\x80\x00\x01\x00\x01\x00\x01\x00\x01\
+      \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+      \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+      \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+      \xc0\x42\x00\x02\x00\x01\x00\x01\xd5\xd3\x00\x11\
+      \x01g\x0cgtld-servers\xc0\x42\
+      \x01a\xc0\x55\x00\x01\x00\x01\x00\x00\xa3\x1c\
+      \x00\x04\xc0\x05\x06\x1e",
+    // SOA in the authority section
+    b"\x9f\xc5\x85\x83\x00\x01\x00\x00\x00\x01\x00\x00\
+      \x0edlkfjkdjdslfkj\x07youtube\x03com\x00\x00\x01\x00\x01\
+      \xc0\x1b\x00\x06\x00\x01\x00\x00\x2a\x30\x00\x1e\xc0\x1b\
+      \x05admin\xc0\x1b\x77\xed\x2a\x73\x00\x00\x51\x80\x00\x00\
+      \x0e\x10\x00\x00\x3a\x80\x00\x00\x2a\x30",
+    // MX
+    b"\xe3\xe8\x81\x80\x00\x01\x00\x02\x00\x00\x00\x00\
+      \x05gmail\x03com\x00\x00\x0f\x00\x01\xc0\x0c\x00\x0f\x00\x01\
+      \x00\x00\x04|\x00\x1b\x00\x05\rgmail-smtp-in\x01l\x06google\xc0\
+      \x12\xc0\x0c\x00\x0f\x00\x01\x00\x00\x04|\x00\t\x00\
+      \n\x04alt1\xc0)",
+    // SRV
+    b"[\xd9\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+      \x0c_xmpp-server\x04_tcp\x05gmail\x03com\x00\x00!\x00\x01\
+      \xc0\x0c\x00!\x00\x01\x00\x00\x03\x84\x00 \x00\x05\x00\x00\
+      \x14\x95\x0bxmpp-server\x01l\x06google\x03com\x00",
+    // TXT
+    b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+      \x08facebook\x03com\x00\x00\x10\x00\x01\
+      \xc0\x0c\x00\x10\x00\x01\x00\x01\x51\x3d\x00\x23\
+      \x15v=spf1 redirect=_spf.\x0cfacebook.com",
+    // HINFO and AAAA, with an OPT record
+    b"\x8e\x6c\x81\x80\x00\x01\x00\x02\x00\x00\x00\x01\
+      \x0acloudflare\x03com\x00\x00\xff\x00\x01\
+      \xc0\x0c\x00\x0d\x00\x01\x00\x00\x0e\x10\x00\x09\
+      \x07RFC8482\x00\
+      \xc0\x0c\x00\x1c\x00\x01\x00\x00\x0e\x10\x00\x10\
+      \x20\x01\x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\
+      \x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x00",
+];
+
+fn parse_all(data: &[u8]) {
+    let _ = Packet::parse(data);
+    let _ = Packet::parse_tcp(data);
+    let _ = Packet::parse_with_options(data, &ParseOptions {
+        skip_authority: true,
+        skip_additional: true,
+        ..ParseOptions::default()
+    });
+    let _ = Packet::parse_with_options(data, &ParseOptions {
+        strict_rdata: true,
+        skip_binary_labels: true,
+        ..ParseOptions::default()
+    });
+    let _ = Packet::parse_salvage(data);
+    let _ = Packet::validate(data);
+}
+
+fn check(data: &[u8]) {
+    let result = panic::catch_unwind(|| parse_all(data));
+    assert!(result.is_ok(), "panicked on {:?}", data);
+}
+
+#[test]
+fn samples_parse() {
+    for data in PACKETS {
+        Packet::parse(data).unwrap();
+    }
+}
+
+#[test]
+fn truncations() {
+    for data in PACKETS {
+        for len in 0..data.len() {
+            check(&data[..len]);
+        }
+    }
+}
+
+#[test]
+fn mutations() {
+    for data in PACKETS {
+        let mut buf = data.to_vec();
+        for pos in 0..buf.len() {
+            let orig = buf[pos];
+            for &byte in &[0x00, 0x01, 0x3f, 0x40, 0x80, 0xc0, 0xff,
+                           orig ^ 1, orig.wrapping_add(1)]
+            {
+                buf[pos] = byte;
+                check(&buf);
+            }
+            buf[pos] = orig;
+        }
+    }
+}
+
+#[test]
+fn random_records() {
+    // Random bytes after a header announcing records in every section
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    for _ in 0..20000 {
+        let mut buf = b"\x00\x00\x81\x80\x00\x01\x00\x02\x00\x02\x00\x02"
+            .to_vec();
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let len = (seed % 96) as usize;
+        for _ in 0..len {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            buf.push(seed as u8);
+        }
+        check(&buf);
+    }
+}
@@ -0,0 +1,187 @@
+use {Header, Packet, Question, ResourceRecord, Error, ParseOptions, Section};
+use parser::{Budget, OPT_RR_START, expanded_names_len};
+use parser::{parse_question, parse_record, parse_opt_record};
+use parser::{skip_question, skip_record};
+use rdata::opt::Record as Opt;
+
+/// An error found by `Packet::parse_salvage`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SalvageError {
+    /// Offset of the entry that failed to parse
+    pub offset: usize,
+    /// Section of the entry, `None` for the header
+    pub section: Option<Section>,
+    /// The error itself
+    pub error: Error,
+}
+
+/// Whatever could be decoded from a possibly malformed packet
+///
+/// Returned by `Packet::parse_salvage`. Entries that failed to parse are
+/// left out of the sections and described in `errors` instead.
+#[derive(Debug)]
+#[allow(missing_docs)]  // same fields as in Packet
+pub struct SalvagedPacket<'a> {
+    /// The header, `None` if the packet is shorter than a header
+    pub header: Option<Header>,
+    pub questions: Vec<Question<'a>>,
+    pub answers: Vec<ResourceRecord<'a>>,
+    pub nameservers: Vec<ResourceRecord<'a>>,
+    pub additional: Vec<ResourceRecord<'a>>,
+    pub opt: Option<Opt<'a>>,
+    /// Errors in the order they were encountered
+    pub errors: Vec<SalvageError>,
+}
+
+impl<'a> SalvagedPacket<'a> {
+    /// Returns true if the whole packet was decoded without errors
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn error(&mut self, offset: usize, section: Option<Section>,
+        error: Error)
+    {
+        self.errors.push(SalvageError { offset, section, error });
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Parse as much of a packet as possible
+    ///
+    /// This never fails. When an entry is malformed but its length is
+    /// known (e.g. it has an unsupported class or bad RDATA) it is skipped
+    /// and the following entries are parsed as usual. When the boundary
+    /// of an entry can't be determined, parsing stops there. Every
+    /// problem is reported in `SalvagedPacket::errors` with its offset,
+    /// which makes this suitable for inspecting captured traffic.
+    pub fn parse_salvage(data: &'a [u8]) -> SalvagedPacket<'a> {
+        let mut result = SalvagedPacket {
+            header: None,
+            questions: Vec::new(),
+            answers: Vec::new(),
+            nameservers: Vec::new(),
+            additional: Vec::new(),
+            opt: None,
+            errors: Vec::new(),
+        };
+        let header = match Header::parse(data) {
+            Ok(header) => header,
+            Err(e) => {
+                result.error(0, None, e);
+                return result;
+            }
+        };
+        result.header = Some(header);
+        let mut budget = Budget::new(&ParseOptions::default(), data);
+        let mut offset = Header::size();
+        for _ in 0..header.questions {
+            let start = offset;
+            if let Err(e) = skip_question(data, &mut offset) {
+                result.error(start, Some(Section::Question), e);
+                return result;
+            }
+            match parse_question(data, &mut start.clone()) {
+                Ok(question) => {
+                    if let Err(e) = budget.spend(question.qname.str_val.len()) {
+                        result.error(start, Some(Section::Question), e);
+                        return result;
+                    }
+                    result.questions.push(question);
+                }
+                Err(e) => result.error(start, Some(Section::Question), e),
+            }
+        }
+        let sections = [
+            (Section::Answer, header.answers),
+            (Section::Authority, header.nameservers),
+            (Section::Additional, header.additional),
+        ];
+        for &(section, count) in &sections {
+            for _ in 0..count {
+                let start = offset;
+                if let Err(e) = skip_record(data, &mut offset) {
+                    result.error(start, Some(section), e);
+                    return result;
+                }
+                if section == Section::Additional &&
+                    data[start..start+3] == OPT_RR_START
+                {
+                    if result.opt.is_some() {
+                        result.error(start, Some(section),
+                            Error::AdditionalOPT);
+                        continue;
+                    }
+                    match parse_opt_record(data, &mut start.clone()) {
+                        Ok(opt) => result.opt = Some(opt),
+                        Err(e) => result.error(start, Some(section), e),
+                    }
+                    continue;
+                }
+                let record = match parse_record(data, &mut start.clone(),
+                                                None)
+                {
+                    Ok(record) => record,
+                    Err(e) => {
+                        result.error(start, Some(section), e);
+                        continue;
+                    }
+                };
+                if let Err(e) = budget.spend(expanded_names_len(&record)) {
+                    result.error(start, Some(section), e);
+                    return result;
+                }
+                match section {
+                    Section::Answer => result.answers.push(record),
+                    Section::Authority => result.nameservers.push(record),
+                    _ => result.additional.push(record),
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Packet, Error, Section, RData};
+    use super::SalvageError;
+
+    const RESPONSE: &[u8] = b"\x06%\x81\x80\x00\x01\x00\x02\x00\x00\x00\x00\
+        \x07example\x03com\x00\x00\x01\x00\x01\
+        \xc0\x0c\xff\x00\x00\x01\x00\x00\x04\xf8\x00\x04\x5d\xb8\xd8\x22\
+        \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\x00\x04\x5d\xb8\xd8\x23";
+
+    #[test]
+    fn skip_bad_record() {
+        // First answer has type 0xFF00
+        assert!(Packet::parse(RESPONSE).is_err());
+        let packet = Packet::parse_salvage(RESPONSE);
+        assert_eq!(packet.header.unwrap().id, 1573);
+        assert_eq!(packet.questions.len(), 1);
+        assert_eq!(packet.answers.len(), 1);
+        assert!(matches!(packet.answers[0].data, RData::A(..)));
+        assert_eq!(packet.errors, vec![SalvageError {
+            offset: 29,
+            section: Some(Section::Answer),
+            error: Error::InvalidType(0xFF00),
+        }]);
+    }
+
+    #[test]
+    fn truncated() {
+        let packet = Packet::parse_salvage(&RESPONSE[..RESPONSE.len()-2]);
+        assert_eq!(packet.answers.len(), 0);
+        assert_eq!(packet.errors.len(), 2);
+        assert_eq!(packet.errors[1], SalvageError {
+            offset: 45,
+            section: Some(Section::Answer),
+            error: Error::UnexpectedEOF,
+        });
+
+        let packet = Packet::parse_salvage(&RESPONSE[..5]);
+        assert!(packet.header.is_none());
+        assert!(!packet.is_complete());
+        assert_eq!(packet.errors[0].section, None);
+    }
+}
@@ -0,0 +1,268 @@
+//! Parser for DNS stamps (`sdns://` URIs)
+//!
+//! A stamp encodes everything needed to connect to a DNSCrypt, DoH, DoT
+//! or DoQ resolver. See <https://dnscrypt.info/stamps-specifications>.
+
+use std::str::FromStr;
+
+use byteorder::{LittleEndian, ByteOrder};
+
+use base64;
+use {Error};
+
+const SCHEME: &str = "sdns://";
+
+/// The protocol a stamp describes
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Protocol {
+    /// Plain DNS over UDP/TCP
+    Plain,
+    /// DNSCrypt
+    DnsCrypt,
+    /// DNS over HTTPS
+    DoH,
+    /// DNS over TLS
+    DoT,
+    /// DNS over QUIC
+    DoQ,
+    /// Oblivious DoH target
+    ODoHTarget,
+    /// Anonymized DNSCrypt relay
+    DnsCryptRelay,
+    /// Oblivious DoH relay
+    ODoHRelay,
+}
+
+/// Informal properties announced by the resolver
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Props(pub u64);
+
+impl Props {
+    /// The resolver does DNSSEC validation
+    pub fn dnssec(&self) -> bool { self.0 & 1 != 0 }
+    /// The resolver doesn't keep logs
+    pub fn no_logs(&self) -> bool { self.0 & 2 != 0 }
+    /// The resolver doesn't intentionally block domains
+    pub fn no_filter(&self) -> bool { self.0 & 4 != 0 }
+}
+
+/// A decoded DNS stamp
+///
+/// Fields not used by the stamp's protocol are left empty.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Stamp {
+    /// Protocol of the server
+    pub protocol: Protocol,
+    /// Properties, always empty for relays
+    pub props: Props,
+    /// IP address with an optional port, may be empty for DoH and DoT
+    pub address: String,
+    /// DNSCrypt provider public key
+    pub public_key: Vec<u8>,
+    /// SHA-256 hashes of TBS certificates in the TLS chain
+    pub hashes: Vec<Vec<u8>>,
+    /// DNSCrypt provider name or TLS server host name
+    pub provider_name: String,
+    /// HTTP path for DoH and ODoH
+    pub path: String,
+    /// Resolvers to use for looking up the host name
+    pub bootstrap: Vec<String>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.pos + len > self.data.len() {
+            return Err(Error::InvalidStamp);
+        }
+        let result = &self.data[self.pos..self.pos+len];
+        self.pos += len;
+        Ok(result)
+    }
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.read(1)?[0])
+    }
+    fn props(&mut self) -> Result<Props, Error> {
+        Ok(Props(LittleEndian::read_u64(self.read(8)?)))
+    }
+    fn lp(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.byte()? as usize;
+        self.read(len)
+    }
+    fn lp_string(&mut self) -> Result<String, Error> {
+        String::from_utf8(self.lp()?.to_vec()).map_err(|_| Error::InvalidStamp)
+    }
+    fn vlp(&mut self) -> Result<Vec<&'a [u8]>, Error> {
+        let mut items = Vec::new();
+        loop {
+            let len = self.byte()?;
+            let item = self.read((len & 0x7F) as usize)?;
+            if !item.is_empty() {
+                items.push(item);
+            }
+            if len & 0x80 == 0 {
+                return Ok(items);
+            }
+        }
+    }
+    fn optional_vlp_strings(&mut self) -> Result<Vec<String>, Error> {
+        if self.pos == self.data.len() {
+            return Ok(Vec::new());
+        }
+        self.vlp()?.into_iter()
+            .map(|x| String::from_utf8(x.to_vec())
+                .map_err(|_| Error::InvalidStamp))
+            .collect()
+    }
+    fn finish(&self) -> Result<(), Error> {
+        if self.pos != self.data.len() {
+            return Err(Error::InvalidStamp);
+        }
+        Ok(())
+    }
+}
+
+impl Stamp {
+    /// Parses an `sdns://` URI
+    pub fn parse(uri: &str) -> Result<Stamp, Error> {
+        if !uri.starts_with(SCHEME) {
+            return Err(Error::InvalidStamp);
+        }
+        let data = base64::decode_url(&uri[SCHEME.len()..])
+            .ok_or(Error::InvalidBase64)?;
+        Stamp::parse_payload(&data)
+    }
+
+    /// Parses the binary payload of a stamp
+    pub fn parse_payload(data: &[u8]) -> Result<Stamp, Error> {
+        use self::Protocol::*;
+        let mut rd = Reader { data, pos: 0 };
+        let protocol = match rd.byte()? {
+            0x00 => Plain,
+            0x01 => DnsCrypt,
+            0x02 => DoH,
+            0x03 => DoT,
+            0x04 => DoQ,
+            0x05 => ODoHTarget,
+            0x81 => DnsCryptRelay,
+            0x85 => ODoHRelay,
+            _ => return Err(Error::InvalidStamp),
+        };
+        let mut stamp = Stamp {
+            protocol,
+            props: Props::default(),
+            address: String::new(),
+            public_key: Vec::new(),
+            hashes: Vec::new(),
+            provider_name: String::new(),
+            path: String::new(),
+            bootstrap: Vec::new(),
+        };
+        if protocol != DnsCryptRelay {
+            stamp.props = rd.props()?;
+        }
+        if protocol != ODoHTarget {
+            stamp.address = rd.lp_string()?;
+        }
+        match protocol {
+            Plain | DnsCryptRelay => {}
+            DnsCrypt => {
+                stamp.public_key = rd.lp()?.to_vec();
+                stamp.provider_name = rd.lp_string()?;
+            }
+            DoH | ODoHRelay => {
+                stamp.hashes = rd.vlp()?.into_iter()
+                    .map(|x| x.to_vec()).collect();
+                stamp.provider_name = rd.lp_string()?;
+                stamp.path = rd.lp_string()?;
+                stamp.bootstrap = rd.optional_vlp_strings()?;
+            }
+            DoT | DoQ => {
+                stamp.hashes = rd.vlp()?.into_iter()
+                    .map(|x| x.to_vec()).collect();
+                stamp.provider_name = rd.lp_string()?;
+                stamp.bootstrap = rd.optional_vlp_strings()?;
+            }
+            ODoHTarget => {
+                stamp.provider_name = rd.lp_string()?;
+                stamp.path = rd.lp_string()?;
+            }
+        }
+        rd.finish()?;
+        Ok(stamp)
+    }
+}
+
+impl FromStr for Stamp {
+    type Err = Error;
+    fn from_str(uri: &str) -> Result<Stamp, Error> {
+        Stamp::parse(uri)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use Error;
+    use super::*;
+
+    #[test]
+    fn doh() {
+        let stamp: Stamp = "sdns://AgcAAAAAAAAABzEuMC4wLjEAEmRucy5jbG91ZG\
+            ZsYXJlLmNvbQovZG5zLXF1ZXJ5".parse().unwrap();
+        assert_eq!(stamp.protocol, Protocol::DoH);
+        assert!(stamp.props.dnssec());
+        assert!(stamp.props.no_logs());
+        assert!(stamp.props.no_filter());
+        assert_eq!(stamp.address, "1.0.0.1");
+        assert!(stamp.hashes.is_empty());
+        assert_eq!(stamp.provider_name, "dns.cloudflare.com");
+        assert_eq!(stamp.path, "/dns-query");
+    }
+
+    #[test]
+    fn dnscrypt() {
+        let stamp = Stamp::parse("sdns://AQMAAAAAAAAAElsyMDAxOmRiODo6MV06O\
+            DQ0MyARERERERERERERERERERERERERERERERERERERERERERsyLmRuc2NyeXB0\
+            LWNlcnQuZXhhbXBsZS5jb20").unwrap();
+        assert_eq!(stamp.protocol, Protocol::DnsCrypt);
+        assert_eq!(stamp.props, Props(3));
+        assert_eq!(stamp.address, "[2001:db8::1]:8443");
+        assert_eq!(stamp.public_key, vec![0x11; 32]);
+        assert_eq!(stamp.provider_name, "2.dnscrypt-cert.example.com");
+    }
+
+    #[test]
+    fn dot_with_hashes_and_bootstrap() {
+        let stamp = Stamp::parse("sdns://AwAAAAAAAAAAAKCqqqqqqqqqqqqqqqqqqq\
+            qqqqqqqqqqqqqqqqqqqqqqqiC7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7\
+            u7u7uw9kb3QuZXhhbXBsZS5jb22HOS45LjkuOQcxLjEuMS4x")
+            .unwrap();
+        assert_eq!(stamp.protocol, Protocol::DoT);
+        assert_eq!(stamp.address, "");
+        assert_eq!(stamp.hashes, vec![vec![0xaa; 32], vec![0xbb; 32]]);
+        assert_eq!(stamp.provider_name, "dot.example.com");
+        assert_eq!(stamp.bootstrap, vec!["9.9.9.9", "1.1.1.1"]);
+    }
+
+    #[test]
+    fn relay() {
+        let stamp = Stamp::parse("sdns://gQ0xOTIuMC4yLjE6NDQz").unwrap();
+        assert_eq!(stamp.protocol, Protocol::DnsCryptRelay);
+        assert_eq!(stamp.address, "192.0.2.1:443");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(Stamp::parse("https://example.com"),
+            Err(Error::InvalidStamp)));
+        assert!(matches!(Stamp::parse("sdns://!!"),
+            Err(Error::InvalidBase64)));
+        // truncated DoH stamp
+        assert!(matches!(Stamp::parse("sdns://AgcAAAAAAAAABzEuMC4wLjEA"),
+            Err(Error::InvalidStamp)));
+    }
+}
@@ -0,0 +1,154 @@
+//! proptest strategies generating well-formed DNS data
+//!
+//! Names are lowercase and made of letters, digits and hyphens, so they
+//! survive a round trip through the `Builder` and the parser unchanged.
+//! Generated values own their data and are `'static`.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use proptest::prelude::*;
+use proptest::collection::vec;
+
+use {Builder, Class, Name, QueryClass, QueryType, Question, RData};
+use rdata::{A, Aaaa, Cname, Hinfo, Mx, Ns, Ptr, Soa, Srv, Txt};
+
+/// A single label of 1 to 20 characters, not starting or ending with `-`
+pub fn label() -> impl Strategy<Value = String> {
+    "[a-z0-9]([a-z0-9-]{0,18}[a-z0-9])?"
+}
+
+/// A name of 1 to 4 labels, without the trailing dot
+pub fn name() -> impl Strategy<Value = String> {
+    vec(label(), 1..=4).prop_map(|labels| labels.join("."))
+}
+
+/// Any class, including ones without a mnemonic
+///
+/// Codes are below 0x8000, the top bit is the mDNS cache-flush bit.
+pub fn class() -> impl Strategy<Value = Class> {
+    prop_oneof![
+        Just(Class::IN), Just(Class::CS), Just(Class::CH), Just(Class::HS),
+        Just(Class::NONE), Just(Class::ANY),
+        (0..0x8000u16).prop_map(Class::from),
+    ]
+}
+
+/// Any query class
+pub fn query_class() -> impl Strategy<Value = QueryClass> {
+    prop_oneof![
+        Just(QueryClass::IN), Just(QueryClass::CS), Just(QueryClass::CH),
+        Just(QueryClass::HS), Just(QueryClass::None), Just(QueryClass::Any),
+    ]
+}
+
+/// Any query type
+pub fn query_type() -> impl Strategy<Value = QueryType> {
+    use QueryType::*;
+    proptest::sample::select(vec![A, NS, MF, CNAME, SOA, MB, MG, MR, NULL,
+        WKS, PTR, HINFO, MINFO, MX, TXT, AAAA, SRV, IXFR, AXFR, MAILB, MAILA,
+        All])
+}
+
+/// A question with a name from `name()`
+pub fn question() -> impl Strategy<Value = Question<'static>> {
+    (name(), any::<bool>(), query_type(), query_class())
+        .prop_map(|(qname, prefer_unicast, qtype, qclass)| Question {
+            qname: Name::from_owned(qname),
+            prefer_unicast,
+            qtype,
+            qclass,
+        })
+}
+
+fn owned_name() -> impl Strategy<Value = Name<'static>> {
+    name().prop_map(Name::from_owned)
+}
+
+/// RDATA of any owned type the crate parses
+pub fn rdata() -> impl Strategy<Value = RData<'static>> {
+    prop_oneof![
+        any::<u32>().prop_map(|x| RData::A(A(Ipv4Addr::from(x)))),
+        any::<u128>().prop_map(|x| RData::AAAA(Aaaa(Ipv6Addr::from(x)))),
+        owned_name().prop_map(|x| RData::CNAME(Cname(x))),
+        owned_name().prop_map(|x| RData::NS(Ns(x))),
+        owned_name().prop_map(|x| RData::PTR(Ptr(x))),
+        (any::<u16>(), owned_name())
+            .prop_map(|(pref, x)| RData::MX(Mx::new(pref, x))),
+        (any::<[u16; 3]>(), owned_name())
+            .prop_map(|(p, x)| RData::SRV(Srv::new(p[0], p[1], p[2], x))),
+        (owned_name(), owned_name(), any::<[u32; 5]>())
+            .prop_map(|(ns, mbox, t)| RData::SOA(Soa {
+                primary_ns: ns,
+                mailbox: mbox,
+                serial: t[0],
+                refresh: t[1],
+                retry: t[2],
+                expire: t[3],
+                minimum_ttl: t[4],
+            })),
+        vec(any::<u8>(), 0..600).prop_map(|x| RData::TXT(Txt::from_bytes(&x))),
+        (vec(any::<u8>(), 0..256), vec(any::<u8>(), 0..256))
+            .prop_map(|(cpu, os)| RData::HINFO(Hinfo::new(cpu, os))),
+    ]
+}
+
+/// Owner name, class, TTL and RDATA of a record
+pub fn record() -> impl Strategy<Value = (String, Class, u32, RData<'static>)>
+{
+    (name(), class(), 0..=i32::MAX as u32, rdata())
+}
+
+/// The wire bytes of a packet made by the `Builder`
+///
+/// Has up to 2 questions and up to 4 records in each of the other
+/// sections, parse it with `Packet::parse`.
+pub fn packet() -> impl Strategy<Value = Vec<u8>> {
+    (any::<u16>(), any::<bool>(), vec(question(), 0..=2),
+     vec(record(), 0..=4), vec(record(), 0..=4), vec(record(), 0..=4))
+        .prop_map(|(id, recursion, questions, an, ns, ar)| {
+            let names = [&an, &ns, &ar].iter()
+                .map(|s| s.iter().map(|r| r.0.clone()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let qnames = questions.iter()
+                .map(|q| q.qname.to_string()).collect::<Vec<_>>();
+            let mut builder = Builder::new(id, recursion);
+            for (q, qname) in questions.iter().zip(&qnames) {
+                builder.question(qname, q.prefer_unicast, q.qtype, q.qclass);
+            }
+            for (name, (_, cls, ttl, data)) in names[0].iter().zip(an) {
+                builder.answer(name, cls, data, false, ttl);
+            }
+            for (name, (_, cls, ttl, data)) in names[1].iter().zip(ns) {
+                builder.nameserver(name, cls, data, false, ttl);
+            }
+            for (name, (_, cls, ttl, data)) in names[2].iter().zip(ar) {
+                builder.additional(name, cls, data, false, ttl);
+            }
+            builder.build().expect("packet is not framed")
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use {Packet, Name};
+    use super::{name, packet};
+
+    proptest! {
+        #[test]
+        fn names_round_trip(name in name()) {
+            let bytes = Name::from_string(&name).to_bytes();
+            let parsed = Name::scan(&bytes, &bytes).unwrap();
+            prop_assert_eq!(parsed.to_string(), name);
+        }
+
+        #[test]
+        fn packets_parse(data in packet()) {
+            let packet = Packet::parse(&data).unwrap();
+            prop_assert_eq!(packet.questions.len(),
+                packet.header.questions as usize);
+            prop_assert_eq!(packet.answers.len(),
+                packet.header.answers as usize);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use byteorder::{BigEndian, ByteOrder};
+
+/// Splits a TCP byte stream into DNS messages
+///
+/// Feed data as it is read from the socket with `push` and take complete
+/// messages out with `next_message`. Data may arrive in chunks of any size,
+/// messages split across reads and multiple messages in one read are both
+/// handled. The returned messages have the two-byte length prefix
+/// stripped and can be parsed with `Packet::parse`.
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    buf: Vec<u8>,
+}
+
+impl StreamDecoder {
+    /// Creates a decoder with an empty buffer
+    pub fn new() -> StreamDecoder {
+        StreamDecoder { buf: Vec::new() }
+    }
+
+    /// Appends bytes received from the stream
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns next complete message if there is one in the buffer
+    pub fn next_message(&mut self) -> Option<Vec<u8>> {
+        let len = self.pending_length()?;
+        if self.buf.len() < len + 2 {
+            return None;
+        }
+        let message = self.buf[2..len+2].to_vec();
+        self.buf.drain(..len+2);
+        Some(message)
+    }
+
+    /// Length of the message currently being received, if known yet
+    pub fn pending_length(&self) -> Option<usize> {
+        if self.buf.len() < 2 {
+            return None;
+        }
+        Some(BigEndian::read_u16(&self.buf[..2]) as usize)
+    }
+
+    /// Number of bytes buffered that don't form a complete message yet
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use Packet;
+    use super::StreamDecoder;
+
+    const QUERY: &[u8] = b"\x00\x1d\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                           \x07example\x03com\x00\x00\x01\x00\x01";
+
+    #[test]
+    fn byte_by_byte() {
+        let mut decoder = StreamDecoder::new();
+        for (i, byte) in QUERY.iter().enumerate() {
+            assert_eq!(decoder.next_message(), None);
+            assert_eq!(decoder.buffered(), i);
+            decoder.push(&[*byte]);
+        }
+        let message = decoder.next_message().unwrap();
+        assert_eq!(&message[..], &QUERY[2..]);
+        assert_eq!(decoder.buffered(), 0);
+        assert_eq!(decoder.next_message(), None);
+        let packet = Packet::parse(&message).unwrap();
+        assert_eq!(packet.header.id, 1573);
+    }
+
+    #[test]
+    fn several_messages_in_one_chunk() {
+        let mut decoder = StreamDecoder::new();
+        let mut data = QUERY.to_vec();
+        data.extend(QUERY);
+        data.extend(&QUERY[..5]);
+        decoder.push(&data);
+        assert_eq!(decoder.next_message().as_ref().map(|x| &x[..]),
+            Some(&QUERY[2..]));
+        assert_eq!(decoder.next_message().as_ref().map(|x| &x[..]),
+            Some(&QUERY[2..]));
+        assert_eq!(decoder.next_message(), None);
+        assert_eq!(decoder.pending_length(), Some(29));
+        decoder.push(&QUERY[5..]);
+        assert_eq!(decoder.next_message().as_ref().map(|x| &x[..]),
+            Some(&QUERY[2..]));
+    }
+}
@@ -1,4 +1,8 @@
-use {QueryType, QueryClass, Name, Class, Header, RData};
+use std::fmt;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
+
+use {QueryType, QueryClass, Name, Class, Header, RData, Section};
 use rdata::opt;
 
 
@@ -17,6 +21,21 @@ pub struct Packet<'a> {
     /// respectively. To keep `ResourceRecord` clean we store the OPT record
     /// here.
     pub opt: Option<opt::Record<'a>>,
+    /// Sections left unparsed because of `ParseOptions`
+    pub skipped: Vec<SkippedSection>,
+}
+
+/// A section of the packet that was validated but not parsed
+///
+/// See `ParseOptions::skip_authority` and `ParseOptions::skip_additional`.
+/// With `ParseOptions::skip_binary_labels` the range may also be a single
+/// question or record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedSection {
+    /// Which section this is
+    pub section: Section,
+    /// Offsets of the section's records in the packet
+    pub range: Range<usize>,
 }
 
 /// A parsed chunk of data in the Query section of the packet
@@ -48,3 +67,67 @@ pub struct ResourceRecord<'a> {
     pub ttl: u32,
     pub data: RData<'a>,
 }
+
+impl<'a> ResourceRecord<'a> {
+    /// The TTL as a `Duration`
+    ///
+    /// Values with the most significant bit set are treated as zero, as
+    /// RFC 2181 section 8 requires.
+    pub fn ttl_duration(&self) -> Duration {
+        if self.ttl > i32::MAX as u32 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs(self.ttl as u64)
+        }
+    }
+
+    /// Returns true if this is an mDNS goodbye, i.e. the TTL is 0
+    ///
+    /// The record is about to go away. RFC 6762 section 10.1 asks
+    /// receivers not to delete it right away but to keep it for one
+    /// more second, see `mdns::GOODBYE_TTL`.
+    pub fn is_goodbye(&self) -> bool {
+        self.ttl == 0
+    }
+
+    /// The time the record expires when it was received at `received`
+    pub fn expires_at(&self, received: SystemTime) -> SystemTime {
+        received + self.ttl_duration()
+    }
+}
+
+/// Presentation format as in zone files
+///
+/// For example `example.com. 3600 IN MX 10 mail.example.com.`
+impl<'a> fmt::Display for ResourceRecord<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}. {} {} {} {}", self.name, self.ttl, self.cls,
+            self.data.type_name(), self.data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use {Name, Class, RData, ResourceRecord};
+
+    #[test]
+    fn ttl_duration() {
+        let mut record = ResourceRecord {
+            name: Name::from_string("example.com"),
+            multicast_unique: false,
+            cls: Class::IN,
+            ttl: 300,
+            data: RData::A(Ipv4Addr::new(192, 0, 2, 1).into()),
+        };
+        assert_eq!(record.ttl_duration(), Duration::from_secs(300));
+        let received = UNIX_EPOCH + Duration::from_secs(1000);
+        assert_eq!(record.expires_at(received),
+            UNIX_EPOCH + Duration::from_secs(1300));
+        record.ttl = 0x8000_0000;
+        assert_eq!(record.ttl_duration(), Duration::from_secs(0));
+        assert_eq!(record.expires_at(received), received);
+    }
+}
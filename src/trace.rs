@@ -0,0 +1,68 @@
+//! Parse tracing for the `trace` feature
+
+/// Logs a parsed field with its offset at the `trace` level
+///
+/// Without the `trace` feature this compiles to nothing, the arguments
+/// aren't evaluated.
+#[cfg(feature = "trace")]
+macro_rules! parse_trace {
+    ($offset:expr, $field:expr, $value:expr) => {
+        trace!(target: "dns_parser::parse", "{:5} {}: {:?}",
+            $offset, $field, $value)
+    };
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! parse_trace {
+    ($offset:expr, $field:expr, $value:expr) => {
+        if false {
+            let _ = (&$offset, &$field, &$value);
+        }
+    };
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod test {
+    use std::sync::Mutex;
+
+    use log::{self, Log, Metadata, Record, LevelFilter};
+
+    use {Builder, Packet, QueryClass, QueryType};
+
+    /// Collects the messages of the parser, from all tests running
+    struct Capture(Mutex<Vec<String>>);
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.target() == "dns_parser::parse"
+        }
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+    #[test]
+    fn traces_fields() {
+        // Fails if another test set a logger, which would lose the lines
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+        let mut bld = Builder::new(0xbeef, false);
+        bld.question("trace-test.example", false, QueryType::TXT,
+            QueryClass::IN);
+        let data = bld.build().unwrap();
+        Packet::parse(&data).unwrap();
+        let lines = CAPTURE.0.lock().unwrap();
+        assert!(lines.iter()
+            .any(|l| l.starts_with("    0 header: Header { id: 48879,")),
+            "{:?}", lines);
+        assert!(lines.iter()
+            .any(|l| l.starts_with("   12 qname: ")
+                     && l.contains("trace-test.example")),
+            "{:?}", lines);
+    }
+}
@@ -0,0 +1,166 @@
+//! Helpers for DNS UPDATE messages (RFC 2136)
+
+use {Packet, Name, Class, Type, RData, ResourceRecord, Opcode, Error};
+
+/// A record of the update section of an UPDATE message
+///
+/// The class and TTL of the record tell what to do with it (RFC 2136
+/// section 2.5). Returned by `Packet::update_ops`.
+#[derive(Debug, Clone, Copy)]
+pub enum UpdateOp<'r, 'a: 'r> {
+    /// Add the record to its RRset, the record has the class of the zone
+    Add(&'r ResourceRecord<'a>),
+    /// Delete the RRset of this type at the name, sent with class ANY
+    DeleteRRset(&'r Name<'a>, Type),
+    /// Delete all RRsets at the name, sent with class and type ANY
+    DeleteName(&'r Name<'a>),
+    /// Delete the record with the same RDATA, sent with class NONE
+    DeleteRecord(&'r ResourceRecord<'a>),
+}
+
+impl<'r, 'a: 'r> UpdateOp<'r, 'a> {
+    /// Interprets a record of the update section of a zone of
+    /// `zone_class`
+    ///
+    /// Fails with `Error::InvalidUpdate` where RFC 2136 section 3.4.1.3
+    /// has the server answer FORMERR: deletions with a TTL other than
+    /// zero, RRset deletions with RDATA, type ANY outside of RRset
+    /// deletions and classes other than the zone class, ANY and NONE.
+    pub fn from_record(record: &'r ResourceRecord<'a>, zone_class: Class)
+        -> Result<UpdateOp<'r, 'a>, Error>
+    {
+        let typ = record.data.raw_type_code();
+        let any_type = typ == Type::ANY as u16;
+        if record.cls == zone_class {
+            if any_type {
+                return Err(Error::InvalidUpdate(
+                    "adding a record of type ANY"));
+            }
+            return Ok(UpdateOp::Add(record));
+        }
+        if record.ttl != 0 {
+            return Err(Error::InvalidUpdate("deletion with a non-zero TTL"));
+        }
+        match record.cls {
+            Class::ANY => match record.data {
+                RData::Unknown(_, b"") if any_type => {
+                    Ok(UpdateOp::DeleteName(&record.name))
+                }
+                RData::Unknown(typ, b"") => {
+                    Ok(UpdateOp::DeleteRRset(&record.name, typ))
+                }
+                _ => Err(Error::InvalidUpdate("RRset deletion with RDATA")),
+            },
+            Class::NONE if any_type => {
+                Err(Error::InvalidUpdate("deleting a record of type ANY"))
+            }
+            Class::NONE => Ok(UpdateOp::DeleteRecord(record)),
+            _ => Err(Error::InvalidUpdate("class isn't the zone class")),
+        }
+    }
+
+    /// The owner name of the records affected
+    pub fn name(&self) -> &'r Name<'a> {
+        match *self {
+            UpdateOp::Add(record) | UpdateOp::DeleteRecord(record) => {
+                &record.name
+            }
+            UpdateOp::DeleteRRset(name, _) | UpdateOp::DeleteName(name) => {
+                name
+            }
+        }
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Interprets the update section of an UPDATE message
+    ///
+    /// In UPDATE messages the sections are the zone, the prerequisites,
+    /// the updates and the additional data, so the zone is the question
+    /// and the updates are in `nameservers`. The zone section must hold
+    /// exactly one SOA question, whose class is the class of the zone.
+    /// Fails with `Error::InvalidUpdate` for other opcodes and on the
+    /// first malformed update, see `UpdateOp::from_record`.
+    pub fn update_ops(&self) -> Result<Vec<UpdateOp<'_, 'a>>, Error> {
+        if self.header.opcode != Opcode::Update {
+            return Err(Error::InvalidUpdate("not an UPDATE message"));
+        }
+        let zone = match self.questions[..] {
+            [ref zone] if zone.qtype as u16 == Type::SOA as u16 => zone,
+            _ => {
+                return Err(Error::InvalidUpdate("zone section isn't one SOA"));
+            }
+        };
+        let zone_class = Class::from(zone.qclass as u16);
+        self.nameservers.iter()
+            .map(|record| UpdateOp::from_record(record, zone_class))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use {Builder, Class as C, Error, Header, Opcode, Packet};
+    use {QueryClass as QC, QueryType as QT, RData, Type};
+    use rdata::A;
+    use super::UpdateOp;
+
+    fn set_update_opcode(data: &mut [u8]) {
+        let mut header = Header::parse(data).unwrap();
+        header.opcode = Opcode::Update;
+        header.write(&mut data[..12]);
+    }
+
+    #[test]
+    fn update_ops() {
+        let a = |x| RData::A(A(Ipv4Addr::new(192, 0, 2, x)));
+        let mut bld = Builder::new(1, false);
+        bld.question("example.com", false, QT::SOA, QC::IN);
+        bld.nameserver("www.example.com", C::IN, a(1), false, 300);
+        bld.nameserver("www.example.com", C::ANY,
+            RData::Unknown(Type::AAAA, b""), false, 0);
+        bld.nameserver("old.example.com", C::ANY,
+            RData::Unknown(Type::ANY, b""), false, 0);
+        bld.nameserver("www.example.com", C::NONE, a(2), false, 0);
+        let mut data = bld.build().unwrap();
+        assert!(Packet::parse(&data).unwrap().update_ops().is_err());
+        set_update_opcode(&mut data);
+
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(packet.header.opcode, Opcode::Update);
+        let ops = packet.update_ops().unwrap();
+        assert_eq!(ops.len(), 4);
+        assert!(matches!(ops[0], UpdateOp::Add(r) if r.ttl == 300));
+        assert!(matches!(ops[1], UpdateOp::DeleteRRset(_, Type::AAAA)));
+        assert!(matches!(ops[2], UpdateOp::DeleteName(_)));
+        assert_eq!(ops[2].name().to_string(), "old.example.com");
+        match ops[3] {
+            UpdateOp::DeleteRecord(r) => {
+                assert_eq!(r.data.to_string(), "192.0.2.2");
+            }
+            op => panic!("wrong op {:?}", op),
+        }
+    }
+
+    #[test]
+    fn malformed_updates() {
+        let check = |cls, data, ttl| {
+            let mut bld = Builder::new(1, false);
+            bld.question("example.com", false, QT::SOA, QC::IN);
+            bld.nameserver("www.example.com", cls, data, false, ttl);
+            let mut data = bld.build().unwrap();
+            set_update_opcode(&mut data);
+            Packet::parse(&data).unwrap().update_ops().map(|_| ())
+        };
+        let a = || RData::A(A(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(check(C::NONE, a(), 60),
+            Err(Error::InvalidUpdate("deletion with a non-zero TTL")));
+        assert_eq!(check(C::ANY, a(), 0),
+            Err(Error::InvalidUpdate("RRset deletion with RDATA")));
+        assert_eq!(check(C::CH, a(), 0),
+            Err(Error::InvalidUpdate("class isn't the zone class")));
+        assert!(check(C::NONE, RData::Unknown(Type::ANY, b""), 0).is_err());
+    }
+}
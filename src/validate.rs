@@ -0,0 +1,236 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use {Packet, Header, Name, Error, Opcode, Type};
+use parser::{skip_question, skip_record};
+
+/// TYPE code of TSIG (RFC 8945), which isn't parsed by the crate
+const TSIG: u16 = 250;
+
+/// Section of a DNS message
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Section {
+    /// The question section
+    Question,
+    /// The answer section
+    Answer,
+    /// The authority (nameservers) section
+    Authority,
+    /// The additional section
+    Additional,
+}
+
+/// A problem found by `Packet::validate`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Violation {
+    /// The message can't be parsed past this point
+    Malformed(Error),
+    /// Header count doesn't match the number of entries in the section
+    CountMismatch {
+        /// The section
+        section: Section,
+        /// Count in the header
+        declared: u16,
+        /// Entries actually present
+        actual: u16,
+    },
+    /// Bytes after the last record that don't form a record
+    TrailingBytes(usize),
+    /// More than one OPT record
+    MultipleOpt,
+    /// OPT record outside of the additional section
+    MisplacedOpt(Section),
+    /// TSIG record that isn't the last record of the message
+    MisplacedTsig,
+    /// A standard query (or its response) must have exactly one question
+    QuestionCount(u16),
+}
+
+struct Walker<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Walker<'a> {
+    fn question(&mut self) -> Result<(), Error> {
+        skip_question(self.data, &mut self.offset)
+    }
+
+    /// Skips a record, returns its TYPE code
+    fn record(&mut self) -> Result<u16, Error> {
+        let start = self.offset + Name::skip(&self.data[self.offset..],
+                                                 self.data)?;
+        skip_record(self.data, &mut self.offset)?;
+        Ok(BigEndian::read_u16(&self.data[start..start+2]))
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Checks a raw message for structural problems
+    ///
+    /// Unlike `parse`, this doesn't stop at the first problem and doesn't
+    /// decode record data, which makes it suitable for lint-style tools.
+    /// Returns an empty list for a well-formed message.
+    pub fn validate(data: &[u8]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let header = match Header::parse(data) {
+            Ok(header) => header,
+            Err(e) => {
+                violations.push(Violation::Malformed(e));
+                return violations;
+            }
+        };
+        if header.opcode == Opcode::StandardQuery && header.questions != 1 {
+            violations.push(Violation::QuestionCount(header.questions));
+        }
+        let mut walker = Walker { data, offset: Header::size() };
+
+        let mut actual = 0;
+        while actual < header.questions {
+            if let Err(e) = walker.question() {
+                violations.push(Violation::Malformed(e));
+                break;
+            }
+            actual += 1;
+        }
+        if actual != header.questions {
+            violations.push(Violation::CountMismatch {
+                section: Section::Question,
+                declared: header.questions,
+                actual,
+            });
+            return violations;
+        }
+
+        let sections = [
+            (Section::Answer, header.answers),
+            (Section::Authority, header.nameservers),
+            (Section::Additional, header.additional),
+        ];
+        let mut types = Vec::new();
+        for &(section, declared) in &sections {
+            let mut actual = 0;
+            while actual < declared {
+                match walker.record() {
+                    Ok(typ) => types.push((section, typ)),
+                    Err(e) => {
+                        violations.push(Violation::Malformed(e));
+                        break;
+                    }
+                }
+                actual += 1;
+            }
+            if actual != declared {
+                violations.push(Violation::CountMismatch {
+                    section, declared, actual });
+                return violations;
+            }
+        }
+
+        if walker.offset < data.len() {
+            // the trailing bytes may be records not accounted for
+            let start = walker.offset;
+            let mut extra = 0usize;
+            while walker.offset < data.len() {
+                match walker.record() {
+                    Ok(typ) => types.push((Section::Additional, typ)),
+                    Err(_) => break,
+                }
+                extra += 1;
+            }
+            if walker.offset == data.len() {
+                violations.push(Violation::CountMismatch {
+                    section: Section::Additional,
+                    declared: header.additional,
+                    actual: header.additional
+                        .saturating_add(extra.min(0xFFFF) as u16),
+                });
+            } else {
+                types.truncate(types.len() - extra);
+                violations.push(Violation::TrailingBytes(data.len() - start));
+            }
+        }
+
+        let opt = Type::OPT as u16;
+        if types.iter().filter(|&&(_, typ)| typ == opt).count() > 1 {
+            violations.push(Violation::MultipleOpt);
+        }
+        for &(section, typ) in &types {
+            if typ == opt && section != Section::Additional {
+                violations.push(Violation::MisplacedOpt(section));
+            }
+        }
+        if let Some(pos) = types.iter().position(|&(_, typ)| typ == TSIG) {
+            if pos + 1 != types.len() || types[pos].0 != Section::Additional {
+                violations.push(Violation::MisplacedTsig);
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use {Builder, Packet, RData, Error};
+    use QueryType as QT;
+    use QueryClass as QC;
+    use Class as C;
+    use rdata::A;
+    use super::{Violation, Section};
+
+    fn response() -> Vec<u8> {
+        let mut bld = Builder::new(1, true);
+        bld.question("example.com", false, QT::A, QC::IN);
+        bld.answer("example.com", C::IN,
+            RData::A(A::new(Ipv4Addr::new(192, 0, 2, 1))), false, 60);
+        bld.edns(1232, 0, 0, 0);
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(Packet::validate(&response()), vec![]);
+    }
+
+    #[test]
+    fn counts_and_trailing() {
+        let mut data = response();
+        data[7] = 2; // two answers declared, OPT is taken as the second
+        assert_eq!(Packet::validate(&data), vec![
+            Violation::Malformed(Error::UnexpectedEOF),
+            Violation::CountMismatch {
+                section: Section::Additional, declared: 1, actual: 0 },
+        ]);
+
+        let mut data = response();
+        data[11] = 0; // OPT not declared
+        assert_eq!(Packet::validate(&data), vec![
+            Violation::CountMismatch {
+                section: Section::Additional, declared: 0, actual: 1 },
+        ]);
+
+        let mut data = response();
+        data.extend(b"\x00\x01");
+        assert_eq!(Packet::validate(&data), vec![
+            Violation::TrailingBytes(2),
+        ]);
+    }
+
+    #[test]
+    fn opt_placement() {
+        let mut data = response();
+        let opt = data[data.len()-11..].to_vec();
+        data.extend(opt);
+        data[11] = 2;
+        data[5] = 2; // second question is bogus, but counted
+        let violations = Packet::validate(&data);
+        assert_eq!(violations[0], Violation::QuestionCount(2));
+
+        let mut data = response();
+        let opt = data[data.len()-11..].to_vec();
+        data.extend(opt);
+        data[11] = 2;
+        assert_eq!(Packet::validate(&data), vec![Violation::MultipleOpt]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Event based parsing that doesn't allocate
+//!
+//! `walk` validates a packet and calls a `Visitor` for each entry with
+//! borrowed views into the buffer. Names aren't decoded unless the
+//! visitor asks for it, which makes this suitable for collecting
+//! statistics over lots of traffic.
+//!
+//! ```
+//! use dns_parser::{Builder, QueryType, QueryClass, Section};
+//! use dns_parser::visit::{walk, Visitor, RecordRef};
+//!
+//! #[derive(Default)]
+//! struct Count(usize);
+//!
+//! impl Visitor for Count {
+//!     fn on_record(&mut self, section: Section, _record: RecordRef) {
+//!         if section == Section::Answer {
+//!             self.0 += 1;
+//!         }
+//!     }
+//! }
+//!
+//! let mut bld = Builder::new(1, true);
+//! bld.question("example.com", false, QueryType::A, QueryClass::IN);
+//! let data = bld.build().unwrap();
+//! let mut count = Count::default();
+//! walk(&data, &mut count).unwrap();
+//! assert_eq!(count.0, 0);
+//! ```
+
+use byteorder::{BigEndian, ByteOrder};
+
+use {Header, Name, Error, Section};
+use parser::has_bytes;
+
+/// Receives the entries of a packet from `walk`, in packet order
+///
+/// All methods do nothing by default.
+pub trait Visitor {
+    /// Called first, with the header
+    fn on_header(&mut self, _header: &Header) {}
+    /// Called for each question
+    fn on_question(&mut self, _qname: NameRef, _qtype: u16, _qclass: u16) {}
+    /// Called for each record, including the OPT record
+    fn on_record(&mut self, _section: Section, _record: RecordRef) {}
+}
+
+/// A name in a packet that hasn't been decoded
+#[derive(Debug, Clone, Copy)]
+pub struct NameRef<'a> {
+    packet: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> NameRef<'a> {
+    /// Offset of the name in the packet
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Iterates over the labels, following compression pointers
+    pub fn labels(&self) -> Labels<'a> {
+        Labels { packet: self.packet, pos: self.offset }
+    }
+
+    /// Compares with a dotted name ignoring ASCII case
+    pub fn eq_ignore_case(&self, other: &str) -> bool {
+        let mut other = other.trim_end_matches('.').split('.')
+            .filter(|label| !label.is_empty());
+        self.labels().all(|label| other.next()
+                .is_some_and(|o| o.as_bytes().eq_ignore_ascii_case(label)))
+            && other.next().is_none()
+    }
+
+    /// Decodes the name
+    pub fn to_name(&self) -> Name<'a> {
+        Name::scan(&self.packet[self.offset..], self.packet)
+            .expect("name was validated by walk")
+    }
+}
+
+/// Iterator over the labels of a name, see `NameRef::labels`
+#[derive(Debug, Clone)]
+pub struct Labels<'a> {
+    packet: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<&'a [u8]> {
+        loop {
+            let byte = self.packet[self.pos];
+            if byte == 0 {
+                return None;
+            } else if byte & 0xC0 == 0xC0 {
+                self.pos = (BigEndian::read_u16(&self.packet[self.pos..])
+                    & 0x3FFF) as usize;
+            } else {
+                let label = &self.packet[self.pos+1..][..byte as usize];
+                self.pos += byte as usize + 1;
+                return Some(label);
+            }
+        }
+    }
+}
+
+/// A record in a packet with its RDATA left undecoded
+#[derive(Debug, Clone, Copy)]
+pub struct RecordRef<'a> {
+    /// The owner name
+    pub name: NameRef<'a>,
+    /// The TYPE code
+    pub typ: u16,
+    /// The CLASS field, for OPT the UDP payload size
+    pub class: u16,
+    /// The TTL field, for OPT the extended RCODE and flags
+    pub ttl: u32,
+    /// The RDATA, names in it may point into the rest of the packet
+    pub rdata: &'a [u8],
+}
+
+/// Validates the packet and reports its entries to the visitor
+///
+/// Fails on the same malformed names and truncated entries as
+/// `Packet::parse`, but types, classes and RDATA aren't checked. The
+/// visitor may have been called for the entries before the error.
+pub fn walk<V: Visitor>(data: &[u8], visitor: &mut V) -> Result<(), Error> {
+    let header = Header::parse(data)?;
+    visitor.on_header(&header);
+    let mut offset = Header::size();
+    for _ in 0..header.questions {
+        let qname = NameRef { packet: data, offset };
+        offset += Name::skip(&data[offset..], data)?;
+        if !has_bytes(data, offset, 4) {
+            return Err(Error::UnexpectedEOF);
+        }
+        let qtype = BigEndian::read_u16(&data[offset..]);
+        let qclass = BigEndian::read_u16(&data[offset+2..]);
+        offset += 4;
+        visitor.on_question(qname, qtype, qclass);
+    }
+    let sections = [
+        (Section::Answer, header.answers),
+        (Section::Authority, header.nameservers),
+        (Section::Additional, header.additional),
+    ];
+    for &(section, count) in &sections {
+        for _ in 0..count {
+            let name = NameRef { packet: data, offset };
+            offset += Name::skip(&data[offset..], data)?;
+            if !has_bytes(data, offset, 10) {
+                return Err(Error::UnexpectedEOF);
+            }
+            let typ = BigEndian::read_u16(&data[offset..]);
+            let class = BigEndian::read_u16(&data[offset+2..]);
+            let ttl = BigEndian::read_u32(&data[offset+4..]);
+            let rdlen = BigEndian::read_u16(&data[offset+8..]) as usize;
+            offset += 10;
+            if !has_bytes(data, offset, rdlen) {
+                return Err(Error::UnexpectedEOF);
+            }
+            let rdata = &data[offset..offset+rdlen];
+            offset += rdlen;
+            visitor.on_record(section, RecordRef {
+                name, typ, class, ttl, rdata,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use {Builder, Class, Error, Header, RData, QueryType, QueryClass};
+    use Section;
+    use super::{walk, Visitor, NameRef, RecordRef};
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Visitor for Log {
+        fn on_header(&mut self, header: &Header) {
+            self.0.push(format!("id {}", header.id));
+        }
+        fn on_question(&mut self, qname: NameRef, qtype: u16, _: u16) {
+            assert!(qname.eq_ignore_case("EXAMPLE.com."));
+            assert!(!qname.eq_ignore_case("www.example.com"));
+            assert!(!qname.eq_ignore_case("com"));
+            self.0.push(format!("{} {}", qname.to_name(), qtype));
+        }
+        fn on_record(&mut self, section: Section, record: RecordRef) {
+            let labels = record.name.labels().count();
+            self.0.push(format!("{:?} {} {} {:?}", section, labels,
+                record.typ, record.rdata));
+        }
+    }
+
+    #[test]
+    fn events() {
+        let mut bld = Builder::new(7, false);
+        bld.question("example.com", false, QueryType::A, QueryClass::IN);
+        bld.answer("example.com", Class::IN,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()), false, 60);
+        bld.edns(1232, 0, 0, 0);
+        let data = bld.build().unwrap();
+        let mut log = Log::default();
+        walk(&data, &mut log).unwrap();
+        assert_eq!(log.0, [
+            "id 7",
+            "example.com 1",
+            "Answer 2 1 [192, 0, 2, 1]",
+            "Additional 0 41 []",
+        ]);
+
+        let mut log = Log::default();
+        assert_eq!(walk(&data[..data.len()-1], &mut log),
+            Err(Error::UnexpectedEOF));
+        assert_eq!(log.0.len(), 3);
+    }
+}
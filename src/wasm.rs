@@ -0,0 +1,160 @@
+//! WebAssembly bindings
+//!
+//! Enabled with the `wasm` feature. `parse` returns the packet as a
+//! JavaScript object in the JSON format of RFC 8427, so browser tools
+//! get exactly what this crate parses.
+use std::fmt::Write;
+
+use js_sys::JSON;
+use wasm_bindgen::prelude::*;
+
+use {Packet, ResourceRecord, RData};
+
+/// Parses a packet into an RFC 8427 object
+///
+/// Throws the parse error as a string for malformed packets.
+#[wasm_bindgen]
+pub fn parse(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let packet = Packet::parse(bytes)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    JSON::parse(&to_json(&packet))
+}
+
+fn push_str(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).unwrap();
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_hex(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+    for b in bytes {
+        write!(out, "{:02X}", b).unwrap();
+    }
+    out.push('"');
+}
+
+fn absolute(name: &str) -> String {
+    format!("{}.", name)
+}
+
+fn push_record(out: &mut String, rr: &ResourceRecord) {
+    out.push_str("{\"NAME\":");
+    push_str(out, &absolute(&rr.name.to_string()));
+    write!(out, ",\"TYPE\":{},\"CLASS\":{},\"TTL\":{},\"RDATAHEX\":",
+        rr.data.raw_type_code(), u16::from(rr.cls), rr.ttl).unwrap();
+    match rr.data {
+        RData::Unknown(_, data) => push_hex(out, data),
+        ref data => push_hex(out, &data.to_bytes()),
+    }
+    let (member, value) = match rr.data {
+        RData::A(ref a) => ("rdataA", a.0.to_string()),
+        RData::AAAA(ref a) => ("rdataAAAA", a.0.to_string()),
+        RData::CNAME(ref n) => ("rdataCNAME", absolute(&n.0.to_string())),
+        RData::NS(ref n) => ("rdataNS", absolute(&n.0.to_string())),
+        RData::PTR(ref n) => ("rdataPTR", absolute(&n.0.to_string())),
+        _ => {
+            out.push('}');
+            return;
+        }
+    };
+    write!(out, ",\"{}\":", member).unwrap();
+    push_str(out, &value);
+    out.push('}');
+}
+
+fn push_records(out: &mut String, member: &str, records: &[ResourceRecord],
+    tail: Option<&str>)
+{
+    write!(out, ",\"{}\":[", member).unwrap();
+    for (i, rr) in records.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_record(out, rr);
+    }
+    if let Some(tail) = tail {
+        if !records.is_empty() {
+            out.push(',');
+        }
+        out.push_str(tail);
+    }
+    out.push(']');
+}
+
+/// Renders the packet in the JSON format of RFC 8427
+pub fn to_json(packet: &Packet) -> String {
+    let h = &packet.header;
+    let mut out = String::new();
+    write!(out, "{{\"ID\":{},\"QR\":{},\"Opcode\":{},\"AA\":{},\"TC\":{},\
+                 \"RD\":{},\"RA\":{},\"AD\":{},\"CD\":{},\"RCODE\":{},\
+                 \"QDCOUNT\":{},\"ANCOUNT\":{},\"NSCOUNT\":{},\
+                 \"ARCOUNT\":{}",
+        h.id, !h.query as u8, u16::from(h.opcode), h.authoritative as u8,
+        h.truncated as u8, h.recursion_desired as u8,
+        h.recursion_available as u8, h.authenticated_data as u8,
+        h.checking_disabled as u8, u8::from(h.response_code),
+        h.questions, h.answers, h.nameservers, h.additional).unwrap();
+    out.push_str(",\"questionRRs\":[");
+    for (i, q) in packet.questions.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"NAME\":");
+        push_str(&mut out, &absolute(&q.qname.to_string()));
+        write!(out, ",\"TYPE\":{},\"CLASS\":{}}}",
+            q.qtype as u16, q.qclass as u16).unwrap();
+    }
+    out.push(']');
+    push_records(&mut out, "answerRRs", &packet.answers, None);
+    push_records(&mut out, "authorityRRs", &packet.nameservers, None);
+    let opt = packet.opt.as_ref().map(|opt| {
+        let mut rec = String::new();
+        write!(rec, "{{\"NAME\":\".\",\"TYPE\":41,\"CLASS\":{},\"TTL\":{},\
+                      \"RDATAHEX\":", opt.udp, opt.ttl()).unwrap();
+        match opt.data {
+            RData::Unknown(_, data) => push_hex(&mut rec, data),
+            _ => push_hex(&mut rec, &[]),
+        }
+        rec.push('}');
+        rec
+    });
+    push_records(&mut out, "additionalRRs", &packet.additional,
+        opt.as_ref().map(|x| &x[..]));
+    out.push('}');
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use Packet;
+    use super::to_json;
+
+    #[test]
+    fn rfc8427() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x01\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04\x5d\xb8\xd8\x22\
+                         \x00\x00\x29\x10\x00\x00\x00\x80\x00\x00\x00";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(to_json(&packet), "{\"ID\":1573,\"QR\":1,\"Opcode\":0,\
+            \"AA\":0,\"TC\":0,\"RD\":1,\"RA\":1,\"AD\":0,\"CD\":0,\
+            \"RCODE\":0,\"QDCOUNT\":1,\"ANCOUNT\":1,\"NSCOUNT\":0,\
+            \"ARCOUNT\":1,\"questionRRs\":[{\"NAME\":\"example.com.\",\
+            \"TYPE\":1,\"CLASS\":1}],\"answerRRs\":[{\"NAME\":\
+            \"example.com.\",\"TYPE\":1,\"CLASS\":1,\"TTL\":1272,\
+            \"RDATAHEX\":\"5DB8D822\",\"rdataA\":\"93.184.216.34\"}],\
+            \"authorityRRs\":[],\"additionalRRs\":[{\"NAME\":\".\",\
+            \"TYPE\":41,\"CLASS\":4096,\"TTL\":32768,\"RDATAHEX\":\"\"}]}");
+    }
+}
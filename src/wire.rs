@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+
+/// Writes data in wire format into a caller-provided writer
+///
+/// This avoids allocating a temporary `Vec` for every record (and every
+/// name inside a record) when building large messages. The `to_bytes`
+/// methods are conveniences on top of this.
+pub trait WireEncode {
+    /// Writes the wire representation, returns the number of bytes written
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<usize>;
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use {Name, RData, Type};
+    use dnssec::Algorithm;
+    use rdata::{A, Aaaa, Caa, Cname, Dnskey, DnskeyFlags, Hinfo, Https, Loc};
+    use rdata::{Mx, Naptr, Ns, Ptr, Record, Rrsig, Soa, Srv, Sshfp, Svcb};
+    use rdata::{Tlsa, Txt, Zonemd};
+    use rdata::sshfp::{Algorithm as SshAlgorithm, FingerprintType};
+    use super::WireEncode;
+
+    const PREFIX: &[u8] = b"\x12\x34prefix";
+
+    /// Writes after `PREFIX`, returns what was written
+    fn write<T: WireEncode>(value: &T) -> Vec<u8> {
+        let mut buf = PREFIX.to_vec();
+        let len = value.write_to(&mut buf).unwrap();
+        assert_eq!(&buf[..PREFIX.len()], PREFIX);
+        assert_eq!(len, buf.len() - PREFIX.len());
+        buf.split_off(PREFIX.len())
+    }
+
+    fn check<'a, R: Record<'a>>(record: &R) {
+        let bytes = write(record);
+        assert_eq!(bytes.len(), record.length() as usize);
+        assert_eq!(bytes, record.to_bytes());
+    }
+
+    fn records() -> Vec<RData<'static>> {
+        let name = || Name::from_string("host.example.com");
+        let svcb = Svcb::new(1, name())
+            .with_param(1, b"\x02h2")
+            .with_param(3, b"\x01\xbb");
+        vec![
+            RData::A(A(Ipv4Addr::new(192, 0, 2, 1))),
+            RData::AAAA(Aaaa(Ipv6Addr::LOCALHOST)),
+            RData::CNAME(Cname(name())),
+            RData::HINFO(Hinfo::new(&b"Intel Xeon"[..], &b"Linux"[..])),
+            RData::MX(Mx::new(10, name())),
+            RData::NS(Ns(name())),
+            RData::PTR(Ptr(Name::from_string("."))),
+            RData::SOA(Soa::new(name(), Name::from_string("admin.example"),
+                1, 2, 3, 4, 5)),
+            RData::SRV(Srv::new(0, 5, 443, name())),
+            RData::TXT(Txt::from_bytes(&[b'x'; 300])),
+            RData::RRSIG(Rrsig {
+                type_covered: Type::A as u16,
+                algorithm: Algorithm::Ed25519,
+                labels: 3,
+                original_ttl: 300,
+                expiration: 2,
+                inception: 1,
+                key_tag: 12345,
+                signer: Name::from_string("example.com"),
+                signature: Cow::Borrowed(b"signature"),
+            }),
+            RData::DNSKEY(Dnskey::new(DnskeyFlags::ZONE, Algorithm::Ed25519,
+                &b"key"[..])),
+            RData::ZONEMD(Zonemd::new(1, 1, 1, &[0xab; 48][..])),
+            RData::SVCB(svcb.clone()),
+            RData::HTTPS(Https::from(svcb)),
+            RData::CAA(Caa::new(0, "issue", &b"ca.example.net"[..])),
+            RData::NAPTR(Naptr::new(100, 10, &b"U"[..], &b"E2U+sip"[..],
+                &b"!^.*$!sip:info@example.com!"[..],
+                Name::from_string("."))),
+            RData::TLSA(Tlsa::new(3, 1, 1, &[0xcd; 32][..])),
+            RData::SSHFP(Sshfp::new(SshAlgorithm::Ed25519,
+                FingerprintType::Sha256, &[0xef; 32][..])),
+            RData::LOC(Loc::from_wgs84(52.37, 4.89, -2.0).unwrap()),
+            RData::Unknown(Type::NULL, b"\x00\x01"),
+        ]
+    }
+
+    #[test]
+    fn write_to_contract() {
+        for data in records() {
+            let bytes = write(&data);
+            assert_eq!(bytes.len(), data.rdata_length() as usize);
+            assert_eq!(bytes, data.to_bytes());
+            match data {
+                RData::A(ref r) => check(r),
+                RData::AAAA(ref r) => check(r),
+                RData::CNAME(ref r) => check(r),
+                RData::HINFO(ref r) => check(r),
+                RData::MX(ref r) => check(r),
+                RData::NS(ref r) => check(r),
+                RData::PTR(ref r) => check(r),
+                RData::SOA(ref r) => check(r),
+                RData::SRV(ref r) => check(r),
+                RData::TXT(ref r) => check(r),
+                RData::RRSIG(ref r) => check(r),
+                RData::DNSKEY(ref r) => check(r),
+                RData::ZONEMD(ref r) => check(r),
+                RData::SVCB(ref r) => check(r),
+                RData::HTTPS(ref r) => check(r),
+                RData::CAA(ref r) => check(r),
+                RData::NAPTR(ref r) => check(r),
+                RData::TLSA(ref r) => check(r),
+                RData::SSHFP(ref r) => check(r),
+                RData::LOC(ref r) => check(r),
+                RData::Unknown(..) => {}
+                RData::Custom(..) => unreachable!(),
+            }
+        }
+        assert_eq!(write(&Name::from_string("example.com")),
+            b"\x07example\x03com\x00");
+        assert_eq!(write(&Name::from_string(".")), b"\x00");
+    }
+}
@@ -0,0 +1,437 @@
+//! Zone transfer (AXFR and IXFR) helpers
+
+use {Packet, ResourceRecord, RData, Error, ResponseCode};
+
+/// State of a zone transfer after processing a message
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransferStatus {
+    /// More messages are expected
+    InProgress,
+    /// The closing SOA record was received
+    Complete,
+}
+
+/// Assembles a zone from the messages of an AXFR response (RFC 5936)
+///
+/// Feed every response message with `push`. The first record of the
+/// transfer must be the SOA of the zone and the transfer ends with the
+/// same SOA record, which `push` reports with `TransferStatus::Complete`.
+///
+/// Records borrow from the message buffers, so all the buffers must
+/// outlive the stream.
+#[derive(Debug, Default)]
+pub struct AxfrStream<'a> {
+    records: Vec<ResourceRecord<'a>>,
+    serial: Option<u32>,
+    complete: bool,
+}
+
+impl<'a> AxfrStream<'a> {
+    /// Creates a stream waiting for the first message
+    pub fn new() -> AxfrStream<'a> {
+        AxfrStream {
+            records: Vec::new(),
+            serial: None,
+            complete: false,
+        }
+    }
+
+    /// Processes the next response message of the transfer
+    pub fn push(&mut self, packet: Packet<'a>) -> Result<TransferStatus, Error> {
+        if self.complete {
+            return Err(Error::InvalidZoneTransfer(
+                "message received after the end of transfer"));
+        }
+        if packet.header.response_code != ResponseCode::NoError {
+            return Err(Error::InvalidZoneTransfer(
+                "server responded with an error"));
+        }
+        for record in packet.answers {
+            if self.complete {
+                return Err(Error::InvalidZoneTransfer(
+                    "record received after the closing SOA"));
+            }
+            let serial = match record.data {
+                RData::SOA(ref soa) => Some(soa.serial),
+                _ => None,
+            };
+            match (self.serial, serial) {
+                (None, None) => {
+                    return Err(Error::InvalidZoneTransfer(
+                        "transfer doesn't start with a SOA record"));
+                }
+                (None, Some(serial)) => {
+                    self.serial = Some(serial);
+                }
+                (Some(first), Some(serial)) => {
+                    if first != serial {
+                        return Err(Error::InvalidZoneTransfer(
+                            "SOA serial changed during transfer"));
+                    }
+                    self.complete = true;
+                    continue;
+                }
+                (Some(_), None) => {}
+            }
+            self.records.push(record);
+        }
+        if self.complete {
+            Ok(TransferStatus::Complete)
+        } else {
+            Ok(TransferStatus::InProgress)
+        }
+    }
+
+    /// Returns true if the closing SOA was received
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Records received so far, starting with the SOA of the zone
+    ///
+    /// The closing SOA record is not included.
+    pub fn records(&self) -> &[ResourceRecord<'a>] {
+        &self.records
+    }
+
+    /// Returns all records of the zone if the transfer is complete
+    pub fn finish(self) -> Result<Vec<ResourceRecord<'a>>, Error> {
+        if !self.complete {
+            return Err(Error::InvalidZoneTransfer("transfer is incomplete"));
+        }
+        Ok(self.records)
+    }
+}
+
+/// Changes between two consecutive versions of a zone
+#[derive(Debug)]
+pub struct IxfrDiff<'a> {
+    /// Serial of the version the changes apply to
+    pub from_serial: u32,
+    /// Serial of the version after the changes
+    pub to_serial: u32,
+    /// Removed records, the first one is the old SOA
+    pub deleted: Vec<ResourceRecord<'a>>,
+    /// Added records, the first one is the new SOA
+    pub added: Vec<ResourceRecord<'a>>,
+}
+
+/// Interpreted IXFR response
+#[derive(Debug)]
+pub enum IxfrResponse<'a> {
+    /// The client's version is current, contains the zone serial
+    UpToDate(u32),
+    /// The server fell back to sending the whole zone, like AXFR does
+    ///
+    /// The closing SOA record is not included.
+    Full(Vec<ResourceRecord<'a>>),
+    /// Sequence of differences to apply in order
+    Incremental(Vec<IxfrDiff<'a>>),
+}
+
+#[derive(Debug)]
+enum IxfrState<'a> {
+    Start,
+    FirstSoa(ResourceRecord<'a>, u32),
+    Full(Vec<ResourceRecord<'a>>, u32),
+    Deleting(Vec<IxfrDiff<'a>>, IxfrDiff<'a>, u32),
+    Adding(Vec<IxfrDiff<'a>>, IxfrDiff<'a>, u32),
+    Done(IxfrResponse<'a>),
+}
+
+/// Interprets the messages of an IXFR response (RFC 1995)
+///
+/// The response is either a single SOA record when the client is up to
+/// date, the whole zone in AXFR format, or a sequence of differences,
+/// each one consisting of the old SOA, deleted records, the new SOA and
+/// added records, all of that enclosed in the SOA of the current version.
+///
+/// As with `AxfrStream`, records borrow from the message buffers.
+#[derive(Debug)]
+pub struct IxfrParser<'a> {
+    state: IxfrState<'a>,
+}
+
+impl<'a> Default for IxfrParser<'a> {
+    fn default() -> IxfrParser<'a> {
+        IxfrParser::new()
+    }
+}
+
+fn soa_serial(record: &ResourceRecord) -> Option<u32> {
+    match record.data {
+        RData::SOA(ref soa) => Some(soa.serial),
+        _ => None,
+    }
+}
+
+impl<'a> IxfrParser<'a> {
+    /// Creates a parser waiting for the first message
+    pub fn new() -> IxfrParser<'a> {
+        IxfrParser { state: IxfrState::Start }
+    }
+
+    /// Processes the next response message of the transfer
+    ///
+    /// A message consisting of just one SOA record completes the transfer
+    /// as `IxfrResponse::UpToDate`.
+    pub fn push(&mut self, packet: Packet<'a>) -> Result<TransferStatus, Error> {
+        use self::IxfrState::*;
+
+        if let Done(..) = self.state {
+            return Err(Error::InvalidZoneTransfer(
+                "message received after the end of transfer"));
+        }
+        if packet.header.response_code != ResponseCode::NoError {
+            return Err(Error::InvalidZoneTransfer(
+                "server responded with an error"));
+        }
+        for record in packet.answers {
+            let serial = soa_serial(&record);
+            let state = ::std::mem::replace(&mut self.state, Start);
+            self.state = match (state, serial) {
+                (Start, Some(serial)) => FirstSoa(record, serial),
+                (Start, None) => {
+                    return Err(Error::InvalidZoneTransfer(
+                        "transfer doesn't start with a SOA record"));
+                }
+                (FirstSoa(first, last), Some(serial)) if serial == last => {
+                    Done(IxfrResponse::Full(vec![first]))
+                }
+                (FirstSoa(_, last), Some(serial)) => {
+                    Deleting(Vec::new(), IxfrDiff {
+                        from_serial: serial,
+                        to_serial: serial,
+                        deleted: vec![record],
+                        added: Vec::new(),
+                    }, last)
+                }
+                (FirstSoa(first, last), None) => {
+                    Full(vec![first, record], last)
+                }
+                (Full(records, last), Some(serial)) => {
+                    if serial != last {
+                        return Err(Error::InvalidZoneTransfer(
+                            "SOA serial changed during transfer"));
+                    }
+                    Done(IxfrResponse::Full(records))
+                }
+                (Full(mut records, last), None) => {
+                    records.push(record);
+                    Full(records, last)
+                }
+                (Deleting(diffs, mut diff, last), Some(serial)) => {
+                    diff.to_serial = serial;
+                    diff.added.push(record);
+                    Adding(diffs, diff, last)
+                }
+                (Deleting(diffs, mut diff, last), None) => {
+                    diff.deleted.push(record);
+                    Deleting(diffs, diff, last)
+                }
+                (Adding(mut diffs, diff, last), Some(serial)) => {
+                    if serial != diff.to_serial {
+                        return Err(Error::InvalidZoneTransfer(
+                            "difference sequence doesn't start with \
+                             the previous SOA"));
+                    }
+                    let finished = serial == last;
+                    diffs.push(diff);
+                    if finished {
+                        Done(IxfrResponse::Incremental(diffs))
+                    } else {
+                        Deleting(diffs, IxfrDiff {
+                            from_serial: serial,
+                            to_serial: serial,
+                            deleted: vec![record],
+                            added: Vec::new(),
+                        }, last)
+                    }
+                }
+                (Adding(diffs, mut diff, last), None) => {
+                    diff.added.push(record);
+                    Adding(diffs, diff, last)
+                }
+                (Done(..), _) => {
+                    return Err(Error::InvalidZoneTransfer(
+                        "record received after the closing SOA"));
+                }
+            };
+        }
+        let state = ::std::mem::replace(&mut self.state, Start);
+        self.state = match state {
+            FirstSoa(_, serial) => Done(IxfrResponse::UpToDate(serial)),
+            state => state,
+        };
+        match self.state {
+            Done(..) => Ok(TransferStatus::Complete),
+            _ => Ok(TransferStatus::InProgress),
+        }
+    }
+
+    /// Returns true if the transfer is complete
+    pub fn is_complete(&self) -> bool {
+        matches!(self.state, IxfrState::Done(..))
+    }
+
+    /// Returns the interpreted response if the transfer is complete
+    pub fn finish(self) -> Result<IxfrResponse<'a>, Error> {
+        match self.state {
+            IxfrState::Done(response) => Ok(response),
+            _ => Err(Error::InvalidZoneTransfer("transfer is incomplete")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use {Builder, Packet, Name, RData, Error};
+    use Class as C;
+    use rdata::{A, Ns, Soa};
+    use super::{AxfrStream, TransferStatus, IxfrParser, IxfrResponse};
+
+    fn soa(serial: u32) -> RData<'static> {
+        RData::SOA(Soa::new(Name::from_string("ns.example.com"),
+            Name::from_string("admin.example.com"),
+            serial, 3600, 600, 86400, 300))
+    }
+
+    fn message(records: Vec<(&'static str, RData<'static>)>) -> Vec<u8> {
+        let mut bld = Builder::new(1, false);
+        for (name, data) in records {
+            bld.answer(name, C::IN, data, false, 3600);
+        }
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn multi_message_transfer() {
+        let first = message(vec![
+            ("example.com", soa(7)),
+            ("example.com", RData::NS(Ns::new(
+                Name::from_string("ns.example.com")))),
+        ]);
+        let second = message(vec![
+            ("ns.example.com", RData::A(A::new(Ipv4Addr::new(192, 0, 2, 1)))),
+            ("example.com", soa(7)),
+        ]);
+        let mut axfr = AxfrStream::new();
+        assert_eq!(axfr.push(Packet::parse(&first).unwrap()).unwrap(),
+            TransferStatus::InProgress);
+        assert_eq!(axfr.records().len(), 2);
+        assert_eq!(axfr.push(Packet::parse(&second).unwrap()).unwrap(),
+            TransferStatus::Complete);
+        assert!(matches!(axfr.push(Packet::parse(&second).unwrap()),
+            Err(Error::InvalidZoneTransfer(_))));
+        let records = axfr.finish().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(&records[2].name.to_string()[..], "ns.example.com");
+    }
+
+    #[test]
+    fn single_message_transfer() {
+        let data = message(vec![
+            ("example.com", soa(1)),
+            ("www.example.com", RData::A(A::new(Ipv4Addr::new(192, 0, 2, 2)))),
+            ("example.com", soa(1)),
+        ]);
+        let mut axfr = AxfrStream::new();
+        assert_eq!(axfr.push(Packet::parse(&data).unwrap()).unwrap(),
+            TransferStatus::Complete);
+        assert_eq!(axfr.finish().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn protocol_errors() {
+        let data = message(vec![
+            ("www.example.com", RData::A(A::new(Ipv4Addr::new(192, 0, 2, 2)))),
+        ]);
+        let mut axfr = AxfrStream::new();
+        assert!(matches!(axfr.push(Packet::parse(&data).unwrap()),
+            Err(Error::InvalidZoneTransfer(_))));
+
+        let data = message(vec![
+            ("example.com", soa(1)),
+            ("example.com", soa(2)),
+        ]);
+        let mut axfr = AxfrStream::new();
+        assert!(matches!(axfr.push(Packet::parse(&data).unwrap()),
+            Err(Error::InvalidZoneTransfer(_))));
+
+        let data = message(vec![("example.com", soa(1))]);
+        let mut axfr = AxfrStream::new();
+        axfr.push(Packet::parse(&data).unwrap()).unwrap();
+        assert!(matches!(axfr.finish(), Err(Error::InvalidZoneTransfer(_))));
+    }
+
+    #[test]
+    fn ixfr_incremental() {
+        let a = |last| RData::A(A::new(Ipv4Addr::new(192, 0, 2, last)));
+        let first = message(vec![
+            ("example.com", soa(3)),
+            ("example.com", soa(1)),
+            ("www.example.com", a(1)),
+            ("example.com", soa(2)),
+            ("www.example.com", a(2)),
+        ]);
+        let second = message(vec![
+            ("example.com", soa(2)),
+            ("example.com", soa(3)),
+            ("ftp.example.com", a(3)),
+            ("example.com", soa(3)),
+        ]);
+        let mut ixfr = IxfrParser::new();
+        assert_eq!(ixfr.push(Packet::parse(&first).unwrap()).unwrap(),
+            TransferStatus::InProgress);
+        assert_eq!(ixfr.push(Packet::parse(&second).unwrap()).unwrap(),
+            TransferStatus::Complete);
+        let diffs = match ixfr.finish().unwrap() {
+            IxfrResponse::Incremental(diffs) => diffs,
+            x => panic!("Wrong response {:?}", x),
+        };
+        assert_eq!(diffs.len(), 2);
+        assert_eq!((diffs[0].from_serial, diffs[0].to_serial), (1, 2));
+        assert_eq!(diffs[0].deleted.len(), 2);
+        assert_eq!(diffs[0].added.len(), 2);
+        assert_eq!((diffs[1].from_serial, diffs[1].to_serial), (2, 3));
+        assert_eq!(diffs[1].deleted.len(), 1);
+        assert_eq!(diffs[1].added.len(), 2);
+        assert_eq!(&diffs[1].added[1].name.to_string()[..], "ftp.example.com");
+    }
+
+    #[test]
+    fn ixfr_full_and_up_to_date() {
+        let full = message(vec![
+            ("example.com", soa(3)),
+            ("www.example.com", RData::A(A::new(Ipv4Addr::new(192, 0, 2, 1)))),
+            ("example.com", soa(3)),
+        ]);
+        let mut ixfr = IxfrParser::new();
+        ixfr.push(Packet::parse(&full).unwrap()).unwrap();
+        match ixfr.finish().unwrap() {
+            IxfrResponse::Full(records) => assert_eq!(records.len(), 2),
+            x => panic!("Wrong response {:?}", x),
+        }
+
+        let current = message(vec![("example.com", soa(3))]);
+        let mut ixfr = IxfrParser::new();
+        assert_eq!(ixfr.push(Packet::parse(&current).unwrap()).unwrap(),
+            TransferStatus::Complete);
+        assert!(matches!(ixfr.finish().unwrap(), IxfrResponse::UpToDate(3)));
+    }
+
+    #[test]
+    fn ixfr_broken_sequence() {
+        let data = message(vec![
+            ("example.com", soa(3)),
+            ("example.com", soa(1)),
+            ("example.com", soa(2)),
+            ("example.com", soa(1)),
+        ]);
+        let mut ixfr = IxfrParser::new();
+        assert!(matches!(ixfr.push(Packet::parse(&data).unwrap()),
+            Err(Error::InvalidZoneTransfer(_))));
+    }
+}
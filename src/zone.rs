@@ -0,0 +1,357 @@
+//! In-memory authoritative zone with lookup
+//!
+//! This is enough to build a small authoritative server on top of the
+//! parser: load the zone from an AXFR transfer (or any list of records)
+//! and answer queries with `Zone::lookup`.
+//!
+//! There is no zone file reader in the crate, so records loaded from
+//! a master file must be converted to `ResourceRecord`s by the caller.
+
+use std::collections::BTreeMap;
+
+use {ResourceRecord, RData, QueryType, Error};
+use rdata::Soa;
+use xfr::AxfrStream;
+
+/// Result of a zone lookup
+#[derive(Debug)]
+pub enum Lookup<'z, 'a: 'z> {
+    /// Records of the requested type at the name
+    Answer(Vec<&'z ResourceRecord<'a>>),
+    /// The name is an alias, contains the CNAME record
+    Cname(&'z ResourceRecord<'a>),
+    /// Records synthesized from a wildcard (RFC 4592)
+    ///
+    /// The records are owned by the wildcard name, the answer must use
+    /// the query name as the owner instead.
+    Wildcard {
+        /// Owner of the wildcard, e.g. `*.example.com`
+        source: String,
+        /// Matching records of the wildcard node
+        records: Vec<&'z ResourceRecord<'a>>,
+    },
+    /// The name is below a zone cut, contains the delegation NS records
+    Delegation(Vec<&'z ResourceRecord<'a>>),
+    /// The name exists but has no records of the requested type
+    NoData,
+    /// The name doesn't exist in the zone
+    NxDomain,
+    /// The name is not within this zone
+    NotInZone,
+}
+
+/// Records of a zone indexed by owner name
+///
+/// Names are compared case-insensitively. Empty non-terminals (names that
+/// have no records but have descendants that do) exist in the zone, as
+/// required for correct NXDOMAIN and wildcard handling.
+#[derive(Debug)]
+pub struct Zone<'a> {
+    origin: String,
+    nodes: BTreeMap<String, Vec<ResourceRecord<'a>>>,
+}
+
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+fn parent(name: &str) -> Option<&str> {
+    name.find('.').map(|pos| &name[pos+1..])
+}
+
+fn type_matches(data: &RData, qtype: QueryType) -> bool {
+    qtype == QueryType::All || qtype as u16 == data.raw_type_code()
+}
+
+impl<'a> Zone<'a> {
+    /// Builds a zone from its records
+    ///
+    /// The origin is the owner of the (single) SOA record. Records outside
+    /// of the origin are rejected.
+    pub fn from_records(records: Vec<ResourceRecord<'a>>)
+        -> Result<Zone<'a>, Error>
+    {
+        let mut soa = records.iter()
+            .filter(|r| matches!(r.data, RData::SOA(..)));
+        let origin = match soa.next() {
+            Some(record) => normalize(&record.name.to_string()),
+            None => return Err(Error::InvalidZone("no SOA record")),
+        };
+        if soa.next().is_some() {
+            return Err(Error::InvalidZone("multiple SOA records"));
+        }
+        let mut zone = Zone { origin, nodes: BTreeMap::new() };
+        zone.nodes.insert(zone.origin.clone(), Vec::new());
+        for record in records {
+            let name = normalize(&record.name.to_string());
+            if !zone.contains(&name) {
+                return Err(Error::InvalidZone("record outside of the zone"));
+            }
+            let mut ancestor = parent(&name);
+            while let Some(anc) = ancestor {
+                if !zone.contains(anc) {
+                    break;
+                }
+                zone.nodes.entry(anc.to_string()).or_default();
+                ancestor = parent(anc);
+            }
+            zone.nodes.entry(name).or_default().push(record);
+        }
+        Ok(zone)
+    }
+
+    /// Builds a zone from a completed AXFR transfer
+    pub fn from_axfr(stream: AxfrStream<'a>) -> Result<Zone<'a>, Error> {
+        Zone::from_records(stream.finish()?)
+    }
+
+    /// The origin of the zone, lowercase and without trailing dot
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// The SOA record of the zone
+    pub fn soa(&self) -> &Soa<'a> {
+        self.nodes[&self.origin].iter().filter_map(|r| match r.data {
+            RData::SOA(ref soa) => Some(soa),
+            _ => None,
+        }).next().expect("zone always has a SOA")
+    }
+
+    /// Returns true if the name is at or below the origin
+    pub fn contains(&self, name: &str) -> bool {
+        let name = normalize(name);
+        self.origin.is_empty() || name == self.origin
+            || name.ends_with(&format!(".{}", self.origin))
+    }
+
+    /// Records owned by exactly this name
+    ///
+    /// Returns `None` if the node doesn't exist and an empty slice for
+    /// empty non-terminals.
+    pub fn get(&self, name: &str) -> Option<&[ResourceRecord<'a>]> {
+        self.nodes.get(&normalize(name)).map(|x| &x[..])
+    }
+
+    /// The closest existing ancestor of the name (or the name itself)
+    ///
+    /// Returns `None` for names outside of the zone.
+    pub fn closest_encloser(&self, name: &str) -> Option<&str> {
+        let name = normalize(name);
+        if !self.contains(&name) {
+            return None;
+        }
+        let mut current = &name[..];
+        loop {
+            if let Some((key, _)) = self.nodes.get_key_value(current) {
+                return Some(key);
+            }
+            // origin always exists, so this terminates within the zone
+            current = parent(current).unwrap_or("");
+        }
+    }
+
+    /// NS records of the zone cut at or above the name, if any
+    ///
+    /// The NS records at the origin are not a zone cut.
+    pub fn zone_cut(&self, name: &str) -> Option<Vec<&ResourceRecord<'a>>> {
+        let name = normalize(name);
+        if !self.contains(&name) {
+            return None;
+        }
+        // check from the top so the highest delegation wins
+        let mut cuts = Vec::new();
+        let mut current = Some(&name[..]);
+        while let Some(cur) = current {
+            if cur == self.origin {
+                break;
+            }
+            cuts.push(cur);
+            current = parent(cur);
+        }
+        for cut in cuts.into_iter().rev() {
+            let ns: Vec<_> = self.nodes.get(cut).into_iter()
+                .flat_map(|x| x.iter())
+                .filter(|r| matches!(r.data, RData::NS(..)))
+                .collect();
+            if !ns.is_empty() {
+                return Some(ns);
+            }
+        }
+        None
+    }
+
+    /// Looks up records of the given type
+    ///
+    /// Zone cuts are checked first, then exact matches (following the
+    /// rules for CNAME and empty non-terminals) and finally the wildcard
+    /// at the closest encloser.
+    pub fn lookup<'z>(&'z self, name: &str, qtype: QueryType)
+        -> Lookup<'z, 'a>
+    {
+        let name = normalize(name);
+        if !self.contains(&name) {
+            return Lookup::NotInZone;
+        }
+        if let Some(ns) = self.zone_cut(&name) {
+            let at_cut = ns[0].name.to_string().eq_ignore_ascii_case(&name);
+            // NS queries at the cut itself get the delegation as answer
+            if !(at_cut && qtype == QueryType::NS) {
+                return Lookup::Delegation(ns);
+            }
+        }
+        if let Some(records) = self.nodes.get(&name) {
+            return Zone::answer(records, qtype);
+        }
+        let encloser = self.closest_encloser(&name).unwrap_or("");
+        let source = if encloser.is_empty() {
+            String::from("*")
+        } else {
+            format!("*.{}", encloser)
+        };
+        match self.nodes.get(&source) {
+            Some(records) => match Zone::answer(records, qtype) {
+                Lookup::Answer(records) => Lookup::Wildcard { source, records },
+                Lookup::Cname(record) => Lookup::Wildcard {
+                    source,
+                    records: vec![record],
+                },
+                other => other,
+            },
+            None => Lookup::NxDomain,
+        }
+    }
+
+    fn answer<'z>(records: &'z [ResourceRecord<'a>], qtype: QueryType)
+        -> Lookup<'z, 'a>
+    {
+        let found: Vec<_> = records.iter()
+            .filter(|r| type_matches(&r.data, qtype))
+            .collect();
+        if !found.is_empty() {
+            return Lookup::Answer(found);
+        }
+        match records.iter().find(|r| matches!(r.data, RData::CNAME(..))) {
+            Some(cname) => Lookup::Cname(cname),
+            None => Lookup::NoData,
+        }
+    }
+
+    /// Iterates over all records of the zone
+    pub fn records(&self) -> impl Iterator<Item=&ResourceRecord<'a>> {
+        self.nodes.values().flat_map(|x| x.iter())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use {Builder, Packet, Name, RData, QueryType as QT, Error};
+    use Class as C;
+    use rdata::{A, Cname, Ns, Soa};
+    use super::{Zone, Lookup};
+
+    fn zone_data() -> Vec<u8> {
+        let a = |last| RData::A(A::new(Ipv4Addr::new(192, 0, 2, last)));
+        let ns = |name| RData::NS(Ns::new(Name::from_string(name)));
+        let mut bld = Builder::new(1, false);
+        bld.answer("example.com", C::IN, RData::SOA(Soa::new(
+                Name::from_string("ns.example.com"),
+                Name::from_string("admin.example.com"),
+                1, 3600, 600, 86400, 300)), false, 3600);
+        bld.answer("example.com", C::IN, ns("ns.example.com"), false, 3600);
+        bld.answer("ns.example.com", C::IN, a(1), false, 3600);
+        bld.answer("www.example.com", C::IN, a(2), false, 3600);
+        bld.answer("web.example.com", C::IN, RData::CNAME(Cname::new(
+                Name::from_string("www.example.com"))), false, 3600);
+        bld.answer("host.deep.example.com", C::IN, a(3), false, 3600);
+        bld.answer("*.wild.example.com", C::IN, a(4), false, 3600);
+        bld.answer("sub.example.com", C::IN, ns("ns.sub.example.com"),
+                false, 3600);
+        bld.answer("ns.sub.example.com", C::IN, a(5), false, 3600);
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn exact_match() {
+        let data = zone_data();
+        let zone = Zone::from_records(Packet::parse(&data).unwrap().answers)
+            .unwrap();
+        assert_eq!(zone.origin(), "example.com");
+        assert_eq!(zone.soa().serial, 1);
+        match zone.lookup("WWW.example.com.", QT::A) {
+            Lookup::Answer(records) => {
+                assert_eq!(records.len(), 1);
+                assert_eq!(records[0].data.ip(),
+                    Some(Ipv4Addr::new(192, 0, 2, 2).into()));
+            }
+            x => panic!("Wrong lookup {:?}", x),
+        }
+        assert!(matches!(zone.lookup("www.example.com", QT::MX),
+            Lookup::NoData));
+        assert!(matches!(zone.lookup("web.example.com", QT::A),
+            Lookup::Cname(_)));
+        assert!(matches!(zone.lookup("www.example.org", QT::A),
+            Lookup::NotInZone));
+        assert_eq!(zone.records().count(), 9);
+    }
+
+    #[test]
+    fn empty_non_terminal_and_nxdomain() {
+        let data = zone_data();
+        let zone = Zone::from_records(Packet::parse(&data).unwrap().answers)
+            .unwrap();
+        assert_eq!(zone.get("deep.example.com").map(|x| x.len()), Some(0));
+        assert!(matches!(zone.lookup("deep.example.com", QT::A),
+            Lookup::NoData));
+        assert!(matches!(zone.lookup("nope.example.com", QT::A),
+            Lookup::NxDomain));
+        assert_eq!(zone.closest_encloser("a.b.deep.example.com"),
+            Some("deep.example.com"));
+        assert_eq!(zone.closest_encloser("other.net"), None);
+    }
+
+    #[test]
+    fn wildcard() {
+        let data = zone_data();
+        let zone = Zone::from_records(Packet::parse(&data).unwrap().answers)
+            .unwrap();
+        match zone.lookup("anything.wild.example.com", QT::A) {
+            Lookup::Wildcard { source, records } => {
+                assert_eq!(source, "*.wild.example.com");
+                assert_eq!(records.len(), 1);
+            }
+            x => panic!("Wrong lookup {:?}", x),
+        }
+        assert!(matches!(zone.lookup("anything.wild.example.com", QT::MX),
+            Lookup::NoData));
+        // wildcard doesn't match beyond the closest encloser
+        assert!(matches!(zone.lookup("x.www.example.com", QT::A),
+            Lookup::NxDomain));
+    }
+
+    #[test]
+    fn delegation() {
+        let data = zone_data();
+        let zone = Zone::from_records(Packet::parse(&data).unwrap().answers)
+            .unwrap();
+        assert!(zone.zone_cut("example.com").is_none());
+        assert_eq!(zone.zone_cut("a.sub.example.com").unwrap().len(), 1);
+        assert!(matches!(zone.lookup("ns.sub.example.com", QT::A),
+            Lookup::Delegation(_)));
+        assert!(matches!(zone.lookup("sub.example.com", QT::NS),
+            Lookup::Answer(_)));
+    }
+
+    #[test]
+    fn invalid_zone() {
+        let mut bld = Builder::new(1, false);
+        bld.answer("www.example.com", C::IN,
+            RData::A(A::new(Ipv4Addr::new(192, 0, 2, 1))), false, 3600);
+        let data = bld.build().unwrap();
+        let records = Packet::parse(&data).unwrap().answers;
+        assert!(matches!(Zone::from_records(records),
+            Err(Error::InvalidZone("no SOA record"))));
+    }
+}
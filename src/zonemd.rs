@@ -0,0 +1,239 @@
+//! Zone digests (ZONEMD, RFC 8976)
+//!
+//! Recipients of a zone, e.g. by AXFR, check with `verify` that it
+//! matches the digest published in the ZONEMD record at the apex. Only
+//! the SIMPLE scheme is defined, with SHA-384 and SHA-512 hashes.
+
+use byteorder::{BigEndian, WriteBytesExt};
+use sha2::{Digest, Sha384, Sha512};
+
+use {Name, RData, ResourceRecord, Type};
+use rdata::zonemd::{SCHEME_SIMPLE, HASH_SHA384, HASH_SHA512};
+use zone::Zone;
+
+/// Result of checking a zone against its ZONEMD records
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Verification {
+    /// The digest of a ZONEMD record matches the zone
+    Verified,
+    /// There is no ZONEMD record at the apex
+    Missing,
+    /// No ZONEMD record has the serial of the SOA record
+    SerialMismatch,
+    /// No ZONEMD record uses the SIMPLE scheme and a known hash algorithm
+    Unsupported,
+    /// The zone doesn't match the digest
+    Mismatch,
+}
+
+/// A record in canonical form with its sort key
+struct Canonical {
+    /// Lowercase labels, starting from the rightmost one
+    owner: Vec<Vec<u8>>,
+    class: u16,
+    typ: u16,
+    rdata: Vec<u8>,
+    /// Owner name, type, class, TTL and RDLENGTH
+    head: Vec<u8>,
+}
+
+fn canonical(record: &ResourceRecord) -> Canonical {
+    let owner = record.name.to_string().to_ascii_lowercase();
+    let rdata = record.data.canonical_bytes();
+    let typ = record.data.raw_type_code();
+    let class = u16::from(record.cls);
+    let mut head = Name::from_string(&owner).to_bytes();
+    head.write_u16::<BigEndian>(typ).unwrap();
+    head.write_u16::<BigEndian>(class).unwrap();
+    head.write_u32::<BigEndian>(record.ttl).unwrap();
+    head.write_u16::<BigEndian>(rdata.len() as u16).unwrap();
+    Canonical {
+        owner: owner.rsplit('.').map(|x| x.as_bytes().to_vec()).collect(),
+        class,
+        typ,
+        rdata,
+        head,
+    }
+}
+
+/// Computes the SIMPLE scheme digest over the records of a zone
+///
+/// The ZONEMD records at `origin` and the RRSIG records covering them
+/// are left out, as the digest is computed before adding them (RFC 8976
+/// section 3.3.1.1). Records are put in canonical order and
+/// duplicates removed, so they may be passed in any order. Returns `None`
+/// for hash algorithms other than `HASH_SHA384` and `HASH_SHA512`.
+pub fn digest<'r, 'a: 'r, I>(records: I, origin: &str, hash_algorithm: u8)
+    -> Option<Vec<u8>>
+    where I: IntoIterator<Item=&'r ResourceRecord<'a>>
+{
+    if hash_algorithm != HASH_SHA384 && hash_algorithm != HASH_SHA512 {
+        return None;
+    }
+    let origin = origin.trim_end_matches('.');
+    let mut records = records.into_iter()
+        .filter(|r| {
+            let zonemd = match r.data {
+                RData::ZONEMD(..) => true,
+                RData::RRSIG(ref rrsig) => {
+                    rrsig.type_covered == Type::ZONEMD as u16
+                }
+                _ => false,
+            };
+            !(zonemd && r.name.to_string().eq_ignore_ascii_case(origin))
+        })
+        .map(canonical)
+        .collect::<Vec<_>>();
+    records.sort_by(|a, b| {
+        (&a.owner, a.class, a.typ, &a.rdata)
+            .cmp(&(&b.owner, b.class, b.typ, &b.rdata))
+    });
+    records.dedup_by(|a, b| {
+        a.owner == b.owner && a.class == b.class && a.typ == b.typ &&
+        a.rdata == b.rdata
+    });
+    let mut buf = Vec::new();
+    for record in &records {
+        buf.extend_from_slice(&record.head);
+        buf.extend_from_slice(&record.rdata);
+    }
+    Some(match hash_algorithm {
+        HASH_SHA384 => Sha384::digest(&buf).to_vec(),
+        _ => Sha512::digest(&buf).to_vec(),
+    })
+}
+
+/// Checks the zone against the ZONEMD records at its apex
+///
+/// Follows the verification procedure of RFC 8976 section 4: the zone
+/// is verified if any ZONEMD record with the serial of the SOA record
+/// and a supported scheme and hash algorithm has a matching digest.
+pub fn verify(zone: &Zone) -> Verification {
+    let apex = zone.get(zone.origin()).unwrap_or(&[]);
+    let zonemds = apex.iter()
+        .filter_map(|r| match r.data {
+            RData::ZONEMD(ref zonemd) => Some(zonemd),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if zonemds.is_empty() {
+        return Verification::Missing;
+    }
+    let serial = zone.soa().serial;
+    let current = zonemds.into_iter()
+        .filter(|x| x.serial == serial)
+        .collect::<Vec<_>>();
+    if current.is_empty() {
+        return Verification::SerialMismatch;
+    }
+    let mut result = Verification::Unsupported;
+    for zonemd in current {
+        if zonemd.scheme != SCHEME_SIMPLE {
+            continue;
+        }
+        match digest(zone.records(), zone.origin(), zonemd.hash_algorithm) {
+            Some(ref digest) if digest[..] == *zonemd.digest => {
+                return Verification::Verified;
+            }
+            Some(_) => result = Verification::Mismatch,
+            None => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use std::borrow::Cow;
+
+    use {Builder, Name, Packet, RData, Type};
+    use Class as C;
+    use dnssec::Algorithm;
+    use rdata::{A, Aaaa, Ns, Rrsig, Soa, Zonemd};
+    use rdata::zonemd::{SCHEME_SIMPLE, HASH_SHA384, HASH_SHA512};
+    use zone::Zone;
+    use super::{digest, verify, Verification};
+
+    // The simple example zone of RFC 8976 appendix A.1
+    const DIGEST: &[u8] = b"\xc6\x80\x90\xd9\x0a\x7a\xed\x71\
+        \x6b\xc4\x59\xf9\x34\x0e\x3d\x7c\x13\x70\xd4\xd2\x4b\x7e\x2f\xc3\
+        \xa1\xdd\xc0\xb9\xa8\x71\x53\xb9\xa9\x71\x3b\x3c\x9a\xe5\xcc\x27\
+        \x77\x7f\x98\xb8\xe7\x30\x04\x4c";
+
+    fn zone_data(serial: u32, hash_algorithm: u8, digest: &[u8]) -> Vec<u8> {
+        let ns = |name| RData::NS(Ns::new(Name::from_string(name)));
+        let mut bld = Builder::new(1, false);
+        bld.answer("example", C::IN, RData::SOA(Soa::new(
+                Name::from_string("ns1.example"),
+                Name::from_string("admin.example"),
+                2018031900, 1800, 900, 604800, 86400)), false, 86400);
+        bld.answer("example", C::IN, ns("ns1.example"), false, 86400);
+        bld.answer("example", C::IN, ns("NS2.example"), false, 86400);
+        bld.answer("example", C::IN, RData::ZONEMD(Zonemd::new(
+                serial, SCHEME_SIMPLE, hash_algorithm, digest)),
+            false, 86400);
+        bld.answer("ns2.example", C::IN, RData::AAAA(Aaaa(
+                "2001:db8::63".parse::<Ipv6Addr>().unwrap())), false, 3600);
+        bld.answer("NS1.example", C::IN, RData::A(A::new(
+                Ipv4Addr::new(203, 0, 113, 63))), false, 3600);
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn rfc8976_simple() {
+        let data = zone_data(2018031900, HASH_SHA384, DIGEST);
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(digest(&packet.answers, "example.", HASH_SHA384).unwrap(),
+            DIGEST);
+        let zone = Zone::from_records(packet.answers).unwrap();
+        assert_eq!(verify(&zone), Verification::Verified);
+    }
+
+    #[test]
+    fn signed_apex() {
+        let rrsig = |type_covered: Type| RData::RRSIG(Rrsig {
+            type_covered: type_covered as u16,
+            algorithm: Algorithm::Ed25519,
+            labels: 1,
+            original_ttl: 86400,
+            expiration: 2,
+            inception: 1,
+            key_tag: 12345,
+            signer: Name::from_string("example"),
+            signature: Cow::Borrowed(b"signature"),
+        });
+        let data = zone_data(2018031900, HASH_SHA384, DIGEST);
+        let mut packet = Packet::parse(&data).unwrap();
+        let mut bld = Builder::from_packet(Packet::parse(&data).unwrap());
+        bld.answer("EXAMPLE.", C::IN, rrsig(Type::ZONEMD), false, 86400);
+        bld.answer("example", C::IN, rrsig(Type::SOA), false, 86400);
+        bld.answer("ns1.example", C::IN, rrsig(Type::ZONEMD), false, 3600);
+        let signed = bld.build().unwrap();
+        let signed = Packet::parse(&signed).unwrap();
+        // The signature over the ZONEMD RRset at the apex is left out
+        assert_eq!(digest(&signed.answers[..7], "example", HASH_SHA384)
+            .unwrap(), DIGEST);
+        assert!(digest(&signed.answers, "example", HASH_SHA384).unwrap()
+            != DIGEST);
+        packet.answers.push(signed.answers.into_iter().nth(6).unwrap());
+        let zone = Zone::from_records(packet.answers).unwrap();
+        assert_eq!(verify(&zone), Verification::Verified);
+    }
+
+    #[test]
+    fn failures() {
+        let check = |serial, hash_algorithm, digest: &[u8]| {
+            let data = zone_data(serial, hash_algorithm, digest);
+            let packet = Packet::parse(&data).unwrap();
+            verify(&Zone::from_records(packet.answers).unwrap())
+        };
+        assert_eq!(check(2018031900, HASH_SHA512, &[0; 64]),
+            Verification::Mismatch);
+        assert_eq!(check(2018031900, 240, &[0; 12]),
+            Verification::Unsupported);
+        assert_eq!(check(2018031901, HASH_SHA384, DIGEST),
+            Verification::SerialMismatch);
+    }
+}